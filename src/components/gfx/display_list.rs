@@ -15,6 +15,7 @@
 /// low-level drawing primitives.
 
 use color::Color;
+use azure::AzFloat;
 use servo_util::geometry::Au;
 use style::computed_values::border_style;
 use render_context::RenderContext;
@@ -70,7 +71,8 @@ pub enum DisplayItem<E> {
     TextDisplayItemClass(~TextDisplayItem<E>),
     ImageDisplayItemClass(~ImageDisplayItem<E>),
     BorderDisplayItemClass(~BorderDisplayItem<E>),
-    ClipDisplayItemClass(~ClipDisplayItem<E>)
+    ClipDisplayItemClass(~ClipDisplayItem<E>),
+    OpacityDisplayItemClass(~OpacityDisplayItem<E>),
 }
 
 /// Information common to all display items.
@@ -150,6 +152,14 @@ pub struct ClipDisplayItem<E> {
     need_clip: bool
 }
 
+/// Renders its children as a group, which is then made translucent as a whole (CSS Color
+/// Level 3 § 3.2).
+pub struct OpacityDisplayItem<E> {
+    base: BaseDisplayItem<E>,
+    child_list: ~[DisplayItem<E>],
+    opacity: AzFloat,
+}
+
 pub enum DisplayItemIterator<'self,E> {
     EmptyDisplayItemIterator,
     ParentDisplayItemIterator(VecIterator<'self,DisplayItem<E>>),
@@ -185,6 +195,14 @@ impl<E> DisplayItem<E> {
                 }
             }
 
+            OpacityDisplayItemClass(ref group) => {
+                do render_context.draw_with_opacity(group.opacity) |render_context| {
+                    for item in group.child_list.iter() {
+                        item.draw_into_context(render_context);
+                    }
+                }
+            }
+
             TextDisplayItemClass(ref text) => {
                 debug!("Drawing text at {:?}.", text.base.bounds);
 
@@ -253,6 +271,7 @@ impl<E> DisplayItem<E> {
                 ImageDisplayItemClass(ref image_item) => transmute_region(&image_item.base),
                 BorderDisplayItemClass(ref border) => transmute_region(&border.base),
                 ClipDisplayItemClass(ref clip) => transmute_region(&clip.base),
+                OpacityDisplayItemClass(ref group) => transmute_region(&group.base),
             }
         }
     }
@@ -264,6 +283,9 @@ impl<E> DisplayItem<E> {
     pub fn children<'a>(&'a self) -> DisplayItemIterator<'a,E> {
         match *self {
             ClipDisplayItemClass(ref clip) => ParentDisplayItemIterator(clip.child_list.iter()),
+            OpacityDisplayItemClass(ref group) => {
+                ParentDisplayItemIterator(group.child_list.iter())
+            }
             SolidColorDisplayItemClass(*) |
             TextDisplayItemClass(*) |
             ImageDisplayItemClass(*) |
@@ -278,6 +300,7 @@ impl<E> DisplayItem<E> {
             ImageDisplayItemClass(_) => "Image",
             BorderDisplayItemClass(_) => "Border",
             ClipDisplayItemClass(_) => "Clip",
+            OpacityDisplayItemClass(_) => "Opacity",
         };
         let mut string = format!("{} @ {:?}", class, self.base().bounds);
         for child in self.children() {
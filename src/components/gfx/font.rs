@@ -193,12 +193,13 @@ impl FontGroup {
         self.fonts = ~[];
     }
 
-    pub fn create_textrun(&self, text: ~str, decoration: text_decoration::T) -> TextRun {
+    pub fn create_textrun(&self, text: ~str, decoration: text_decoration::T,
+                          letter_spacing: Au, word_spacing: Au) -> TextRun {
         assert!(self.fonts.len() > 0);
 
         // TODO(Issue #177): Actually fall back through the FontGroup when a font is unsuitable.
         self.fonts[0].with_mut_borrow(|font| {
-            TextRun::new(font, text.clone(), decoration)
+            TextRun::new(font, text.clone(), decoration, letter_spacing, word_spacing)
         })
     }
 }
@@ -403,8 +404,9 @@ impl Font {
         azglyphs.reserve(range.length());
 
         for (glyphs, _offset, slice_range) in run.iter_slices_for_range(range) {
+            let spacing = run.glyph_spacing(glyphs);
             for (_i, glyph) in glyphs.iter_glyphs_for_char_range(&slice_range) {
-                let glyph_advance = glyph.advance();
+                let glyph_advance = glyph.advance() + spacing;
                 let glyph_offset = glyph.offset().unwrap_or(Au::zero_point());
 
                 let azglyph = struct__AzGlyph {
@@ -440,11 +442,11 @@ impl Font {
 
     pub fn measure_text(&self, run: &TextRun, range: &Range) -> RunMetrics {
         // TODO(Issue #199): alter advance direction for RTL
-        // TODO(Issue #98): using inter-char and inter-word spacing settings  when measuring text
         let mut advance = Au(0);
         for (glyphs, _offset, slice_range) in run.iter_slices_for_range(range) {
+            let spacing = run.glyph_spacing(glyphs);
             for (_i, glyph) in glyphs.iter_glyphs_for_char_range(&slice_range) {
-                advance = advance + glyph.advance();
+                advance = advance + glyph.advance() + spacing;
             }
         }
         RunMetrics::new(advance, self.metrics.ascent, self.metrics.descent)
@@ -8,6 +8,7 @@
 use azure::azure_hl::{BackendType, CairoBackend, CoreGraphicsBackend};
 use azure::azure_hl::{CoreGraphicsAcceleratedBackend, Direct2DBackend, SkiaBackend};
 use extra::getopts::groups;
+use std::rt;
 
 /// Global flags for Servo, currently set on the command line.
 #[deriving(Clone)]
@@ -23,6 +24,9 @@ pub struct Opts {
     /// FIXME(pcwalton): This is not currently used. All rendering is sequential.
     n_render_threads: uint,
 
+    /// How many tasks to use for CSS selector matching and cascading (`-y`).
+    n_style_threads: uint,
+
     /// True to use CPU painting, false to use GPU painting via Skia-GL (`-c`). Note that
     /// compositing is always done on the GPU.
     cpu_painting: bool,
@@ -37,6 +41,18 @@ pub struct Opts {
     /// True to exit after the page load (`-x`).
     exit_after_load: bool,
 
+    /// The number of device pixels per CSS pixel, used to evaluate `resolution` media
+    /// queries (`--device-pixel-ratio`).
+    device_pixel_ratio: f64,
+
+    /// True to lay the document out for print media and write each page to its own PNG file
+    /// derived from `output_file` (`--print`).
+    print: bool,
+
+    /// The file that the browsing history is loaded from and saved to, if any. Without it, links
+    /// only match `:visited` for pages visited since the browser started (`--history-file`).
+    history_file: Option<~str>,
+
     output_file: Option<~str>,
     headless: bool,
     hard_fail: bool,
@@ -57,7 +73,11 @@ pub fn from_cmdline_args(args: &[~str]) -> Opts {
         groups::optopt("r", "rendering", "Rendering backend", "direct2d|core-graphics|core-graphics-accelerated|cairo|skia."),
         groups::optopt("s", "size", "Size of tiles", "512"),
         groups::optopt("t", "threads", "Number of render threads", "1"),
+        groups::optopt("y", "style-threads", "Number of style threads (defaults to the number of cores)", "N"),
         groups::optflagopt("p", "profile", "Profiler flag and output interval", "10"),
+        groups::optopt("", "device-pixel-ratio", "Device pixels per CSS pixel", "1.0"),
+        groups::optflag("", "print", "Paginate for print media, writing one PNG per page (with -o)"),
+        groups::optopt("", "history-file", "File to load and save the browsing history", "history.txt"),
         groups::optflag("x", "exit", "Exit after load flag"),
         groups::optflag("z", "headless", "Headless mode"),
         groups::optflag("f", "hard-fail", "Exit on task failure instead of displaying about:failure"),
@@ -112,20 +132,34 @@ pub fn from_cmdline_args(args: &[~str]) -> Opts {
         None => 1,      // FIXME: Number of cores.
     };
 
+    let n_style_threads: uint = match opt_match.opt_str("y") {
+        Some(n_style_threads_str) => from_str(n_style_threads_str).unwrap(),
+        None => rt::default_sched_threads(),
+    };
+
     // if only flag is present, default to 5 second period
     let profiler_period = do opt_match.opt_default("p", "5").map |period| {
         from_str(period).unwrap()
     };
 
+    let device_pixel_ratio: f64 = match opt_match.opt_str("device-pixel-ratio") {
+        Some(ratio_str) => from_str(ratio_str).unwrap(),
+        None => 1.0,
+    };
+
     let cpu_painting = opt_match.opt_present("c");
 
     Opts {
         urls: urls,
         render_backend: render_backend,
         n_render_threads: n_render_threads,
+        n_style_threads: n_style_threads,
         cpu_painting: cpu_painting,
         tile_size: tile_size,
         profiler_period: profiler_period,
+        device_pixel_ratio: device_pixel_ratio,
+        print: opt_match.opt_present("print"),
+        history_file: opt_match.opt_str("history-file"),
         exit_after_load: opt_match.opt_present("x"),
         output_file: opt_match.opt_str("o"),
         headless: opt_match.opt_present("z"),
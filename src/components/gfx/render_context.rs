@@ -11,6 +11,7 @@ use azure::azure_hl::{DrawSurfaceOptions, DrawTarget, Linear, StrokeOptions};
 use azure::AZ_CAP_BUTT;
 use azure::AzFloat;
 use extra::arc::Arc;
+use geom::matrix2d::Matrix2D;
 use geom::point::Point2D;
 use geom::rect::Rect;
 use geom::size::Size2D;
@@ -85,6 +86,45 @@ impl<'self> RenderContext<'self>  {
         self.draw_target.pop_clip();
     }    
 
+    /// Draws what `draw` draws as a group with the given opacity: it is drawn into an offscreen
+    /// layer the size of this context, which is then composited onto this context.
+    pub fn draw_with_opacity(&mut self, opacity: AzFloat, draw: &fn(&mut RenderContext)) {
+        let size = Size2D(self.screen_rect.size.width as i32, self.screen_rect.size.height as i32);
+        let layer = self.draw_target.create_similar_draw_target(&size, B8G8R8A8);
+
+        // Use the current transform of this context, which is not only the transform from page
+        // coordinates to pixels: e.g. printed pages move their part of the document into view.
+        let transform = self.draw_target.get_transform();
+        layer.make_current();
+        layer.set_transform(&transform);
+        {
+            let mut layer_context = RenderContext {
+                draw_target: &layer,
+                font_ctx: &mut *self.font_ctx,
+                opts: self.opts,
+                page_rect: self.page_rect,
+                screen_rect: self.screen_rect,
+            };
+            draw(&mut layer_context);
+        }
+        layer.flush();
+
+        // The layer is in pixels already, so it is composited without the transform.
+        let layer_rect = Rect(Point2D(0 as AzFloat, 0 as AzFloat),
+                              Size2D(size.width as AzFloat, size.height as AzFloat));
+        let draw_surface_options = DrawSurfaceOptions(Linear, true);
+        let draw_options = DrawOptions(opacity, 0);
+        let identity: Matrix2D<AzFloat> = Matrix2D::identity();
+        self.draw_target.make_current();
+        self.draw_target.set_transform(&identity);
+        self.draw_target.draw_surface(layer.snapshot(),
+                                      layer_rect,
+                                      layer_rect,
+                                      draw_surface_options,
+                                      draw_options);
+        self.draw_target.set_transform(&transform);
+    }
+
     pub fn draw_image(&self, bounds: Rect<Au>, image: Arc<~Image>) {
         let image = image.get();
         let size = Size2D(image.width as i32, image.height as i32);
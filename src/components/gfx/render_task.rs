@@ -7,19 +7,23 @@
 use azure::azure_hl::{B8G8R8A8, Color, DrawTarget, StolenGLResources};
 use azure::AzFloat;
 use geom::matrix2d::Matrix2D;
+use geom::point::Point2D;
 use geom::rect::Rect;
 use geom::size::Size2D;
 use layers::platform::surface::{NativePaintingGraphicsContext, NativeSurface};
 use layers::platform::surface::{NativeSurfaceMethods};
 use layers;
-use servo_msg::compositor_msg::{Epoch, IdleRenderState, LayerBuffer, LayerBufferSet};
-use servo_msg::compositor_msg::{RenderListener, RenderingRenderState};
+use servo_msg::compositor_msg::{DocumentLayer, Epoch, FixedLayer, IdleRenderState, LayerBuffer};
+use servo_msg::compositor_msg::{LayerBufferSet, LayerKind, RenderListener, RenderingRenderState};
 use servo_msg::constellation_msg::{ConstellationChan, PipelineId, RendererReadyMsg};
 use servo_msg::platform::surface::NativeSurfaceAzureMethods;
+use servo_util::geometry::Au;
 use servo_util::time::{ProfilerChan, profile};
 use servo_util::time;
 
+use png;
 use std::comm::{Chan, Port, SharedChan};
+use std::path::Path;
 use std::task::spawn_with;
 use extra::arc::Arc;
 
@@ -32,12 +36,31 @@ use render_context::RenderContext;
 pub struct RenderLayer<T> {
     display_list: Arc<DisplayList<T>>,
     size: Size2D<uint>,
+    color: Color,
+    /// The display list of the fixed positioned boxes, if there are any. They are painted into a
+    /// layer of their own, which has the size of the viewport and does not scroll.
+    fixed_display_list: Option<Arc<DisplayList<T>>>,
+    /// The size of the viewport, in pixels.
+    viewport_size: Size2D<uint>,
+}
+
+/// A document laid out for print media and split into pages.
+pub struct PrintLayer<T> {
+    display_list: Arc<DisplayList<T>>,
+    /// The size of the page box, in pixels.
+    page_size: Size2D<uint>,
+    /// The offset of the page area from the top left corner of the page box, i.e. the top and
+    /// left page margins.
+    content_origin: Point2D<Au>,
+    /// The part of the document shown in the page area of each page, in document coordinates.
+    pages: ~[Rect<Au>],
     color: Color
 }
 
 pub enum Msg<T> {
     RenderMsg(RenderLayer<T>),
-    ReRenderMsg(~[BufferRequest], f32, Epoch),
+    PrintMsg(PrintLayer<T>),
+    ReRenderMsg(~[BufferRequest], LayerKind, f32, Epoch),
     UnusedBufferMsg(~[~LayerBuffer]),
     PaintPermissionGranted,
     PaintPermissionRevoked,
@@ -186,17 +209,17 @@ impl<C: RenderListener + Send,T:Send+Freeze> RenderTask<C,T> {
         loop {
             match self.port.recv() {
                 RenderMsg(render_layer) => {
+                    self.render_layer = Some(render_layer);
                     if self.paint_permission {
-                        self.epoch.next();
-                        self.compositor.set_layer_page_size_and_color(self.id, render_layer.size, self.epoch, render_layer.color);
+                        self.send_layer_sizes();
                     } else {
                         self.constellation_chan.send(RendererReadyMsg(self.id));
                     }
-                    self.render_layer = Some(render_layer);
                 }
-                ReRenderMsg(tiles, scale, epoch) => {
+                PrintMsg(print_layer) => self.print(&print_layer),
+                ReRenderMsg(tiles, kind, scale, epoch) => {
                     if self.epoch == epoch {
-                        self.render(tiles, scale);
+                        self.render(tiles, kind, scale);
                     } else {
                         debug!("renderer epoch mismatch: {:?} != {:?}", self.epoch, epoch);
                     }
@@ -209,12 +232,8 @@ impl<C: RenderListener + Send,T:Send+Freeze> RenderTask<C,T> {
                 }
                 PaintPermissionGranted => {
                     self.paint_permission = true;
-                    match self.render_layer {
-                        Some(ref render_layer) => {
-                            self.epoch.next();
-                            self.compositor.set_layer_page_size_and_color(self.id, render_layer.size, self.epoch, render_layer.color);
-                        }
-                        None => {}
+                    if self.render_layer.is_some() {
+                        self.send_layer_sizes();
                     }
                 }
                 PaintPermissionRevoked => {
@@ -228,11 +247,104 @@ impl<C: RenderListener + Send,T:Send+Freeze> RenderTask<C,T> {
         }
     }
 
-    fn render(&mut self, tiles: ~[BufferRequest], scale: f32) {
-        let render_layer;
+    /// Renders each page of a printed document to its own PNG file, named after the output file.
+    fn print(&mut self, print_layer: &PrintLayer<T>) {
+        let output_file = match self.opts.output_file {
+            Some(ref output_file) => output_file.clone(),
+            None => {
+                warn!("render_task: no output file given, not printing");
+                return
+            }
+        };
+
+        do time::profile(time::RenderingCategory, self.profiler_chan.clone()) {
+            let width = print_layer.page_size.width;
+            let height = print_layer.page_size.height;
+            for (i, page) in print_layer.pages.iter().enumerate() {
+                // Pages are rendered into draw targets in main memory with the configured
+                // backend, even when tiles are rendered on the GPU, so that they can be read
+                // back.
+                let draw_target = DrawTarget::new(self.opts.render_backend,
+                                                  Size2D(width as i32, height as i32),
+                                                  B8G8R8A8);
+                {
+                    let mut ctx = RenderContext {
+                        draw_target: &draw_target,
+                        font_ctx: &mut self.font_ctx,
+                        opts: &self.opts,
+                        page_rect: Rect(Point2D(0f32, 0f32),
+                                        Size2D(width as f32, height as f32)),
+                        screen_rect: Rect(Point2D(0u, 0u), print_layer.page_size),
+                    };
+
+                    ctx.draw_solid_color(&Rect(Au::zero_point(),
+                                               Size2D(Au::from_px(width as int),
+                                                      Au::from_px(height as int))),
+                                         print_layer.color);
+
+                    // Move the part of the document on this page into the page area.
+                    let offset = print_layer.content_origin - page.origin;
+                    let matrix: Matrix2D<AzFloat> = Matrix2D::identity();
+                    let matrix = matrix.translate(offset.x.to_nearest_px() as AzFloat,
+                                                  offset.y.to_nearest_px() as AzFloat);
+                    ctx.draw_target.set_transform(&matrix);
+
+                    do profile(time::RenderingDrawingCategory, self.profiler_chan.clone()) {
+                        ctx.draw_push_clip(page);
+                        print_layer.display_list.get().draw_into_context(&mut ctx);
+                        ctx.draw_pop_clip();
+                        ctx.draw_target.flush();
+                    }
+                }
+
+                let file_name = page_file_name(output_file, i + 1);
+                do draw_target.snapshot().get_data_surface().with_data |data| {
+                    // Azure surfaces are BGRA.
+                    let mut pixels = data.to_owned();
+                    for j in range(0, pixels.len() / 4) {
+                        pixels.swap(j * 4, j * 4 + 2);
+                    }
+                    let img = png::Image {
+                        width: width as u32,
+                        height: height as u32,
+                        color_type: png::RGBA8,
+                        pixels: pixels,
+                    };
+                    let path = from_str::<Path>(file_name).unwrap();
+                    if png::store_png(&img, &path).is_err() {
+                        error!("render_task: failed to write page to {:s}", file_name);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Starts a new epoch and tells the compositor the sizes of the layers of the render layer,
+    /// which makes it request all of their tiles again.
+    fn send_layer_sizes(&mut self) {
+        self.epoch.next();
+        let render_layer = self.render_layer.get_ref();
+        self.compositor.set_layer_page_size_and_color(self.id,
+                                                      render_layer.size,
+                                                      self.epoch,
+                                                      render_layer.color);
+        let fixed_layer_size = render_layer.fixed_display_list.as_ref().map(|_| {
+            render_layer.viewport_size
+        });
+        self.compositor.set_fixed_layer_size(self.id, fixed_layer_size, self.epoch);
+    }
+
+    fn render(&mut self, tiles: ~[BufferRequest], kind: LayerKind, scale: f32) {
+        let display_list;
         match self.render_layer {
             Some(ref r_layer) => {
-                render_layer = r_layer;
+                display_list = match kind {
+                    DocumentLayer => &r_layer.display_list,
+                    FixedLayer => match r_layer.fixed_display_list {
+                        Some(ref fixed_display_list) => fixed_display_list,
+                        None => return, // the fixed layer was removed since the request
+                    },
+                };
             }
             _ => return, // nothing to do
         }
@@ -284,12 +396,15 @@ impl<C: RenderListener + Send,T:Send+Freeze> RenderTask<C,T> {
                         
                         ctx.draw_target.set_transform(&matrix);
                         
-                        // Clear the buffer.
-                        ctx.clear();
+                        // Clear the buffer. The fixed layer is drawn over the document, so it is
+                        // left transparent as the draw target starts out.
+                        if kind == DocumentLayer {
+                            ctx.clear();
+                        }
                         
                         // Draw the display list.
                         do profile(time::RenderingDrawingCategory, self.profiler_chan.clone()) {
-                            render_layer.display_list.get().draw_into_context(&mut ctx);
+                            display_list.get().draw_into_context(&mut ctx);
                             ctx.draw_target.flush();
                         }
                     }
@@ -370,7 +485,7 @@ impl<C: RenderListener + Send,T:Send+Freeze> RenderTask<C,T> {
 
             debug!("render_task: returning surface");
             if self.paint_permission {
-                self.compositor.paint(self.id, layer_buffer_set, self.epoch);
+                self.compositor.paint(self.id, kind, layer_buffer_set, self.epoch);
             } else {
                 self.constellation_chan.send(RendererReadyMsg(self.id));
             }
@@ -379,3 +494,23 @@ impl<C: RenderListener + Send,T:Send+Freeze> RenderTask<C,T> {
     }
 }
 
+/// The file that page `index` (counting from 1) of a printed document is written to:
+/// `out.png` becomes `out-1.png`, `out-2.png`, etc.
+fn page_file_name(output_file: &str, index: uint) -> ~str {
+    let extension_start = match (output_file.rfind('.'), output_file.rfind('/')) {
+        (Some(dot), Some(slash)) if dot < slash => None,
+        (dot, _) => dot,
+    };
+    match extension_start {
+        Some(dot) => format!("{:s}-{:u}{:s}", output_file.slice_to(dot), index,
+                             output_file.slice_from(dot)),
+        None => format!("{:s}-{:u}", output_file, index),
+    }
+}
+
+#[test]
+fn test_page_file_name() {
+    assert_eq!(page_file_name("out.png", 1), ~"out-1.png");
+    assert_eq!(page_file_name("reports/q3.report.png", 12), ~"reports/q3.report-12.png");
+    assert_eq!(page_file_name("./out", 2), ~"./out-2");
+}
@@ -19,6 +19,10 @@ pub struct TextRun {
     font_metrics: FontMetrics,
     font_style: FontStyle,
     decoration: text_decoration::T,
+    /// Extra space after each glyph, from 'letter-spacing'.
+    letter_spacing: Au,
+    /// Extra space after each whitespace glyph, from 'word-spacing'.
+    word_spacing: Au,
     glyphs: Arc<~[Arc<GlyphStore>]>,
 }
 
@@ -97,7 +101,8 @@ impl<'self> Iterator<Range> for LineIterator<'self> {
 }
 
 impl<'self> TextRun {
-    pub fn new(font: &mut Font, text: ~str, decoration: text_decoration::T) -> TextRun {
+    pub fn new(font: &mut Font, text: ~str, decoration: text_decoration::T,
+               letter_spacing: Au, word_spacing: Au) -> TextRun {
         let glyphs = TextRun::break_and_shape(font, text);
 
         let run = TextRun {
@@ -106,6 +111,8 @@ impl<'self> TextRun {
             font_metrics: font.metrics.clone(),
             font_descriptor: font.get_descriptor(),
             decoration: decoration,
+            letter_spacing: letter_spacing,
+            word_spacing: word_spacing,
             glyphs: Arc::new(glyphs),
         };
         return run;
@@ -186,22 +193,34 @@ impl<'self> TextRun {
         true
     }
 
+    /// Returns the space added after each glyph of a slice by 'letter-spacing' and
+    /// 'word-spacing'.
+    #[inline]
+    pub fn glyph_spacing(&self, glyphs: &GlyphStore) -> Au {
+        if glyphs.is_whitespace() {
+            self.letter_spacing + self.word_spacing
+        } else {
+            self.letter_spacing
+        }
+    }
+
     pub fn metrics_for_range(&self, range: &Range) -> RunMetrics {
         // TODO(Issue #199): alter advance direction for RTL
-        // TODO(Issue #98): using inter-char and inter-word spacing settings  when measuring text
         let mut advance = Au(0);
         for (glyphs, _offset, slice_range) in self.iter_slices_for_range(range) {
+            let spacing = self.glyph_spacing(glyphs);
             for (_i, glyph) in glyphs.iter_glyphs_for_char_range(&slice_range) {
-                advance = advance + glyph.advance();
+                advance = advance + glyph.advance() + spacing;
             }
         }
         RunMetrics::new(advance, self.font_metrics.ascent, self.font_metrics.descent)
     }
 
     pub fn metrics_for_slice(&self, glyphs: &GlyphStore, slice_range: &Range) -> RunMetrics {
+        let spacing = self.glyph_spacing(glyphs);
         let mut advance = Au(0);
         for (_i, glyph) in glyphs.iter_glyphs_for_char_range(slice_range) {
-            advance = advance + glyph.advance();
+            advance = advance + glyph.advance() + spacing;
         }
         RunMetrics::new(advance, self.font_metrics.ascent, self.font_metrics.descent)
     }
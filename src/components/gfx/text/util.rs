@@ -2,8 +2,11 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use std::ascii::AsciiCast;
+use style::computed_values::text_transform;
+
 #[deriving(Eq)]
-enum CompressionMode {
+pub enum CompressionMode {
     CompressNone,
     CompressWhitespace,
     CompressWhitespaceNewline,
@@ -95,6 +98,38 @@ pub fn transform_text(text: &str, mode: CompressionMode, incoming_whitespace: bo
     }
 }
 
+/// Applies the 'text-transform' property to `text`. `incoming_whitespace` tells whether the text
+/// starts a word, for 'capitalize'.
+///
+/// FIXME: Only ASCII letters change case.
+pub fn apply_text_transform(text: &str, transform: text_transform::T, incoming_whitespace: bool)
+                            -> ~str {
+    let mut out_str: ~str = ~"";
+    let mut word_start = incoming_whitespace;
+    for ch in text.iter() {
+        let upper = match transform {
+            text_transform::none => {
+                out_str.push_char(ch);
+                continue
+            }
+            text_transform::uppercase => true,
+            text_transform::lowercase => false,
+            text_transform::capitalize => word_start,
+        };
+        if !ch.is_ascii() {
+            out_str.push_char(ch)
+        } else if upper {
+            out_str.push_char(ch.to_ascii().to_upper().to_char())
+        } else if transform == text_transform::lowercase {
+            out_str.push_char(ch.to_ascii().to_lower().to_char())
+        } else {
+            out_str.push_char(ch)
+        }
+        word_start = ch == ' ' || ch == '\t' || ch == '\n';
+    }
+    out_str
+}
+
 pub fn float_to_fixed(before: int, f: f64) -> i32 {
     (1i32 << before) * (f as i32)
 }
@@ -256,3 +291,12 @@ fn test_transform_compress_whitespace_newline_no_incoming() {
         assert_eq!(&trimmed_str, &oracle_strs[i])
     }
 }
+
+#[test]
+fn test_apply_text_transform() {
+    assert_eq!(apply_text_transform("foo bAr", text_transform::uppercase, false), ~"FOO BAR");
+    assert_eq!(apply_text_transform("FOO bAr", text_transform::lowercase, false), ~"foo bar");
+    assert_eq!(apply_text_transform("foo bAr", text_transform::capitalize, true), ~"Foo BAr");
+    assert_eq!(apply_text_transform("foo bar", text_transform::capitalize, false), ~"foo Bar");
+    assert_eq!(apply_text_transform("foo bar", text_transform::none, true), ~"foo bar");
+}
@@ -16,13 +16,14 @@ use layers::platform::surface::{NativeCompositingGraphicsContext, NativeSurfaceM
 use layers::texturegl::{Texture, TextureTarget};
 #[cfg(target_os="macos")] use layers::texturegl::TextureTargetRectangle;
 use pipeline::Pipeline;
-use script::dom::event::{ClickEvent, MouseDownEvent, MouseUpEvent};
+use script::dom::event::{ClickEvent, MouseDownEvent, MouseUpEvent, MouseMoveEvent};
 use script::script_task::SendEventMsg;
-use servo_msg::compositor_msg::{LayerBuffer, LayerBufferSet, Epoch, Tile};
+use servo_msg::compositor_msg::{DocumentLayer, Epoch, FixedLayer, LayerBuffer, LayerBufferSet};
+use servo_msg::compositor_msg::{LayerKind, Tile};
 use servo_msg::constellation_msg::PipelineId;
 use std::cell::Cell;
 use windowing::{MouseWindowEvent, MouseWindowClickEvent, MouseWindowMouseDownEvent};
-use windowing::{MouseWindowMouseUpEvent};
+use windowing::{MouseWindowMouseUpEvent, MouseWindowMoveEvent};
 use azure::azure_hl::Color;
 use gfx;
 
@@ -51,6 +52,15 @@ pub struct CompositorLayer {
     /// clipping rect. Children are stored in the order in which they are drawn.
     children: ~[CompositorLayerChild],
 
+    /// The layer of the fixed positioned boxes of this layer's page, if it has any. It is drawn
+    /// above the page and its children, and its container moves it back by the scroll offset of
+    /// this layer, so that it stays in place in the window.
+    fixed_layer: Option<CompositorLayerChild>,
+
+    /// Which of the layers of its pipeline this layer is. Buffer requests and buffers are
+    /// tagged with it.
+    kind: LayerKind,
+
     /// This layer's quadtree. This is where all buffers are stored for this layer.
     quadtree: MaybeQuadtree,
 
@@ -115,6 +125,8 @@ impl CompositorLayer {
             page_size: page_size,
             scroll_offset: Point2D(0f32, 0f32),
             children: ~[],
+            fixed_layer: None,
+            kind: DocumentLayer,
             quadtree: match page_size {
                 None => NoTree(tile_size, max_mem),
                 Some(page_size) => Tree(Quadtree::new(Size2D(page_size.width as uint,
@@ -215,6 +227,7 @@ impl CompositorLayer {
                 self.root_layer.common.set_transform(identity().translate(self.scroll_offset.x,
                                                                           self.scroll_offset.y,
                                                                           0.0));
+                self.position_fixed_layer();
                 true
             }
             FixedPosition => false, // Ignore this scroll event.
@@ -246,6 +259,7 @@ impl CompositorLayer {
             MouseWindowClickEvent(button, _) => ClickEvent(button, cursor),
             MouseWindowMouseDownEvent(button, _) => MouseDownEvent(button, cursor),
             MouseWindowMouseUpEvent(button, _) => MouseUpEvent(button, cursor),
+            MouseWindowMoveEvent(_) => MouseMoveEvent(cursor),
         };
         
         self.pipeline.script_chan.send(SendEventMsg(self.pipeline.id.clone(), message));
@@ -275,7 +289,10 @@ impl CompositorLayer {
                 self.pipeline.render_chan.send(UnusedBufferMsg(unused));
             }
             if !request.is_empty() { // ask for tiles
-                self.pipeline.render_chan.try_send(ReRenderMsg(request, scale, self.epoch));
+                self.pipeline.render_chan.try_send(ReRenderMsg(request,
+                                                               self.kind,
+                                                               scale,
+                                                               self.epoch));
             }
         }
         if redisplay {
@@ -304,9 +321,17 @@ impl CompositorLayer {
                 }
             }
         };
+        // The fixed layer is not scrolled, so it shows the same part of the page as the window.
+        let fixed_redisplay = match self.fixed_layer {
+            Some(ref mut fixed) => {
+                !fixed.child.hidden &&
+                    fixed.child.get_buffer_request(graphics_context, window_rect, scale)
+            }
+            None => false,
+        };
         self.children.mut_iter().filter(|x| !x.child.hidden)
             .map(transform)
-            .fold(false, |a, b| a || b) || redisplay
+            .fold(false, |a, b| a || b) || fixed_redisplay || redisplay
     }
 
 
@@ -406,12 +431,75 @@ impl CompositorLayer {
                 self.root_layer.common.set_transform(identity().translate(self.scroll_offset.x,
                                                                           self.scroll_offset.y,
                                                                           0.0));
+                self.position_fixed_layer();
                 true
             }
             FixedPosition => false  // Ignore this scroll event.
         }
     }
 
+    // Sets the size of the fixed layer of the specified layer, creating the fixed layer if there is
+    // none yet, or removes it if no size is given. This method returns false if the specified
+    // layer is not found.
+    pub fn set_fixed_layer_size(&mut self,
+                                pipeline_id: PipelineId,
+                                new_size: Option<Size2D<f32>>,
+                                tile_size: uint,
+                                max_mem: Option<uint>,
+                                epoch: Epoch)
+                                -> bool {
+        if self.pipeline.id != pipeline_id {
+            // ID does not match ours, so recurse on descendents (including hidden children).
+            return self.children.mut_iter().map(|x| &mut x.child).any(|x| {
+                x.set_fixed_layer_size(pipeline_id, new_size, tile_size, max_mem, epoch)
+            })
+        }
+
+        match new_size {
+            None => {
+                // Send back all tiles to renderer. The layer tree drops the container of the
+                // fixed layer when it is built again.
+                for fixed in self.fixed_layer.mut_iter() {
+                    fixed.child.clear()
+                }
+                self.fixed_layer = None;
+            }
+            Some(new_size) => {
+                if self.fixed_layer.is_none() {
+                    let mut child = ~CompositorLayer::new(self.pipeline.clone(),
+                                                          None,
+                                                          tile_size,
+                                                          max_mem,
+                                                          self.cpu_painting);
+                    child.kind = FixedLayer;
+                    child.scroll_behavior = FixedPosition;
+                    let container = @mut ContainerLayer();
+                    container.add_child_start(ContainerLayerKind(child.root_layer));
+                    self.fixed_layer = Some(CompositorLayerChild {
+                        child: child,
+                        container: container,
+                    });
+                    self.position_fixed_layer();
+                }
+
+                // The fixed layer has the size of the viewport, so it never scrolls.
+                let fixed = self.fixed_layer.get_mut_ref();
+                fixed.child.resize(pipeline_id, new_size, new_size, epoch);
+            }
+        }
+        true
+    }
+
+    // Moves the fixed layer back by the scroll offset of this layer, which cancels the scrolling
+    // of the layer tree that the fixed layer is in.
+    fn position_fixed_layer(&mut self) {
+        for fixed in self.fixed_layer.iter() {
+            fixed.container.common.set_transform(identity().translate(-self.scroll_offset.x,
+                                                                      -self.scroll_offset.y,
+                                                                      0.0));
+        }
+    }
+
     // Returns whether the layer should be vertically flipped.
     #[cfg(target_os="macos")]
     fn texture_flip_and_target(cpu_painting: bool, size: Size2D<uint>) -> (Flip, TextureTarget) {
@@ -545,8 +633,9 @@ impl CompositorLayer {
             texture_layer.common.set_transform(transform);
         }
 
-        // Add child layers.
-        for child in self.children.mut_iter().filter(|x| !x.child.hidden) {
+        // Add child layers, and the fixed layer above them.
+        for child in self.children.mut_iter().chain(self.fixed_layer.mut_iter())
+                                             .filter(|x| !x.child.hidden) {
             current_layer_child = match current_layer_child {
                 None => {
                     child.container.common.parent = None;
@@ -571,10 +660,27 @@ impl CompositorLayer {
     pub fn add_buffers(&mut self,
                        graphics_context: &NativeCompositingGraphicsContext,
                        pipeline_id: PipelineId,
+                       kind: LayerKind,
                        new_buffers: ~LayerBufferSet,
                        epoch: Epoch)
                        -> Option<~LayerBufferSet> {
         let cell = Cell::new(new_buffers);
+        if self.pipeline.id == pipeline_id && self.kind != kind {
+            match self.fixed_layer {
+                Some(ref mut fixed) => {
+                    return fixed.child.add_buffers(graphics_context,
+                                                   pipeline_id,
+                                                   kind,
+                                                   cell.take(),
+                                                   epoch)
+                }
+                None => {
+                    debug!("compositor: no fixed layer for buffers, id: {:?}", self.pipeline.id);
+                    self.pipeline.render_chan.send(UnusedBufferMsg(cell.take().buffers));
+                    return None;
+                }
+            }
+        }
         if self.pipeline.id == pipeline_id {
             if self.epoch != epoch {
                 debug!("compositor epoch mismatch: {:?} != {:?}, id: {:?}",
@@ -610,6 +716,7 @@ impl CompositorLayer {
         for child_layer in self.children.mut_iter() {
             match child_layer.child.add_buffers(graphics_context,
                                                 pipeline_id,
+                                                kind,
                                                 cell.take(),
                                                 epoch) {
                 None => return None,
@@ -727,6 +834,11 @@ impl CompositorLayer {
                 self.pipeline.render_chan.send(UnusedBufferMsg(tiles))
             }
         }
+
+        // The fixed layer belongs to the same page.
+        for fixed in self.fixed_layer.mut_iter() {
+            fixed.child.clear()
+        }
     }
 
     /// Destroys all quadtree tiles of all layers, including child layers, sending the buffers
@@ -756,7 +868,7 @@ impl CompositorLayer {
             }
         }
 
-        for kid in self.children.mut_iter() {
+        for kid in self.children.mut_iter().chain(self.fixed_layer.mut_iter()) {
             kid.child.forget_all_tiles();
         }
     }
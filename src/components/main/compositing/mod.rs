@@ -13,7 +13,8 @@ use geom::rect::Rect;
 use geom::size::Size2D;
 use gfx::opts::Opts;
 use layers::platform::surface::{NativeCompositingGraphicsContext, NativeGraphicsMetadata};
-use servo_msg::compositor_msg::{Epoch, RenderListener, LayerBufferSet, RenderState, ReadyState};
+use servo_msg::compositor_msg::{Epoch, RenderListener, LayerBufferSet, LayerKind, RenderState};
+use servo_msg::compositor_msg::ReadyState;
 use servo_msg::compositor_msg::{ScriptListener, Tile};
 use servo_msg::constellation_msg::{ConstellationChan, PipelineId};
 use servo_util::time::ProfilerChan;
@@ -67,8 +68,12 @@ impl RenderListener for CompositorChan {
         port.recv()
     }
 
-    fn paint(&self, id: PipelineId, layer_buffer_set: ~LayerBufferSet, epoch: Epoch) {
-        self.chan.send(Paint(id, layer_buffer_set, epoch))
+    fn paint(&self,
+             id: PipelineId,
+             kind: LayerKind,
+             layer_buffer_set: ~LayerBufferSet,
+             epoch: Epoch) {
+        self.chan.send(Paint(id, kind, layer_buffer_set, epoch))
     }
 
     fn new_layer(&self, id: PipelineId, page_size: Size2D<uint>) {
@@ -81,6 +86,11 @@ impl RenderListener for CompositorChan {
         self.chan.send(SetLayerPageSize(id, Size2D(width as f32, height as f32), epoch))
     }
 
+    fn set_fixed_layer_size(&self, id: PipelineId, size: Option<Size2D<uint>>, epoch: Epoch) {
+        let size = size.map(|size| Size2D(size.width as f32, size.height as f32));
+        self.chan.send(SetFixedLayerSize(id, size, epoch))
+    }
+
     fn set_layer_clip_rect(&self, id: PipelineId, new_rect: Rect<uint>) {
         let new_rect = Rect(Point2D(new_rect.origin.x as f32,
                                     new_rect.origin.y as f32),
@@ -125,6 +135,9 @@ pub enum Msg {
     NewLayer(PipelineId, Size2D<f32>),
     /// Alerts the compositor that the specified layer's page has changed size.
     SetLayerPageSize(PipelineId, Size2D<f32>, Epoch),
+    /// Alerts the compositor that the fixed layer of the specified layer has changed size, or
+    /// that it has been removed.
+    SetFixedLayerSize(PipelineId, Option<Size2D<f32>>, Epoch),
     /// Alerts the compositor that the specified layer's clipping rect has changed.
     SetLayerClipRect(PipelineId, Rect<f32>),
     /// Alerts the compositor that the specified layer has been deleted.
@@ -134,7 +147,7 @@ pub enum Msg {
     /// Scroll a page in a window
     ScrollFragmentPoint(PipelineId, Point2D<f32>),
     /// Requests that the compositor paint the given layer buffer set for the given page size.
-    Paint(PipelineId, ~LayerBufferSet, Epoch),
+    Paint(PipelineId, LayerKind, ~LayerBufferSet, Epoch),
     /// Alerts the compositor to the current status of page loading.
     ChangeReadyState(ReadyState),
     /// Alerts the compositor to the current status of rendering.
@@ -9,6 +9,7 @@ use windowing::{ApplicationMethods, WindowEvent, WindowMethods};
 use windowing::{IdleWindowEvent, ResizeWindowEvent, LoadUrlWindowEvent, MouseWindowEventClass};
 use windowing::{ScrollWindowEvent, ZoomWindowEvent, NavigationWindowEvent, FinishedWindowEvent};
 use windowing::{QuitWindowEvent, MouseWindowClickEvent, MouseWindowMouseDownEvent, MouseWindowMouseUpEvent};
+use windowing::MouseWindowMoveEvent;
 
 use azure::azure_hl::SourceSurfaceMethods;
 use azure::azure_hl;
@@ -166,6 +167,20 @@ pub fn run_compositor(compositor: &CompositorTask) {
                     }
                 }
 
+                SetFixedLayerSize(id, new_size, epoch) => {
+                    match compositor_layer {
+                        Some(ref mut layer) => {
+                            assert!(layer.set_fixed_layer_size(id,
+                                                               new_size,
+                                                               compositor.opts.tile_size,
+                                                               Some(10000000u),
+                                                               epoch));
+                            ask_for_tiles();
+                        }
+                        None => {}
+                    }
+                }
+
                 SetLayerClipRect(id, new_rect) => {
                     match compositor_layer {
                         Some(ref mut layer) => {
@@ -186,7 +201,7 @@ pub fn run_compositor(compositor: &CompositorTask) {
                     }
                 }
 
-                Paint(id, new_layer_buffer_set, epoch) => {
+                Paint(id, kind, new_layer_buffer_set, epoch) => {
                     debug!("osmain: received new frame");
 
                     // From now on, if we destroy the buffers, they will leak.
@@ -197,6 +212,7 @@ pub fn run_compositor(compositor: &CompositorTask) {
                         Some(ref mut layer) => {
                             assert!(layer.add_buffers(&graphics_context,
                                                       id,
+                                                      kind,
                                                       new_layer_buffer_set,
                                                       epoch).is_none());
                             recomposite = true;
@@ -267,6 +283,7 @@ pub fn run_compositor(compositor: &CompositorTask) {
                     MouseWindowClickEvent(_, p) => Point2D(p.x / world_zoom, p.y / world_zoom),
                     MouseWindowMouseDownEvent(_, p) => Point2D(p.x / world_zoom, p.y / world_zoom),
                     MouseWindowMouseUpEvent(_, p) => Point2D(p.x / world_zoom, p.y / world_zoom),
+                    MouseWindowMoveEvent(p) => Point2D(p.x / world_zoom, p.y / world_zoom),
                 };
                 for layer in compositor_layer.iter() {
                     layer.send_mouse_event(mouse_window_event, point);
@@ -31,8 +31,9 @@ pub fn run_compositor(compositor: &CompositorTask) {
             // we'll notice and think about whether it needs a response, like
             // SetIds.
 
-            NewLayer(*) | SetLayerPageSize(*) | SetLayerClipRect(*) | DeleteLayer(*) |
-            Paint(*) | InvalidateRect(*) | ChangeReadyState(*) | ChangeRenderState(*)|
+            NewLayer(*) | SetLayerPageSize(*) | SetFixedLayerSize(*) | SetLayerClipRect(*) |
+            DeleteLayer(*) | Paint(*) | InvalidateRect(*) | ChangeReadyState(*) |
+            ChangeRenderState(*)|
             ScrollFragmentPoint(*) | SetUnRenderedColor(*)
                 => ()
         }
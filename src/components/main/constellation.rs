@@ -18,6 +18,7 @@ use servo_msg::constellation_msg;
 use servo_net::image_cache_task::{ImageCacheTask, ImageCacheTaskClient};
 use servo_net::resource_task::ResourceTask;
 use servo_net::resource_task;
+use servo_util::history::{SharedHistoryStore, new_history_store};
 use servo_util::time::ProfilerChan;
 use servo_util::url::make_url;
 use std::comm::Port;
@@ -40,6 +41,8 @@ pub struct Constellation {
     pending_sizes: HashMap<(PipelineId, SubpageId), Rect<f32>>,
     profiler_chan: ProfilerChan,
     window_size: Size2D<uint>,
+    /// The URLs of the pages loaded so far, which links are matched against for `:visited`.
+    history: SharedHistoryStore,
     opts: Opts,
 }
 
@@ -282,6 +285,7 @@ impl Constellation {
                 pending_sizes: HashMap::new(),
                 profiler_chan: profiler_chan,
                 window_size: Size2D(500u, 500u),
+                history: new_history_store(opts.history_file.clone()),
                 opts: opts
             };
             constellation.run();
@@ -388,6 +392,7 @@ impl Constellation {
                                              self.image_cache_task.clone(),
                                              self.resource_task.clone(),
                                              self.profiler_chan.clone(),
+                                             self.history.clone(),
                                              self.opts.clone());
         let failure = ~"about:failure";
         let url = make_url(failure, None);
@@ -409,7 +414,9 @@ impl Constellation {
                                              self.image_cache_task.clone(),
                                              self.resource_task.clone(),
                                              self.profiler_chan.clone(),
+                                             self.history.clone(),
                                              self.opts.clone());
+        self.add_visit(&url);
         pipeline.load(url);
 
         self.pending_frames.push(FrameChange {
@@ -533,6 +540,7 @@ impl Constellation {
                                   self.compositor_chan.clone(),
                                   self.image_cache_task.clone(),
                                   self.profiler_chan.clone(),
+                                  self.history.clone(),
                                   self.opts.clone(),
                                   source_pipeline)
         } else {
@@ -545,10 +553,12 @@ impl Constellation {
                              self.image_cache_task.clone(),
                              self.resource_task.clone(),
                              self.profiler_chan.clone(),
+                             self.history.clone(),
                              self.opts.clone())
         };
 
         debug!("Constellation: sending load msg to pipeline {:?}", pipeline.id);
+        self.add_visit(&url);
         pipeline.load(url);
         let rect = self.pending_sizes.pop(&(source_pipeline_id, subpage_id));
         for frame_tree in frame_trees.iter() {
@@ -598,8 +608,10 @@ impl Constellation {
                                              self.image_cache_task.clone(),
                                              self.resource_task.clone(),
                                              self.profiler_chan.clone(),
+                                             self.history.clone(),
                                              self.opts.clone());
 
+        self.add_visit(&url);
         pipeline.load(url);
 
         self.pending_frames.push(FrameChange{
@@ -650,12 +662,22 @@ impl Constellation {
 
         for frame in destination_frame.iter() {
             let pipeline = &frame.pipeline;
+            for url in pipeline.url.iter() {
+                self.add_visit(url);
+            }
             pipeline.reload();
         }
         self.grant_paint_permission(destination_frame, constellation_msg::Navigate);
 
     }
     
+    /// Records a visit to `url` in the history, so that links to it match `:visited`.
+    fn add_visit(&self, url: &Url) {
+        do self.history.write |history| {
+            history.add_visit(url)
+        }
+    }
+
     fn handle_renderer_ready_msg(&mut self, pipeline_id: PipelineId) {
         debug!("Renderer {:?} ready to send paint msg", pipeline_id);
         // This message could originate from a pipeline in the navigation context or
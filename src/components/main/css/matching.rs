@@ -4,123 +4,621 @@
 
 // High-level interface to CSS selector matching.
 
-use std::cell::Cell;
 use std::comm;
+use std::ptr;
 use std::task;
-use std::vec;
-use extra::arc::{Arc, RWArc};
+use extra::arc::{Arc, MutexArc, RWArc};
+use extra::container::Deque;
+use extra::ringbuf::RingBuf;
+use extra::time::precise_time_ns;
+use extra::url::Url;
 
 use css::node_style::StyledNode;
+use layout::incremental::RestyleDamage;
 use layout::incremental;
 use layout::util::LayoutDataAccess;
 
 use script::dom::node::{AbstractNode, LayoutView};
-use style::Stylist;
+use style::{AncestorFilter, ComputedValues, Device, Stylist};
 use style::cascade;
-use servo_util::tree::TreeNodeRef;
+use style::{PropertyDeclaration, PseudoElement, Before, After, FirstLine, FirstLetter};
+use servo_util::atom::Atom;
+use servo_util::history::{HistoryStore, SharedHistoryStore};
+use servo_util::time::{AncestorFilterMsg, ProfilerChan, ProfilerMsg, StyleSharingMsg};
+use servo_util::tree::{TreeNodeRef, ElementLike};
+use servo_util::url::make_url;
+
+/// The pseudo-elements that are styled separately from their element. ::first-letter comes after
+/// ::first-line, which it inherits from.
+static PSEUDO_ELEMENTS: [PseudoElement, ..4] = [Before, After, FirstLine, FirstLetter];
+
+/// How many subtrees per task the top of the tree is split into for parallel styling. More
+/// subtrees balance the work better, but the top of the tree is styled sequentially.
+static SUBTREES_PER_TASK: uint = 4;
+
+/// How many recently styled elements the style sharing cache keeps.
+static STYLE_SHARING_CACHE_SIZE: uint = 40;
+
+/// Recently styled elements whose style can be reused by similar elements without cascading
+/// again, e.g. for the items of a list or the cells of a table.
+///
+/// An element can share the style of another if they have the same parent style and the same
+/// applicable declarations, which is only checked after a cheap comparison of their tag name
+/// and classes. Elements with an id or a style attribute, or inside a visited link, are never
+/// shared since they are unlikely to match the same declarations as another element.
+pub struct StyleSharingCache {
+    /// The candidates, most recently used first.
+    priv candidates: ~[StyleSharingCandidate],
+    /// The number of times a style was reused.
+    hits: uint,
+    /// The number of elements that looked for a style to reuse.
+    lookups: uint,
+    /// The number of styles that were cascaded.
+    cascades: uint,
+    /// The time spent cascading, in nanoseconds.
+    cascade_time: u64,
+}
+
+/// What elements are compared on before their declarations when looking for a style to share.
+#[deriving(Eq)]
+struct StyleSharingKey {
+    local_name: Atom,
+    classes: ~[Atom],
+}
+
+impl StyleSharingKey {
+    /// Returns the key of an element, or `None` if its style is never shared.
+    fn new(local_name: Atom, id: Option<Atom>, classes: &[Atom], has_style_attribute: bool)
+           -> Option<StyleSharingKey> {
+        if id.is_some() || has_style_attribute {
+            return None
+        }
+        Some(StyleSharingKey {
+            local_name: local_name,
+            classes: classes.to_owned(),
+        })
+    }
+}
+
+struct StyleSharingCandidate {
+    key: StyleSharingKey,
+    parent_style: Arc<ComputedValues>,
+    applicable_declarations: ~[Arc<~[PropertyDeclaration]>],
+    style: Arc<ComputedValues>,
+}
+
+impl StyleSharingCache {
+    pub fn new() -> StyleSharingCache {
+        StyleSharingCache {
+            candidates: ~[],
+            hits: 0,
+            lookups: 0,
+            cascades: 0,
+            cascade_time: 0,
+        }
+    }
+
+    /// Cascades a style that could not be shared, timing it to estimate the time that hits save.
+    fn cascade(&mut self,
+               applicable_declarations: &[Arc<~[PropertyDeclaration]>],
+               parent_style: Option<&Arc<ComputedValues>>,
+               device: &Device)
+               -> Arc<ComputedValues> {
+        let start_time = precise_time_ns();
+        let style = Arc::new(cascade(applicable_declarations,
+                                     parent_style.map(|parent_style| parent_style.get()),
+                                     device));
+        self.cascades += 1;
+        self.cascade_time += precise_time_ns() - start_time;
+        style
+    }
+
+    /// Returns the message that reports the hits of the cache to the profiler.
+    fn profiler_msg(&self) -> ProfilerMsg {
+        StyleSharingMsg(self.hits, self.lookups, self.cascades, ns_to_ms(self.cascade_time))
+    }
+
+    /// Returns the style of `element` given its applicable declarations and its parent style,
+    /// reusing the style of a candidate or cascading if there is none.
+    fn style_for(&mut self,
+                 element: AbstractNode<LayoutView>,
+                 applicable_declarations: &[Arc<~[PropertyDeclaration]>],
+                 parent_style: Option<&Arc<ComputedValues>>,
+                 device: &Device)
+                 -> Arc<ComputedValues> {
+        let parent_style = match parent_style {
+            Some(parent_style) => parent_style,
+            None => return self.cascade(applicable_declarations, None, device),
+        };
+        if !element.is_element() {
+            return self.cascade(applicable_declarations, Some(parent_style), device)
+        }
+        let key = do element.with_imm_element |element| {
+            StyleSharingKey::new(element.get_local_name(),
+                                 element.get_id(),
+                                 element.get_classes(),
+                                 element.style_attribute.is_some())
+        };
+        match key {
+            Some(key) => self.share_or_cascade(key, applicable_declarations, parent_style, device),
+            None => self.cascade(applicable_declarations, Some(parent_style), device),
+        }
+    }
+
+    /// Returns the style of a candidate with the same key, parent style and applicable
+    /// declarations, or cascades and adds a candidate if there is none.
+    fn share_or_cascade(&mut self,
+                        key: StyleSharingKey,
+                        applicable_declarations: &[Arc<~[PropertyDeclaration]>],
+                        parent_style: &Arc<ComputedValues>,
+                        device: &Device)
+                        -> Arc<ComputedValues> {
+        self.lookups += 1;
+        let position = do self.candidates.iter().position |candidate| {
+            candidate.key == key && same_arc(&candidate.parent_style, parent_style) &&
+                candidate.applicable_declarations.len() == applicable_declarations.len() &&
+                candidate.applicable_declarations.iter().zip(applicable_declarations.iter())
+                                                        .all(|(a, b)| same_arc(a, b))
+        };
+        match position {
+            Some(position) => {
+                self.hits += 1;
+                let candidate = self.candidates.remove(position);
+                let style = candidate.style.clone();
+                self.candidates.unshift(candidate);
+                style
+            }
+            None => {
+                let style = self.cascade(applicable_declarations, Some(parent_style), device);
+                self.candidates.unshift(StyleSharingCandidate {
+                    key: key,
+                    parent_style: parent_style.clone(),
+                    applicable_declarations: applicable_declarations.to_owned(),
+                    style: style.clone(),
+                });
+                self.candidates.truncate(STYLE_SHARING_CACHE_SIZE);
+                style
+            }
+        }
+    }
+}
+
+/// Returns the message that reports the rejections of `ancestor_filter` to the profiler.
+fn ancestor_filter_msg(ancestor_filter: &AncestorFilter) -> ProfilerMsg {
+    AncestorFilterMsg(ancestor_filter.rejected, ancestor_filter.lookups, ancestor_filter.matched,
+                      ns_to_ms(ancestor_filter.matching_time))
+}
+
+#[inline]
+fn ns_to_ms(ns: u64) -> f64 {
+    (ns as f64) / 1000000f64
+}
+
+/// Returns true if `a` and `b` point to the same value, which is cheaper than comparing them.
+#[inline]
+fn same_arc<T: Freeze + Send>(a: &Arc<T>, b: &Arc<T>) -> bool {
+    ptr::to_unsafe_ptr(a.get()) == ptr::to_unsafe_ptr(b.get())
+}
+
+/// Returns the node that `node` inherits its style from: its parent, unless that is the document,
+/// which has no style (or layout data) of its own.
+#[inline]
+fn style_parent(node: &AbstractNode<LayoutView>) -> Option<AbstractNode<LayoutView>> {
+    match node.parent_node() {
+        Some(parent) if parent.is_element() => Some(parent),
+        _ => None,
+    }
+}
 
 pub trait MatchMethods {
-    fn match_node(&self, stylist: &Stylist);
-    fn match_subtree(&self, stylist: RWArc<Stylist>);
+    /// Matches this element against the rules of `stylist`. `ancestor_filter` must contain the
+    /// ancestors of this element.
+    fn match_node(&self,
+                  stylist: &Stylist,
+                  history: &HistoryStore,
+                  base_url: &Url,
+                  ancestor_filter: &mut AncestorFilter);
+
+    /// Returns the nearest link among this element and its ancestors if it points to a visited
+    /// URL. Relative links are resolved against `base_url`.
+    fn visited_link(&self, history: &HistoryStore, base_url: &Url)
+                    -> Option<AbstractNode<LayoutView>>;
+
+    /// Cascades this node. Viewport-percentage lengths are relative to the viewport of
+    /// `device`.
+    fn cascade_node(&self,
+                    parent: Option<AbstractNode<LayoutView>>,
+                    device: &Device,
+                    style_sharing_cache: &mut StyleSharingCache);
+
+    /// Matches and cascades this node and its descendants in this task. `parent` must have been
+    /// styled already, and `ancestor_filter` must contain the ancestors of this node.
+    fn match_and_cascade_subtree(&self,
+                                 parent: Option<AbstractNode<LayoutView>>,
+                                 stylist: &Stylist,
+                                 history: &HistoryStore,
+                                 base_url: &Url,
+                                 ancestor_filter: &mut AncestorFilter,
+                                 style_sharing_cache: &mut StyleSharingCache);
 
-    fn cascade_node(&self, parent: Option<AbstractNode<LayoutView>>);
-    fn cascade_subtree(&self, parent: Option<AbstractNode<LayoutView>>);
+    /// Matches and cascades this node and its descendants with `num_tasks` tasks. The results are
+    /// the same as those of `match_and_cascade_subtree`. The hit rates of the style sharing caches
+    /// and of the ancestor filters are reported to the profiler.
+    fn match_and_cascade_subtree_in_parallel(&self,
+                                             stylist: RWArc<Stylist>,
+                                             history: SharedHistoryStore,
+                                             base_url: &Url,
+                                             num_tasks: uint,
+                                             profiler_chan: ProfilerChan);
 }
 
 impl MatchMethods for AbstractNode<LayoutView> {
-    fn match_node(&self, stylist: &Stylist) {
-        let applicable_declarations = do self.with_imm_element |element| {
+    fn match_node(&self,
+                  stylist: &Stylist,
+                  history: &HistoryStore,
+                  base_url: &Url,
+                  ancestor_filter: &mut AncestorFilter) {
+        let visited_link = self.visited_link(history, base_url);
+        let (applicable_declarations, visited_applicable_declarations) =
+                do self.with_imm_element |element| {
             let style_attribute = match element.style_attribute {
                 None => None,
                 Some(ref style_attribute) => Some(style_attribute)
             };
-            stylist.get_applicable_declarations(self, style_attribute, None)
+            (stylist.get_applicable_declarations(self, style_attribute, None, ancestor_filter),
+             visited_link.as_ref().map(|link| {
+                 stylist.get_visited_declarations(self, style_attribute, None, link,
+                                                  ancestor_filter)
+             }))
         };
+        let pseudo_element_applicable_declarations: ~[~[Arc<~[PropertyDeclaration]>]] =
+            PSEUDO_ELEMENTS.iter().map(|pseudo_element| {
+                stylist.get_applicable_declarations(self, None, Some(*pseudo_element),
+                                                    ancestor_filter)
+            }).collect();
 
         match *self.mutate_layout_data().ptr {
             Some(ref mut layout_data) => {
-                layout_data.applicable_declarations = applicable_declarations
+                layout_data.applicable_declarations = applicable_declarations;
+                layout_data.visited_applicable_declarations = visited_applicable_declarations;
+                for (pseudo_element, declarations) in PSEUDO_ELEMENTS.iter().zip(
+                        pseudo_element_applicable_declarations.move_iter()) {
+                    layout_data.pseudo_element(*pseudo_element).applicable_declarations =
+                        declarations
+                }
             }
             None => fail!("no layout data")
         }
     }
-    fn match_subtree(&self, stylist: RWArc<Stylist>) {
-        // FIXME(pcwalton): Racy. Parallel CSS selector matching is disabled.
-        //let num_tasks = rt::default_sched_threads() * 2;
-        let num_tasks = 1;
-        let mut node_count = 0;
-        let mut nodes_per_task = vec::from_elem(num_tasks, ~[]);
 
-        for node in self.traverse_preorder() {
+    fn visited_link(&self, history: &HistoryStore, base_url: &Url)
+                    -> Option<AbstractNode<LayoutView>> {
+        let mut node = *self;
+        loop {
             if node.is_element() {
-                nodes_per_task[node_count % num_tasks].push(node);
-                node_count += 1;
-            }
-        }
-
-        let (port, chan) = comm::stream();
-        let chan = comm::SharedChan::new(chan);
-        let mut num_spawned = 0;
-
-        for nodes in nodes_per_task.move_iter() {
-            if nodes.len() > 0 {
-                let chan = chan.clone();
-                let stylist = stylist.clone();
-                do task::spawn_with((nodes, stylist)) |(nodes, stylist)| {
-                    let nodes = Cell::new(nodes);
-                    do stylist.read |stylist| {
-                        for node in nodes.take().move_iter() {
-                            node.match_node(stylist);
-                        }
+                let link = do node.with_imm_element |element| {
+                    element.get_link()
+                };
+                match link {
+                    Some(href) => {
+                        let url = make_url(href, Some(base_url.clone()));
+                        return if history.is_visited(&url) { Some(node) } else { None }
                     }
-                    chan.send(());
+                    None => {}
                 }
-                num_spawned += 1;
             }
-        }
-        for _ in range(0, num_spawned) {
-            port.recv();
+            match node.parent_node() {
+                Some(parent) => node = parent,
+                None => return None,
+            }
         }
     }
 
-    fn cascade_node(&self, parent: Option<AbstractNode<LayoutView>>) {
-        let parent_style = match parent {
-            Some(ref parent) => Some(parent.style()),
-            None => None
+    fn cascade_node(&self,
+                    parent: Option<AbstractNode<LayoutView>>,
+                    device: &Device,
+                    style_sharing_cache: &mut StyleSharingCache) {
+        let (parent_style, parent_unvisited_style) = match parent {
+            Some(ref parent) => (Some(parent.style()), Some(parent.unvisited_style())),
+            None => (None, None)
         };
 
-        let computed_values = unsafe {
-            Arc::new(cascade(self.borrow_layout_data_unchecked()
-                                 .as_ref()
-                                 .unwrap()
-                                 .applicable_declarations,
-                             parent_style.map(|parent_style| parent_style.get())))
+        // Inside a visited link, the style used for rendering takes its colours from the style
+        // computed as if the link were visited. Everything else, and everything that script can
+        // observe, comes from the style computed as if all links were unvisited.
+        let (computed_values, unvisited_values) = unsafe {
+            let layout_data = self.borrow_layout_data_unchecked().as_ref().unwrap();
+            match layout_data.visited_applicable_declarations {
+                None => {
+                    (style_sharing_cache.style_for(*self,
+                                                   layout_data.applicable_declarations,
+                                                   parent_unvisited_style,
+                                                   device),
+                     None)
+                }
+                Some(ref visited_applicable_declarations) => {
+                    let unvisited_values = Arc::new(cascade(
+                        layout_data.applicable_declarations,
+                        parent_unvisited_style.map(|parent_style| parent_style.get()),
+                        device));
+                    let visited_values = cascade(
+                        visited_applicable_declarations.as_slice(),
+                        parent_style.map(|parent_style| parent_style.get()),
+                        device);
+                    (Arc::new(unvisited_values.get().with_visited_colors(&visited_values)),
+                     Some(unvisited_values))
+                }
+            }
         };
 
         match *self.mutate_layout_data().ptr {
             None => fail!("no layout data"),
             Some(ref mut layout_data) => {
-                let style = &mut layout_data.style;
-                match *style {
-                    None => (),
+                let mut damage = match layout_data.style {
+                    None => None,
                     Some(ref previous_style) => {
-                        layout_data.restyle_damage =
-                            Some(incremental::compute_damage(previous_style.get(),
-                                                             computed_values.get()).to_int())
+                        Some(incremental::compute_damage(previous_style.get(),
+                                                         computed_values.get()))
                     }
+                };
+
+                // Pseudo-elements inherit from this element, except for ::first-letter which
+                // inherits from ::first-line if there is one. A change in their style damages
+                // this element.
+                //
+                // FIXME: Pseudo-elements are always matched as if links were unvisited, but they
+                // inherit the colours of a visited link.
+                for pseudo_element in PSEUDO_ELEMENTS.iter() {
+                    let parent_style = match (*pseudo_element, &layout_data.first_line.style) {
+                        (FirstLetter, &Some(ref first_line_style)) => first_line_style.clone(),
+                        _ => computed_values.clone(),
+                    };
+                    let pseudo_element_data = layout_data.pseudo_element(*pseudo_element);
+                    let pseudo_style = if pseudo_element_data.applicable_declarations.is_empty() {
+                        None
+                    } else {
+                        Some(Arc::new(cascade(pseudo_element_data.applicable_declarations,
+                                              Some(parent_style.get()),
+                                              device)))
+                    };
+                    damage = match (damage, &pseudo_element_data.style, &pseudo_style) {
+                        (None, _, _) => None,
+                        (Some(damage), &Some(ref previous_style), &Some(ref new_style)) => {
+                            Some(damage.union(incremental::compute_damage(previous_style.get(),
+                                                                          new_style.get())))
+                        }
+                        (Some(damage), &None, &None) => Some(damage),
+                        (Some(_), _, _) => Some(RestyleDamage::all()),
+                    };
+                    pseudo_element_data.style = pseudo_style
                 }
-                *style = Some(computed_values)
+
+                layout_data.restyle_damage = damage.map(|damage| damage.to_int());
+                layout_data.style = Some(computed_values);
+                layout_data.unvisited_style = unvisited_values
             }
         }
     }
 
-    fn cascade_subtree(&self, parent: Option<AbstractNode<LayoutView>>) {
-        self.cascade_node(parent);
+    fn match_and_cascade_subtree(&self,
+                                 parent: Option<AbstractNode<LayoutView>>,
+                                 stylist: &Stylist,
+                                 history: &HistoryStore,
+                                 base_url: &Url,
+                                 ancestor_filter: &mut AncestorFilter,
+                                 style_sharing_cache: &mut StyleSharingCache) {
+        if self.is_element() {
+            self.match_node(stylist, history, base_url, ancestor_filter);
+        }
+        self.cascade_node(parent, stylist.device(), style_sharing_cache);
 
+        if self.is_element() {
+            ancestor_filter.push(self);
+        }
         for kid in self.children() {
             if kid.is_element() {
-                kid.cascade_subtree(Some(*self));
+                kid.match_and_cascade_subtree(Some(*self), stylist, history, base_url,
+                                              ancestor_filter, style_sharing_cache);
             }
         }
+        if self.is_element() {
+            ancestor_filter.pop(self);
+        }
+    }
+
+    fn match_and_cascade_subtree_in_parallel(&self,
+                                             stylist: RWArc<Stylist>,
+                                             history: SharedHistoryStore,
+                                             base_url: &Url,
+                                             num_tasks: uint,
+                                             profiler_chan: ProfilerChan) {
+        let mut style_sharing_cache = StyleSharingCache::new();
+        if num_tasks <= 1 {
+            let mut ancestor_filter = AncestorFilter::for_ancestors_of(self);
+            do stylist.read |stylist| {
+                do history.read |history| {
+                    self.match_and_cascade_subtree(style_parent(self), stylist, &**history,
+                                                   base_url, &mut ancestor_filter,
+                                                   &mut style_sharing_cache)
+                }
+            }
+            profiler_chan.send_deferred(style_sharing_cache.profiler_msg());
+            profiler_chan.send_deferred(ancestor_filter_msg(&ancestor_filter));
+            return
+        }
+
+        // Style the top of the tree in this task, breadth first, until it is split into enough
+        // subtrees to keep all the tasks busy. Each of these subtrees is then styled by a single
+        // task. A task only writes the layout data of the nodes in its own subtrees, and only
+        // reads the styles of their ancestors, which are final by then, so tasks never race.
+        // Breadth-first order does not keep the ancestor filter up to date as it goes, so each node
+        // at the top of the tree gets a filter of its own.
+        let mut subtrees = RingBuf::new();
+        subtrees.push_back(*self);
+        do stylist.read |stylist| {
+            do history.read |history| {
+                while subtrees.len() > 0 && subtrees.len() < num_tasks * SUBTREES_PER_TASK {
+                    let node = subtrees.pop_front().unwrap();
+                    if node.is_element() {
+                        let mut ancestor_filter = AncestorFilter::for_ancestors_of(&node);
+                        node.match_node(stylist, &**history, base_url, &mut ancestor_filter);
+                        profiler_chan.send_deferred(ancestor_filter_msg(&ancestor_filter));
+                    }
+                    node.cascade_node(style_parent(&node), stylist.device(),
+                                      &mut style_sharing_cache);
+                    for kid in node.children() {
+                        if kid.is_element() {
+                            subtrees.push_back(kid);
+                        }
+                    }
+                }
+            }
+        }
+        profiler_chan.send_deferred(style_sharing_cache.profiler_msg());
+        if subtrees.len() == 0 {
+            return
+        }
+
+        // Subtrees vary a lot in size, so rather than being assigned a fixed share, each task
+        // takes the next subtree from a shared queue whenever it is done with one.
+        let subtrees: ~[AbstractNode<LayoutView>] = subtrees.iter().map(|node| *node).collect();
+        let queue = MutexArc::new(subtrees);
+        let (port, chan) = comm::stream();
+        let chan = comm::SharedChan::new(chan);
+        for _ in range(0, num_tasks) {
+            let chan = chan.clone();
+            let queue = queue.clone();
+            let stylist = stylist.clone();
+            let history = history.clone();
+            let base_url = base_url.clone();
+            let profiler_chan = profiler_chan.clone();
+            do task::spawn_with((queue, stylist, history, base_url, profiler_chan))
+                    |(queue, stylist, history, base_url, profiler_chan)| {
+                let mut style_sharing_cache = StyleSharingCache::new();
+                do stylist.read |stylist| {
+                    do history.read |history| {
+                        loop {
+                            match queue.access(|subtrees| subtrees.pop_opt()) {
+                                Some(node) => {
+                                    let mut ancestor_filter =
+                                        AncestorFilter::for_ancestors_of(&node);
+                                    node.match_and_cascade_subtree(style_parent(&node), stylist,
+                                                                   &**history, &base_url,
+                                                                   &mut ancestor_filter,
+                                                                   &mut style_sharing_cache);
+                                    profiler_chan.send_deferred(
+                                        ancestor_filter_msg(&ancestor_filter));
+                                }
+                                None => break,
+                            }
+                        }
+                    }
+                }
+                profiler_chan.send_deferred(style_sharing_cache.profiler_msg());
+                chan.send(());
+            }
+        }
+        for _ in range(0, num_tasks) {
+            port.recv();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{STYLE_SHARING_CACHE_SIZE, StyleSharingCache, StyleSharingKey, same_arc};
+
+    use extra::arc::Arc;
+    use servo_util::atom::Atom;
+    use servo_util::geometry::Au;
+    use style::{ComputedValues, Device, PropertyDeclaration, Screen, cascade};
+
+    fn key(classes: &[&str]) -> StyleSharingKey {
+        let classes: ~[Atom] = classes.iter().map(|class| Atom::from_slice(*class)).collect();
+        StyleSharingKey::new(Atom::from_slice("li"), None, classes.as_slice(), false).unwrap()
+    }
+
+    fn device() -> Device {
+        Device::new(Screen, Au::from_px(800), Au::from_px(600), 1.0)
+    }
+
+    fn root_style(device: &Device) -> Arc<ComputedValues> {
+        Arc::new(cascade([], None, device))
+    }
+
+    #[test]
+    fn test_share_with_same_parent_style_and_declarations() {
+        let device = device();
+        let parent_style = root_style(&device);
+        let declarations: ~[Arc<~[PropertyDeclaration]>] = ~[Arc::new(~[])];
+        let mut cache = StyleSharingCache::new();
+        let first = cache.share_or_cascade(key(["item"]), declarations, &parent_style, &device);
+        let second = cache.share_or_cascade(key(["item"]), declarations, &parent_style, &device);
+        assert!(same_arc(&first, &second));
+        assert_eq!(cache.hits, 1);
+        assert_eq!(cache.lookups, 2);
+
+        // Declarations or parent styles that are equal but not the same are not compared.
+        let other_declarations: ~[Arc<~[PropertyDeclaration]>] = ~[Arc::new(~[])];
+        let third = cache.share_or_cascade(key(["item"]), other_declarations, &parent_style,
+                                           &device);
+        assert!(!same_arc(&first, &third));
+        let other_parent_style = root_style(&device);
+        let fourth = cache.share_or_cascade(key(["item"]), declarations, &other_parent_style,
+                                            &device);
+        assert!(!same_arc(&first, &fourth));
+        assert_eq!(cache.hits, 1);
+    }
+
+    #[test]
+    fn test_no_sharing_with_other_classes() {
+        let device = device();
+        let parent_style = root_style(&device);
+        let declarations: ~[Arc<~[PropertyDeclaration]>] = ~[Arc::new(~[])];
+        let mut cache = StyleSharingCache::new();
+        let first = cache.share_or_cascade(key(["item"]), declarations, &parent_style, &device);
+        let second = cache.share_or_cascade(key(["item", "selected"]), declarations,
+                                            &parent_style, &device);
+        let third = cache.share_or_cascade(key([]), declarations, &parent_style, &device);
+        assert!(!same_arc(&first, &second));
+        assert!(!same_arc(&first, &third));
+        assert!(!same_arc(&second, &third));
+        assert_eq!(cache.hits, 0);
+        assert_eq!(cache.lookups, 3);
+    }
+
+    #[test]
+    fn test_no_sharing_with_id_or_style_attribute() {
+        let li = Atom::from_slice("li");
+        assert!(StyleSharingKey::new(li.clone(), Some(Atom::from_slice("first")), [], false)
+                    .is_none());
+        assert!(StyleSharingKey::new(li.clone(), None, [], true).is_none());
+        assert!(StyleSharingKey::new(li, None, [], false).is_some());
+    }
+
+    #[test]
+    fn test_least_recently_used_candidates_are_dropped() {
+        let device = device();
+        let parent_style = root_style(&device);
+        let declarations: ~[Arc<~[PropertyDeclaration]>] = ~[Arc::new(~[])];
+        let class_key = |i: uint| key([i.to_str().as_slice()]);
+        let mut cache = StyleSharingCache::new();
+        for i in range(0, STYLE_SHARING_CACHE_SIZE) {
+            cache.share_or_cascade(class_key(i), declarations, &parent_style, &device);
+        }
+        assert_eq!(cache.candidates.len(), STYLE_SHARING_CACHE_SIZE);
+
+        // Using the oldest candidate makes it the most recent, so the next one is dropped instead
+        // when a new candidate is added.
+        cache.share_or_cascade(class_key(0), declarations, &parent_style, &device);
+        cache.share_or_cascade(class_key(STYLE_SHARING_CACHE_SIZE), declarations, &parent_style,
+                               &device);
+        assert_eq!(cache.candidates.len(), STYLE_SHARING_CACHE_SIZE);
+        assert_eq!(cache.hits, 1);
+        cache.share_or_cascade(class_key(0), declarations, &parent_style, &device);
+        assert_eq!(cache.hits, 2);
+        cache.share_or_cascade(class_key(1), declarations, &parent_style, &device);
+        assert_eq!(cache.hits, 2);
+        assert_eq!(cache.candidates.len(), STYLE_SHARING_CACHE_SIZE);
     }
 }
@@ -14,6 +14,7 @@ use script::dom::node::{AbstractNode, LayoutView};
 /// Node mixin providing `style` method that returns a `NodeStyle`
 pub trait StyledNode {
     fn style<'a>(&'a self) -> &'a Arc<ComputedValues>;
+    fn unvisited_style<'a>(&'a self) -> &'a Arc<ComputedValues>;
     fn restyle_damage(&self) -> RestyleDamage;
 }
 
@@ -23,6 +24,11 @@ impl StyledNode for AbstractNode<LayoutView> {
         self.get_css_select_results()
     }
 
+    #[inline]
+    fn unvisited_style<'a>(&'a self) -> &'a Arc<ComputedValues> {
+        self.get_unvisited_css_select_results()
+    }
+
     fn restyle_damage(&self) -> RestyleDamage {
         self.get_restyle_damage()
     }
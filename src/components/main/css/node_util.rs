@@ -13,6 +13,7 @@ use servo_util::tree::TreeNodeRef;
 
 pub trait NodeUtil {
     fn get_css_select_results<'a>(&'a self) -> &'a Arc<ComputedValues>;
+    fn get_unvisited_css_select_results<'a>(&'a self) -> &'a Arc<ComputedValues>;
     fn have_css_select_results(self) -> bool;
 
     fn get_restyle_damage(self) -> RestyleDamage;
@@ -37,6 +38,20 @@ impl NodeUtil for AbstractNode<LayoutView> {
         }
     }
 
+    /// Returns the style results for the given node as if all links were unvisited. This is the
+    /// style that script may observe. If CSS selector matching has not yet been performed, fails.
+    #[inline]
+    fn get_unvisited_css_select_results<'a>(&'a self) -> &'a Arc<ComputedValues> {
+        unsafe {
+            let layout_data = self.borrow_layout_data_unchecked().as_ref().unwrap();
+            let style = match layout_data.unvisited_style {
+                Some(ref style) => style,
+                None => layout_data.style.as_ref().unwrap(),
+            };
+            cast::transmute_region(style)
+        }
+    }
+
     /// Does this node have a computed style yet?
     fn have_css_select_results(self) -> bool {
         self.borrow_layout_data().ptr.as_ref().unwrap().style.is_some()
@@ -2,13 +2,20 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use style::{Stylesheet, Stylist, UserAgentOrigin, with_errors_silenced};
+use std::from_str::FromStr;
+use style::{Device, Stylesheet, Stylist, UserAgentOrigin, SilentErrorReporter};
 
 
-pub fn new_stylist() -> Stylist {
-    let mut stylist = Stylist::new();
-    let ua_stylesheet = with_errors_silenced(
-        || Stylesheet::from_str(include_str!("user-agent.css")));
+pub fn new_stylist(device: Device) -> Stylist {
+    let mut stylist = Stylist::new(device);
+    let ua_stylesheet = Stylesheet::from_str(include_str!("user-agent.css"),
+                                             FromStr::from_str("chrome:///user-agent.css").unwrap(),
+                                             &mut SilentErrorReporter);
     stylist.add_stylesheet(ua_stylesheet, UserAgentOrigin);
+    let quirks_mode_stylesheet = Stylesheet::from_str(
+        include_str!("quirks-mode.css"),
+        FromStr::from_str("chrome:///quirks-mode.css").unwrap(),
+        &mut SilentErrorReporter);
+    stylist.set_quirks_mode_stylesheet(quirks_mode_stylesheet);
     stylist
 }
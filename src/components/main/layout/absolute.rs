@@ -0,0 +1,575 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! CSS absolute and fixed positioning (CSS 2.1 § 9.6).
+//!
+//! An `AbsoluteFlow` stays in the flow tree where its element is, so that its parent gives it its
+//! static position like any other child, but it takes no room in the normal flow. The main layout
+//! traversals skip it: its containing block lays it out with `lay_out_absolute_descendants()`
+//! once its own size is known.
+
+use layout::block::{BlockFlow, constrain_height};
+use layout::box::Box;
+use layout::context::LayoutContext;
+use layout::float_context::FloatContext;
+use layout::flow::{AbsoluteFlowClass, BlockFlowClass, Flow, FlowClass, FlowData};
+use layout::flow::{ImmutableFlowUtils, MutableFlowUtils, PostorderFlowTraversal};
+use layout::flow::{PreorderFlowTraversal};
+use layout::flow;
+use layout::layout_task::{AssignHeightsAndStoreOverflowTraversal, AssignWidthsTraversal};
+use layout::layout_task::ClearDamageTraversal;
+use layout::model::{MaybeAuto, Specified, Auto, shrink_to_fit, specified_or_none, specified};
+
+use geom::{Point2D, Rect};
+use servo_util::geometry::Au;
+use servo_util::geometry;
+
+/// A flow for a box with `position: absolute` or `position: fixed`.
+pub struct AbsoluteFlow {
+    /// The block flow that lays out the contents of this flow. This must be the first field: it
+    /// starts with the `FlowData` that `flow::base()` returns.
+    block: BlockFlow,
+
+    /// Whether this flow is positioned relative to the viewport (`position: fixed`). Fixed flows
+    /// are painted into a layer of their own, which stays in place when the document scrolls.
+    is_fixed: bool,
+
+    /// The position that the parent gave to this flow in the normal flow, relative to the parent.
+    static_position: Point2D<Au>,
+
+    /// The padding box of the containing block, relative to the parent of this flow.
+    containing_block: Rect<Au>,
+}
+
+impl AbsoluteFlow {
+    pub fn from_box(base: FlowData, box: Box, is_fixed: bool) -> AbsoluteFlow {
+        AbsoluteFlow {
+            block: BlockFlow::from_box(base, box),
+            is_fixed: is_fixed,
+            static_position: Point2D(Au(0), Au(0)),
+            containing_block: Au::zero_rect(),
+        }
+    }
+
+    pub fn teardown(&mut self) {
+        self.block.teardown()
+    }
+
+    /// Lays out this flow and its descendants in `containing_block`, the padding box of its
+    /// containing block relative to the parent of this flow. The parent must have given this flow
+    /// its static position already.
+    pub fn lay_out(&mut self, containing_block: Rect<Au>, ctx: &mut LayoutContext) {
+        self.static_position = self.block.base.position.origin;
+        self.containing_block = containing_block;
+
+        // The main traversals prune this flow, so it is processed by hand around its children.
+        // Those that lack the damage keep the layout of the last reflow, as elsewhere.
+        {
+            let mut traversal = AssignWidthsTraversal(ctx);
+            traversal.process(self as &mut Flow);
+            for kid in self.block.base.child_iter() {
+                kid.traverse_preorder(&mut traversal);
+            }
+        }
+        {
+            let mut traversal = AssignHeightsAndStoreOverflowTraversal(ctx);
+            for kid in self.block.base.child_iter() {
+                kid.traverse_postorder(&mut traversal);
+            }
+            traversal.process(self as &mut Flow);
+        }
+
+        // The damage that the widths gave to the children is cleared here, since the traversal
+        // that clears the damage of the flow tree does not reach them if this flow has none.
+        let mut traversal = ClearDamageTraversal;
+        traversal.process(self as &mut Flow);
+        for kid in self.block.base.child_iter() {
+            kid.traverse_preorder(&mut traversal);
+        }
+    }
+
+    /// Solves the vertical constraints of CSS 2.1 § 10.6.4 now that the height of the contents is
+    /// known, and moves this flow to its place in the containing block.
+    fn place_vertically(&mut self) {
+        let containing_block = self.containing_block;
+        let static_top = self.static_position.y - containing_block.origin.y;
+        let mut top = Au(0);
+        let mut margin_box_height = Au(0);
+        let mut content_offset = Au(0);
+        for box in self.block.box.iter() {
+            let style = box.style();
+            let containing_height = containing_block.size.height;
+
+            let border = box.border.get();
+            let padding = box.padding.get();
+            let noncontent_height = border.top + border.bottom + padding.top + padding.bottom;
+            let mut position = box.position.get();
+            let mut margin = box.margin.get();
+            let content_height = position.size.height - noncontent_height;
+
+            // Percentages of the vertical margins refer to the width of the containing block.
+            let offset_top = MaybeAuto::from_style(style.Box.top, containing_height);
+            let offset_bottom = MaybeAuto::from_style(style.Box.bottom, containing_height);
+            let margin_top = MaybeAuto::from_style(style.Margin.margin_top,
+                                                   containing_block.size.width);
+            let margin_bottom = MaybeAuto::from_style(style.Margin.margin_bottom,
+                                                      containing_block.size.width);
+            let solve = |height| {
+                solve_vertical_constraints(offset_top,
+                                           height,
+                                           offset_bottom,
+                                           margin_top,
+                                           margin_bottom,
+                                           static_top,
+                                           containing_height - noncontent_height,
+                                           content_height)
+            };
+
+            // If 'min-height' or 'max-height' change the height, solve again with that height.
+            let (solved_top, height, solved_margin_top, solved_margin_bottom) = {
+                let solution = solve(MaybeAuto::from_style(style.Box.height, containing_height));
+                let (_, tentative_height, _, _) = solution;
+                let height = constrain_height(style, tentative_height, Some(containing_height));
+                if height != tentative_height {
+                    solve(Specified(height))
+                } else {
+                    solution
+                }
+            };
+
+            // The contents move along with the top margin.
+            content_offset = solved_margin_top - margin.top;
+            margin.top = solved_margin_top;
+            margin.bottom = solved_margin_bottom;
+            position.origin.y = solved_margin_top;
+            position.size.height = height + noncontent_height;
+            box.margin.set(margin);
+            box.position.set(position);
+
+            top = solved_top;
+            margin_box_height = solved_margin_top + position.size.height + solved_margin_bottom;
+        }
+
+        if content_offset != Au(0) {
+            for kid in self.block.base.child_iter() {
+                flow::mut_base(*kid).position.origin.y = flow::base(*kid).position.origin.y +
+                    content_offset
+            }
+        }
+        self.block.base.position.origin.y = containing_block.origin.y + top;
+        self.block.base.position.size.height = margin_box_height;
+    }
+}
+
+impl Flow for AbsoluteFlow {
+    fn class(&self) -> FlowClass {
+        AbsoluteFlowClass
+    }
+
+    fn as_block<'a>(&'a mut self) -> &'a mut BlockFlow {
+        &mut self.block
+    }
+
+    fn as_absolute<'a>(&'a mut self) -> &'a mut AbsoluteFlow {
+        self
+    }
+
+    fn bubble_widths(&mut self, ctx: &mut LayoutContext) {
+        self.block.bubble_widths(ctx)
+    }
+
+    /// Solves the horizontal constraints of CSS 2.1 § 10.3.7 in the containing block and assigns
+    /// the resulting width to the children.
+    fn assign_widths(&mut self, _: &mut LayoutContext) {
+        debug!("assign_widths_absolute: assigning width for flow {}", self.block.base.id);
+
+        let containing_block = self.containing_block;
+        let static_left = self.static_position.x - containing_block.origin.x;
+        let mut left = Au(0);
+        let mut margin_box_width = Au(0);
+        let mut x_offset = Au(0);
+        let mut content_width = Au(0);
+        for box in self.block.box.iter() {
+            let style = box.style();
+            let containing_width = containing_block.size.width;
+
+            // Can compute padding here since we know containing block width.
+            box.compute_padding(style, containing_width);
+            let border = box.border.get();
+            let padding = box.padding.get();
+            let noncontent_width = border.left + border.right + padding.left + padding.right;
+
+            let offset_left = MaybeAuto::from_style(style.Box.left, containing_width);
+            let offset_right = MaybeAuto::from_style(style.Box.right, containing_width);
+            let margin_left = MaybeAuto::from_style(style.Margin.margin_left, containing_width);
+            let margin_right = MaybeAuto::from_style(style.Margin.margin_right, containing_width);
+
+            // The minimum and preferred widths include the borders, padding and margins.
+            let margins_and_noncontent_width = noncontent_width +
+                margin_left.specified_or_zero() + margin_right.specified_or_zero();
+            let min_width = self.block.base.min_width - margins_and_noncontent_width;
+            let min_width = geometry::max(Au(0), min_width);
+            let pref_width = self.block.base.pref_width - margins_and_noncontent_width;
+            let pref_width = geometry::max(Au(0), pref_width);
+            let solve = |width| {
+                solve_horizontal_constraints(offset_left,
+                                             width,
+                                             offset_right,
+                                             margin_left,
+                                             margin_right,
+                                             static_left,
+                                             containing_width - noncontent_width,
+                                             min_width,
+                                             pref_width)
+            };
+
+            // If the tentative width is greater than 'max-width', or smaller than 'min-width',
+            // solve again with that width, as for blocks in the normal flow.
+            let solution = solve(MaybeAuto::from_style(style.Box.width, containing_width));
+            let solution = match (specified_or_none(style.Box.max_width, containing_width),
+                                  solution) {
+                (Some(max_width), (_, width, _, _)) if max_width < width => {
+                    solve(Specified(max_width))
+                }
+                _ => solution,
+            };
+            let (solved_left, width, solved_margin_left, solved_margin_right) = {
+                let min_width = specified(style.Box.min_width, containing_width);
+                match solution {
+                    (_, width, _, _) if min_width > width => solve(Specified(min_width)),
+                    _ => solution,
+                }
+            };
+
+            // Vertical margins are solved once the height is known.
+            let mut margin = box.margin.get();
+            margin.top = MaybeAuto::from_style(style.Margin.margin_top,
+                                               containing_width).specified_or_zero();
+            margin.bottom = MaybeAuto::from_style(style.Margin.margin_bottom,
+                                                  containing_width).specified_or_zero();
+            margin.left = solved_margin_left;
+            margin.right = solved_margin_right;
+            box.margin.set(margin);
+
+            // The associated box is the border box of this flow.
+            let position_ref = box.position.mutate();
+            position_ref.ptr.origin.x = solved_margin_left;
+            position_ref.ptr.size.width = width + noncontent_width;
+
+            left = solved_left;
+            margin_box_width = solved_margin_left + width + noncontent_width + solved_margin_right;
+            x_offset = box.offset();
+            content_width = width;
+        }
+
+        self.block.base.position.origin.x = containing_block.origin.x + left;
+        self.block.base.position.size.width = margin_box_width;
+
+        // Like the root flow, an absolutely positioned flow lays out its floats by itself.
+        self.block.base.floats_in = FloatContext::new(self.block.base.num_floats);
+        self.block.base.flags.set_inorder(false);
+
+        self.block.assign_widths_of_children(x_offset, content_width);
+    }
+
+    fn assign_height(&mut self, ctx: &mut LayoutContext) {
+        debug!("assign_height_absolute: assigning height for flow {}", self.block.base.id);
+        let has_floats = self.block.base.num_floats > 0;
+        self.block.assign_height_block_base(ctx, has_floats);
+        self.place_vertically();
+        self.block.lay_out_absolute_descendants(ctx);
+    }
+
+    /// An absolutely positioned flow does not affect the floats around it.
+    fn assign_height_inorder(&mut self, _: &mut LayoutContext) {
+        self.block.base.floats_out = self.block.base.floats_in.clone();
+    }
+
+    fn collapse_margins(&mut self,
+                        _: bool,
+                        _: &mut bool,
+                        _: &mut Au,
+                        _: &mut Au,
+                        collapsing: &mut Au,
+                        _: &mut Au) {
+        // An absolutely positioned box is not in flow: the margins of its siblings collapse as if
+        // it were not there.
+        *collapsing = Au::new(0);
+    }
+
+    fn debug_str(&self) -> ~str {
+        let txt = if self.is_fixed {
+            ~"FixedFlow: "
+        } else {
+            ~"AbsoluteFlow: "
+        };
+        txt.append(match self.block.box {
+            Some(ref rb) => rb.debug_str(),
+            None => ~"",
+        })
+    }
+}
+
+/// Lays out the absolutely positioned descendants of `flow` whose containing block is
+/// `containing_block`, a rectangle relative to `flow`. Positioned descendants are the containing
+/// blocks of their own absolutely positioned descendants, which they lay out themselves. The root
+/// flow, whose containing block is the viewport, lays out all the fixed positioned flows too.
+///
+/// The caller must store the overflow of `flow` afterwards, since it may have grown.
+pub fn lay_out_absolute_descendants(flow: &mut Flow,
+                                    containing_block: Rect<Au>,
+                                    is_root: bool,
+                                    ctx: &mut LayoutContext) {
+    lay_out_descendants(flow, Point2D(Au(0), Au(0)), &containing_block, true, is_root, ctx);
+}
+
+/// Walks the descendants of `flow`, which is at `offset` from the flow that owns the containing
+/// block, looking for the absolutely positioned flows (if `absolute` is true) and the fixed
+/// positioned flows (if `fixed` is true) to lay out. Returns true if it laid out any.
+///
+/// The flows in between have stored their overflow before these flows were laid out, so they
+/// store it again to take them in.
+fn lay_out_descendants(flow: &mut Flow,
+                       offset: Point2D<Au>,
+                       containing_block: &Rect<Au>,
+                       absolute: bool,
+                       fixed: bool,
+                       ctx: &mut LayoutContext)
+                       -> bool {
+    let mut laid_out = false;
+    for kid in flow::child_iter(flow) {
+        let kid_laid_out = if kid.is_absolutely_positioned() {
+            let is_fixed = kid.as_absolute().is_fixed;
+            let mut kid_laid_out = false;
+            if (is_fixed && fixed) || (!is_fixed && absolute) {
+                let kid_containing_block = Rect(containing_block.origin - offset,
+                                                containing_block.size);
+                kid.as_absolute().lay_out(kid_containing_block, ctx);
+                laid_out = true
+            }
+
+            // Fixed positioned flows may be inside it; nothing else is left to lay out.
+            if fixed {
+                let kid_offset = offset + position_in_parent(*kid);
+                kid_laid_out = lay_out_descendants(*kid, kid_offset, containing_block, false,
+                                                   true, ctx)
+            }
+            kid_laid_out
+        } else {
+            let is_positioned = flow::is_positioned(*kid);
+            if is_positioned && !fixed {
+                continue
+            }
+            let kid_offset = offset + position_in_parent(*kid);
+            lay_out_descendants(*kid,
+                                kid_offset,
+                                containing_block,
+                                absolute && !is_positioned,
+                                fixed,
+                                ctx)
+        };
+        if kid_laid_out {
+            kid.store_overflow(ctx);
+            laid_out = true
+        }
+    }
+    laid_out
+}
+
+/// Returns the position of `flow` relative to its parent. Floats are moved by their placement.
+fn position_in_parent(flow: &mut Flow) -> Point2D<Au> {
+    let origin = flow::base(flow).position.origin;
+    match flow.class() {
+        BlockFlowClass => {
+            match flow.as_block().float {
+                Some(ref info) => origin + info.rel_pos,
+                None => origin,
+            }
+        }
+        _ => origin,
+    }
+}
+
+/// Solves `left + margin-left + width + margin-right + right = available_width` per CSS 2.1 §
+/// 10.3.7, where `available_width` is the width of the containing block less the horizontal
+/// borders and padding. Returns the used left offset, width, and left and right margins.
+///
+/// FIXME: This assumes that the containing block is left-to-right.
+fn solve_horizontal_constraints(left: MaybeAuto,
+                                width: MaybeAuto,
+                                right: MaybeAuto,
+                                margin_left: MaybeAuto,
+                                margin_right: MaybeAuto,
+                                static_left: Au,
+                                available_width: Au,
+                                min_width: Au,
+                                pref_width: Au)
+                                -> (Au, Au, Au, Au) {
+    // Unless none of the offsets and width is 'auto', 'auto' margins are treated as 0.
+    let margin_left_or_zero = margin_left.specified_or_zero();
+    let margin_right_or_zero = margin_right.specified_or_zero();
+    let margins = margin_left_or_zero + margin_right_or_zero;
+    let (left, width) = match (left, width, right) {
+        (Auto, Auto, Auto) => {
+            (static_left, shrink_to_fit(min_width, pref_width, available_width - static_left -
+                                        margins))
+        }
+        (Auto, Auto, Specified(right)) => {
+            let width = shrink_to_fit(min_width, pref_width, available_width - margins - right);
+            (available_width - margins - right - width, width)
+        }
+        (Auto, Specified(width), Auto) => (static_left, width),
+        (Specified(left), Auto, Auto) => {
+            (left, shrink_to_fit(min_width, pref_width, available_width - left - margins))
+        }
+        (Auto, Specified(width), Specified(right)) => {
+            (available_width - margins - right - width, width)
+        }
+        (Specified(left), Auto, Specified(right)) => {
+            (left, geometry::max(Au(0), available_width - left - margins - right))
+        }
+        (Specified(left), Specified(width), Auto) => (left, width),
+        (Specified(left), Specified(width), Specified(right)) => {
+            // 'auto' margins take up the remaining space, equally if both are 'auto' and that
+            // leaves them positive. If the system is over-constrained, 'right' is ignored.
+            let remaining = available_width - left - width - right;
+            return match (margin_left, margin_right) {
+                (Auto, Auto) if remaining < Au(0) => (left, width, Au(0), remaining),
+                (Auto, Auto) => {
+                    let margin_left = remaining.scale_by(0.5);
+                    (left, width, margin_left, remaining - margin_left)
+                }
+                (Auto, Specified(margin_right)) => {
+                    (left, width, remaining - margin_right, margin_right)
+                }
+                (Specified(margin_left), Auto) => {
+                    (left, width, margin_left, remaining - margin_left)
+                }
+                (Specified(margin_left), Specified(margin_right)) => {
+                    (left, width, margin_left, margin_right)
+                }
+            }
+        }
+    };
+    (left, width, margin_left_or_zero, margin_right_or_zero)
+}
+
+/// Solves `top + margin-top + height + margin-bottom + bottom = available_height` per CSS 2.1 §
+/// 10.6.4, where `available_height` is the height of the containing block less the vertical
+/// borders and padding. Returns the used top offset, height, and top and bottom margins.
+fn solve_vertical_constraints(top: MaybeAuto,
+                              height: MaybeAuto,
+                              bottom: MaybeAuto,
+                              margin_top: MaybeAuto,
+                              margin_bottom: MaybeAuto,
+                              static_top: Au,
+                              available_height: Au,
+                              content_height: Au)
+                              -> (Au, Au, Au, Au) {
+    // Unless none of the offsets and height is 'auto', 'auto' margins are treated as 0.
+    let margin_top_or_zero = margin_top.specified_or_zero();
+    let margin_bottom_or_zero = margin_bottom.specified_or_zero();
+    let margins = margin_top_or_zero + margin_bottom_or_zero;
+    let (top, height) = match (top, height, bottom) {
+        (Auto, Auto, Auto) => (static_top, content_height),
+        (Auto, Auto, Specified(bottom)) => {
+            (available_height - margins - bottom - content_height, content_height)
+        }
+        (Auto, Specified(height), Auto) => (static_top, height),
+        (Specified(top), Auto, Auto) => (top, content_height),
+        (Auto, Specified(height), Specified(bottom)) => {
+            (available_height - margins - bottom - height, height)
+        }
+        (Specified(top), Auto, Specified(bottom)) => {
+            (top, geometry::max(Au(0), available_height - top - margins - bottom))
+        }
+        (Specified(top), Specified(height), Auto) => (top, height),
+        (Specified(top), Specified(height), Specified(bottom)) => {
+            // 'auto' margins take up the remaining space, equally if both are 'auto'. If the
+            // system is over-constrained, 'bottom' is ignored.
+            let remaining = available_height - top - height - bottom;
+            return match (margin_top, margin_bottom) {
+                (Auto, Auto) => {
+                    let margin_top = remaining.scale_by(0.5);
+                    (top, height, margin_top, remaining - margin_top)
+                }
+                (Auto, Specified(margin_bottom)) => {
+                    (top, height, remaining - margin_bottom, margin_bottom)
+                }
+                (Specified(margin_top), Auto) => (top, height, margin_top, remaining - margin_top),
+                (Specified(margin_top), Specified(margin_bottom)) => {
+                    (top, height, margin_top, margin_bottom)
+                }
+            }
+        }
+    };
+    (top, height, margin_top_or_zero, margin_bottom_or_zero)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{solve_horizontal_constraints, solve_vertical_constraints};
+    use layout::model::{Auto, Specified};
+    use servo_util::geometry::Au;
+
+    fn px(value: int) -> Au {
+        Au::from_px(value)
+    }
+
+    #[test]
+    fn test_horizontal_shrink_to_fit_at_static_position() {
+        // left, width and right are all 'auto'.
+        assert_eq!(solve_horizontal_constraints(Auto, Auto, Auto, Auto, Specified(px(5)),
+                                                px(20), px(100), px(30), px(60)),
+                   (px(20), px(60), px(0), px(5)));
+        assert_eq!(solve_horizontal_constraints(Auto, Auto, Auto, Auto, Auto,
+                                                px(80), px(100), px(10), px(60)),
+                   (px(80), px(20), px(0), px(0)));
+    }
+
+    #[test]
+    fn test_horizontal_offsets() {
+        // Right-aligned shrink-to-fit box.
+        assert_eq!(solve_horizontal_constraints(Auto, Auto, Specified(px(10)), Auto, Auto,
+                                                px(0), px(100), px(10), px(40)),
+                   (px(50), px(40), px(0), px(0)));
+        // Stretched between the offsets.
+        assert_eq!(solve_horizontal_constraints(Specified(px(10)), Auto, Specified(px(20)),
+                                                Specified(px(5)), Auto,
+                                                px(0), px(100), px(10), px(40)),
+                   (px(10), px(65), px(5), px(0)));
+        // Centered with 'auto' margins.
+        assert_eq!(solve_horizontal_constraints(Specified(px(0)), Specified(px(60)),
+                                                Specified(px(0)), Auto, Auto,
+                                                px(0), px(100), px(10), px(40)),
+                   (px(0), px(60), px(20), px(20)));
+        // Over-constrained: 'right' is ignored.
+        assert_eq!(solve_horizontal_constraints(Specified(px(10)), Specified(px(60)),
+                                                Specified(px(10)), Specified(px(0)),
+                                                Specified(px(0)),
+                                                px(0), px(100), px(10), px(40)),
+                   (px(10), px(60), px(0), px(0)));
+    }
+
+    #[test]
+    fn test_vertical_constraints() {
+        // Static position with the height of the contents.
+        assert_eq!(solve_vertical_constraints(Auto, Auto, Auto, Specified(px(5)), Auto,
+                                              px(30), px(200), px(50)),
+                   (px(30), px(50), px(5), px(0)));
+        // Anchored to the bottom.
+        assert_eq!(solve_vertical_constraints(Auto, Auto, Specified(px(10)), Auto, Auto,
+                                              px(30), px(200), px(50)),
+                   (px(140), px(50), px(0), px(0)));
+        // Stretched between the offsets.
+        assert_eq!(solve_vertical_constraints(Specified(px(10)), Auto, Specified(px(20)),
+                                              Auto, Auto, px(30), px(200), px(50)),
+                   (px(10), px(170), px(0), px(0)));
+        // Centered with 'auto' margins.
+        assert_eq!(solve_vertical_constraints(Specified(px(0)), Specified(px(100)),
+                                              Specified(px(0)), Auto, Auto,
+                                              px(30), px(200), px(50)),
+                   (px(0), px(100), px(50), px(50)));
+    }
+}
@@ -4,6 +4,7 @@
 
 //! CSS block formatting contexts.
 
+use layout::absolute;
 use layout::box::Box;
 use layout::context::LayoutContext;
 use layout::display_list_builder::{DisplayListBuilder, ExtraDisplayListData};
@@ -13,10 +14,15 @@ use layout::model::{MaybeAuto, Specified, Auto, specified_or_none, specified};
 use layout::float_context::{FloatContext, PlacementInfo, Invalid, FloatType};
 
 use std::cell::Cell;
-use geom::{Point2D, Rect, SideOffsets2D};
+use geom::{Point2D, Rect, Size2D, SideOffsets2D};
 use gfx::display_list::DisplayList;
+use script::dom::node::{AbstractNode, LayoutView};
 use servo_util::geometry::Au;
 use servo_util::geometry;
+use servo_util::tree::{TreeNodeRef, ElementLike};
+use style::{ComputedValues, FullQuirks};
+use style::computed_values::{display, position};
+use computed = style::computed_values;
 
 /// Information specific to floated blocks.
 pub struct FloatedBlockInfo {
@@ -112,6 +118,40 @@ impl BlockFlow {
         self.float.is_some()
     }
 
+    /// Returns true if the box of this flow is positioned, i.e. its 'position' is not 'static'.
+    pub fn is_positioned(&self) -> bool {
+        match self.box {
+            Some(ref box) => box.style().Box.position != position::static_,
+            None => false,
+        }
+    }
+
+    /// Returns true if this flow establishes a new block formatting context whose margins do not
+    /// collapse with those of its children (CSS 2.1 § 8.3.1). Floats are laid out separately.
+    ///
+    /// FIXME: So do blocks whose 'overflow' is not 'visible'.
+    fn establishes_block_formatting_context(&self) -> bool {
+        if self.is_root {
+            return true
+        }
+        match self.box {
+            Some(ref box) => {
+                let style = box.style();
+                match style.Box.display {
+                    display::inline_block | display::table_cell | display::table_caption => {
+                        return true
+                    }
+                    _ => {}
+                }
+                match style.Box.position {
+                    position::absolute | position::fixed => true,
+                    position::static_ | position::relative => false,
+                }
+            }
+            None => false,
+        }
+    }
+
     pub fn teardown(&mut self) {
         for box in self.box.iter() {
             box.teardown();
@@ -122,7 +162,7 @@ impl BlockFlow {
 
     /// Computes left and right margins and width based on CSS 2.1 section 10.3.3.
     /// Requires borders and padding to already be computed.
-    fn compute_horiz(&self,
+    pub fn compute_horiz(&self,
                      width: MaybeAuto,
                      left_margin: MaybeAuto,
                      right_margin: MaybeAuto,
@@ -232,7 +272,7 @@ impl BlockFlow {
     // inline(always) because this is only ever called by in-order or non-in-order top-level
     // methods
     #[inline(always)]
-    fn assign_height_block_base(&mut self, ctx: &mut LayoutContext, inorder: bool) {
+    pub fn assign_height_block_base(&mut self, ctx: &mut LayoutContext, inorder: bool) {
         let mut cur_y = Au::new(0);
         let mut clearance = Au::new(0);
         let mut top_offset = Au::new(0);
@@ -280,11 +320,13 @@ impl BlockFlow {
         let mut bottom_margin_collapsible = false;
         let mut first_in_flow = true;
         for box in self.box.iter() {
-            if !self.is_root && box.border.get().top == Au(0) && box.padding.get().top == Au(0) {
+            let collapses_with_children = !self.establishes_block_formatting_context();
+            if collapses_with_children && box.border.get().top == Au(0) &&
+                    box.padding.get().top == Au(0) {
                 collapsible = box.margin.get().top;
                 top_margin_collapsible = true;
             }
-            if !self.is_root && box.border.get().bottom == Au(0) &&
+            if collapses_with_children && box.border.get().bottom == Au(0) &&
                     box.padding.get().bottom == Au(0) {
                 bottom_margin_collapsible = true;
             }
@@ -300,10 +342,15 @@ impl BlockFlow {
                                  &mut collapsing,
                                  &mut collapsible);
 
+            // An absolutely positioned child takes no room: it is just given its static
+            // position here.
+            let in_flow = !kid.is_absolutely_positioned();
             let child_node = flow::mut_base(*kid);
             cur_y = cur_y - collapsing;
             child_node.position.origin.y = cur_y;
-            cur_y = cur_y + child_node.position.size.height;
+            if in_flow {
+                cur_y = cur_y + child_node.position.size.height;
+            }
         }
 
         // The bottom margin collapses with its last in-flow block-level child's bottom margin
@@ -338,10 +385,23 @@ impl BlockFlow {
             // At this point, `height` is the height of the containing block, so passing `height`
             // as the second argument here effectively makes percentages relative to the containing
             // block per CSS 2.1 § 10.5.
-            height = match MaybeAuto::from_style(style.Box.height, height) {
+            let containing_height = height;
+            height = match MaybeAuto::from_style(style.Box.height, containing_height) {
+                Auto if ctx.quirks_mode == FullQuirks && is_body(box.node) => {
+                    // In quirks mode, the body element fills the viewport unless its height is
+                    // specified. FIXME: The margins, borders and padding of the root element
+                    // should be taken out too.
+                    let margin = box.margin.get();
+                    let border = box.border.get();
+                    let padding = box.padding.get();
+                    let noncontent_height = margin.top + margin.bottom + border.top +
+                        border.bottom + padding.top + padding.bottom;
+                    geometry::max(height, ctx.screen_size.size.height - noncontent_height)
+                }
                 Auto => height,
                 Specified(value) => value
             };
+            height = constrain_height(style, height, Some(containing_height));
         }
 
         let mut noncontent_height = Au::new(0);
@@ -433,9 +493,12 @@ impl BlockFlow {
         }
 
         for kid in self.base.child_iter() {
+            let in_flow = !kid.is_absolutely_positioned();
             let child_base = flow::mut_base(*kid);
             child_base.position.origin.y = cur_y;
-            cur_y = cur_y + child_base.position.size.height;
+            if in_flow {
+                cur_y = cur_y + child_base.position.size.height;
+            }
         }
 
         let mut height = cur_y - top_offset;
@@ -454,7 +517,10 @@ impl BlockFlow {
         let height_prop = MaybeAuto::from_style(box.style().Box.height,
                                                 Au::new(0)).specified_or_zero();
 
-        height = geometry::max(height, height_prop) + noncontent_height;
+        // Like those of 'height', percentages of 'min-height' and 'max-height' are not resolved
+        // against the height of the containing block, which is not known yet.
+        height = constrain_height(box.style(), geometry::max(height, height_prop), None) +
+            noncontent_height;
         debug!("assign_height_float -- height: {}", height);
 
         position.size.height = height;
@@ -471,6 +537,10 @@ impl BlockFlow {
             return self.build_display_list_float(builder, dirty, list);
         }
 
+        // Positioned descendants may be visible even if this flow is not.
+        let this_position = self.base.abs_position;
+        self.set_abs_position_of_children(this_position);
+
         let abs_rect = Rect(self.base.abs_position, self.base.position.size);
         if !abs_rect.intersects(dirty) {
             return true;
@@ -483,13 +553,6 @@ impl BlockFlow {
             box.build_display_list(builder, dirty, self.base.abs_position, (&*self) as &Flow, list)
         }
 
-        // TODO: handle any out-of-flow elements
-        let this_position = self.base.abs_position;
-        for child in self.base.child_iter() {
-            let child_base = flow::mut_base(*child);
-            child_base.abs_position = this_position + child_base.position.origin;
-        }
-
         false
     }
 
@@ -499,27 +562,101 @@ impl BlockFlow {
                                     dirty: &Rect<Au>,
                                     list: &Cell<DisplayList<E>>)
                                     -> bool {
+        // go deeper into the flow tree
+        let offset = self.base.abs_position + self.float.get_ref().rel_pos;
+        self.set_abs_position_of_children(offset);
+
         let abs_rect = Rect(self.base.abs_position, self.base.position.size);
         if !abs_rect.intersects(dirty) {
             return true
         }
 
-        let offset = self.base.abs_position + self.float.get_ref().rel_pos;
         // add box that starts block context
         for box in self.box.iter() {
             box.build_display_list(builder, dirty, offset, (&*self) as &Flow, list)
         }
 
+        false
+    }
 
-        // TODO: handle any out-of-flow elements
+    /// Returns the size of the containing block that this flow establishes for its children: that
+    /// of the content box.
+    pub fn containing_block_size(&self) -> Size2D<Au> {
+        match self.box {
+            Some(ref box) => {
+                let size = box.position.get().size;
+                let (border, padding) = (box.border.get(), box.padding.get());
+                Size2D(size.width - border.left - border.right - padding.left - padding.right,
+                       size.height - border.top - border.bottom - padding.top - padding.bottom)
+            }
+            None => self.base.position.size,
+        }
+    }
 
-        // go deeper into the flow tree
+    /// Sets the absolute position of the children of this flow, whose origins are relative to
+    /// `offset`. Relatively positioned children are shifted, along with their descendants, after
+    /// layout, so that their siblings are not disturbed (CSS 2.1 § 9.4.3).
+    fn set_abs_position_of_children(&mut self, offset: Point2D<Au>) {
+        let containing_block_size = self.containing_block_size();
         for child in self.base.child_iter() {
+            let relative_position = flow::relative_position(*child, &containing_block_size);
             let child_base = flow::mut_base(*child);
-            child_base.abs_position = offset + child_base.position.origin;
+            child_base.abs_position = offset + child_base.position.origin + relative_position;
         }
+    }
 
-        false
+    /// Lays out the absolutely positioned descendants for which this flow is the containing
+    /// block, now that its size is known. The root flow lays out those of the initial containing
+    /// block, which has the size of the viewport, and all the fixed positioned flows.
+    ///
+    /// Fixed positioned flows are placed relative to the viewport as it is before the page is
+    /// scrolled. The compositor keeps the layer that they are painted into at that place.
+    pub fn lay_out_absolute_descendants(&mut self, ctx: &mut LayoutContext) {
+        let is_root = self.is_root;
+        let containing_block = if is_root {
+            Rect(Au::zero_point(), ctx.screen_size.size)
+        } else if self.is_positioned() {
+            // The containing block is the padding box.
+            let box = self.box.get_ref();
+            let position = box.position.get();
+            let border = box.border.get();
+            Rect(Point2D(position.origin.x + border.left, position.origin.y + border.top),
+                 Size2D(position.size.width - border.left - border.right,
+                        position.size.height - border.top - border.bottom))
+        } else {
+            return
+        };
+        absolute::lay_out_absolute_descendants(self as &mut Flow, containing_block, is_root, ctx)
+    }
+
+    /// Gives the children of this flow their width and horizontal position, `x_offset`, inside
+    /// the content box of this flow, whose width is `width`.
+    pub fn assign_widths_of_children(&mut self, x_offset: Au, width: Au) {
+        let has_inorder_children = if self.is_float() {
+            self.base.num_floats > 0
+        } else {
+            self.base.flags.inorder() || self.base.num_floats > 0
+        };
+
+        for kid in self.base.child_iter() {
+            assert!(kid.starts_block_flow() || kid.starts_inline_flow() ||
+                    kid.is_absolutely_positioned());
+            let in_flow = !kid.is_absolutely_positioned();
+
+            let child_base = flow::mut_base(*kid);
+            child_base.position.origin.x = x_offset;
+            child_base.position.size.width = width;
+            child_base.flags.set_inorder(has_inorder_children);
+
+            if !child_base.flags.inorder() {
+                child_base.floats_in = FloatContext::new(0);
+            }
+
+            // Per CSS 2.1 § 16.3.1, text decoration propagates to all children in flow.
+            if in_flow {
+                child_base.flags.propagate_text_decoration_from_parent(self.base.flags)
+            }
+        }
     }
 }
 
@@ -538,7 +675,6 @@ impl Flow for BlockFlow {
     min/pref widths based on child context widths and dimensions of
     any boxes it is responsible for flowing.  */
 
-    /* TODO: absolute contexts */
     /* TODO: inline-blocks */
     fn bubble_widths(&mut self, _: &mut LayoutContext) {
         let mut min_width = Au::new(0);
@@ -547,7 +683,14 @@ impl Flow for BlockFlow {
 
         /* find max width from child block contexts */
         for child_ctx in self.base.child_iter() {
-            assert!(child_ctx.starts_block_flow() || child_ctx.starts_inline_flow());
+            assert!(child_ctx.starts_block_flow() || child_ctx.starts_inline_flow() ||
+                    child_ctx.is_absolutely_positioned());
+
+            // Absolutely positioned children neither take room in this flow nor share its
+            // floats.
+            if child_ctx.is_absolutely_positioned() {
+                continue
+            }
 
             let child_base = flow::mut_base(*child_ctx);
             min_width = geometry::max(min_width, child_base.min_width);
@@ -651,29 +794,7 @@ impl Flow for BlockFlow {
             self.base.position.size.width = remaining_width;
         }
 
-        let has_inorder_children = if self.is_float() {
-            self.base.num_floats > 0
-        } else {
-            self.base.flags.inorder() || self.base.num_floats > 0
-        };
-
-        for kid in self.base.child_iter() {
-            assert!(kid.starts_block_flow() || kid.starts_inline_flow());
-
-            let child_base = flow::mut_base(*kid);
-            child_base.position.origin.x = x_offset;
-            child_base.position.size.width = remaining_width;
-            child_base.flags.set_inorder(has_inorder_children);
-
-            if !child_base.flags.inorder() {
-                child_base.floats_in = FloatContext::new(0);
-            }
-
-            // Per CSS 2.1 § 16.3.1, text decoration propagates to all children in flow.
-            //
-            // TODO(pcwalton): When we have out-of-flow children, don't unconditionally propagate.
-            child_base.flags.propagate_text_decoration_from_parent(self.base.flags)
-        }
+        self.assign_widths_of_children(x_offset, remaining_width)
     }
 
     fn assign_height_inorder(&mut self, ctx: &mut LayoutContext) {
@@ -683,6 +804,7 @@ impl Flow for BlockFlow {
         } else {
             debug!("assign_height_inorder: assigning height for block {}", self.base.id);
             self.assign_height_block_base(ctx, true);
+            self.lay_out_absolute_descendants(ctx);
         }
     }
 
@@ -690,6 +812,7 @@ impl Flow for BlockFlow {
         if self.is_float() {
             debug!("assign_height_float: assigning height for float {}", self.base.id);
             self.assign_height_float(ctx);
+            self.lay_out_absolute_descendants(ctx);
         } else {
             debug!("assign_height: assigning height for block {}", self.base.id);
             // This is the only case in which a block flow can start an inorder
@@ -699,6 +822,7 @@ impl Flow for BlockFlow {
                 return;
             }
             self.assign_height_block_base(ctx, false);
+            self.lay_out_absolute_descendants(ctx);
         }
     }
 
@@ -757,3 +881,33 @@ impl Flow for BlockFlow {
     }
 }
 
+/// Returns true if `node` is the body element, a child of the root element.
+fn is_body(node: AbstractNode<LayoutView>) -> bool {
+    if !node.is_element() || node.with_imm_element(|element| {
+            element.get_local_name().as_slice() != "body"
+        }) {
+        return false
+    }
+    match node.parent_node() {
+        Some(parent) => parent.parent_node().map_default(false, |root| root.is_document()),
+        None => false,
+    }
+}
+
+/// Applies 'max-height' and then 'min-height' to a tentative content height, per CSS 2.1 § 10.7.
+/// Percentages are treated as 'none' and '0' when the height of the containing block is not
+/// known, which is passed as `None`.
+///
+/// FIXME: Percentages should also be treated that way when the height of the containing block is
+/// not specified; like 'height', they are resolved against `containing_height` for now.
+pub fn constrain_height(style: &ComputedValues, height: Au, containing_height: Option<Au>) -> Au {
+    let max_height = match style.Box.max_height {
+        computed::LPN_Percentage(_) if containing_height.is_none() => None,
+        max_height => specified_or_none(max_height, containing_height.unwrap_or(Au(0))),
+    };
+    let height = match max_height {
+        Some(max_height) if max_height < height => max_height,
+        _ => height,
+    };
+    geometry::max(height, specified(style.Box.min_height, containing_height.unwrap_or(Au(0))))
+}
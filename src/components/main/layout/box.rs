@@ -12,7 +12,7 @@ use gfx::display_list::{BaseDisplayItem, BorderDisplayItem, BorderDisplayItemCla
 use gfx::display_list::{DisplayList, ImageDisplayItem, ImageDisplayItemClass};
 use gfx::display_list::{SolidColorDisplayItem, SolidColorDisplayItemClass, TextDisplayItem};
 use gfx::display_list::{TextDisplayItemClass, TextDisplayItemFlags, ClipDisplayItem};
-use gfx::display_list::{ClipDisplayItemClass};
+use gfx::display_list::{ClipDisplayItemClass, OpacityDisplayItem, OpacityDisplayItemClass};
 use gfx::font::{FontStyle, FontWeight300};
 use gfx::text::text_run::TextRun;
 use script::dom::node::{AbstractNode, LayoutView};
@@ -28,18 +28,20 @@ use std::cast;
 use std::cell::Cell;
 use std::cmp::ApproxEq;
 use std::num::Zero;
-use style::ComputedValues;
-use style::computed_values::{LengthOrPercentage, overflow};
+use style::{ComputedValues, PseudoElement};
+use style::computed_values::{LengthOrPercentage, background_attachment, background_repeat};
+use style::computed_values::{border_collapse, display, overflow, position};
 use style::computed_values::{border_style, clear, font_family, font_style, line_height};
 use style::computed_values::{text_align, text_decoration, vertical_align, visibility};
+use style::computed_values::white_space;
 
 use css::node_style::StyledNode;
 use layout::context::LayoutContext;
 use layout::display_list_builder::{DisplayListBuilder, ExtraDisplayListData, ToGfxColor};
 use layout::float_context::{ClearType, ClearLeft, ClearRight, ClearBoth};
-use layout::flow::Flow;
+use layout::flow::{Flow, ImmutableFlowUtils, InlineFlowClass};
 use layout::flow;
-use layout::model::{MaybeAuto, specified};
+use layout::model::{Auto, MaybeAuto, Specified, specified};
 
 /// Boxes (`struct Box`) are the leaves of the layout tree. They cannot position themselves. In
 /// general, boxes do not have a simple correspondence with CSS boxes in the specification:
@@ -56,6 +58,9 @@ use layout::model::{MaybeAuto, specified};
 /// A `GenericBox` is an empty box that contributes only borders, margins, padding, and
 /// backgrounds. It is analogous to a CSS nonreplaced content box.
 ///
+/// An `InlineBlockBox` holds the place of an inline-block in the lines of an inline flow. The
+/// `InlineBlockFlow` that is the child of that inline flow lays out and paints the inline-block.
+///
 /// A box's type influences how its styles are interpreted during layout. For example, replaced
 /// content such as images are resized differently from tables, text, or other content. Different
 /// types of boxes may also contain custom data; for example, text boxes contain text.
@@ -85,6 +90,10 @@ pub struct Box {
 
     /// Info specific to the kind of box. Keep this enum small.
     specific: SpecificBoxInfo,
+
+    /// The pseudo-element whose generated content or first letter this box is, if any. The node
+    /// of such a box is the element that generates it.
+    pseudo_element: Option<PseudoElement>,
 }
 
 /// Info specific to the kind of box. Keep this enum small.
@@ -93,6 +102,7 @@ pub enum SpecificBoxInfo {
     GenericBox,
     ImageBox(ImageBoxInfo),
     IframeBox(IframeBoxInfo),
+    InlineBlockBox(InlineBlockBoxInfo),
     ScannedTextBox(ScannedTextBoxInfo),
     UnscannedTextBox(UnscannedTextBoxInfo),
 }
@@ -173,6 +183,22 @@ impl IframeBoxInfo {
     }
 }
 
+/// The place of an inline-block in the lines of an inline flow.
+#[deriving(Clone)]
+pub struct InlineBlockBoxInfo {
+    /// The distance from the top of the margin box of the inline-block to its baseline, once the
+    /// `InlineBlockFlow` is laid out.
+    baseline: Slot<Au>,
+}
+
+impl InlineBlockBoxInfo {
+    pub fn new() -> InlineBlockBoxInfo {
+        InlineBlockBoxInfo {
+            baseline: Slot::init(Au(0)),
+        }
+    }
+}
+
 /// A scanned text box represents a single run of text with a distinct style. A `TextBox` may be
 /// split into two or more boxes across line breaks. Several `TextBox`es may correspond to a single
 /// DOM text node. Split text boxes are implemented by referring to subsets of a single `TextRun`
@@ -214,6 +240,14 @@ impl UnscannedTextBoxInfo {
             }
         })
     }
+
+    /// Creates a new instance of `UnscannedTextBoxInfo` from text that is not in the DOM, such as
+    /// generated content.
+    pub fn from_text(text: ~str) -> UnscannedTextBoxInfo {
+        UnscannedTextBoxInfo {
+            text: text,
+        }
+    }
 }
 
 /// Represents the outcome of attempting to split a box.
@@ -246,14 +280,23 @@ impl Box {
             nearest_ancestor_element = node.parent_node().expect("no nearest element?!");
         }
 
+        Box::from_style(node, (*nearest_ancestor_element.style()).clone(), specific)
+    }
+
+    /// Constructs a new `Box` instance with a style other than the one of its node. This is used
+    /// for the boxes of pseudo-elements, whose node is the element they belong to.
+    pub fn from_style(node: AbstractNode<LayoutView>, style: Arc<ComputedValues>,
+                      specific: SpecificBoxInfo)
+                      -> Box {
         Box {
             node: node,
-            style: (*nearest_ancestor_element.style()).clone(),
+            style: style,
             position: Slot::init(Au::zero_rect()),
             border: Slot::init(Zero::zero()),
             padding: Slot::init(Zero::zero()),
             margin: Slot::init(Zero::zero()),
             specific: specific,
+            pseudo_element: None,
         }
     }
 
@@ -276,12 +319,18 @@ impl Box {
             padding: Slot::init(self.padding.get()),
             margin: Slot::init(self.margin.get()),
             specific: specific,
+            pseudo_element: self.pseudo_element,
         }
     }
 
     fn guess_width(&self) -> Au {
-        if !self.node.is_element() {
-            return Au(0)
+        // Text boxes, including generated ones whose node is an element, are sized by their text.
+        match self.specific {
+            ScannedTextBox(_) | UnscannedTextBox(_) => return Au(0),
+            // The inline flow accounts for the widths of inline-blocks.
+            InlineBlockBox(_) => return Au(0),
+            _ if !self.node.is_element() => return Au(0),
+            _ => {}
         }
 
         let style = self.style();
@@ -378,6 +427,45 @@ impl Box {
         }
     }
 
+    /// Returns the offset of this box from its position in the normal flow, if it is relatively
+    /// positioned (CSS 2.1 § 9.4.3). Percentages refer to `containing_block_size`.
+    pub fn relative_position(&self, containing_block_size: &Size2D<Au>) -> Point2D<Au> {
+        relative_position_of_style(self.style(), containing_block_size)
+    }
+
+    /// Returns the offset of this box, in `flow`, an inline flow, from its position in the normal
+    /// flow: that of its own style, plus those of the inline elements that contain it.
+    ///
+    /// FIXME: Percentages of 'top' and 'bottom' should refer to the height of the containing
+    /// block, not of the inline flow. The boxes of ::before and ::after miss the offset of their
+    /// element.
+    pub fn relative_position_in_inline_flow(&self, flow: &Flow) -> Point2D<Au> {
+        let base = flow::base(flow);
+        let containing_block_size = base.position.size;
+
+        // A text box takes the style of its parent element, whose offset is added below.
+        let mut position = if self.node.is_element() {
+            self.relative_position(&containing_block_size)
+        } else {
+            Au::zero_point()
+        };
+        let mut node = if self.node == base.node {
+            None
+        } else {
+            self.node.parent_node()
+        };
+        loop {
+            match node {
+                Some(element) if element != base.node && element.is_element() => {
+                    position = position + relative_position_of_style(element.style().get(),
+                                                                     &containing_block_size);
+                    node = element.parent_node()
+                }
+                _ => return position,
+            }
+        }
+    }
+
     /// Converts this node's computed style to a font style used for rendering.
     ///
     /// FIXME(pcwalton): This should not be necessary; just make the font part of style sharable
@@ -425,6 +513,11 @@ impl Box {
         self.style().Text.text_align
     }
 
+    /// Returns the 'white-space' property of the nearest ancestor-or-self `Element` node.
+    pub fn white_space(&self) -> white_space::T {
+        self.style().Text.white_space
+    }
+
     pub fn line_height(&self) -> line_height::T {
         self.style().Box.line_height
     }
@@ -458,14 +551,62 @@ impl Box {
         }
     }
 
-    /// Returns true if this element can be split. This is true for text boxes.
+    /// Returns true if this element can be split. This is true for text boxes, unless
+    /// 'white-space' prevents wrapping.
     pub fn can_split(&self) -> bool {
         match self.specific {
-            ScannedTextBox(*) => true,
+            ScannedTextBox(*) => self.wraps(),
+            _ => false,
+        }
+    }
+
+    /// Returns true if lines may wrap inside this box according to 'white-space'.
+    pub fn wraps(&self) -> bool {
+        match self.white_space() {
+            white_space::normal | white_space::pre_wrap | white_space::pre_line => true,
+            white_space::pre | white_space::nowrap => false,
+        }
+    }
+
+    /// Returns true if this is a text box that ends with a newline preserved by 'white-space',
+    /// which forces a line break.
+    pub fn ends_with_newline(&self) -> bool {
+        match self.specific {
+            ScannedTextBox(ref text_box_info) => {
+                let ranges = newline_separated_ranges(text_box_info.run.get(),
+                                                      &text_box_info.range);
+                ranges.len() > 1 && ranges.last().length() == 0
+            }
             _ => false,
         }
     }
 
+    /// If this is a text box with a preserved newline before its end, splits it after the first
+    /// newline. The left box ends with the newline.
+    pub fn split_after_newline(&self) -> Option<(Box, Box)> {
+        let text_box_info = match self.specific {
+            ScannedTextBox(ref text_box_info) => text_box_info,
+            _ => return None,
+        };
+        let range = text_box_info.range;
+        let ranges = newline_separated_ranges(text_box_info.run.get(), &range);
+        if ranges.len() < 2 || ranges[1].begin() == range.end() {
+            return None
+        }
+        let left_range = Range::new(range.begin(), ranges[1].begin() - range.begin());
+        let right_range = Range::new(ranges[1].begin(), range.end() - ranges[1].begin());
+        let make_box = |range: Range| {
+            let new_text_box_info = ScannedTextBoxInfo::new(text_box_info.run.clone(), range);
+            let new_metrics = new_text_box_info.run.get().metrics_for_range(&range);
+            let mut new_text_box = Box::from_style(self.node, self.style.clone(),
+                                                   ScannedTextBox(new_text_box_info));
+            new_text_box.pseudo_element = self.pseudo_element;
+            new_text_box.set_size(new_metrics.bounding_box.size);
+            new_text_box
+        };
+        Some((make_box(left_range), make_box(right_range)))
+    }
+
     /// Returns the amount of left and right "fringe" used by this box. This is based on margins,
     /// borders, padding, and width.
     pub fn get_used_width(&self) -> (Au, Au) {
@@ -484,6 +625,7 @@ impl Box {
     /// necessary.
     pub fn paint_background_if_applicable<E:ExtraDisplayListData>(
                                           &self,
+                                          builder: &DisplayListBuilder,
                                           list: &Cell<DisplayList<E>>,
                                           absolute_bounds: &Rect<Au>) {
         // FIXME: This causes a lot of background colors to be displayed when they are clearly not
@@ -505,6 +647,80 @@ impl Box {
                 list.append_item(SolidColorDisplayItemClass(solid_color_display_item))
             })
         }
+
+        // The background image is painted on top of the background color.
+        let image_url = match style.Background.background_image {
+            Some(ref image_url) => image_url.clone(),
+            None => return,
+        };
+        let mut holder = ImageHolder::new(image_url, builder.ctx.image_cache.clone());
+        let image = match holder.get_image() {
+            Some(image) => image,
+            None => return,
+        };
+        let image_size = Size2D(Au::from_px(image.get().width as int),
+                                Au::from_px(image.get().height as int));
+        if image_size.width == Au(0) || image_size.height == Au(0) {
+            return
+        }
+
+        // Per CSS 2.1 § 14.2.1, the image is positioned relative to the padding box, or to the
+        // viewport if it is fixed, and it is painted within the border box.
+        let border = self.border.get();
+        let positioning_area = match style.Background.background_attachment {
+            background_attachment::scroll => {
+                Rect(Point2D(absolute_bounds.origin.x + border.left,
+                             absolute_bounds.origin.y + border.top),
+                     Size2D(absolute_bounds.size.width - border.left - border.right,
+                            absolute_bounds.size.height - border.top - border.bottom))
+            }
+            background_attachment::fixed => builder.ctx.screen_size,
+        };
+        let position = style.Background.background_position;
+        let origin = Point2D(
+            positioning_area.origin.x +
+                specified(position.horizontal,
+                          positioning_area.size.width - image_size.width),
+            positioning_area.origin.y +
+                specified(position.vertical,
+                          positioning_area.size.height - image_size.height));
+
+        let (repeat_x, repeat_y) = match style.Background.background_repeat {
+            background_repeat::repeat => (true, true),
+            background_repeat::repeat_x => (true, false),
+            background_repeat::repeat_y => (false, true),
+            background_repeat::no_repeat => (false, false),
+        };
+        let xs = tile_positions(origin.x, image_size.width, absolute_bounds.origin.x,
+                                absolute_bounds.origin.x + absolute_bounds.size.width, repeat_x);
+        let ys = tile_positions(origin.y, image_size.height, absolute_bounds.origin.y,
+                                absolute_bounds.origin.y + absolute_bounds.size.height, repeat_y);
+
+        let mut tiles = ~[];
+        for &y in ys.iter() {
+            for &x in xs.iter() {
+                tiles.push(ImageDisplayItemClass(~ImageDisplayItem {
+                    base: BaseDisplayItem {
+                        bounds: Rect(Point2D(x, y), image_size),
+                        extra: ExtraDisplayListData::new(self),
+                    },
+                    image: image.clone(),
+                }))
+            }
+        }
+
+        // Clip the tiles to the border box.
+        list.with_mut_ref(|list| {
+            let clip_display_item = ~ClipDisplayItem {
+                base: BaseDisplayItem {
+                    bounds: *absolute_bounds,
+                    extra: ExtraDisplayListData::new(self),
+                },
+                child_list: tiles,
+                need_clip: true
+            };
+            list.append_item(ClipDisplayItemClass(clip_display_item))
+        })
     }
 
     /// Adds the display items necessary to paint the borders of this box to a display list if
@@ -519,7 +735,17 @@ impl Box {
             return
         }
 
+        // In the collapsing border model, the cells paint the borders of the whole table.
         let style = self.style();
+        if style.Table.border_collapse == border_collapse::collapse {
+            match style.Box.display {
+                display::table | display::inline_table | display::table_row_group |
+                display::table_header_group | display::table_footer_group |
+                display::table_row | display::table_cell => return,
+                _ => {}
+            }
+        }
+
         let top_color = style.resolve_color(style.Border.border_top_color);
         let right_color = style.resolve_color(style.Border.border_right_color);
         let bottom_color = style.resolve_color(style.Border.border_bottom_color);
@@ -572,6 +798,12 @@ impl Box {
                               offset: Point2D<Au>,
                               flow: &Flow,
                               list: &Cell<DisplayList<E>>) {
+        // Block flows offset themselves, along with their descendants, if they are relatively
+        // positioned.
+        let offset = match flow.class() {
+            InlineFlowClass => offset + self.relative_position_in_inline_flow(flow),
+            _ => offset,
+        };
         let box_bounds = self.position.get();
         let absolute_box_bounds = box_bounds.translate(&offset);
         debug!("Box::build_display_list at rel={}, abs={}: {:s}",
@@ -582,6 +814,12 @@ impl Box {
             return;
         }
 
+        // The flow of an inline-block paints it.
+        match self.specific {
+            InlineBlockBox(_) => return,
+            _ => {}
+        }
+
         if absolute_box_bounds.intersects(dirty) {
             debug!("Box::build_display_list: intersected. Adding display item...");
         } else {
@@ -589,8 +827,16 @@ impl Box {
             return;
         }
 
+        // The items of this box, from here on, are the group to composite with the opacity of
+        // this box, if it is translucent. Block flows do that for their own box and descendants.
+        //
+        // FIXME: An inline element split across several boxes is composited once per box, and
+        // its descendants are not part of the group.
+        let opacity = self.style().Box.opacity;
+        let first_item = list.with_mut_ref(|list| list.list.len());
+
         // Add the background to the list, if applicable.
-        self.paint_background_if_applicable(list, &absolute_box_bounds);
+        self.paint_background_if_applicable(builder, list, &absolute_box_bounds);
 
         match self.specific {
             UnscannedTextBox(_) => fail!("Shouldn't see unscanned boxes here."),
@@ -679,6 +925,7 @@ impl Box {
                     ()
                 });
             },
+            InlineBlockBox(_) => {}
             GenericBox | IframeBox(_) => {
                 do list.with_mut_ref |list| {
                     let item = ~ClipDisplayItem {
@@ -767,24 +1014,55 @@ impl Box {
             IframeBox(ref iframe_box) => {
                 self.finalize_position_and_size_of_iframe(iframe_box, offset, builder.ctx)
             }
-            GenericBox | ImageBox(_) | ScannedTextBox(_) | UnscannedTextBox(_) => {}
+            GenericBox | ImageBox(_) | InlineBlockBox(_) | ScannedTextBox(_) |
+            UnscannedTextBox(_) => {}
         }
 
         // Add a border, if applicable.
         //
         // TODO: Outlines.
         self.paint_borders_if_applicable(list, &absolute_box_bounds);
+
+        let in_block_flow = flow.is_block_based();
+        if opacity < 1.0 && !in_block_flow {
+            do list.with_mut_ref |list| {
+                let mut child_list = ~[];
+                while list.list.len() > first_item {
+                    child_list.push(list.list.pop());
+                }
+                child_list.reverse();
+                let opacity_display_item = ~OpacityDisplayItem {
+                    base: BaseDisplayItem {
+                        bounds: absolute_box_bounds,
+                        extra: ExtraDisplayListData::new(self),
+                    },
+                    child_list: child_list,
+                    opacity: opacity,
+                };
+                list.append_item(OpacityDisplayItemClass(opacity_display_item))
+            }
+        }
     }
 
     /// Returns the *minimum width* and *preferred width* of this box as defined by CSS 2.1.
     pub fn minimum_and_preferred_widths(&self) -> (Au, Au) {
         let guessed_width = self.guess_width();
         let (additional_minimum, additional_preferred) = match self.specific {
-            GenericBox | IframeBox(_) => (Au(0), Au(0)),
+            GenericBox | IframeBox(_) | InlineBlockBox(_) => (Au(0), Au(0)),
             ImageBox(ref image_box_info) => {
                 let image_width = image_box_info.image_width();
                 (image_width, image_width)
             }
+            ScannedTextBox(ref text_box_info) if !self.wraps() => {
+                // Only preserved newlines can break the text.
+                let run = text_box_info.run.get();
+                let mut line_width = Au::new(0);
+                for line_range in newline_separated_ranges(run, &text_box_info.range).iter() {
+                    line_width = Au::max(line_width,
+                                         run.metrics_for_range(line_range).advance_width);
+                }
+                (line_width, line_width)
+            }
             ScannedTextBox(ref text_box_info) => {
                 let range = &text_box_info.range;
                 let min_line_width = text_box_info.run.get().min_width_for_range(range);
@@ -809,6 +1087,8 @@ impl Box {
     pub fn box_height(&self) -> Au {
         match self.specific {
             GenericBox | IframeBox(_) => Au(0),
+            // The inline flow sizes the box from the flow of the inline-block.
+            InlineBlockBox(_) => self.position.get().size.height,
             ImageBox(ref image_box_info) => {
                 let size = image_box_info.image.mutate().ptr.get_size();
                 let height = Au::from_px(size.unwrap_or(Size2D(0, 0)).height);
@@ -833,7 +1113,7 @@ impl Box {
     /// Attempts to split this box so that its width is no more than `max_width`.
     pub fn split_to_width(&self, max_width: Au, starts_line: bool) -> SplitBoxResult {
         match self.specific {
-            GenericBox | IframeBox(_) | ImageBox(_) => CannotSplit,
+            GenericBox | IframeBox(_) | ImageBox(_) | InlineBlockBox(_) => CannotSplit,
             UnscannedTextBox(_) => fail!("Unscanned text boxes should have been scanned by now!"),
             ScannedTextBox(ref text_box_info) => {
                 let mut pieces_processed_count: uint = 0;
@@ -908,7 +1188,9 @@ impl Box {
                 let left_box = if left_range.length() > 0 {
                     let new_text_box_info = ScannedTextBoxInfo::new(text_box_info.run.clone(), left_range);
                     let new_metrics = new_text_box_info.run.get().metrics_for_range(&left_range);
-                    let new_text_box = Box::new(self.node, ScannedTextBox(new_text_box_info));
+                    let mut new_text_box = Box::from_style(self.node, self.style.clone(),
+                                                           ScannedTextBox(new_text_box_info));
+                    new_text_box.pseudo_element = self.pseudo_element;
                     new_text_box.set_size(new_metrics.bounding_box.size);
                     Some(new_text_box)
                 } else {
@@ -918,7 +1200,9 @@ impl Box {
                 let right_box = right_range.map_default(None, |range: Range| {
                     let new_text_box_info = ScannedTextBoxInfo::new(text_box_info.run.clone(), range);
                     let new_metrics = new_text_box_info.run.get().metrics_for_range(&range);
-                    let new_text_box = Box::new(self.node, ScannedTextBox(new_text_box_info));
+                    let mut new_text_box = Box::from_style(self.node, self.style.clone(),
+                                                           ScannedTextBox(new_text_box_info));
+                    new_text_box.pseudo_element = self.pseudo_element;
                     new_text_box.set_size(new_metrics.bounding_box.size);
                     Some(new_text_box)
                 });
@@ -932,10 +1216,18 @@ impl Box {
         }
     }
 
-    /// Returns true if this box is an unscanned text box that consists entirely of whitespace.
+    /// Returns true if this box is an unscanned text box that consists entirely of whitespace
+    /// that 'white-space' allows to collapse.
     pub fn is_whitespace_only(&self) -> bool {
         match self.specific {
-            UnscannedTextBox(ref text_box_info) => text_box_info.text.is_whitespace(),
+            UnscannedTextBox(ref text_box_info) => {
+                // Preserved whitespace is never ignorable.
+                text_box_info.text.is_whitespace() && match self.white_space() {
+                    white_space::normal | white_space::nowrap => true,
+                    white_space::pre_line => !text_box_info.text.contains_char('\n'),
+                    white_space::pre | white_space::pre_wrap => false,
+                }
+            }
             _ => false,
         }
     }
@@ -951,8 +1243,9 @@ impl Box {
                 let image_width = image_box_info.image_width();
                 self.position.mutate().ptr.size.width = image_width
             }
-            ScannedTextBox(_) => {
-                // Scanned text boxes will have already had their widths assigned by this point.
+            ScannedTextBox(_) | InlineBlockBox(_) => {
+                // Scanned text boxes will have already had their widths assigned by this point,
+                // and the inline flow sizes inline-block boxes from their flows.
             }
             UnscannedTextBox(_) => fail!("Unscanned text boxes should have been scanned by now!"),
         }
@@ -962,8 +1255,14 @@ impl Box {
     pub fn can_merge_with_box(&self, other: &Box) -> bool {
         match (&self.specific, &other.specific) {
             (&UnscannedTextBox(_), &UnscannedTextBox(_)) => {
+                let (text, other_text) = (&self.style().Text, &other.style().Text);
                 self.font_style() == other.font_style() &&
-                    self.text_decoration() == other.text_decoration()
+                    self.style().Color.color == other.style().Color.color &&
+                    self.text_decoration() == other.text_decoration() &&
+                    text.white_space == other_text.white_space &&
+                    text.text_transform == other_text.text_transform &&
+                    text.letter_spacing == other_text.letter_spacing &&
+                    text.word_spacing == other_text.word_spacing
             }
             _ => false,
         }
@@ -977,9 +1276,12 @@ impl Box {
         }
     }
 
-    /// Returns true if the contents should be clipped (i.e. if `overflow` is `hidden`).
+    /// Returns true if the contents should be clipped (i.e. if `overflow` is not `visible`).
+    ///
+    /// FIXME: 'scroll' and 'auto' clip like 'hidden'. There are no scroll bars, and there is no way
+    /// to scroll the contents yet.
     pub fn needs_clip(&self) -> bool {
-        self.style().Box.overflow == overflow::hidden
+        self.style().Box.overflow != overflow::visible
     }
 
     /// Returns a debugging string describing this box.
@@ -988,6 +1290,7 @@ impl Box {
             GenericBox => "GenericBox",
             IframeBox(_) => "IframeBox",
             ImageBox(_) => "ImageBox",
+            InlineBlockBox(_) => "InlineBlockBox",
             ScannedTextBox(_) => "ScannedTextBox",
             UnscannedTextBox(_) => "UnscannedTextBox",
         };
@@ -1039,3 +1342,105 @@ impl Box {
     }
 }
 
+/// Returns the positions along one axis of the tiles of a background image of length `length`
+/// placed at `origin`, that intersect the range from `start` to `end`.
+fn tile_positions(origin: Au, length: Au, start: Au, end: Au, repeat: bool) -> ~[Au] {
+    if !repeat {
+        return if origin < end && origin + length > start { ~[origin] } else { ~[] }
+    }
+    // The first tile at or before `start`.
+    let mut offset = (origin - start) % length;
+    if offset > Au(0) {
+        offset = offset - length
+    }
+    let mut positions = ~[];
+    let mut position = start + offset;
+    while position < end {
+        positions.push(position);
+        position = position + length;
+    }
+    positions
+}
+
+/// Splits `range`, a range of characters of `run`, at newline characters. The newlines are not
+/// part of the resulting ranges, so a range ending with a newline is followed by an empty one.
+fn newline_separated_ranges(run: &TextRun, range: &Range) -> ~[Range] {
+    let mut ranges = ~[];
+    let mut start = range.begin();
+    for (i, ch) in run.text.get().iter().enumerate() {
+        if i >= range.end() {
+            break
+        }
+        if i >= range.begin() && ch == '\n' {
+            ranges.push(Range::new(start, i - start));
+            start = i + 1;
+        }
+    }
+    ranges.push(Range::new(start, range.end() - start));
+    ranges
+}
+
+/// Returns the offset from its position in the normal flow of a box with the given style, if it is
+/// relatively positioned (CSS 2.1 § 9.4.3). 'left' wins over 'right', and 'top' over 'bottom'.
+fn relative_position_of_style(style: &ComputedValues, containing_block_size: &Size2D<Au>)
+                              -> Point2D<Au> {
+    if style.Box.position != position::relative {
+        return Au::zero_point()
+    }
+    let x = match (MaybeAuto::from_style(style.Box.left, containing_block_size.width),
+                   MaybeAuto::from_style(style.Box.right, containing_block_size.width)) {
+        (Specified(left), _) => left,
+        (Auto, Specified(right)) => -right,
+        (Auto, Auto) => Au(0),
+    };
+    let y = match (MaybeAuto::from_style(style.Box.top, containing_block_size.height),
+                   MaybeAuto::from_style(style.Box.bottom, containing_block_size.height)) {
+        (Specified(top), _) => top,
+        (Auto, Specified(bottom)) => -bottom,
+        (Auto, Auto) => Au(0),
+    };
+    Point2D(x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::relative_position_of_style;
+    use geom::{Point2D, Size2D};
+    use servo_util::geometry::Au;
+    use std::from_str::FromStr;
+    use style::{ComputedValues, Device, LogErrorReporter, Screen, cascade};
+    use style::parse_style_attribute;
+
+    fn px(value: int) -> Au {
+        Au::from_px(value)
+    }
+
+    fn style(declarations: &str) -> ComputedValues {
+        let block = parse_style_attribute(declarations,
+                                          &FromStr::from_str("http://example.com/").unwrap(),
+                                          &mut LogErrorReporter);
+        cascade([block.normal.clone()], None, &Device::new(Screen, px(800), px(600), 1.))
+    }
+
+    #[test]
+    fn test_relative_position_of_style() {
+        let containing_block_size = Size2D(px(200), px(100));
+        let position = |declarations: &str| {
+            relative_position_of_style(&style(declarations), &containing_block_size)
+        };
+        assert_eq!(position("position: relative; left: 10px; top: 20px"),
+                   Point2D(px(10), px(20)));
+        // 'right' and 'bottom' move the other way.
+        assert_eq!(position("position: relative; right: 10px; bottom: 20px"),
+                   Point2D(px(-10), px(-20)));
+        // 'left' wins over 'right', and 'top' over 'bottom'.
+        assert_eq!(position("position: relative; left: 10px; right: 30px; top: 20px; bottom: 5px"),
+                   Point2D(px(10), px(20)));
+        // Percentages refer to the size of the containing block.
+        assert_eq!(position("position: relative; left: 10%; bottom: 50%"),
+                   Point2D(px(20), px(-50)));
+        // Boxes that are not relatively positioned stay in place.
+        assert_eq!(position("position: static; left: 10px; top: 20px"), Point2D(px(0), px(0)));
+        assert_eq!(position("position: relative"), Point2D(px(0), px(0)));
+    }
+}
@@ -11,34 +11,60 @@
 //! maybe it's an absolute or fixed position thing that hasn't found its containing block yet.
 //! Construction items bubble up the tree from children to parents until they find their homes.
 //!
-//! TODO(pcwalton): There is no incremental reflow yet. This scheme requires that nodes either have
-//! weak references to flows or that there be some mechanism to efficiently (O(1) time) "blow
-//! apart" a flow tree and have the flows migrate "home" to their respective DOM nodes while we
-//! perform flow tree construction. The precise mechanism for this will take some experimentation
-//! to get right.
+//! Before construction, `reclaim_flows` blows apart the flow tree of the last reflow: the flows of
+//! the nodes that have not been damaged since, along with everything under them, migrate "home" to
+//! their respective DOM nodes as their construction results, and the traversal prunes those
+//! nodes. The damage of a node is propagated to its ancestors first, since their flows hold its
+//! flows and boxes and so need to be rebuilt as well.
+//!
+//! FIXME: Blowing apart the tree takes time proportional to the number of damaged flows, not O(1)
+//! time. Boxes also keep copies of the styles of their nodes, so even a change that only needs a
+//! repaint rebuilds the flows of the node for now.
 //!
 //! TODO(pcwalton): This scheme should be amenable to parallelization, but, of course, that's not
 //! yet implemented.
 
 use css::node_style::StyledNode;
+use css::node_util::NodeUtil;
+use layout::absolute::AbsoluteFlow;
 use layout::block::BlockFlow;
 use layout::box::{Box, GenericBox, IframeBox, IframeBoxInfo, ImageBox, ImageBoxInfo};
-use layout::box::{UnscannedTextBox, UnscannedTextBoxInfo};
+use layout::box::{InlineBlockBox, InlineBlockBoxInfo, UnscannedTextBox, UnscannedTextBoxInfo};
 use layout::context::LayoutContext;
 use layout::float_context::FloatType;
-use layout::flow::{Flow, FlowData, MutableFlowUtils};
+use layout::flow::{AbsoluteFlowClass, Flow, FlowClass, FlowData, ImmutableFlowUtils};
+use layout::flow::{InlineBlockFlowClass, InlineFlowClass, MutableFlowUtils};
+use layout::flow::{TableCaptionFlowClass, TableCellFlowClass, TableRowFlowClass};
+use layout::flow::{TableRowGroupFlowClass};
+use layout::flow;
+use layout::incremental::{ReconstructFlow, RestyleDamage};
 use layout::inline::InlineFlow;
+use layout::inline_block::InlineBlockFlow;
+use layout::table::TableFlow;
+use layout::table_caption::TableCaptionFlow;
+use layout::table_cell::TableCellFlow;
+use layout::table_row::TableRowFlow;
+use layout::table_rowgroup::TableRowGroupFlow;
 use layout::text::TextRunScanner;
 use layout::util::LayoutDataAccess;
 
 use script::dom::element::{HTMLIframeElementTypeId, HTMLImageElementTypeId};
+use script::dom::element::{HTMLTableDataCellElementTypeId, HTMLTableHeaderCellElementTypeId};
 use script::dom::node::{AbstractNode, CommentNodeTypeId, DoctypeNodeTypeId};
 use script::dom::node::{DocumentFragmentNodeTypeId, DocumentNodeTypeId, ElementNodeTypeId};
-use script::dom::node::{LayoutView, PostorderNodeMutTraversal, TextNodeTypeId};
+use script::dom::node::{LayoutView, PostorderNodeMutTraversal, PostorderNodeTraversal};
+use script::dom::node::{TextNodeTypeId};
+use extra::arc::Arc;
 use servo_util::slot::Slot;
-use servo_util::tree::TreeNodeRef;
+use servo_util::tree::{TreeNodeRef, ElementLike};
+use std::cmp;
+use std::str;
+use std::uint;
 use std::util;
-use style::computed_values::{display, float};
+use std::vec;
+use style::{ComputedValues, cascade};
+use style::computed_values::{LengthOrPercentageOrAuto, display, float, position};
+use style::{PseudoElement, Before, After, FirstLine, FirstLetter};
 
 /// The results of flow construction for a DOM node.
 pub enum ConstructionResult {
@@ -61,6 +87,10 @@ pub enum ConstructionResult {
 enum ConstructionItem {
     /// Inline boxes and associated {ib} splits that have not yet found flows.
     InlineBoxesConstructionItem(InlineBoxesConstructionResult),
+
+    /// The 'width' of each column that a column or column group stands for, bubbling up to its
+    /// table. Anything other than a table ignores them.
+    TableColumnsConstructionItem(~[LengthOrPercentageOrAuto]),
 }
 
 /// Represents inline boxes and {ib} splits that are bubbling up from an inline.
@@ -72,6 +102,14 @@ struct InlineBoxesConstructionResult {
 
     /// Any boxes that succeed the {ib} splits.
     boxes: ~[Box],
+
+    /// Any absolutely or fixed positioned flows that we're bubbling up. They are out of the normal
+    /// flow, so they do not split the inline.
+    absolute_flows: Option<~[~Flow:]>,
+
+    /// The flows of any inline-blocks among the boxes, in document order. They will become
+    /// children of the inline flows that hold their boxes.
+    inline_block_flows: Option<~[~Flow:]>,
 }
 
 /// Represents an {ib} split that has not yet found the containing block that it belongs to. This
@@ -183,11 +221,13 @@ pub struct FlowConstructor<'self> {
 }
 
 impl<'self> FlowConstructor<'self> {
-    /// Creates a new flow constructor.
-    pub fn init<'a>(layout_context: &'a mut LayoutContext) -> FlowConstructor<'a> {
+    /// Creates a new flow constructor that assigns IDs from `next_flow_id` on. Flows reused from
+    /// the last reflow keep their IDs, so the layout task keeps the counter across reflows.
+    pub fn init<'a>(layout_context: &'a mut LayoutContext, next_flow_id: int)
+                    -> FlowConstructor<'a> {
         FlowConstructor {
             layout_context: layout_context,
-            next_flow_id: Slot::init(0),
+            next_flow_id: Slot::init(next_flow_id),
         }
     }
 
@@ -198,6 +238,11 @@ impl<'self> FlowConstructor<'self> {
         id
     }
 
+    /// Returns the ID that the next flow constructed will have.
+    pub fn unused_flow_id(&self) -> int {
+        self.next_flow_id.get()
+    }
+
     /// Builds the `ImageBoxInfo` for the given image. This is out of line to guide inlining.
     fn build_box_info_for_image(&mut self, node: AbstractNode<LayoutView>) -> Option<ImageBoxInfo> {
         // FIXME(pcwalton): Don't copy URLs.
@@ -231,32 +276,177 @@ impl<'self> FlowConstructor<'self> {
         Box::new(node, specific)
     }
 
+    /// Builds the box of the ::before or ::after pseudo-element of the given element, if it
+    /// generates one.
+    ///
+    /// FIXME: Generated content is always inline, whatever its 'display' property.
+    fn build_box_for_pseudo_element(&mut self,
+                                    node: AbstractNode<LayoutView>,
+                                    pseudo_element: PseudoElement)
+                                    -> Option<Box> {
+        if !node.is_element() {
+            return None
+        }
+        match *node.mutate_layout_data().ptr {
+            Some(ref mut layout_data) => {
+                let data = layout_data.pseudo_element(pseudo_element);
+                match (&data.style, &data.content) {
+                    (&Some(ref style), &Some(ref text)) => {
+                        let info = UnscannedTextBoxInfo::from_text(text.clone());
+                        let mut box = Box::from_style(node, style.clone(), UnscannedTextBox(info));
+                        box.pseudo_element = Some(pseudo_element);
+                        Some(box)
+                    }
+                    _ => None,
+                }
+            }
+            None => fail!("no layout data"),
+        }
+    }
+
+    /// Builds the construction result of the ::before or ::after pseudo-element of the given
+    /// element: its box, as the inline contents of the element. Tables and their rows and row
+    /// groups wrap it in anonymous table parts like their other inline contents.
+    fn build_construction_result_for_pseudo_element(&mut self,
+                                                    node: AbstractNode<LayoutView>,
+                                                    pseudo_element: PseudoElement)
+                                                    -> ConstructionResult {
+        match self.build_box_for_pseudo_element(node, pseudo_element) {
+            Some(box) => {
+                let construction_item =
+                    InlineBoxesConstructionItem(InlineBoxesConstructionResult {
+                        splits: None,
+                        boxes: ~[box],
+                        absolute_flows: None,
+                        inline_block_flows: None,
+                    });
+                ConstructionItemConstructionResult(construction_item)
+            }
+            None => NoConstructionResult,
+        }
+    }
+
+    /// Creates an inline flow from a set of inline boxes and scans its text into runs.
+    fn build_inline_flow(&mut self, boxes: ~[Box], node: AbstractNode<LayoutView>) -> ~Flow: {
+        let inline_base = FlowData::new(self.next_flow_id(), node);
+        let mut inline_flow = ~InlineFlow::from_boxes(inline_base, boxes) as ~Flow:;
+        TextRunScanner::new().scan_for_runs(self.layout_context, inline_flow);
+        inline_flow
+    }
+
+    /// Splits the ::first-letter pseudo-element of `node` off the first text of `boxes`, which
+    /// start the first line of `node`. A floated first letter becomes a float child of `flow` that
+    /// precedes the line (CSS 2.1 § 5.12.2).
+    fn build_first_letter(&mut self,
+                          boxes: &mut ~[Box],
+                          flow: &mut ~Flow:,
+                          node: AbstractNode<LayoutView>) {
+        let style = match node.pseudo_element_style(FirstLetter) {
+            Some(style) => style,
+            None => return,
+        };
+        let index = match boxes.iter().position(|box| !box.is_whitespace_only()) {
+            Some(index) => index,
+            None => return,
+        };
+        let (letter, rest) = match boxes[index].specific {
+            UnscannedTextBox(ref text_box_info) => {
+                match split_first_letter(text_box_info.text) {
+                    Some(split) => split,
+                    None => return,
+                }
+            }
+            _ => return,
+        };
+
+        if rest.is_empty() {
+            let _ = boxes.remove(index);
+        } else {
+            let rest_box = Box::from_style(boxes[index].node,
+                                           boxes[index].style.clone(),
+                                           UnscannedTextBox(UnscannedTextBoxInfo::from_text(rest)));
+            boxes[index] = rest_box
+        }
+
+        let letter_info = UnscannedTextBoxInfo::from_text(letter);
+        let mut letter_box = Box::from_style(node, style.clone(), UnscannedTextBox(letter_info));
+        letter_box.pseudo_element = Some(FirstLetter);
+        match style.get().Box.float {
+            float::none => boxes.insert(index, letter_box),
+            float_value => {
+                let base = FlowData::new(self.next_flow_id(), node);
+                let mut box = Box::from_style(node, style.clone(), GenericBox);
+                box.pseudo_element = Some(FirstLetter);
+                let float_type = FloatType::from_property(float_value);
+                let mut float_flow = ~BlockFlow::float_from_box(base, float_type, box) as ~Flow:;
+                let mut letter_flow = self.build_inline_flow(~[letter_box], node);
+                letter_flow.as_inline().indent_first_line = false;
+                float_flow.add_new_child(letter_flow);
+                flow.add_new_child(float_flow)
+            }
+        }
+    }
+
     /// Creates an inline flow from a set of inline boxes and adds it as a child of the given flow.
+    /// `is_first_line` is true if the boxes start the first line of `node`, which gets its
+    /// ::first-letter and ::first-line styles and 'text-indent'.
     ///
     /// `#[inline(always)]` because this is performance critical and LLVM will not inline it
     /// otherwise.
     #[inline(always)]
     fn flush_inline_boxes_to_flow(&mut self,
                                   boxes: ~[Box],
+                                  inline_block_flows: &mut ~[~Flow:],
                                   flow: &mut ~Flow:,
-                                  node: AbstractNode<LayoutView>) {
+                                  node: AbstractNode<LayoutView>,
+                                  is_first_line: bool) {
+        let mut boxes = boxes;
+        let first_line_style = if is_first_line {
+            self.build_first_letter(&mut boxes, flow, node);
+            node.pseudo_element_style(FirstLine)
+        } else {
+            None
+        };
         if boxes.len() > 0 {
-            let inline_base = FlowData::new(self.next_flow_id(), node);
-            let mut inline_flow = ~InlineFlow::from_boxes(inline_base, boxes) as ~Flow:;
-            TextRunScanner::new().scan_for_runs(self.layout_context, inline_flow);
+            let mut inline_block_count = 0;
+            for box in boxes.iter() {
+                match box.specific {
+                    InlineBlockBox(_) => inline_block_count += 1,
+                    _ => {}
+                }
+            }
+
+            let mut inline_flow = self.build_inline_flow(boxes, node);
+            {
+                let inline = inline_flow.as_inline();
+                inline.first_line_style = first_line_style;
+                inline.indent_first_line = is_first_line;
+            }
+
+            // The flows of the inline-blocks among the boxes are next in line.
+            for _ in range(0, inline_block_count) {
+                inline_flow.add_new_child(inline_block_flows.remove(0))
+            }
             flow.add_new_child(inline_flow)
         }
     }
 
     /// Creates an inline flow from a set of inline boxes, if present, and adds it as a child of
-    /// the given flow.
+    /// the given flow. Clears `is_first_line` if it does.
     fn flush_inline_boxes_to_flow_if_necessary(&mut self,
                                                opt_boxes: &mut Option<~[Box]>,
+                                               inline_block_flows: &mut ~[~Flow:],
                                                flow: &mut ~Flow:,
-                                               node: AbstractNode<LayoutView>) {
+                                               node: AbstractNode<LayoutView>,
+                                               is_first_line: &mut bool) {
         let opt_boxes = util::replace(opt_boxes, None);
         if opt_boxes.len() > 0 {
-            self.flush_inline_boxes_to_flow(opt_boxes.to_vec(), flow, node)
+            self.flush_inline_boxes_to_flow(opt_boxes.to_vec(),
+                                            inline_block_flows,
+                                            flow,
+                                            node,
+                                            *is_first_line);
+            *is_first_line = false
         }
     }
 
@@ -266,13 +456,55 @@ impl<'self> FlowConstructor<'self> {
     fn build_children_of_block_flow(&mut self,
                                     flow: &mut ~Flow:,
                                     node: AbstractNode<LayoutView>) {
-        // Gather up boxes for the inline flows we might need to create.
+        let before = self.build_box_for_pseudo_element(node, Before);
+        let contents: ~[ConstructionResult] = node.children().map(|kid| {
+            kid.swap_out_construction_result()
+        }).collect();
+        let after = self.build_box_for_pseudo_element(node, After);
+        self.build_block_contents(flow, node, before, contents, after)
+    }
+
+    /// Adds `contents`, the construction results of the children of `node`, to `flow`, which
+    /// lays them out as a block does, between the boxes of the ::before and ::after
+    /// pseudo-elements of `node`, if any.
+    fn build_block_contents(&mut self,
+                            flow: &mut ~Flow:,
+                            node: AbstractNode<LayoutView>,
+                            before: Option<Box>,
+                            contents: ~[ConstructionResult],
+                            after: Option<Box>) {
+        // Gather up boxes for the inline flows we might need to create, and the flows of the
+        // inline-blocks among them.
         let mut opt_boxes_for_inline_flow = None;
+        let mut inline_block_flows = ~[];
         let mut first_box = true;
-        for kid in node.children() {
-            match kid.swap_out_construction_result() {
+
+        // Table parts that are waiting for the anonymous table that will hold them.
+        let mut table_parts = ~[];
+
+        // Whether the first line of this block is still to come. Floats do not end it.
+        //
+        // FIXME: If the block starts with a block child, the first line of that child should be
+        // the first line of this block.
+        let mut is_first_line = true;
+        for box in before.move_iter() {
+            opt_boxes_for_inline_flow.push(box)
+        }
+        for result in contents.move_iter() {
+            match result {
                 NoConstructionResult => {}
-                FlowConstructionResult(kid_flow) => {
+                ConstructionItemConstructionResult(TableColumnsConstructionItem(_)) => {}
+                FlowConstructionResult(mut kid_flow) => {
+                    // Absolutely positioned flows are out of the normal flow, so they neither
+                    // break the inline content around them nor end the first line.
+                    //
+                    // FIXME: The static position of such a flow is approximated by the start of
+                    // the inline content that surrounds it.
+                    if kid_flow.is_absolutely_positioned() {
+                        flow.add_new_child(kid_flow);
+                        continue
+                    }
+
                     // Strip ignorable whitespace from the start of this flow per CSS 2.1 §
                     // 9.2.1.1.
                     if first_box {
@@ -286,15 +518,36 @@ impl<'self> FlowConstructor<'self> {
                            opt_boxes_for_inline_flow.as_ref()
                                                     .map_default(0, |boxes| boxes.len()));
                     self.flush_inline_boxes_to_flow_if_necessary(&mut opt_boxes_for_inline_flow,
+                                                                 &mut inline_block_flows,
                                                                  flow,
-                                                                 node);
+                                                                 node,
+                                                                 &mut is_first_line);
+                    if !kid_flow.is_block_like() || !kid_flow.as_block().is_float() {
+                        is_first_line = false
+                    }
+                    if is_table_part(kid_flow.class()) {
+                        table_parts.push(kid_flow);
+                        continue
+                    }
+                    self.flush_table_parts_to_flow_if_necessary(&mut table_parts, flow, node);
                     flow.add_new_child(kid_flow);
                 }
                 ConstructionItemConstructionResult(InlineBoxesConstructionItem(
                         InlineBoxesConstructionResult {
                             splits: opt_splits,
-                            boxes: boxes
+                            boxes: boxes,
+                            absolute_flows: opt_absolute_flows,
+                            inline_block_flows: opt_inline_block_flows
                         })) => {
+                    // Add any absolutely positioned flows.
+                    for kid_flow in opt_absolute_flows.to_vec().move_iter() {
+                        flow.add_new_child(kid_flow)
+                    }
+
+                    // Keep the flows of any inline-blocks for the inline flows that will hold
+                    // their boxes.
+                    inline_block_flows.push_all_move(opt_inline_block_flows.to_vec());
+
                     // Add any {ib} splits.
                     match opt_splits {
                         None => {}
@@ -306,7 +559,11 @@ impl<'self> FlowConstructor<'self> {
                                     predecessor_boxes: predecessor_boxes,
                                     flow: kid_flow
                                 } = split;
-                                opt_boxes_for_inline_flow.push_all_move(predecessor_boxes);
+                                self.push_boxes_after_table_parts(predecessor_boxes,
+                                                                  &mut opt_boxes_for_inline_flow,
+                                                                  &mut table_parts,
+                                                                  flow,
+                                                                  node);
 
                                 // If this is the first box in flow, then strip ignorable
                                 // whitespace per CSS 2.1 § 9.2.1.1.
@@ -323,28 +580,89 @@ impl<'self> FlowConstructor<'self> {
                                                                              |boxes| boxes.len()));
                                 self.flush_inline_boxes_to_flow_if_necessary(
                                         &mut opt_boxes_for_inline_flow,
+                                        &mut inline_block_flows,
                                         flow,
-                                        node);
+                                        node,
+                                        &mut is_first_line);
 
                                 // Push the flow generated by the {ib} split onto our list of
-                                // flows.
+                                // flows, or onto the table parts that wait for a table.
+                                is_first_line = false;
+                                if is_table_part(kid_flow.class()) {
+                                    table_parts.push(kid_flow);
+                                    continue
+                                }
+                                self.flush_table_parts_to_flow_if_necessary(&mut table_parts,
+                                                                            flow,
+                                                                            node);
                                 flow.add_new_child(kid_flow);
                             }
                         }
                     }
 
                     // Add the boxes to the list we're maintaining.
-                    opt_boxes_for_inline_flow.push_all_move(boxes)
+                    self.push_boxes_after_table_parts(boxes,
+                                                      &mut opt_boxes_for_inline_flow,
+                                                      &mut table_parts,
+                                                      flow,
+                                                      node)
                 }
             }
         }
 
-        // Perform a final flush of any inline boxes that we were gathering up to handle {ib}
-        // splits, after stripping ignorable whitespace.
+        for box in after.move_iter() {
+            self.push_boxes_after_table_parts(~[box],
+                                              &mut opt_boxes_for_inline_flow,
+                                              &mut table_parts,
+                                              flow,
+                                              node)
+        }
+
+        // Perform a final flush of any table parts or inline boxes that we were gathering up to
+        // handle {ib} splits, after stripping ignorable whitespace.
+        self.flush_table_parts_to_flow_if_necessary(&mut table_parts, flow, node);
         strip_ignorable_whitespace_from_end(&mut opt_boxes_for_inline_flow);
         self.flush_inline_boxes_to_flow_if_necessary(&mut opt_boxes_for_inline_flow,
+                                                     &mut inline_block_flows,
                                                      flow,
-                                                     node);
+                                                     node,
+                                                     &mut is_first_line);
+    }
+
+    /// Adds `boxes` to the inline boxes that we are gathering up in `opt_boxes`. If there are
+    /// table parts waiting for an anonymous table in `table_parts`, then whitespace-only boxes
+    /// between them are dropped, and anything else ends the table first (CSS 2.1 § 17.2.1).
+    fn push_boxes_after_table_parts(&mut self,
+                                    boxes: ~[Box],
+                                    opt_boxes: &mut Option<~[Box]>,
+                                    table_parts: &mut ~[~Flow:],
+                                    flow: &mut ~Flow:,
+                                    node: AbstractNode<LayoutView>) {
+        if !table_parts.is_empty() {
+            if boxes.iter().all(|box| box.is_whitespace_only()) {
+                return
+            }
+            self.flush_table_parts_to_flow_if_necessary(table_parts, flow, node)
+        }
+        opt_boxes.push_all_move(boxes)
+    }
+
+    /// Wraps `table_parts`, consecutive table parts among the children of `node` that have no
+    /// table to hold them, in an anonymous table, if there are any, and adds it as a child of the
+    /// given flow (CSS 2.1 § 17.2.1).
+    fn flush_table_parts_to_flow_if_necessary(&mut self,
+                                              table_parts: &mut ~[~Flow:],
+                                              flow: &mut ~Flow:,
+                                              node: AbstractNode<LayoutView>) {
+        if table_parts.is_empty() {
+            return
+        }
+        let table_parts = util::replace(table_parts, ~[]);
+        let contents: ~[ConstructionResult] = table_parts.move_iter().map(|table_part| {
+            FlowConstructionResult(table_part)
+        }).collect();
+        let table_flow = self.build_anonymous_table_flow(node, display::table, contents);
+        flow.add_new_child(table_flow)
     }
 
     /// Builds a flow for a node with `display: block`. This yields a `BlockFlow` with possibly
@@ -358,6 +676,17 @@ impl<'self> FlowConstructor<'self> {
         flow
     }
 
+    /// Builds the flow for a node with `position: {absolute|fixed}`. This yields an `AbsoluteFlow`
+    /// with flows underneath it as for a block.
+    fn build_flow_for_absolute(&mut self, node: AbstractNode<LayoutView>, is_fixed: bool)
+                               -> ~Flow: {
+        let base = FlowData::new(self.next_flow_id(), node);
+        let box = self.build_box_for_node(node);
+        let mut flow = ~AbsoluteFlow::from_box(base, box, is_fixed) as ~Flow:;
+        self.build_children_of_block_flow(&mut flow, node);
+        flow
+    }
+
     /// Builds the flow for a node with `float: {left|right}`. This yields a float `BlockFlow` with
     /// a `BlockFlow` underneath it.
     fn build_flow_for_floated_block(&mut self, node: AbstractNode<LayoutView>, float_type: FloatType)
@@ -376,11 +705,21 @@ impl<'self> FlowConstructor<'self> {
                                                   -> ConstructionResult {
         let mut opt_inline_block_splits = None;
         let mut opt_box_accumulator = None;
+        let mut opt_absolute_flows = None;
+        let mut opt_inline_block_flows = None;
+        for box in self.build_box_for_pseudo_element(node, Before).move_iter() {
+            opt_box_accumulator.push(box)
+        }
 
         // Concatenate all the boxes of our kids, creating {ib} splits as necessary.
         for kid in node.children() {
             match kid.swap_out_construction_result() {
                 NoConstructionResult => {}
+                ConstructionItemConstructionResult(TableColumnsConstructionItem(_)) => {}
+                FlowConstructionResult(flow) if flow.is_absolutely_positioned() => {
+                    // Absolutely positioned flows bubble up to the enclosing block.
+                    opt_absolute_flows.push(flow)
+                }
                 FlowConstructionResult(flow) => {
                     // {ib} split. Flush the accumulator to our new split and make a new
                     // accumulator to hold any subsequent boxes we come across.
@@ -393,8 +732,14 @@ impl<'self> FlowConstructor<'self> {
                 ConstructionItemConstructionResult(InlineBoxesConstructionItem(
                         InlineBoxesConstructionResult {
                             splits: opt_splits,
-                            boxes: boxes
+                            boxes: boxes,
+                            absolute_flows: opt_kid_absolute_flows,
+                            inline_block_flows: opt_kid_inline_block_flows
                         })) => {
+                    // Bubble up absolutely positioned flows and the flows of inline-blocks.
+                    opt_absolute_flows.push_opt_vec_move(opt_kid_absolute_flows);
+                    opt_inline_block_flows.push_opt_vec_move(opt_kid_inline_block_flows);
+
                     // Bubble up {ib} splits.
                     match opt_splits {
                         None => {}
@@ -422,13 +767,20 @@ impl<'self> FlowConstructor<'self> {
             }
         }
 
+        for box in self.build_box_for_pseudo_element(node, After).move_iter() {
+            opt_box_accumulator.push(box)
+        }
+
         // TODO(pcwalton): Add in our own borders/padding/margins if necessary.
 
         // Finally, make a new construction result.
-        if opt_inline_block_splits.len() > 0 || opt_box_accumulator.len() > 0 {
+        if opt_inline_block_splits.len() > 0 || opt_box_accumulator.len() > 0 ||
+                opt_absolute_flows.len() > 0 {
             let construction_item = InlineBoxesConstructionItem(InlineBoxesConstructionResult {
                 splits: opt_inline_block_splits,
                 boxes: opt_box_accumulator.to_vec(),
+                absolute_flows: opt_absolute_flows,
+                inline_block_flows: opt_inline_block_flows,
             });
             ConstructionItemConstructionResult(construction_item)
         } else {
@@ -449,10 +801,349 @@ impl<'self> FlowConstructor<'self> {
             boxes: ~[
                 self.build_box_for_node(node)
             ],
+            absolute_flows: None,
+            inline_block_flows: None,
+        });
+        ConstructionItemConstructionResult(construction_item)
+    }
+
+    /// Builds the flow for a node with `display: inline-block`, which lays out its kids as a block
+    /// does, and the box that holds its place in the lines of the enclosing inline flow. This
+    /// yields an `InlineBoxesConstructionResult`.
+    fn build_boxes_for_inline_block(&mut self, node: AbstractNode<LayoutView>)
+                                    -> ConstructionResult {
+        let base = FlowData::new(self.next_flow_id(), node);
+        let box = self.build_box_for_node(node);
+        let mut flow = ~InlineBlockFlow::from_box(base, box) as ~Flow:;
+        self.build_children_of_block_flow(&mut flow, node);
+
+        let construction_item = InlineBoxesConstructionItem(InlineBoxesConstructionResult {
+            splits: None,
+            boxes: ~[
+                Box::new(node, InlineBlockBox(InlineBlockBoxInfo::new()))
+            ],
+            absolute_flows: None,
+            inline_block_flows: Some(~[flow]),
         });
         ConstructionItemConstructionResult(construction_item)
     }
 
+    /// Returns the style of an anonymous box with the given 'display' that `node` generates
+    /// around some of its contents. It inherits what it can from the style of `node` and has the
+    /// initial values of the other properties.
+    fn build_anonymous_style(&mut self, node: AbstractNode<LayoutView>, display: display::T)
+                             -> ComputedValues {
+        let mut style = cascade([], Some(node.style().get()), &self.layout_context.device);
+        style.Box.display = display;
+        style
+    }
+
+    /// Builds the flow for a node with `display: table`. Its captions, row groups and absolutely
+    /// positioned children become its children, and its columns give it their widths.
+    fn build_flow_for_table(&mut self, node: AbstractNode<LayoutView>) -> ~Flow: {
+        let mut column_specs = ~[];
+        let mut contents = ~[self.build_construction_result_for_pseudo_element(node, Before)];
+        for kid in node.children() {
+            match kid.swap_out_construction_result() {
+                ConstructionItemConstructionResult(TableColumnsConstructionItem(specs)) => {
+                    column_specs.push_all_move(specs)
+                }
+                result => contents.push(result),
+            }
+        }
+        contents.push(self.build_construction_result_for_pseudo_element(node, After));
+
+        let base = FlowData::new(self.next_flow_id(), node);
+        let box = self.build_box_for_node(node);
+        let mut flow = ~TableFlow::from_box(base, box, column_specs) as ~Flow:;
+        self.build_children_of_table_flow(&mut flow, node, contents);
+        flow
+    }
+
+    /// Builds the flow for a node with `display: table` and `float: {left|right}`. This yields a
+    /// float `BlockFlow` with an anonymous box, which has a `TableFlow` underneath it.
+    fn build_flow_for_floated_table(&mut self,
+                                    node: AbstractNode<LayoutView>,
+                                    float_type: FloatType)
+                                    -> ~Flow: {
+        let table_flow = self.build_flow_for_table(node);
+
+        let mut style = self.build_anonymous_style(node, display::block);
+        style.Box.float = node.style().get().Box.float;
+        style.Box.clear = node.style().get().Box.clear;
+        let base = FlowData::new(self.next_flow_id(), node);
+        let box = Box::from_style(node, Arc::new(style), GenericBox);
+        let mut flow = ~BlockFlow::float_from_box(base, float_type, box) as ~Flow:;
+        flow.add_new_child(table_flow);
+        flow
+    }
+
+    /// Builds the flow for a node with `display: inline-table`: a table inside an anonymous
+    /// inline-block, and the box that holds its place in the lines of the enclosing inline flow.
+    /// This yields an `InlineBoxesConstructionResult`.
+    fn build_boxes_for_inline_table(&mut self, node: AbstractNode<LayoutView>)
+                                    -> ConstructionResult {
+        let table_flow = self.build_flow_for_table(node);
+
+        // The table keeps the style of the node, and so does its relative offset. The box that
+        // holds its place in the lines has the anonymous style, so that it is not offset twice.
+        let mut style = self.build_anonymous_style(node, display::inline_block);
+        style.Box.vertical_align = node.style().get().Box.vertical_align;
+        let style = Arc::new(style);
+        let base = FlowData::new(self.next_flow_id(), node);
+        let box = Box::from_style(node, style.clone(), GenericBox);
+        let mut flow = ~InlineBlockFlow::from_box(base, box) as ~Flow:;
+        flow.add_new_child(table_flow);
+
+        let construction_item = InlineBoxesConstructionItem(InlineBoxesConstructionResult {
+            splits: None,
+            boxes: ~[
+                Box::from_style(node, style, InlineBlockBox(InlineBlockBoxInfo::new()))
+            ],
+            absolute_flows: None,
+            inline_block_flows: Some(~[flow]),
+        });
+        ConstructionItemConstructionResult(construction_item)
+    }
+
+    /// Builds the flow for a node with `display: table-row-group`, `table-header-group` or
+    /// `table-footer-group`. Its rows and absolutely positioned children become its children.
+    fn build_flow_for_table_row_group(&mut self, node: AbstractNode<LayoutView>) -> ~Flow: {
+        let base = FlowData::new(self.next_flow_id(), node);
+        let box = self.build_box_for_node(node);
+        let mut flow = ~TableRowGroupFlow::from_box(base, box) as ~Flow:;
+        let contents = self.build_contents_of_table_part(node);
+        self.build_children_of_table_part(&mut flow, node, contents, display::table_row);
+        flow
+    }
+
+    /// Returns the construction results of the children of a row group or a row, between those of
+    /// its ::before and ::after pseudo-elements.
+    fn build_contents_of_table_part(&mut self, node: AbstractNode<LayoutView>)
+                                    -> ~[ConstructionResult] {
+        let mut contents = ~[self.build_construction_result_for_pseudo_element(node, Before)];
+        for kid in node.children() {
+            contents.push(kid.swap_out_construction_result())
+        }
+        contents.push(self.build_construction_result_for_pseudo_element(node, After));
+        contents
+    }
+
+    /// Builds the flow for a node with `display: table-row`. Its cells and absolutely positioned
+    /// children become its children.
+    fn build_flow_for_table_row(&mut self, node: AbstractNode<LayoutView>) -> ~Flow: {
+        let base = FlowData::new(self.next_flow_id(), node);
+        let box = self.build_box_for_node(node);
+        let mut flow = ~TableRowFlow::from_box(base, box) as ~Flow:;
+        let contents = self.build_contents_of_table_part(node);
+        self.build_children_of_table_part(&mut flow, node, contents, display::table_cell);
+        flow
+    }
+
+    /// Builds the flow for a node with `display: table-cell`, which lays out its kids as a block
+    /// does. Only the cells of HTML tables span more than one row or column.
+    fn build_flow_for_table_cell(&mut self, node: AbstractNode<LayoutView>) -> ~Flow: {
+        // HTML clamps 'colspan' to 1...1000 and 'rowspan' to 0...65534, where a 'rowspan' of zero
+        // spans the rest of the row group.
+        let (colspan, rowspan) = match node.type_id() {
+            ElementNodeTypeId(HTMLTableDataCellElementTypeId) |
+            ElementNodeTypeId(HTMLTableHeaderCellElementTypeId) => {
+                let colspan = match node.unsigned_attribute("colspan") {
+                    Some(colspan) if colspan > 0 => cmp::min(colspan, 1000),
+                    _ => 1,
+                };
+                let rowspan = node.unsigned_attribute("rowspan").map_default(1, |rowspan| {
+                    cmp::min(rowspan, 65534)
+                });
+                (colspan, rowspan)
+            }
+            _ => (1, 1),
+        };
+
+        let base = FlowData::new(self.next_flow_id(), node);
+        let box = self.build_box_for_node(node);
+        let mut flow = ~TableCellFlow::from_box(base, box, colspan, rowspan) as ~Flow:;
+        self.build_children_of_block_flow(&mut flow, node);
+        flow
+    }
+
+    /// Builds the flow for a node with `display: table-caption`, which lays out its kids as a
+    /// block does.
+    fn build_flow_for_table_caption(&mut self, node: AbstractNode<LayoutView>) -> ~Flow: {
+        let base = FlowData::new(self.next_flow_id(), node);
+        let box = self.build_box_for_node(node);
+        let mut flow = ~TableCaptionFlow::from_box(base, box) as ~Flow:;
+        self.build_children_of_block_flow(&mut flow, node);
+        flow
+    }
+
+    /// Builds the construction result of a node with `display: table-column` or
+    /// `table-column-group`: the 'width' of each column that it stands for. A column group with
+    /// columns of its own stands for those, and otherwise for as many columns as its 'span'
+    /// attribute says (CSS 2.1 § 17.3).
+    fn build_table_columns(&mut self, node: AbstractNode<LayoutView>) -> ConstructionResult {
+        let mut widths = ~[];
+        for kid in node.children() {
+            match kid.swap_out_construction_result() {
+                ConstructionItemConstructionResult(TableColumnsConstructionItem(kid_widths)) => {
+                    widths.push_all_move(kid_widths)
+                }
+                _ => {}
+            }
+        }
+        if widths.is_empty() {
+            let span = match node.unsigned_attribute("span") {
+                Some(span) if span > 0 => cmp::min(span, 1000),
+                _ => 1,
+            };
+            widths = vec::from_elem(span, node.style().get().Box.width)
+        }
+        ConstructionItemConstructionResult(TableColumnsConstructionItem(widths))
+    }
+
+    /// Adds `contents`, the construction results of the children of a table, to its flow.
+    /// Captions, row groups and absolutely positioned flows become its children. Runs of anything
+    /// else are wrapped in anonymous row groups (CSS 2.1 § 17.2.1). The first header group moves
+    /// to the top of the table, and the first footer group to the bottom (CSS 2.1 § 17.2).
+    fn build_children_of_table_flow(&mut self,
+                                    flow: &mut ~Flow:,
+                                    node: AbstractNode<LayoutView>,
+                                    contents: ~[ConstructionResult]) {
+        let mut kids = ~[];
+        let mut anonymous_contents = ~[];
+        for result in contents.move_iter() {
+            match result {
+                FlowConstructionResult(kid_flow) => {
+                    match kid_flow.class() {
+                        TableCaptionFlowClass | TableRowGroupFlowClass => {
+                            self.flush_anonymous_contents(&mut anonymous_contents,
+                                                          &mut kids,
+                                                          node,
+                                                          display::table_row_group);
+                            kids.push(kid_flow)
+                        }
+                        AbsoluteFlowClass if anonymous_contents.is_empty() => kids.push(kid_flow),
+                        _ => anonymous_contents.push(FlowConstructionResult(kid_flow)),
+                    }
+                }
+                result => push_anonymous_content(&mut anonymous_contents, result),
+            }
+        }
+        self.flush_anonymous_contents(&mut anonymous_contents,
+                                      &mut kids,
+                                      node,
+                                      display::table_row_group);
+
+        let header = kids.mut_iter().position(|kid| {
+            is_row_group(*kid, display::table_header_group)
+        });
+        for &index in header.iter() {
+            let header = kids.remove(index);
+            kids.insert(0, header)
+        }
+        let footer = kids.mut_iter().position(|kid| {
+            is_row_group(*kid, display::table_footer_group)
+        });
+        for &index in footer.iter() {
+            let footer = kids.remove(index);
+            kids.push(footer)
+        }
+
+        for kid in kids.move_iter() {
+            flow.add_new_child(kid)
+        }
+    }
+
+    /// Adds `contents`, the construction results of the children of a row group or a row, to its
+    /// flow. The table parts that it holds, of the given 'display', and absolutely positioned
+    /// flows become its children. Runs of anything else are wrapped in an anonymous one of those
+    /// table parts (CSS 2.1 § 17.2.1).
+    fn build_children_of_table_part(&mut self,
+                                    flow: &mut ~Flow:,
+                                    node: AbstractNode<LayoutView>,
+                                    contents: ~[ConstructionResult],
+                                    kid_display: display::T) {
+        let mut kids = ~[];
+        let mut anonymous_contents = ~[];
+        for result in contents.move_iter() {
+            match result {
+                FlowConstructionResult(kid_flow) => {
+                    let is_kid = match (kid_flow.class(), kid_display) {
+                        (TableRowFlowClass, display::table_row) |
+                        (TableCellFlowClass, display::table_cell) => true,
+                        _ => false,
+                    };
+                    if is_kid {
+                        self.flush_anonymous_contents(&mut anonymous_contents,
+                                                      &mut kids,
+                                                      node,
+                                                      kid_display);
+                        kids.push(kid_flow)
+                    } else if kid_flow.is_absolutely_positioned() && anonymous_contents.is_empty() {
+                        kids.push(kid_flow)
+                    } else {
+                        anonymous_contents.push(FlowConstructionResult(kid_flow))
+                    }
+                }
+                result => push_anonymous_content(&mut anonymous_contents, result),
+            }
+        }
+        self.flush_anonymous_contents(&mut anonymous_contents, &mut kids, node, kid_display);
+
+        for kid in kids.move_iter() {
+            flow.add_new_child(kid)
+        }
+    }
+
+    /// Wraps `anonymous_contents`, if there are any, in an anonymous table part with the given
+    /// 'display', which becomes the next of `kids`.
+    fn flush_anonymous_contents(&mut self,
+                                anonymous_contents: &mut ~[ConstructionResult],
+                                kids: &mut ~[~Flow:],
+                                node: AbstractNode<LayoutView>,
+                                display: display::T) {
+        if anonymous_contents.is_empty() {
+            return
+        }
+        let contents = util::replace(anonymous_contents, ~[]);
+        kids.push(self.build_anonymous_table_flow(node, display, contents))
+    }
+
+    /// Builds the flow of an anonymous table, row group, row or cell, as the given 'display' says,
+    /// that `node` generates around `contents`, some of its contents that are missing that table
+    /// part around them (CSS 2.1 § 17.2.1).
+    fn build_anonymous_table_flow(&mut self,
+                                  node: AbstractNode<LayoutView>,
+                                  display: display::T,
+                                  contents: ~[ConstructionResult])
+                                  -> ~Flow: {
+        let style = Arc::new(self.build_anonymous_style(node, display));
+        let base = FlowData::new(self.next_flow_id(), node);
+        let box = Box::from_style(node, style, GenericBox);
+        match display {
+            display::table => {
+                let mut flow = ~TableFlow::from_box(base, box, ~[]) as ~Flow:;
+                self.build_children_of_table_flow(&mut flow, node, contents);
+                flow
+            }
+            display::table_row_group => {
+                let mut flow = ~TableRowGroupFlow::from_box(base, box) as ~Flow:;
+                self.build_children_of_table_part(&mut flow, node, contents, display::table_row);
+                flow
+            }
+            display::table_row => {
+                let mut flow = ~TableRowFlow::from_box(base, box) as ~Flow:;
+                self.build_children_of_table_part(&mut flow, node, contents, display::table_cell);
+                flow
+            }
+            _ => {
+                let mut flow = ~TableCellFlow::from_box(base, box, 1, 1) as ~Flow:;
+                self.build_block_contents(&mut flow, node, None, contents, None);
+                flow
+            }
+        }
+    }
+
     /// Builds one or more boxes for a node with `display: inline`. This yields an
     /// `InlineBoxesConstructionResult`.
     fn build_boxes_for_inline(&mut self, node: AbstractNode<LayoutView>) -> ConstructionResult {
@@ -472,56 +1163,199 @@ impl<'self> PostorderNodeMutTraversal for FlowConstructor<'self> {
     // reason LLVM's inlining heuristics go awry here.
     #[inline(always)]
     fn process(&mut self, node: AbstractNode<LayoutView>) -> bool {
-        // Get the `display` property for this node, and determine whether this node is floated.
-        let (display, float) = match node.type_id() {
+        // Get the `display` property for this node, and determine whether this node is floated
+        // or positioned.
+        let (display, float, position) = match node.type_id() {
             ElementNodeTypeId(_) => {
                 let style = node.style().get();
-                (style.Box.display, style.Box.float)
+                (style.Box.display, style.Box.float, style.Box.position)
             }
-            TextNodeTypeId => (display::inline, float::none),
+            TextNodeTypeId => (display::inline, float::none, position::static_),
             CommentNodeTypeId |
             DoctypeNodeTypeId |
             DocumentFragmentNodeTypeId |
-            DocumentNodeTypeId(_) => (display::none, float::none),
+            DocumentNodeTypeId(_) => (display::none, float::none, position::static_),
         };
 
-        debug!("building flow for node: {:?} {:?}", display, float);
+        debug!("building flow for node: {:?} {:?} {:?}", display, float, position);
 
-        // Switch on display and floatedness.
-        match (display, float) {
+        // Switch on display, floatedness and positioning.
+        match (display, float, position) {
             // `display: none` contributes no flow construction result. Nuke the flow construction
             // results of children.
-            (display::none, _) => {
+            (display::none, _, _) => {
                 for child in node.children() {
                     child.set_flow_construction_result(NoConstructionResult)
                 }
             }
 
+            // Absolutely positioned elements are blockified and never floated (CSS 2.1 § 9.7).
+            // They contribute flows that their containing blocks lay out.
+            //
+            // FIXME: The root element is always laid out as a block for now.
+            (_, _, position::absolute) | (_, _, position::fixed) if !node.is_root_element() => {
+                let flow = self.build_flow_for_absolute(node, position == position::fixed);
+                node.set_flow_construction_result(FlowConstructionResult(flow))
+            }
+
             // Inline items contribute inline box construction results.
-            (display::inline, float::none) => {
+            (display::inline, float::none, _) => {
                 let construction_result = self.build_boxes_for_inline(node);
                 node.set_flow_construction_result(construction_result)
             }
 
+            // Inline-blocks contribute a box, which sits in a line, and the flow that lays out
+            // their kids. Replaced inline-blocks are laid out as replaced inlines are.
+            //
+            // FIXME: The root element is always laid out as a block for now.
+            (display::inline_block, float::none, _) if node.is_replaced_content() => {
+                let construction_result = self.build_boxes_for_replaced_inline_content(node);
+                node.set_flow_construction_result(construction_result)
+            }
+            (display::inline_block, float::none, _) if !node.is_root_element() => {
+                let construction_result = self.build_boxes_for_inline_block(node);
+                node.set_flow_construction_result(construction_result)
+            }
+
+            // Tables and the parts of tables contribute flows, which the parts that hold them
+            // take as children. Parts that are missing around them are generated anonymously
+            // (CSS 2.1 § 17.2.1). Internal table elements that are floated or positioned have been
+            // blockified, and floated inline tables are tables (CSS 2.1 § 9.7).
+            //
+            // FIXME: The root element is always laid out as a block for now.
+            (display::table, float::none, _) if !node.is_root_element() => {
+                let flow = self.build_flow_for_table(node);
+                node.set_flow_construction_result(FlowConstructionResult(flow))
+            }
+            (display::table, float_value, _) if !node.is_root_element() => {
+                let float_type = FloatType::from_property(float_value);
+                let flow = self.build_flow_for_floated_table(node, float_type);
+                node.set_flow_construction_result(FlowConstructionResult(flow))
+            }
+            (display::inline_table, float::none, _) if !node.is_root_element() => {
+                let construction_result = self.build_boxes_for_inline_table(node);
+                node.set_flow_construction_result(construction_result)
+            }
+            (display::table_row_group, _, _) |
+            (display::table_header_group, _, _) |
+            (display::table_footer_group, _, _) => {
+                let flow = self.build_flow_for_table_row_group(node);
+                node.set_flow_construction_result(FlowConstructionResult(flow))
+            }
+            (display::table_row, _, _) => {
+                let flow = self.build_flow_for_table_row(node);
+                node.set_flow_construction_result(FlowConstructionResult(flow))
+            }
+            (display::table_cell, _, _) => {
+                let flow = self.build_flow_for_table_cell(node);
+                node.set_flow_construction_result(FlowConstructionResult(flow))
+            }
+            (display::table_caption, _, _) => {
+                let flow = self.build_flow_for_table_caption(node);
+                node.set_flow_construction_result(FlowConstructionResult(flow))
+            }
+            (display::table_column, _, _) | (display::table_column_group, _, _) => {
+                let construction_result = self.build_table_columns(node);
+                node.set_flow_construction_result(construction_result)
+            }
+
             // Block flows that are not floated contribute block flow construction results.
             //
             // TODO(pcwalton): Make this only trigger for blocks and handle the other `display`
             // properties separately.
-            (_, float::none) => {
+            (_, float::none, _) => {
                 let flow = self.build_flow_for_block(node);
                 node.set_flow_construction_result(FlowConstructionResult(flow))
             }
 
             // Floated flows contribute float flow construction results.
-            (_, float_value) => {
+            (_, float_value, _) => {
                 let float_type = FloatType::from_property(float_value);
                 let flow = self.build_flow_for_floated_block(node, float_type);
                 node.set_flow_construction_result(FlowConstructionResult(flow))
             }
         }
 
+        // The flows and boxes of this node are up to date now.
+        node.set_restyle_damage(RestyleDamage::none());
         true
     }
+
+    /// Nodes whose flows are reused from the last reflow keep them as their construction results,
+    /// and so do their descendants.
+    #[inline(always)]
+    fn should_prune(&self, node: AbstractNode<LayoutView>) -> bool {
+        match *node.borrow_layout_data().ptr {
+            Some(ref layout_data) => {
+                match layout_data.flow_construction_result {
+                    FlowConstructionResult(_) => true,
+                    _ => false,
+                }
+            }
+            None => fail!("no layout data"),
+        }
+    }
+}
+
+/// Marks the ancestors of the nodes under `root` that have restyle damage for flow
+/// reconstruction, since their flows hold the flows and boxes of their descendants.
+pub fn propagate_damage_to_ancestors(root: AbstractNode<LayoutView>) {
+    root.traverse_postorder(&PropagateDamageToAncestorsTraversal {
+        root: root,
+    });
+}
+
+struct PropagateDamageToAncestorsTraversal {
+    root: AbstractNode<LayoutView>,
+}
+
+impl PostorderNodeTraversal for PropagateDamageToAncestorsTraversal {
+    fn process(&self, node: AbstractNode<LayoutView>) -> bool {
+        if node != self.root && node.restyle_damage().is_nonempty() {
+            for parent in node.parent_node().iter() {
+                parent.set_restyle_damage(parent.restyle_damage().add(ReconstructFlow))
+            }
+        }
+        true
+    }
+}
+
+/// Blows apart `flow`, the flow tree of the last reflow. The flows of the nodes that have no
+/// restyle damage migrate home to their nodes, with all the flows under them, so that flow
+/// construction reuses them. The other flows are thrown away.
+///
+/// The boxes of the flows that migrate keep the styles of the last reflow. A node without damage
+/// may still have been cascaded again since, so these have the values of its current styles, but
+/// they are not the same objects.
+///
+/// The damage of the nodes must have been propagated to their ancestors with
+/// `propagate_damage_to_ancestors` first.
+pub fn reclaim_flows(flow: ~Flow:) {
+    reclaim_flow(flow, None)
+}
+
+/// Reclaims `flow`, the child of a flow of `parent_node`, if any, and the flows under it.
+fn reclaim_flow(mut flow: ~Flow:, parent_node: Option<AbstractNode<LayoutView>>) {
+    let node = flow::base(flow).node;
+
+    // Only the flow that a node contributes as its construction result can migrate home. Inline
+    // flows, inline-blocks and the anonymous flows around the contents of a node are rebuilt
+    // along with the node that holds them.
+    let is_construction_result = match flow.class() {
+        InlineFlowClass | InlineBlockFlowClass => false,
+        _ => parent_node != Some(node),
+    };
+    if is_construction_result && node.restyle_damage().is_empty() {
+        node.set_flow_construction_result(FlowConstructionResult(flow));
+        return
+    }
+
+    loop {
+        match flow::mut_base(flow).children.pop_front() {
+            Some(kid) => reclaim_flow(kid, Some(node)),
+            None => break,
+        }
+    }
 }
 
 /// A utility trait with some useful methods for node queries.
@@ -529,6 +1363,9 @@ trait NodeUtils {
     /// Returns true if this node doesn't render its kids and false otherwise.
     fn is_replaced_content(self) -> bool;
 
+    /// Returns true if this node is the root element of its document.
+    fn is_root_element(self) -> bool;
+
     /// Sets the construction result of a flow.
     fn set_flow_construction_result(self, result: ConstructionResult);
 
@@ -539,6 +1376,13 @@ trait NodeUtils {
     /// Returns true if this node consists entirely of ignorable whitespace and false otherwise.
     /// Ignorable whitespace is defined as whitespace that would be removed per CSS 2.1 § 16.6.1.
     fn is_ignorable_whitespace(self) -> bool;
+
+    /// Returns the style of the given pseudo-element of this element, if it has one.
+    fn pseudo_element_style(self, pseudo_element: PseudoElement) -> Option<Arc<ComputedValues>>;
+
+    /// Returns the value of the given attribute of this element, such as the 'colspan' of a
+    /// table cell, if it is a valid non-negative integer.
+    fn unsigned_attribute(self, name: &str) -> Option<uint>;
 }
 
 impl NodeUtils for AbstractNode<LayoutView> {
@@ -554,6 +1398,10 @@ impl NodeUtils for AbstractNode<LayoutView> {
         }
     }
 
+    fn is_root_element(self) -> bool {
+        self.is_element() && self.parent_node().map_default(false, |parent| parent.is_document())
+    }
+
     #[inline(always)]
     fn set_flow_construction_result(self, result: ConstructionResult) {
         match *self.mutate_layout_data().ptr {
@@ -575,6 +1423,123 @@ impl NodeUtils for AbstractNode<LayoutView> {
     fn is_ignorable_whitespace(self) -> bool {
         self.is_text() && self.with_imm_text(|text| text.element.data.is_whitespace())
     }
+
+    fn pseudo_element_style(self, pseudo_element: PseudoElement) -> Option<Arc<ComputedValues>> {
+        match *self.mutate_layout_data().ptr {
+            Some(ref mut layout_data) => layout_data.pseudo_element(pseudo_element).style.clone(),
+            None => fail!("no layout data"),
+        }
+    }
+
+    fn unsigned_attribute(self, name: &str) -> Option<uint> {
+        if !self.is_element() {
+            return None
+        }
+        self.with_imm_element(|element| {
+            element.get_attr(None, name).and_then(|string| parse_non_negative_integer(string))
+        })
+    }
+}
+
+/// Returns true if flows of the given class are table parts, which need a table around them.
+fn is_table_part(class: FlowClass) -> bool {
+    match class {
+        TableCaptionFlowClass | TableRowGroupFlowClass | TableRowFlowClass |
+        TableCellFlowClass => true,
+        _ => false,
+    }
+}
+
+/// Returns true if `flow` is a row group with the given 'display'.
+fn is_row_group(flow: &mut Flow, display: display::T) -> bool {
+    match flow.class() {
+        TableRowGroupFlowClass => {
+            flow.as_block().box.as_ref().map_default(false, |box| {
+                box.style().Box.display == display
+            })
+        }
+        _ => false,
+    }
+}
+
+/// Adds `result`, a construction result among the contents of a table part that will be wrapped
+/// in anonymous table parts, to `anonymous_contents`. Whitespace that those would start with is
+/// dropped, and so are columns that are not in a table (CSS 2.1 § 17.2.1).
+fn push_anonymous_content(anonymous_contents: &mut ~[ConstructionResult],
+                          result: ConstructionResult) {
+    let is_ignorable = match result {
+        NoConstructionResult |
+        ConstructionItemConstructionResult(TableColumnsConstructionItem(_)) => true,
+        ConstructionItemConstructionResult(InlineBoxesConstructionItem(ref inline_result)) => {
+            anonymous_contents.is_empty() && inline_result.splits.is_none() &&
+                inline_result.absolute_flows.is_none() &&
+                inline_result.inline_block_flows.is_none() &&
+                inline_result.boxes.iter().all(|box| box.is_whitespace_only())
+        }
+        FlowConstructionResult(_) => false,
+    };
+    if !is_ignorable {
+        anonymous_contents.push(result)
+    }
+}
+
+/// Returns true for the characters that ::first-letter includes around the letter.
+fn is_punctuation(ch: char) -> bool {
+    !ch.is_alphanumeric() && !ch.is_whitespace()
+}
+
+/// Splits `text` into its first letter, with the punctuation that precedes and follows it, and
+/// the rest of the text. Returns `None` if the text does not start with a letter or digit.
+fn split_first_letter(text: &str) -> Option<(~str, ~str)> {
+    let chars: ~[char] = text.trim_left().iter().collect();
+    let mut end = 0;
+    while end < chars.len() && is_punctuation(chars[end]) {
+        end += 1
+    }
+    if end == chars.len() || chars[end].is_whitespace() {
+        return None
+    }
+    end += 1;
+    while end < chars.len() && is_punctuation(chars[end]) {
+        end += 1
+    }
+    Some((str::from_chars(chars.slice_to(end)), str::from_chars(chars.slice_from(end))))
+}
+
+/// Parses `string` with the HTML rules for parsing non-negative integers: whitespace and a sign
+/// may come first, and the digits are read up to the first other character, so that "2px" is 2.
+/// Values too large for a `uint` are clamped to the largest one, which callers clamp further.
+fn parse_non_negative_integer(string: &str) -> Option<uint> {
+    let mut chars = string.chars().skip_while(|&c| {
+        c == ' ' || c == '\t' || c == '\n' || c == '\x0C' || c == '\r'
+    }).peekable();
+    let negative = match chars.peek() {
+        Some(&'-') => true,
+        _ => false,
+    };
+    match chars.peek() {
+        Some(&'-') | Some(&'+') => {
+            chars.next();
+        }
+        _ => {}
+    }
+    let mut value = None;
+    for c in chars {
+        match c.to_digit(10) {
+            Some(digit) => {
+                value = match value.unwrap_or(0) {
+                    so_far if so_far > (uint::max_value - digit) / 10 => Some(uint::max_value),
+                    so_far => Some(so_far * 10 + digit),
+                }
+            }
+            None => break,
+        }
+    }
+    match value {
+        // "-0" is zero, which is not negative.
+        Some(value) if negative && value != 0 => None,
+        value => value,
+    }
 }
 
 /// Strips ignorable whitespace from the start of a list of boxes.
@@ -616,3 +1581,33 @@ fn strip_ignorable_whitespace_from_end(opt_boxes: &mut Option<~[Box]>) {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::{parse_non_negative_integer, split_first_letter};
+    use std::uint;
+
+    #[test]
+    fn test_split_first_letter() {
+        assert_eq!(split_first_letter("Hello"), Some((~"H", ~"ello")));
+        assert_eq!(split_first_letter("  \"Quoted.\""), Some((~"\"Q", ~"uoted.\"")));
+        assert_eq!(split_first_letter("A. B"), Some((~"A.", ~" B")));
+        assert_eq!(split_first_letter("7"), Some((~"7", ~"")));
+        assert_eq!(split_first_letter("   "), None);
+        assert_eq!(split_first_letter("-- x"), None);
+    }
+
+    #[test]
+    fn test_parse_non_negative_integer() {
+        assert_eq!(parse_non_negative_integer("2"), Some(2));
+        assert_eq!(parse_non_negative_integer(" 3 "), Some(3));
+        assert_eq!(parse_non_negative_integer("2px"), Some(2));
+        assert_eq!(parse_non_negative_integer("\t+12.5"), Some(12));
+        assert_eq!(parse_non_negative_integer("-0"), Some(0));
+        assert_eq!(parse_non_negative_integer("-1"), None);
+        // Overflowing values are clamped.
+        assert_eq!(parse_non_negative_integer("99999999999999999999999"), Some(uint::max_value));
+        assert_eq!(parse_non_negative_integer("-99999999999999999999999"), None);
+        assert_eq!(parse_non_negative_integer("px"), None);
+        assert_eq!(parse_non_negative_integer(""), None);
+    }
+}
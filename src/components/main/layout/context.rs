@@ -10,12 +10,17 @@ use gfx::font_context::FontContext;
 use servo_msg::constellation_msg::ConstellationChan;
 use servo_net::local_image_cache::LocalImageCache;
 use servo_util::geometry::Au;
+use style::{Device, QuirksMode};
 
 /// Data needed by the layout task.
 pub struct LayoutContext {
     font_ctx: ~FontContext,
     image_cache: MutexArc<LocalImageCache>,
     screen_size: Rect<Au>,
+    /// The device that styles are computed for, e.g. for viewport-percentage lengths.
+    device: Device,
+    /// The compatibility mode of the document.
+    quirks_mode: QuirksMode,
 
     /// A channel up to the constellation.
     constellation_chan: ConstellationChan,
@@ -24,8 +24,19 @@
 ///   boxes/flows that are subject to inline layout and line breaking and structs to represent
 ///   line breaks and mapping to CSS boxes, for the purpose of handling `getClientRects()` and
 ///   similar methods.
+///
+/// * `AbsoluteFlow`: A flow for an absolutely or fixed positioned box. It lays out its contents
+///   like a `BlockFlow`, but it is out of the normal flow: its containing block places it.
+///
+/// * `InlineBlockFlow`: A flow for an inline-block box. It lays out its contents like a
+///   `BlockFlow`, and it is a child of the `InlineFlow` whose line holds it.
+///
+/// * `TableFlow`: A flow for a table box and its captions. Its children are `TableCaptionFlow`s
+///   and `TableRowGroupFlow`s, whose children are `TableRowFlow`s, whose children are
+///   `TableCellFlow`s. The table lays out the grid of their cells.
 
 use css::node_style::StyledNode;
+use layout::absolute::AbsoluteFlow;
 use layout::block::BlockFlow;
 use layout::box::Box;
 use layout::context::LayoutContext;
@@ -33,17 +44,27 @@ use layout::display_list_builder::{DisplayListBuilder, ExtraDisplayListData};
 use layout::float_context::{FloatContext, Invalid};
 use layout::incremental::RestyleDamage;
 use layout::inline::InlineFlow;
+use layout::inline_block::InlineBlockFlow;
+use layout::table::TableFlow;
+use layout::table_cell::TableCellFlow;
+use layout::table_row::TableRowFlow;
+use layout::table_rowgroup::TableRowGroupFlow;
 
 use extra::dlist::{DList, DListIterator, MutDListIterator};
+use extra::sort::quick_sort;
 use extra::container::Deque;
 use geom::point::Point2D;
 use geom::rect::Rect;
-use gfx::display_list::{ClipDisplayItemClass, DisplayList};
+use geom::size::Size2D;
+use gfx::display_list::{BaseDisplayItem, ClipDisplayItemClass, DisplayItem, DisplayList};
+use gfx::display_list::{OpacityDisplayItem, OpacityDisplayItemClass};
 use script::dom::node::{AbstractNode, LayoutView};
 use servo_util::geometry::Au;
 use std::cast;
+use std::util;
 use std::cell::Cell;
 use style::ComputedValues;
+use style::computed_values::{position, z_index};
 
 /// Virtual methods that make up a float context.
 ///
@@ -62,6 +83,36 @@ pub trait Flow {
         fail!("called as_block() on a non-block flow")
     }
 
+    /// If this is an absolutely positioned flow, returns the underlying object. Fails otherwise.
+    fn as_absolute<'a>(&'a mut self) -> &'a mut AbsoluteFlow {
+        fail!("called as_absolute() on a non-absolute flow")
+    }
+
+    /// If this is an inline-block flow, returns the underlying object. Fails otherwise.
+    fn as_inline_block<'a>(&'a mut self) -> &'a mut InlineBlockFlow {
+        fail!("called as_inline_block() on a non-inline-block flow")
+    }
+
+    /// If this is a table flow, returns the underlying object. Fails otherwise.
+    fn as_table<'a>(&'a mut self) -> &'a mut TableFlow {
+        fail!("called as_table() on a non-table flow")
+    }
+
+    /// If this is a table row group flow, returns the underlying object. Fails otherwise.
+    fn as_table_row_group<'a>(&'a mut self) -> &'a mut TableRowGroupFlow {
+        fail!("called as_table_row_group() on a non-table-row-group flow")
+    }
+
+    /// If this is a table row flow, returns the underlying object. Fails otherwise.
+    fn as_table_row<'a>(&'a mut self) -> &'a mut TableRowFlow {
+        fail!("called as_table_row() on a non-table-row flow")
+    }
+
+    /// If this is a table cell flow, returns the underlying object. Fails otherwise.
+    fn as_table_cell<'a>(&'a mut self) -> &'a mut TableCellFlow {
+        fail!("called as_table_cell() on a non-table-cell flow")
+    }
+
     /// If this is an inline flow, returns the underlying object, borrowed immutably. Fails
     /// otherwise.
     fn as_immutable_inline<'a>(&'a self) -> &'a InlineFlow {
@@ -148,12 +199,79 @@ pub fn child_iter<'a>(flow: &'a mut Flow) -> MutDListIterator<'a,~Flow:> {
     mut_base(flow).children.mut_iter()
 }
 
+/// The opacity of the box of a block flow, which applies to the flow and its descendants as a
+/// group.
+fn opacity(flow: &mut Flow) -> f32 {
+    if !flow.is_block_based() {
+        return 1.0
+    }
+    match flow.as_block().box {
+        Some(ref box) => box.style().Box.opacity,
+        None => 1.0,
+    }
+}
+
+/// Returns the 'z-index' of the given flow, or zero if it does not apply.
+fn z_index(flow: &mut Flow) -> i32 {
+    if !flow.is_block_based() {
+        return 0
+    }
+    for box in flow.as_block().box.iter() {
+        let style = box.style();
+        match (style.Box.position, style.Box.z_index) {
+            (position::static_, _) | (_, z_index::Auto) => {}
+            (_, z_index::Number(value)) => return value,
+        }
+    }
+    0
+}
+
+/// Returns true if the given flow is fixed positioned.
+fn is_fixed(flow: &mut Flow) -> bool {
+    match flow.class() {
+        AbsoluteFlowClass => flow.as_absolute().is_fixed,
+        _ => false,
+    }
+}
+
+/// Returns true if the box of the given flow is positioned, i.e. its 'position' is not 'static'.
+/// Positioned flows are the containing blocks of their absolutely positioned descendants.
+pub fn is_positioned(flow: &mut Flow) -> bool {
+    flow.is_block_based() && flow.as_block().is_positioned()
+}
+
+/// Returns the offset of the given flow from its position in the normal flow, if it is a
+/// relatively positioned block. Percentages refer to `containing_block_size`.
+pub fn relative_position(flow: &mut Flow, containing_block_size: &Size2D<Au>) -> Point2D<Au> {
+    match flow.class() {
+        BlockFlowClass | TableFlowClass => {
+            match flow.as_block().box {
+                Some(ref box) => box.relative_position(containing_block_size),
+                None => Au::zero_point(),
+            }
+        }
+        _ => Au::zero_point(),
+    }
+}
+
+/// Returns true if the given flow is the root flow.
+fn is_root(flow: &mut Flow) -> bool {
+    match flow.class() {
+        BlockFlowClass => flow.as_block().is_root,
+        _ => false,
+    }
+}
+
 pub trait ImmutableFlowUtils {
     // Convenience functions
 
     /// Returns true if this flow is a block or a float flow.
     fn is_block_like(self) -> bool;
 
+    /// Returns true if this flow lays out its box and children with a `BlockFlow`, which
+    /// `as_block()` returns.
+    fn is_block_based(self) -> bool;
+
     /// Returns true if this flow has no children.
     fn is_leaf(self) -> bool;
 
@@ -163,6 +281,9 @@ pub trait ImmutableFlowUtils {
     /// Returns true if this flow is an inline flow.
     fn starts_inline_flow(self) -> bool;
 
+    /// Returns true if this flow is an absolutely or fixed positioned flow.
+    fn is_absolutely_positioned(self) -> bool;
+
     /// Dumps the flow tree for debugging.
     fn dump(self);
 
@@ -199,12 +320,14 @@ pub trait MutableFlowUtils {
     /// Computes the overflow region for this flow.
     fn store_overflow(self, _: &mut LayoutContext);
 
-    /// Builds a display list for this flow and its children.
+    /// Builds a display list for this flow and its children, except for the fixed positioned
+    /// descendants, whose display items go to `fixed_list`.
     fn build_display_list<E:ExtraDisplayListData>(
                           self,
                           builder: &DisplayListBuilder,
                           dirty: &Rect<Au>,
-                          list: &Cell<DisplayList<E>>)
+                          list: &Cell<DisplayList<E>>,
+                          fixed_list: &Cell<DisplayList<E>>)
                           -> bool;
 }
 
@@ -214,62 +337,10 @@ pub enum FlowClass {
     InlineBlockFlowClass,
     InlineFlowClass,
     TableFlowClass,
-}
-
-// Miscellaneous flows that are not yet implemented.
-
-pub struct AbsoluteFlow {
-    base: FlowData,
-}
-
-impl AbsoluteFlow {
-    pub fn new(base: FlowData) -> AbsoluteFlow {
-        AbsoluteFlow {
-            base: base,
-        }
-    }
-}
-
-impl Flow for AbsoluteFlow {
-    fn class(&self) -> FlowClass {
-        AbsoluteFlowClass
-    }
-}
-
-pub struct InlineBlockFlow {
-    base: FlowData,
-}
-
-impl InlineBlockFlow {
-    pub fn new(base: FlowData) -> InlineBlockFlow {
-        InlineBlockFlow {
-            base: base,
-        }
-    }
-}
-
-impl Flow for InlineBlockFlow {
-    fn class(&self) -> FlowClass {
-        InlineBlockFlowClass
-    }
-}
-
-pub struct TableFlow {
-    base: FlowData,
-}
-
-impl TableFlow {
-    pub fn new(base: FlowData) -> TableFlow {
-        TableFlow {
-            base: base,
-        }
-    }
-}
-
-impl Flow for TableFlow {
-    fn class(&self) -> FlowClass {
-        TableFlowClass
-    }
+    TableCaptionFlowClass,
+    TableRowGroupFlowClass,
+    TableRowFlowClass,
+    TableCellFlowClass,
 }
 
 /// A top-down traversal.
@@ -313,6 +384,10 @@ pub struct FlowFlags(u8);
 /// NB: If you update this field, you must update the bitfields below.
 static TEXT_DECORATION_OVERRIDE_BITMASK: u8 = 0b00001110;
 
+/// The shift from the text decoration flags that get propagated downward to those that come from
+/// the style of the flow itself.
+static OWN_TEXT_DECORATION_SHIFT: u8 = 3;
+
 impl FlowFlags {
     /// Creates a new set of flow flags from the given style.
     fn new(style: &ComputedValues) -> FlowFlags {
@@ -321,12 +396,18 @@ impl FlowFlags {
         flags.set_override_underline(text_decoration.underline);
         flags.set_override_overline(text_decoration.overline);
         flags.set_override_line_through(text_decoration.line_through);
-        flags
+        let own = (*flags & TEXT_DECORATION_OVERRIDE_BITMASK) << OWN_TEXT_DECORATION_SHIFT;
+        FlowFlags(*flags | own)
     }
 
     /// Propagates text decoration flags from an appropriate parent flow per CSS 2.1 § 16.3.1.
+    ///
+    /// The flags that this flow had from its previous parent are replaced, so that a flow that is
+    /// reused across reflows does not keep text decoration that its new parent no longer has.
     pub fn propagate_text_decoration_from_parent(&mut self, parent: FlowFlags) {
-        *self = FlowFlags(**self | (*parent & TEXT_DECORATION_OVERRIDE_BITMASK))
+        let own = (**self >> OWN_TEXT_DECORATION_SHIFT) & TEXT_DECORATION_OVERRIDE_BITMASK;
+        *self = FlowFlags((**self & !TEXT_DECORATION_OVERRIDE_BITMASK) | own |
+                          (*parent & TEXT_DECORATION_OVERRIDE_BITMASK))
     }
 }
 
@@ -348,6 +429,9 @@ bitfield!(FlowFlags, override_overline, set_override_overline, 0x04)
 // NB: If you update this, you need to update TEXT_DECORATION_OVERRIDE_BITMASK.
 bitfield!(FlowFlags, override_line_through, set_override_line_through, 0x08)
 
+// Bits 0x10, 0x20 and 0x40 hold the text decoration of the style of this flow itself, shifted
+// by OWN_TEXT_DECORATION_SHIFT from the flags above.
+
 /// Data common to all flows.
 ///
 /// FIXME: We need a naming convention for pseudo-inheritance like this. How about
@@ -408,7 +492,8 @@ impl FlowData {
         let style = node.style();
         FlowData {
             node: node,
-            restyle_damage: node.restyle_damage(),
+            // A new flow has never been laid out.
+            restyle_damage: RestyleDamage::all(),
 
             children: DList::new(),
 
@@ -437,7 +522,20 @@ impl<'self> ImmutableFlowUtils for &'self Flow {
     fn is_block_like(self) -> bool {
         match self.class() {
             BlockFlowClass => true,
-            AbsoluteFlowClass | InlineBlockFlowClass | InlineFlowClass | TableFlowClass => false,
+            AbsoluteFlowClass | InlineBlockFlowClass | InlineFlowClass | TableFlowClass |
+            TableCaptionFlowClass | TableRowGroupFlowClass | TableRowFlowClass |
+            TableCellFlowClass => false,
+        }
+    }
+
+    /// Returns true if this flow lays out its box and children with a `BlockFlow`, which
+    /// `as_block()` returns.
+    fn is_block_based(self) -> bool {
+        match self.class() {
+            AbsoluteFlowClass | BlockFlowClass | InlineBlockFlowClass | TableFlowClass |
+            TableCaptionFlowClass | TableRowGroupFlowClass | TableRowFlowClass |
+            TableCellFlowClass => true,
+            InlineFlowClass => false,
         }
     }
 
@@ -446,11 +544,13 @@ impl<'self> ImmutableFlowUtils for &'self Flow {
         base(self).children.len() == 0
     }
 
-    /// Returns true if this flow is a block flow, an inline-block flow, or a float flow.
+    /// Returns true if this flow is a block flow, an inline-block flow, a table flow, or a float
+    /// flow.
     fn starts_block_flow(self) -> bool {
         match self.class() {
-            BlockFlowClass | InlineBlockFlowClass => true,
-            AbsoluteFlowClass | InlineFlowClass | TableFlowClass => false,
+            BlockFlowClass | InlineBlockFlowClass | TableFlowClass => true,
+            AbsoluteFlowClass | InlineFlowClass | TableCaptionFlowClass |
+            TableRowGroupFlowClass | TableRowFlowClass | TableCellFlowClass => false,
         }
     }
 
@@ -458,8 +558,19 @@ impl<'self> ImmutableFlowUtils for &'self Flow {
     fn starts_inline_flow(self) -> bool {
         match self.class() {
             InlineFlowClass => true,
-            AbsoluteFlowClass | BlockFlowClass | InlineBlockFlowClass |
-            TableFlowClass => false,
+            AbsoluteFlowClass | BlockFlowClass | InlineBlockFlowClass | TableFlowClass |
+            TableCaptionFlowClass | TableRowGroupFlowClass | TableRowFlowClass |
+            TableCellFlowClass => false,
+        }
+    }
+
+    /// Returns true if this flow is an absolutely or fixed positioned flow.
+    fn is_absolutely_positioned(self) -> bool {
+        match self.class() {
+            AbsoluteFlowClass => true,
+            BlockFlowClass | InlineBlockFlowClass | InlineFlowClass | TableFlowClass |
+            TableCaptionFlowClass | TableRowGroupFlowClass | TableRowFlowClass |
+            TableCellFlowClass => false,
         }
     }
 
@@ -547,10 +658,16 @@ impl<'self> MutableFlowUtils for &'self mut Flow {
 
     fn store_overflow(self, _: &mut LayoutContext) {
         let my_position = mut_base(self).position;
+        let containing_block_size = match self.class() {
+            InlineFlowClass => my_position.size,
+            _ => self.as_block().containing_block_size(),
+        };
         let mut overflow = my_position;
         for kid in mut_base(self).child_iter() {
+            // Relatively positioned kids are painted away from their position.
+            let kid_offset = my_position.origin + relative_position(*kid, &containing_block_size);
             let mut kid_overflow = base(*kid).overflow;
-            kid_overflow = kid_overflow.translate(&my_position.origin);
+            kid_overflow = kid_overflow.translate(&kid_offset);
             overflow = overflow.union(&kid_overflow)
         }
         mut_base(self).overflow = overflow
@@ -560,41 +677,162 @@ impl<'self> MutableFlowUtils for &'self mut Flow {
                           self,
                           builder: &DisplayListBuilder,
                           dirty: &Rect<Au>,
-                          list: &Cell<DisplayList<E>>)
+                          list: &Cell<DisplayList<E>>,
+                          fixed_list: &Cell<DisplayList<E>>)
                           -> bool {
-        debug!("Flow: building display list for f{}", base(self).id);
-        match self.class() {
-            BlockFlowClass => self.as_block().build_display_list_block(builder, dirty, list),
-            InlineFlowClass => self.as_inline().build_display_list_inline(builder, dirty, list),
-            _ => fail!("Tried to build_display_list_recurse of flow: {:?}", self),
-        };
+        let mut positioned_lists = ~[];
+        let mut fixed_lists = ~[];
+        build_display_list_for_flow(self,
+                                    builder,
+                                    dirty,
+                                    list,
+                                    &mut positioned_lists,
+                                    &mut fixed_lists);
+
+        // Unless this is the root flow, positioned descendants may be left to paint on top.
+        for (_, items) in positioned_lists.move_iter() {
+            let items = Cell::new(items);
+            list.with_mut_ref(|list| list.list.push_all_move(items.take()))
+        }
 
-        if list.with_mut_ref(|list| list.list.len() == 0) {
-            return true;
+        // The fixed positioned flows are stacked among themselves, in the layer that they share.
+        let paint_order = stacking_order(fixed_lists);
+        let mut lists: ~[Option<~[DisplayItem<E>]>] =
+            fixed_lists.move_iter().map(|(_, items)| Some(items)).collect();
+        for &(_, i) in paint_order.iter() {
+            let items = Cell::new(lists[i].take_unwrap());
+            fixed_list.with_mut_ref(|list| list.list.push_all_move(items.take()))
         }
+        true
+    }
+}
 
-        let child_list = ~Cell::new(DisplayList::new());
-        for kid in child_iter(self) {
-            kid.build_display_list(builder,dirty,child_list);
+/// Returns the indices of the display lists of positioned flows, along with their 'z-index', in
+/// order of increasing 'z-index' and in tree order for equal values.
+fn stacking_order<E>(lists: &[(i32, ~[DisplayItem<E>])]) -> ~[(i32, uint)] {
+    let mut order = ~[];
+    for (i, &(z_index, _)) in lists.iter().enumerate() {
+        order.push((z_index, i))
+    }
+    quick_sort(order, |a, b| *a <= *b);
+    order
+}
+
+/// Builds the display list of `flow` and its descendants.
+///
+/// Positioned descendants are painted above the in-flow content of their nearest positioned or
+/// translucent ancestor, or of the root, in order of 'z-index' (CSS 2.1 § 9.9.1 and Appendix E).
+/// Flows that are none of these hand the display items of their positioned descendants up through
+/// `positioned_lists`, along with their 'z-index'. Fixed positioned flows are handed up to the
+/// root through `fixed_lists` instead, since they are painted into a layer of their own.
+///
+/// FIXME: A positioned box whose 'z-index' is 'auto' does not establish a stacking context, so its
+/// positioned descendants should be ordered along with its siblings.
+fn build_display_list_for_flow<E:ExtraDisplayListData>(
+                               flow: &mut Flow,
+                               builder: &DisplayListBuilder,
+                               dirty: &Rect<Au>,
+                               list: &Cell<DisplayList<E>>,
+                               positioned_lists: &mut ~[(i32, ~[DisplayItem<E>])],
+                               fixed_lists: &mut ~[(i32, ~[DisplayItem<E>])]) {
+    debug!("Flow: building display list for f{}", base(flow).id);
+    match flow.class() {
+        TableCellFlowClass => flow.as_table_cell().build_display_list_table_cell(builder,
+                                                                                 dirty,
+                                                                                 list),
+        InlineFlowClass => flow.as_inline().build_display_list_inline(builder, dirty, list),
+        _ => flow.as_block().build_display_list_block(builder, dirty, list),
+    };
+
+    let mut kid_items = ~[];
+    let mut positioned_kid_lists = ~[];
+    for kid in child_iter(flow) {
+        let kid_list = ~Cell::new(DisplayList::new());
+        build_display_list_for_flow(*kid,
+                                    builder,
+                                    dirty,
+                                    kid_list,
+                                    &mut positioned_kid_lists,
+                                    fixed_lists);
+        if is_fixed(*kid) {
+            fixed_lists.push((z_index(*kid), kid_list.take().list))
+        } else if is_positioned(*kid) {
+            positioned_kid_lists.push((z_index(*kid), kid_list.take().list))
+        } else {
+            kid_items.push_all_move(kid_list.take().list)
         }
+    }
 
+    let opacity = opacity(flow);
+    let child_items = if is_positioned(flow) || opacity < 1.0 || is_root(flow) {
+        // Paint the positioned descendants with a negative 'z-index' below the in-flow content
+        // and the others above it, in order of increasing 'z-index' and in tree order for equal
+        // values.
+        let paint_order = stacking_order(positioned_kid_lists);
+        let mut lists: ~[Option<~[DisplayItem<E>]>] =
+            positioned_kid_lists.move_iter().map(|(_, items)| Some(items)).collect();
+        let mut child_items = ~[];
+        for &(z_index, i) in paint_order.iter() {
+            if z_index < 0 {
+                child_items.push_all_move(lists[i].take_unwrap())
+            }
+        }
+        child_items.push_all_move(kid_items);
+        for &(z_index, i) in paint_order.iter() {
+            if z_index >= 0 {
+                child_items.push_all_move(lists[i].take_unwrap())
+            }
+        }
+        child_items
+    } else {
+        positioned_lists.push_all_move(positioned_kid_lists);
+        kid_items
+    };
+
+    // The children of an inline flow are inline-blocks, which the items of its boxes do not clip.
+    if !flow.is_block_based() {
+        let child_items = Cell::new(child_items);
+        list.with_mut_ref(|list| list.list.push_all_move(child_items.take()))
+    } else if !child_items.is_empty() {
+        let child_items = Cell::new(child_items);
         do list.with_mut_ref |list| {
             let result = list.list.mut_rev_iter().position(|item| {
                 match *item {
                     ClipDisplayItemClass(ref mut item) => {
-                        item.child_list.push_all_move(child_list.take().list);
+                        item.child_list.push_all_move(child_items.take());
                         true
                     },
                     _ => false,
                 }
             });
 
+            // A flow outside the dirty rectangle has no display items of its own, but the
+            // positioned descendants that it contains may be inside.
             if result.is_none() {
-                fail!("fail to find parent item");
+                list.list.push_all_move(child_items.take())
             }
+        }
+    }
 
+    // Composite a translucent flow, with its descendants, as a single group.
+    if opacity < 1.0 && list.with_mut_ref(|list| list.list.len() > 0) {
+        let bounds = {
+            let base = base(flow);
+            Rect(base.abs_position + base.overflow.origin - base.position.origin,
+                 base.overflow.size)
+        };
+        let block = flow.as_block();
+        let box = block.box.get_ref();
+        do list.with_mut_ref |list| {
+            let opacity_display_item = ~OpacityDisplayItem {
+                base: BaseDisplayItem {
+                    bounds: bounds,
+                    extra: ExtraDisplayListData::new(box),
+                },
+                child_list: util::replace(&mut list.list, ~[]),
+                opacity: opacity,
+            };
+            list.append_item(OpacityDisplayItemClass(opacity_display_item))
         }
-        true
     }
 }
-
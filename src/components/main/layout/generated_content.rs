@@ -0,0 +1,279 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Computes the text of the ::before and ::after pseudo-elements from their 'content' property,
+//! including counters (CSS 2.1 § 12.4) and quotes (CSS 2.1 § 12.3.2).
+//!
+//! Counters depend on everything that precedes an element in the document, so this is a separate
+//! preorder traversal that runs after styling and before flow construction, which is bottom-up.
+
+use css::node_style::StyledNode;
+use layout::incremental::RestyleDamage;
+use layout::util::LayoutDataAccess;
+
+use script::dom::node::{AbstractNode, LayoutView};
+use servo_util::tree::{TreeNodeRef, ElementLike};
+use std::ascii::StrAsciiExt;
+use std::cmp;
+use std::hashmap::HashMap;
+use std::str;
+use style::{ComputedValues, PseudoElement, Before, After};
+use style::computed_values::{content, display};
+
+/// The counters in scope and the nesting level of quotes at some point of the document.
+struct GeneratedContentResolver {
+    /// The instances of each counter in scope, from the outermost to the innermost.
+    counters: HashMap<~str, ~[i32]>,
+    /// The number of quotes that have been opened and not closed yet.
+    quote_depth: uint,
+}
+
+/// Resolves the generated content of `root` and all of its descendants, in document order. The
+/// results are stored in the layout data of each element.
+pub fn resolve_generated_content(root: AbstractNode<LayoutView>) {
+    let mut resolver = GeneratedContentResolver {
+        counters: HashMap::new(),
+        quote_depth: 0,
+    };
+    let mut created = ~[];
+    resolver.resolve_element(root, &mut created)
+}
+
+impl GeneratedContentResolver {
+    /// `created` lists the counters instantiated by `element` and its preceding siblings. Their
+    /// scope ends with the parent of `element`.
+    fn resolve_element(&mut self, element: AbstractNode<LayoutView>, created: &mut ~[~str]) {
+        let style = element.style().clone();
+        let style = style.get();
+        if style.Box.display == display::none {
+            // Elements that are not displayed do not affect counters (CSS 2.1 § 12.4.3), and
+            // neither they nor their descendants generate boxes.
+            return
+        }
+        self.update_counters(style, created);
+
+        // ::before and ::after behave like the first and last children of the element.
+        let mut created_inside = ~[];
+        let before = self.resolve_pseudo_element(element, Before, &mut created_inside);
+        for kid in element.children() {
+            if kid.is_element() {
+                self.resolve_element(kid, &mut created_inside)
+            }
+        }
+        let after = self.resolve_pseudo_element(element, After, &mut created_inside);
+        self.pop_counters(created_inside);
+
+        match *element.mutate_layout_data().ptr {
+            Some(ref mut layout_data) => {
+                // Counters and quotes depend on the rest of the document, so the text may change
+                // even though the style of the element did not. The boxes of the element have to
+                // be rebuilt then.
+                if layout_data.before.content != before || layout_data.after.content != after {
+                    layout_data.restyle_damage = Some(RestyleDamage::all().to_int())
+                }
+                layout_data.before.content = before;
+                layout_data.after.content = after;
+            }
+            None => fail!("no layout data"),
+        }
+    }
+
+    /// Returns the text of the given pseudo-element of `element`, or `None` if it does not
+    /// generate a box.
+    fn resolve_pseudo_element(&mut self,
+                              element: AbstractNode<LayoutView>,
+                              pseudo_element: PseudoElement,
+                              created: &mut ~[~str])
+                              -> Option<~str> {
+        let style = match *element.mutate_layout_data().ptr {
+            Some(ref mut layout_data) => layout_data.pseudo_element(pseudo_element).style.clone(),
+            None => fail!("no layout data"),
+        };
+        let style = match style {
+            Some(style) => style,
+            None => return None,
+        };
+        let style = style.get();
+        let items = match style.Box.content {
+            content::Content(ref items) => items,
+            // 'normal' computes to 'none' for ::before and ::after.
+            content::normal | content::none => return None,
+        };
+        if style.Box.display == display::none {
+            return None
+        }
+        self.update_counters(style, created);
+
+        let mut text = ~"";
+        for item in items.iter() {
+            match *item {
+                content::StringContent(ref string) => text.push_str(string.as_slice()),
+                content::AttrContent(ref name) => {
+                    let value = element.with_imm_element(|element| {
+                        element.get_attr(None, name.as_slice()).map(|value| value.to_owned())
+                    });
+                    text.push_str(value.unwrap_or(~""))
+                }
+                content::CounterContent(ref name, counter_style) => {
+                    let instances = self.instances(name);
+                    text.push_str(format_counter(*instances.last(), counter_style))
+                }
+                content::CountersContent(ref name, ref separator, counter_style) => {
+                    let values: ~[~str] = self.instances(name).iter().map(|value| {
+                        format_counter(*value, counter_style)
+                    }).collect();
+                    text.push_str(values.connect(separator.as_slice()))
+                }
+                content::OpenQuote => {
+                    text.push_str(quote(style, self.quote_depth, true));
+                    self.quote_depth += 1
+                }
+                content::CloseQuote => {
+                    // A close-quote without a matching open-quote produces nothing.
+                    if self.quote_depth > 0 {
+                        self.quote_depth -= 1;
+                        text.push_str(quote(style, self.quote_depth, false))
+                    }
+                }
+                content::NoOpenQuote => self.quote_depth += 1,
+                content::NoCloseQuote => {
+                    if self.quote_depth > 0 {
+                        self.quote_depth -= 1
+                    }
+                }
+            }
+        }
+        Some(text)
+    }
+
+    /// Returns the instances of a counter in scope. A counter that is not in scope behaves as if
+    /// it had been reset to zero.
+    fn instances(&self, name: &~str) -> ~[i32] {
+        match self.counters.find(name) {
+            Some(instances) if !instances.is_empty() => instances.clone(),
+            _ => ~[0],
+        }
+    }
+
+    /// Applies 'counter-reset', then 'counter-increment'.
+    fn update_counters(&mut self, style: &ComputedValues, created: &mut ~[~str]) {
+        for &(ref name, value) in style.Box.counter_reset.iter() {
+            self.reset_counter(name.clone(), value, created)
+        }
+        for &(ref name, value) in style.Box.counter_increment.iter() {
+            // Incrementing a counter that is not in scope instantiates it first.
+            let in_scope = self.counters.find(name).map_default(false, |instances| {
+                !instances.is_empty()
+            });
+            if !in_scope {
+                self.reset_counter(name.clone(), 0, created)
+            }
+            let instances = self.counters.get_mut(name);
+            let innermost = instances.len() - 1;
+            instances[innermost] += value
+        }
+    }
+
+    /// Instantiates a counter. A counter instantiated by a preceding sibling is reset instead of
+    /// being nested in a new instance.
+    fn reset_counter(&mut self, name: ~str, value: i32, created: &mut ~[~str]) {
+        if created.contains(&name) {
+            let instances = self.counters.get_mut(&name);
+            let innermost = instances.len() - 1;
+            instances[innermost] = value;
+            return
+        }
+        created.push(name.clone());
+        self.counters.find_or_insert(name, ~[]).push(value)
+    }
+
+    /// Ends the scope of the given counter instances.
+    fn pop_counters(&mut self, created: ~[~str]) {
+        for name in created.iter() {
+            self.counters.get_mut(name).pop();
+        }
+    }
+}
+
+/// Returns the open or close quote for the given nesting level. Levels deeper than the 'quotes'
+/// property specifies use its innermost pair.
+fn quote(style: &ComputedValues, depth: uint, open: bool) -> ~str {
+    let quotes = &style.Box.quotes;
+    if quotes.is_empty() {
+        return ~""
+    }
+    let (ref open_quote, ref close_quote) = quotes[cmp::min(depth, quotes.len() - 1)];
+    if open { open_quote.clone() } else { close_quote.clone() }
+}
+
+/// Formats the value of a counter in the given style (CSS 2.1 § 12.6.2).
+pub fn format_counter(value: i32, counter_style: content::CounterStyle) -> ~str {
+    match counter_style {
+        content::Disc => ~"\u2022",
+        content::Circle => ~"\u25e6",
+        content::Square => ~"\u25aa",
+        content::Decimal => value.to_str(),
+        content::LowerRoman => format_roman(value, false),
+        content::UpperRoman => format_roman(value, true),
+        content::LowerAlpha => format_alpha(value, false),
+        content::UpperAlpha => format_alpha(value, true),
+        content::NoCounterStyle => ~"",
+    }
+}
+
+/// Roman numerals only go from 1 to 3999. Other values are formatted as decimal numbers.
+fn format_roman(value: i32, upper: bool) -> ~str {
+    static NUMERALS: [(i32, &'static str), ..13] = [
+        (1000, "m"), (900, "cm"), (500, "d"), (400, "cd"), (100, "c"), (90, "xc"), (50, "l"),
+        (40, "xl"), (10, "x"), (9, "ix"), (5, "v"), (4, "iv"), (1, "i"),
+    ];
+    if value < 1 || value > 3999 {
+        return value.to_str()
+    }
+    let mut value = value;
+    let mut result = ~"";
+    for &(numeral_value, numeral) in NUMERALS.iter() {
+        while value >= numeral_value {
+            result.push_str(numeral);
+            value -= numeral_value
+        }
+    }
+    if upper { result.to_ascii_upper() } else { result }
+}
+
+/// Letters go a, b, ..., z, aa, ab, ... from 1. Other values are formatted as decimal numbers.
+fn format_alpha(value: i32, upper: bool) -> ~str {
+    if value < 1 {
+        return value.to_str()
+    }
+    let first = if upper { 'A' as u8 } else { 'a' as u8 };
+    let mut value = value;
+    let mut letters = ~[];
+    while value > 0 {
+        value -= 1;
+        letters.push((first + (value % 26) as u8) as char);
+        value /= 26
+    }
+    letters.reverse();
+    str::from_chars(letters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_counter;
+    use style::computed_values::content;
+
+    #[test]
+    fn test_format_counter() {
+        assert_eq!(format_counter(3, content::Decimal), ~"3");
+        assert_eq!(format_counter(-2, content::Decimal), ~"-2");
+        assert_eq!(format_counter(1994, content::UpperRoman), ~"MCMXCIV");
+        assert_eq!(format_counter(14, content::LowerRoman), ~"xiv");
+        assert_eq!(format_counter(0, content::LowerRoman), ~"0");
+        assert_eq!(format_counter(1, content::LowerAlpha), ~"a");
+        assert_eq!(format_counter(28, content::UpperAlpha), ~"AB");
+        assert_eq!(format_counter(5, content::NoCounterStyle), ~"");
+        assert_eq!(format_counter(5, content::Disc), ~"\u2022");
+    }
+}
@@ -14,14 +14,19 @@ pub enum RestyleEffect {
     Repaint = 0x01,
 
     /// Recompute intrinsic widths (minimum and preferred).
-    /// Propagates down the flow tree because the computation is
-    /// bottom-up.
+    /// Propagates up the flow tree because the intrinsic widths of
+    /// a flow depend on those of its children.
     BubbleWidths = 0x02,
 
     /// Recompute actual widths and heights.
-    /// Propagates up the flow tree because the computation is
-    /// top-down.
+    /// Propagates up the flow tree because the height of a flow
+    /// depends on those of its children.
     Reflow = 0x04,
+
+    /// Rebuild the flows and boxes of the node.
+    /// Propagates up the DOM because the flows of a node hold those
+    /// of its descendants.
+    ReconstructFlow = 0x08,
 }
 
 /// A set of RestyleEffects.
@@ -44,7 +49,7 @@ impl RestyleDamage {
     }
 
     pub fn all() -> RestyleDamage {
-        restyle_damage!(Repaint, BubbleWidths, Reflow)
+        restyle_damage!(Repaint, BubbleWidths, Reflow, ReconstructFlow)
     }
 
     /// Create a RestyleDamage from the underlying bit field.
@@ -92,12 +97,13 @@ impl RestyleDamage {
 
     /// Elements of self which should also get set on any ancestor flow.
     pub fn propagate_up(self) -> RestyleDamage {
-        self.intersect(restyle_damage!(Reflow))
+        self.intersect(restyle_damage!(BubbleWidths, Reflow))
     }
 
-    /// Elements of self which should also get set on any child flows.
+    /// Elements of self which should also get set on the child flows of a flow that lays them
+    /// out along with itself, like the parts of a table.
     pub fn propagate_down(self) -> RestyleDamage {
-        self.intersect(restyle_damage!(BubbleWidths))
+        self.intersect(restyle_damage!(BubbleWidths, Reflow))
     }
 }
 
@@ -123,18 +129,31 @@ pub fn compute_damage(old: &ComputedValues, new: &ComputedValues) -> RestyleDama
     // FIXME: We can short-circuit more of this.
 
     add_if_not_equal!(old, new, damage, [ Repaint ],
-        [ Color.color, Background.background_color,
+        [ Color.color, Background.background_color, Background.background_image,
+          Background.background_repeat, Background.background_attachment,
+          Background.background_position,
           Border.border_top_color, Border.border_right_color,
-          Border.border_bottom_color, Border.border_left_color ]);
+          Border.border_bottom_color, Border.border_left_color,
+          Border.border_top_style, Border.border_right_style,
+          Border.border_bottom_style, Border.border_left_style,
+          Box.overflow, Box.z_index, Box.opacity, Box.visibility,
+          Box.page_break_before, Box.page_break_after, Box.page_break_inside,
+          Table.empty_cells ]);
 
     add_if_not_equal!(old, new, damage, [ Repaint, BubbleWidths, Reflow ],
         [ Border.border_top_width, Border.border_right_width,
           Border.border_bottom_width, Border.border_left_width,
           Margin.margin_top, Margin.margin_right, Margin.margin_bottom, Margin.margin_left,
           Padding.padding_top, Padding.padding_right, Padding.padding_bottom, Padding.padding_left,
-          Box.position, Box.width, Box.height, Box.float, Box.display,
+          Box.position, Box.width, Box.height, Box.float, Box.display, Box.clear,
+          Box.top, Box.right, Box.bottom, Box.left,
+          Box.min_width, Box.max_width, Box.min_height, Box.max_height, Box.vertical_align,
+          Box.content, Box.counter_increment, Box.counter_reset, Box.quotes,
           Font.font_family, Font.font_size, Font.font_style, Font.font_weight,
-          Text.text_align, Text.text_decoration, Box.line_height ]);
+          Text.text_align, Text.text_decoration, Text.text_indent, Text.letter_spacing,
+          Text.word_spacing, Text.text_transform, Text.white_space, Box.line_height,
+          Font.font_variant,
+          Table.table_layout, Table.caption_side, Table.border_collapse, Table.border_spacing ]);
 
     // FIXME: test somehow that we checked every CSS property
 
@@ -145,6 +164,9 @@ pub fn compute_damage(old: &ComputedValues, new: &ComputedValues) -> RestyleDama
 #[cfg(test)]
 mod restyle_damage_tests {
     use super::*;
+    use servo_util::geometry::Au;
+    use style::{cascade, Device, Screen};
+    use style::computed_values::{LPN_Length, overflow};
 
     #[test]
     fn none_is_empty() {
@@ -193,4 +215,39 @@ mod restyle_damage_tests {
         assert!(d.lacks(BubbleWidths));
         assert!(d.lacks(Reflow));
     }
+
+    #[test]
+    fn layout_damage_propagates_up() {
+        let d = RestyleDamage::all().propagate_up();
+        assert!(d.has(BubbleWidths));
+        assert!(d.has(Reflow));
+        assert!(d.lacks(Repaint));
+        assert!(d.lacks(ReconstructFlow));
+    }
+
+    #[test]
+    fn height_constraints_cause_reflow() {
+        let old = cascade([], None, &Device::new(Screen, Au::from_px(800), Au::from_px(600), 1.));
+        let mut new = old.clone();
+        new.Box.max_height = LPN_Length(Au::from_px(10));
+        assert!(compute_damage(&old, &new).has(Reflow));
+    }
+
+    #[test]
+    fn border_spacing_causes_reflow() {
+        let old = cascade([], None, &Device::new(Screen, Au::from_px(800), Au::from_px(600), 1.));
+        let mut new = old.clone();
+        new.Table.border_spacing.vertical = Au::from_px(2);
+        assert!(compute_damage(&old, &new).has(Reflow));
+    }
+
+    #[test]
+    fn overflow_causes_repaint_only() {
+        let old = cascade([], None, &Device::new(Screen, Au::from_px(800), Au::from_px(600), 1.));
+        let mut new = old.clone();
+        new.Box.overflow = overflow::scroll;
+        let d = compute_damage(&old, &new);
+        assert!(d.has(Repaint));
+        assert!(d.lacks(Reflow));
+    }
 }
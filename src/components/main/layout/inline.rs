@@ -3,24 +3,32 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use css::node_style::StyledNode;
-use layout::box::{Box, CannotSplit, GenericBox, IframeBox, ImageBox, ScannedTextBox, SplitDidFit};
-use layout::box::{SplitDidNotFit, UnscannedTextBox};
+use layout::box::{Box, CannotSplit, GenericBox, IframeBox, ImageBox, InlineBlockBox};
+use layout::box::{ScannedTextBox, ScannedTextBoxInfo, SplitDidFit, SplitDidNotFit};
+use layout::box::{UnscannedTextBox};
 use layout::context::LayoutContext;
 use layout::display_list_builder::{DisplayListBuilder, ExtraDisplayListData};
 use layout::flow::{FlowClass, Flow, FlowData, InlineFlowClass};
 use layout::flow;
 use layout::float_context::FloatContext;
-use layout::util::{ElementMapping};
+use layout::inline_block::InlineBlockFlow;
+use layout::text::restyle_text_box;
+use layout::util::{ElementMapping, LayoutDataAccess};
 use layout::float_context::{PlacementInfo, FloatLeft};
+use layout::model::specified;
 
+use extra::arc::Arc;
 use extra::container::Deque;
 use extra::ringbuf::RingBuf;
 use geom::{Point2D, Rect, Size2D};
 use gfx::display_list::DisplayList;
+use script::dom::node::{AbstractNode, LayoutView};
+use style::{ComputedValues, cascade};
 use style::computed_values::text_align;
 use style::computed_values::vertical_align;
 use servo_util::geometry::Au;
 use servo_util::range::Range;
+use servo_util::tree::TreeNodeRef;
 use std::cell::Cell;
 use std::u16;
 use std::util;
@@ -50,10 +58,12 @@ use std::util;
 /// with a float or a horizontal wall of the containing block. The top
 /// left corner of the green zone is the same as that of the line, but
 /// the green zone can be taller and wider than the line itself.
-struct LineBox {
+pub struct LineBox {
     range: Range,
     bounds: Rect<Au>,
-    green_zone: Size2D<Au>
+    green_zone: Size2D<Au>,
+    /// The distance from the top of the line to its baseline, once the boxes are laid out.
+    baseline: Au,
 }
 
 struct LineboxScanner {
@@ -63,10 +73,20 @@ struct LineboxScanner {
     pending_line: LineBox,
     lines: ~[LineBox],
     cur_y: Au,
+    /// The used value of 'text-indent', which shifts the first line.
+    text_indent: Au,
+    /// The ::first-line style of the block, if this flow holds its first line.
+    first_line_style: Option<Arc<ComputedValues>>,
+    /// The original version of the last box taken from the flow while the first line is laid
+    /// out, if it was restyled for the first line. Boxes are taken one at a time, so only parts of
+    /// it can be left over once the first line is complete, and they get its style back.
+    first_line_original: Option<Box>,
 }
 
 impl LineboxScanner {
-    pub fn new(float_ctx: FloatContext) -> LineboxScanner {
+    pub fn new(float_ctx: FloatContext, text_indent: Au,
+               first_line_style: Option<Arc<ComputedValues>>)
+               -> LineboxScanner {
         LineboxScanner {
             floats: float_ctx,
             new_boxes: ~[],
@@ -74,10 +94,14 @@ impl LineboxScanner {
             pending_line: LineBox {
                 range: Range::empty(),
                 bounds: Rect(Point2D(Au::new(0), Au::new(0)), Size2D(Au::new(0), Au::new(0))),
-                green_zone: Size2D(Au::new(0), Au::new(0))
+                green_zone: Size2D(Au::new(0), Au::new(0)),
+                baseline: Au::new(0),
             },
             lines: ~[],
-            cur_y: Au::new(0)
+            cur_y: Au::new(0),
+            text_indent: text_indent,
+            first_line_style: first_line_style,
+            first_line_original: None,
         }
     }
 
@@ -111,9 +135,11 @@ impl LineboxScanner {
         self.pending_line.green_zone = Size2D(Au::new(0), Au::new(0))
     }
 
-    pub fn scan_for_lines(&mut self, flow: &mut InlineFlow) {
+    pub fn scan_for_lines(&mut self, ctx: &mut LayoutContext, flow: &mut InlineFlow) {
         self.reset_scanner(flow);
 
+        // The index of the next box of the box list in the original boxes of the flow.
+        let mut box_index = 0;
         loop {
             // acquire the next box to lay out from work list or box list
             let cur_box = if self.work_list.is_empty() {
@@ -122,6 +148,12 @@ impl LineboxScanner {
                 }
                 let box = flow.boxes.remove(0); // FIXME: use a linkedlist
                 debug!("LineboxScanner: Working with box from box list: b{}", box.debug_id());
+                let box = if self.lines.is_empty() {
+                    self.restyle_for_first_line(ctx, box, box_index, flow)
+                } else {
+                    box
+                };
+                box_index += 1;
                 box
             } else {
                 let box = self.work_list.pop_front().unwrap();
@@ -129,14 +161,23 @@ impl LineboxScanner {
                 box
             };
 
+            let was_first_line = self.lines.is_empty();
             let box_was_appended = self.try_append_to_line(cur_box, flow);
             if !box_was_appended {
                 debug!("LineboxScanner: Box wasn't appended, because line {:u} was full.",
                         self.lines.len());
                 self.flush_current_line();
+            } else if self.new_boxes.last().ends_with_newline() {
+                debug!("LineboxScanner: appended a box ending with a preserved newline to line \
+                        {:u}",
+                       self.lines.len());
+                self.flush_current_line();
             } else {
                 debug!("LineboxScanner: appended a box to line {:u}", self.lines.len());
             }
+            if was_first_line && !self.lines.is_empty() {
+                self.end_first_line()
+            }
         }
 
         if self.pending_line.range.length() > 0 {
@@ -150,6 +191,137 @@ impl LineboxScanner {
         self.swap_out_results(flow);
     }
 
+    /// Gives a box from the first line, the one at `index` in the original boxes of `flow`, its
+    /// style on the first line. The restyled boxes are kept by the flow, so that their text is
+    /// shaped once.
+    fn restyle_for_first_line(&mut self,
+                              ctx: &mut LayoutContext,
+                              box: Box,
+                              index: uint,
+                              flow: &mut InlineFlow)
+                              -> Box {
+        if self.first_line_style.is_none() {
+            return box
+        }
+        if index == flow.first_line_boxes.len() {
+            let restyled_box = match self.first_line_style_of(ctx, &box, flow.base.node) {
+                None => None,
+                Some(style) => {
+                    match box.specific {
+                        ScannedTextBox(_) => Some(restyle_text_box(ctx, &box, style)),
+                        _ => Some(with_style(&box, style)),
+                    }
+                }
+            };
+            flow.first_line_boxes.push(restyled_box)
+        }
+
+        // Text is sized by its run, while the other boxes keep the size they have been given.
+        let restyled_box = match flow.first_line_boxes[index] {
+            None => None,
+            Some(ref restyled_box) => {
+                match restyled_box.specific {
+                    ScannedTextBox(_) => Some(restyled_box.clone()),
+                    _ => Some(with_style(&box, restyled_box.style.clone())),
+                }
+            }
+        };
+        match restyled_box {
+            Some(restyled_box) => {
+                self.first_line_original = Some(box);
+                restyled_box
+            }
+            None => {
+                self.first_line_original = None;
+                box
+            }
+        }
+    }
+
+    /// Returns the style of `box` on the first line, if it has one: the ::first-line style, with
+    /// the declarations of the elements between the block and the box cascaded again on top of it,
+    /// and then those of the pseudo-element that the box is, if any (CSS 2.1 § 5.12.1). Text,
+    /// generated content and replaced elements are restyled.
+    ///
+    /// FIXME: The contents of inline-blocks keep their style.
+    fn first_line_style_of(&self,
+                           ctx: &mut LayoutContext,
+                           box: &Box,
+                           block: AbstractNode<LayoutView>)
+                           -> Option<Arc<ComputedValues>> {
+        let first_line_style = match self.first_line_style {
+            Some(ref style) => style.clone(),
+            None => return None,
+        };
+
+        // The text of an element takes its style from the element, while generated content and
+        // the first letter belong to the element that generates them.
+        let (node, pseudo_element) = match box.specific {
+            ScannedTextBox(_) if !box.node.is_element() => (box.node.parent_node(), None),
+            ScannedTextBox(_) => {
+                match box.pseudo_element {
+                    Some(pseudo_element) => (Some(box.node), Some(pseudo_element)),
+                    None => return None,
+                }
+            }
+            ImageBox(_) | IframeBox(_) => (Some(box.node), None),
+            _ => return None,
+        };
+
+        // The elements from `node` up to the block, exclusive.
+        let mut ancestors = ~[];
+        let mut node = node;
+        loop {
+            match node {
+                Some(element) if element == block => break,
+                Some(element) => {
+                    ancestors.push(element);
+                    node = element.parent_node()
+                }
+                None => return None,
+            }
+        }
+
+        let mut style = first_line_style;
+        for element in ancestors.rev_iter() {
+            style = match *element.mutate_layout_data().ptr {
+                Some(ref layout_data) => {
+                    Arc::new(cascade(layout_data.applicable_declarations, Some(style.get()),
+                                     &ctx.device))
+                }
+                None => fail!("no layout data"),
+            };
+        }
+        for &pseudo_element in pseudo_element.iter() {
+            style = match *box.node.mutate_layout_data().ptr {
+                Some(ref mut layout_data) => {
+                    let declarations = layout_data.pseudo_element(pseudo_element)
+                                                  .applicable_declarations.as_slice();
+                    Arc::new(cascade(declarations, Some(style.get()), &ctx.device))
+                }
+                None => fail!("no layout data"),
+            };
+        }
+        Some(style)
+    }
+
+    /// Gives the remaining parts of the box restyled for the first line their original style
+    /// back, now that the first line is complete.
+    fn end_first_line(&mut self) {
+        let original = match util::replace(&mut self.first_line_original, None) {
+            Some(original) => original,
+            None => return,
+        };
+        let mut work_list = RingBuf::new();
+        loop {
+            match self.work_list.pop_front() {
+                None => break,
+                Some(box) => work_list.push_back(restore_original_style(box, &original)),
+            }
+        }
+        self.work_list = work_list
+    }
+
     fn swap_out_results(&mut self, flow: &mut InlineFlow) {
         debug!("LineboxScanner: Propagating scanned lines[n={:u}] to inline flow f{:d}",
                self.lines.len(),
@@ -322,11 +494,26 @@ impl LineboxScanner {
     /// Tries to append the given box to the line, splitting it if necessary. Returns false only if
     /// we should break the line.
     fn try_append_to_line(&mut self, in_box: Box, flow: &mut InlineFlow) -> bool {
+        // A preserved newline forces a line break, so only the text up to the first one is
+        // considered for this line.
+        let in_box = match in_box.split_after_newline() {
+            Some((left_box, right_box)) => {
+                debug!("LineboxScanner: case=box has a preserved newline; deferring the text \
+                        after it.");
+                self.work_list.push_front(right_box);
+                left_box
+            }
+            None => in_box,
+        };
+
         let line_is_empty = self.pending_line.range.length() == 0;
         if line_is_empty {
             let (line_bounds, _) = self.initial_line_placement(&in_box, self.cur_y, flow);
             self.pending_line.bounds.origin = line_bounds.origin;
             self.pending_line.green_zone = line_bounds.size;
+            if self.lines.is_empty() {
+                self.pending_line.bounds.size.width = self.text_indent;
+            }
         }
 
         debug!("LineboxScanner: Trying to append box to line {:u} (box size: {}, green zone: \
@@ -431,6 +618,46 @@ impl LineboxScanner {
     }
 }
 
+/// Calls `f` with each inline-block child of an inline flow, whose data is `base`, and the box of
+/// `boxes`, the boxes of that flow, that holds its place in the lines. Both are in document order.
+fn each_inline_block(base: &mut FlowData, boxes: &[Box], f: &fn(&mut InlineBlockFlow, &Box)) {
+    let mut inline_block_boxes = boxes.iter().filter(|box| {
+        match box.specific {
+            InlineBlockBox(_) => true,
+            _ => false,
+        }
+    });
+    for kid in base.child_iter() {
+        match inline_block_boxes.next() {
+            Some(box) => f(kid.as_inline_block(), box),
+            None => fail!("no box for inline-block flow"),
+        }
+    }
+}
+
+/// Returns `box`, a part of the box restyled for the first line whose original version is
+/// `original`, with its original style. A restyled run has the same characters at the same
+/// indices as the original range, so `box` maps to the same characters of the original run.
+fn restore_original_style(box: Box, original: &Box) -> Box {
+    match (&box.specific, &original.specific) {
+        (&ScannedTextBox(ref text_box_info), &ScannedTextBox(ref original_info)) => {
+            let range = Range::new(original_info.range.begin() + text_box_info.range.begin(),
+                                   text_box_info.range.length());
+            let size = original_info.run.get().metrics_for_range(&range).bounding_box.size;
+            let new_text_box_info = ScannedTextBoxInfo::new(original_info.run.clone(), range);
+            original.transform(size, ScannedTextBox(new_text_box_info))
+        }
+        _ => original.clone(),
+    }
+}
+
+/// Returns a copy of `box`, with the same size, that has the given style.
+fn with_style(box: &Box, style: Arc<ComputedValues>) -> Box {
+    let mut new_box = box.transform(box.position.get().size, box.specific.clone());
+    new_box.style = style;
+    new_box
+}
+
 pub struct InlineFlow {
     /// Data common to all flows.
     base: FlowData,
@@ -445,7 +672,22 @@ pub struct InlineFlow {
     // vec of ranges into boxes that represent elements. These ranges
     // must be well-nested, and are only related to the content of
     // boxes (not lines). Ranges are only kept for non-leaf elements.
-    elems: ElementMapping
+    elems: ElementMapping,
+
+    /// The ::first-line style of the block, if the first line of the block is in this flow.
+    first_line_style: Option<Arc<ComputedValues>>,
+
+    /// Whether 'text-indent' applies to the first line of this flow, which is the case if it is
+    /// the first line of the block.
+    indent_first_line: bool,
+
+    /// The boxes of this flow as they were before they were split into lines, so that the flow
+    /// can be laid out again when it is reused across reflows.
+    original_boxes: Option<~[Box]>,
+
+    /// The boxes restyled for the first line, by their index in `original_boxes`, or `None` for
+    /// those that keep their style, so that their text is shaped only once.
+    first_line_boxes: ~[Option<Box>],
 }
 
 impl InlineFlow {
@@ -455,6 +697,10 @@ impl InlineFlow {
             boxes: ~[],
             lines: ~[],
             elems: ElementMapping::new(),
+            first_line_style: None,
+            indent_first_line: true,
+            original_boxes: None,
+            first_line_boxes: ~[],
         }
     }
 
@@ -464,6 +710,10 @@ impl InlineFlow {
             boxes: boxes,
             lines: ~[],
             elems: ElementMapping::new(),
+            first_line_style: None,
+            indent_first_line: true,
+            original_boxes: None,
+            first_line_boxes: ~[],
         }
     }
 
@@ -472,14 +722,47 @@ impl InlineFlow {
             box.teardown();
         }
         self.boxes = ~[];
+        self.original_boxes = None;
+        self.first_line_boxes = ~[];
+    }
+
+    /// Puts back the boxes that this flow had before it was split into lines, if it has been, so
+    /// that it can be laid out again.
+    fn restore_original_boxes(&mut self) {
+        if self.lines.is_empty() {
+            return
+        }
+        for original_boxes in self.original_boxes.iter() {
+            self.boxes = original_boxes.clone();
+        }
+        self.lines = ~[];
     }
 
     pub fn build_display_list_inline<E:ExtraDisplayListData>(
-                                     &self,
+                                     &mut self,
                                      builder: &DisplayListBuilder,
                                      dirty: &Rect<Au>,
                                      list: &Cell<DisplayList<E>>)
                                      -> bool {
+        // Inline-blocks may be visible even if this flow is not. They move along with the
+        // relatively positioned inline elements that contain them.
+        let mut relative_positions = ~[];
+        for box in self.boxes.iter() {
+            match box.specific {
+                InlineBlockBox(_) => {
+                    relative_positions.push(box.relative_position_in_inline_flow(&*self as &Flow))
+                }
+                _ => {}
+            }
+        }
+        let abs_position = self.base.abs_position;
+        for (kid, relative_position) in self.base.child_iter().zip(relative_positions.iter()) {
+            let kid_base = flow::mut_base(*kid);
+            kid_base.abs_position = abs_position + kid_base.position.origin + *relative_position
+        }
+
+        // FIXME: Relatively positioned boxes may be shifted into the dirty rectangle from outside
+        // of this flow.
         let abs_rect = Rect(self.base.abs_position, self.base.position.size);
         if !abs_rect.intersects(dirty) {
             return true;
@@ -495,10 +778,6 @@ impl InlineFlow {
             box.build_display_list(builder, dirty, self.base.abs_position, (&*self) as &Flow, list)
         }
 
-        // TODO(#225): Should `inline-block` elements have flows as children of the inline flow or
-        // should the flow be nested inside the box somehow?
-
-        // For now, don't traverse the subtree rooted here
         true
     }
 
@@ -570,16 +849,23 @@ impl InlineFlow {
                 let percent_offset = line_height.scale_by(p);
                 (-(percent_offset + ascent), false)
             }
+            vertical_align::Calc(calc) => {
+                let pt_size = cur_box.font_style().pt_size;
+                let line_height = cur_box.calculate_line_height(Au::from_pt(pt_size));
+                (-(calc.resolve(line_height) + ascent), false)
+            }
         }
     }
 
-    /// Sets box X positions based on alignment for one line.
-    fn set_horizontal_box_positions(boxes: &[Box], line: &LineBox, linebox_align: text_align::T) {
+    /// Sets box X positions based on alignment for one line. `indent` is the part of the width
+    /// of the line taken by 'text-indent'.
+    fn set_horizontal_box_positions(boxes: &[Box], line: &LineBox, linebox_align: text_align::T,
+                                    indent: Au) {
         // Figure out how much width we have.
         let slack_width = Au::max(Au(0), line.green_zone.width - line.bounds.size.width);
 
         // Set the box x positions based on that alignment.
-        let mut offset_x = line.bounds.origin.x;
+        let mut offset_x = line.bounds.origin.x + indent;
         offset_x = offset_x + match linebox_align {
             // So sorry, but justified text is more complicated than shuffling linebox
             // coordinates.
@@ -613,17 +899,19 @@ impl Flow for InlineFlow {
     }
 
     fn bubble_widths(&mut self, _: &mut LayoutContext) {
-        let mut num_floats = 0;
+        self.restore_original_boxes();
 
+        let mut min_width = Au::new(0);
+        let mut pref_width = Au::new(0);
+
+        // The children are inline-blocks, whose boxes leave their widths to them. They lay out
+        // their floats by themselves.
         for kid in self.base.child_iter() {
             let child_base = flow::mut_base(*kid);
-            num_floats += child_base.num_floats;
-            child_base.floats_in = FloatContext::new(child_base.num_floats);
+            min_width = Au::max(min_width, child_base.min_width);
+            pref_width = Au::max(pref_width, child_base.pref_width);
         }
 
-        let mut min_width = Au::new(0);
-        let mut pref_width = Au::new(0);
-
         for box in self.boxes.iter() {
             debug!("Flow[{:d}]: measuring {:s}", self.base.id, box.debug_str());
             let (this_minimum_width, this_preferred_width) =
@@ -634,7 +922,7 @@ impl Flow for InlineFlow {
 
         self.base.min_width = min_width;
         self.base.pref_width = pref_width;
-        self.base.num_floats = num_floats;
+        self.base.num_floats = 0;
     }
 
     /// Recursively (top-down) determines the actual width of child contexts and boxes. When called
@@ -645,6 +933,7 @@ impl Flow for InlineFlow {
         // TODO: Combine this with `LineboxScanner`'s walk in the box list, or put this into `Box`.
 
         debug!("assign_widths_inline: floats_in: {:?}", self.base.floats_in);
+        self.restore_original_boxes();
         {
             let this = &mut *self;
             for box in this.boxes.iter() {
@@ -652,18 +941,13 @@ impl Flow for InlineFlow {
             }
         }
 
+        // The inline-block children shrink to fit in the width of this flow. Their boxes get their
+        // size once they are laid out, before the lines are.
         for kid in self.base.child_iter() {
             let child_base = flow::mut_base(*kid);
             child_base.position.size.width = self.base.position.size.width;
             child_base.flags.set_inorder(self.base.flags.inorder());
         }
-        // There are no child contexts, so stop here.
-
-        // TODO(Issue #225): once there are 'inline-block' elements, this won't be
-        // true.  In that case, set the InlineBlockBox's width to the
-        // shrink-to-fit width, perform inline flow, and set the block
-        // flow context's width as the assigned width of the
-        // 'inline-block' box that created this flow before recursing.
     }
 
     fn assign_height_inorder(&mut self, ctx: &mut LayoutContext) {
@@ -673,7 +957,7 @@ impl Flow for InlineFlow {
         self.assign_height(ctx);
     }
 
-    fn assign_height(&mut self, _: &mut LayoutContext) {
+    fn assign_height(&mut self, ctx: &mut LayoutContext) {
         debug!("assign_height_inline: assigning height for flow {}", self.base.id);
 
         // Divide the boxes into lines.
@@ -687,11 +971,30 @@ impl Flow for InlineFlow {
         // TODO(pcwalton): Cache the linebox scanner?
         debug!("assign_height_inline: floats_in: {:?}", self.base.floats_in);
 
+        let text_indent = if self.indent_first_line {
+            specified(self.base.node.style().get().Text.text_indent, self.base.position.size.width)
+        } else {
+            Au(0)
+        };
+        // The inline-block children have been laid out, so their boxes can take their size.
+        do each_inline_block(&mut self.base, self.boxes) |inline_block, box| {
+            let size = inline_block.block.base.position.size;
+            box.position.set(Rect(box.position.get().origin, size));
+            match box.specific {
+                InlineBlockBox(ref info) => info.baseline.set(inline_block.baseline),
+                _ => fail!("expected an inline-block box"),
+            }
+        }
+
+        if self.original_boxes.is_none() {
+            self.original_boxes = Some(self.boxes.clone());
+        }
         let scanner_floats = self.base.floats_in.clone();
-        let mut scanner = LineboxScanner::new(scanner_floats);
+        let mut scanner = LineboxScanner::new(scanner_floats, text_indent,
+                                              self.first_line_style.clone());
 
         // Access the linebox scanner.
-        scanner.scan_for_lines(self);
+        scanner.scan_for_lines(ctx, self);
 
         let mut line_height_offset = Au::new(0);
 
@@ -699,9 +1002,10 @@ impl Flow for InlineFlow {
         let text_align = self.base.node.style().get().Text.text_align;
 
         // Now, go through each line and lay out the boxes inside.
-        for line in self.lines.mut_iter() {
+        for (line_i, line) in self.lines.mut_iter().enumerate() {
             // Lay out boxes horizontally.
-            InlineFlow::set_horizontal_box_positions(self.boxes, line, text_align);
+            let indent = if line_i == 0 { text_indent } else { Au(0) };
+            InlineFlow::set_horizontal_box_positions(self.boxes, line, text_align, indent);
 
             // Set the top y position of the current linebox.
             // `line_height_offset` is updated at the end of the previous loop.
@@ -761,6 +1065,12 @@ impl Flow for InlineFlow {
 
                         (text_offset, line_height - text_offset, text_ascent)
                     },
+                    InlineBlockBox(ref info) => {
+                        // The size of the margin box was set from the flow of the inline-block.
+                        let height = cur_box.position.get().size.height;
+                        let baseline = info.baseline.get();
+                        (baseline, height - baseline, baseline)
+                    },
                     GenericBox | IframeBox(_) => {
                         let height = cur_box.position.get().size.height;
                         (height, Au::new(0), height)
@@ -790,7 +1100,11 @@ impl Flow for InlineFlow {
                 // content area. But for now we assume it's zero.
                 let parent_text_bottom = Au::new(0);
 
-                let parent = cur_box.node.parent_node().unwrap();
+                // The text of a pseudo-element belongs to its element rather than to a text node.
+                let parent = match cur_box.specific {
+                    ScannedTextBox(_) if cur_box.node.is_element() => cur_box.node,
+                    _ => cur_box.node.parent_node().unwrap(),
+                };
                 let parent_style = parent.style();
                 let font_size = parent_style.get().Font.font_size;
                 parent_text_top = font_size;
@@ -838,6 +1152,7 @@ impl Flow for InlineFlow {
 
             // Now, the baseline offset from the top of linebox is set as topmost.
             let baseline_offset = topmost;
+            line.baseline = baseline_offset;
 
             // All boxes' y position is updated following the new baseline offset.
             for box_i in line.range.eachi() {
@@ -858,6 +1173,11 @@ impl Flow for InlineFlow {
             line.bounds.size.height = topmost + bottommost;
         } // End of `lines.each` loop.
 
+        // Move the inline-block children to where their boxes went.
+        do each_inline_block(&mut self.base, self.boxes) |inline_block, box| {
+            inline_block.block.base.position.origin = box.position.get().origin
+        }
+
         self.base.position.size.height =
             if self.lines.len() > 0 {
                 self.lines.last().bounds.origin.y + self.lines.last().bounds.size.height
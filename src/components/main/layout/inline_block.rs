@@ -0,0 +1,196 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! CSS inline-block boxes (CSS 2.1 § 9.2.4).
+//!
+//! An `InlineBlockFlow` is a child of the `InlineFlow` whose lines hold it. An `InlineBlockBox` of
+//! the inline flow takes its place in the line: the inline flow sizes that box from this flow
+//! before it breaks lines, and then moves this flow to where the box went. This flow establishes a
+//! new block formatting context, so it is laid out as a whole and never split across lines.
+
+use layout::block::BlockFlow;
+use layout::box::Box;
+use layout::context::LayoutContext;
+use layout::float_context::FloatContext;
+use layout::flow::{BlockFlowClass, Flow, FlowClass, FlowData, InlineBlockFlowClass};
+use layout::flow::{InlineFlowClass};
+use layout::flow;
+use layout::model::{MaybeAuto, shrink_to_fit, specified_or_none, specified};
+
+use geom::SideOffsets2D;
+use servo_util::geometry::Au;
+use servo_util::geometry;
+
+/// A flow for a box with `display: inline-block`.
+pub struct InlineBlockFlow {
+    /// The block flow that lays out the contents of this flow. This must be the first field: it
+    /// starts with the `FlowData` that `flow::base()` returns.
+    block: BlockFlow,
+
+    /// The distance from the top of the margin box of this flow to its baseline, which the line
+    /// aligns (CSS 2.1 § 10.8.1).
+    baseline: Au,
+}
+
+impl InlineBlockFlow {
+    pub fn from_box(base: FlowData, box: Box) -> InlineBlockFlow {
+        InlineBlockFlow {
+            block: BlockFlow::from_box(base, box),
+            baseline: Au(0),
+        }
+    }
+
+    pub fn teardown(&mut self) {
+        self.block.teardown()
+    }
+
+    /// Returns the baseline of the last line box in the normal flow of this flow, or its bottom
+    /// margin edge if it has no line boxes or its 'overflow' is not 'visible'.
+    fn compute_baseline(&mut self) -> Au {
+        let bottom = self.block.base.position.size.height;
+        let clips = self.block.box.as_ref().map_default(false, |box| box.needs_clip());
+        if clips {
+            return bottom
+        }
+        last_line_baseline(self as &mut Flow).unwrap_or(bottom)
+    }
+}
+
+impl Flow for InlineBlockFlow {
+    fn class(&self) -> FlowClass {
+        InlineBlockFlowClass
+    }
+
+    fn as_block<'a>(&'a mut self) -> &'a mut BlockFlow {
+        &mut self.block
+    }
+
+    fn as_inline_block<'a>(&'a mut self) -> &'a mut InlineBlockFlow {
+        self
+    }
+
+    fn bubble_widths(&mut self, ctx: &mut LayoutContext) {
+        self.block.bubble_widths(ctx)
+    }
+
+    /// Gives this flow its shrink-to-fit width in the width of the line (CSS 2.1 § 10.3.9) and
+    /// assigns widths to the children.
+    fn assign_widths(&mut self, _: &mut LayoutContext) {
+        debug!("assign_widths_inline_block: assigning width for flow {}", self.block.base.id);
+
+        // The parent inline flow set the width of this flow to its own.
+        let containing_width = self.block.base.position.size.width;
+        let mut margin_box_width = Au(0);
+        let mut x_offset = Au(0);
+        let mut content_width = Au(0);
+        for box in self.block.box.iter() {
+            let style = box.style();
+
+            // Can compute padding here since we know containing block width.
+            box.compute_padding(style, containing_width);
+            let border = box.border.get();
+            let padding = box.padding.get();
+            let noncontent_width = border.left + border.right + padding.left + padding.right;
+
+            // 'auto' margins are 0.
+            let margin = SideOffsets2D::new(
+                MaybeAuto::from_style(style.Margin.margin_top,
+                                      containing_width).specified_or_zero(),
+                MaybeAuto::from_style(style.Margin.margin_right,
+                                      containing_width).specified_or_zero(),
+                MaybeAuto::from_style(style.Margin.margin_bottom,
+                                      containing_width).specified_or_zero(),
+                MaybeAuto::from_style(style.Margin.margin_left,
+                                      containing_width).specified_or_zero());
+            box.margin.set(margin);
+
+            // The minimum and preferred widths include the borders, padding and margins.
+            let margins_and_noncontent_width = noncontent_width + margin.left + margin.right;
+            let min_width = self.block.base.min_width - margins_and_noncontent_width;
+            let min_width = geometry::max(Au(0), min_width);
+            let pref_width = self.block.base.pref_width - margins_and_noncontent_width;
+            let pref_width = geometry::max(Au(0), pref_width);
+            let available_width = containing_width - margins_and_noncontent_width;
+            let width = MaybeAuto::from_style(style.Box.width, containing_width)
+                .specified_or_default(shrink_to_fit(min_width, pref_width, available_width));
+            let width = match specified_or_none(style.Box.max_width, containing_width) {
+                Some(max_width) if max_width < width => max_width,
+                _ => width,
+            };
+            let width = geometry::max(width, specified(style.Box.min_width, containing_width));
+
+            // The associated box is the border box of this flow.
+            let position_ref = box.position.mutate();
+            position_ref.ptr.origin.x = margin.left;
+            position_ref.ptr.size.width = width + noncontent_width;
+
+            margin_box_width = width + margins_and_noncontent_width;
+            x_offset = box.offset();
+            content_width = width;
+        }
+
+        self.block.base.position.size.width = margin_box_width;
+
+        // Like the root flow, an inline-block lays out its floats by itself.
+        self.block.base.floats_in = FloatContext::new(self.block.base.num_floats);
+        self.block.base.flags.set_inorder(false);
+
+        self.block.assign_widths_of_children(x_offset, content_width);
+    }
+
+    fn assign_height(&mut self, ctx: &mut LayoutContext) {
+        debug!("assign_height_inline_block: assigning height for flow {}", self.block.base.id);
+        let has_floats = self.block.base.num_floats > 0;
+        self.block.assign_height_block_base(ctx, has_floats);
+        self.block.lay_out_absolute_descendants(ctx);
+        self.baseline = self.compute_baseline();
+    }
+
+    /// An inline-block has been laid out already, and it does not affect the floats around it.
+    fn assign_height_inorder(&mut self, _: &mut LayoutContext) {
+        self.block.base.floats_out = self.block.base.floats_in.clone();
+    }
+
+    fn debug_str(&self) -> ~str {
+        let txt = ~"InlineBlockFlow: ";
+        txt.append(match self.block.box {
+            Some(ref rb) => rb.debug_str(),
+            None => ~"",
+        })
+    }
+}
+
+/// Returns the baseline of the last line box in the normal flow inside `flow`, relative to the top
+/// of `flow`, if there is one. The line boxes of floats and of absolutely positioned flows do not
+/// count, nor do those inside other inline-blocks, whose baselines are already in a line.
+fn last_line_baseline(flow: &mut Flow) -> Option<Au> {
+    let mut baseline = None;
+    for kid in flow::child_iter(flow) {
+        let kid_baseline = match kid.class() {
+            InlineFlowClass => {
+                let lines = &kid.as_inline().lines;
+                if lines.is_empty() {
+                    None
+                } else {
+                    Some(lines.last().bounds.origin.y + lines.last().baseline)
+                }
+            }
+            BlockFlowClass => {
+                if kid.as_block().is_float() {
+                    None
+                } else {
+                    last_line_baseline(*kid)
+                }
+            }
+            _ => None,
+        };
+        match kid_baseline {
+            Some(kid_baseline) => {
+                baseline = Some(flow::base(*kid).position.origin.y + kid_baseline)
+            }
+            None => {}
+        }
+    }
+    baseline
+}
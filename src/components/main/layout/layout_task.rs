@@ -9,13 +9,17 @@ use css::matching::MatchMethods;
 use css::select::new_stylist;
 use css::node_style::StyledNode;
 use layout::construct::{FlowConstructionResult, FlowConstructor, NoConstructionResult};
+use layout::construct::{propagate_damage_to_ancestors, reclaim_flows};
 use layout::context::LayoutContext;
 use layout::display_list_builder::{DisplayListBuilder, ToGfxColor};
 use layout::extra::LayoutAuxMethods;
 use layout::flow::{Flow, ImmutableFlowUtils, MutableFlowUtils, PreorderFlowTraversal};
-use layout::flow::{PostorderFlowTraversal};
+use layout::flow::{PostorderFlowTraversal, TableFlowClass, TableRowFlowClass};
+use layout::flow::{TableRowGroupFlowClass};
 use layout::flow;
-use layout::incremental::{RestyleDamage};
+use layout::generated_content::resolve_generated_content;
+use layout::incremental::{BubbleWidths, Reflow, RestyleDamage};
+use layout::pagination;
 use layout::util::{LayoutData, LayoutDataAccess};
 
 use extra::arc::{Arc, RWArc, MutexArc};
@@ -23,36 +27,42 @@ use geom::point::Point2D;
 use geom::rect::Rect;
 use geom::size::Size2D;
 use gfx::display_list::{ClipDisplayItemClass, DisplayItem, DisplayItemIterator, DisplayList};
+use gfx::display_list::OpacityDisplayItemClass;
 use gfx::font_context::FontContext;
 use gfx::opts::Opts;
-use gfx::render_task::{RenderMsg, RenderChan, RenderLayer};
+use gfx::render_task::{PrintLayer, PrintMsg, RenderMsg, RenderChan, RenderLayer};
 use gfx::{render_task, color};
 use script::dom::event::ReflowEvent;
 use script::dom::node::{AbstractNode, LayoutDataRef, LayoutView, ElementNodeTypeId};
 use script::dom::element::{HTMLBodyElementTypeId, HTMLHtmlElementTypeId};
-use script::layout_interface::{AddStylesheetMsg, ContentBoxQuery};
+use script::layout_interface::{AddStylesheetMsg, ComputedStyleQuery, ComputedStyleResponse};
+use script::layout_interface::ContentBoxQuery;
 use script::layout_interface::{ContentBoxesQuery, ContentBoxesResponse, ExitNowMsg, LayoutQuery};
 use script::layout_interface::{HitTestQuery, ContentBoxResponse, HitTestResponse};
-use script::layout_interface::{ContentChangedDocumentDamage, Msg, PrepareToExitMsg};
+use script::layout_interface::{ContentChangedDocumentDamage, MatchSelectorsDocumentDamage, Msg};
+use script::layout_interface::{PrepareToExitMsg};
 use script::layout_interface::{QueryMsg, ReapLayoutDataMsg, Reflow, ReflowDocumentDamage};
-use script::layout_interface::{ReflowForDisplay, ReflowMsg};
+use script::layout_interface::{ReflowForDisplay, ReflowForPrint, ReflowMsg};
 use script::script_task::{ReflowCompleteMsg, ScriptChan, SendEventMsg};
 use servo_msg::constellation_msg::{ConstellationChan, PipelineId};
 use servo_net::image_cache_task::{ImageCacheTask, ImageResponseMsg};
 use servo_net::local_image_cache::{ImageResponder, LocalImageCache};
 use servo_util::geometry::Au;
+use servo_util::history::SharedHistoryStore;
 use servo_util::time::{ProfilerChan, profile};
 use servo_util::time;
 use servo_util::tree::TreeNodeRef;
 use std::cast::transmute;
 use std::cast;
 use std::cell::Cell;
+use std::cmp::ApproxEq;
 use std::comm::Port;
 use std::task;
 use std::util;
-use style::AuthorOrigin;
+use style::{AuthorOrigin, Device, MediaType, PageBox, Print, Screen, QuirksMode, NoQuirks};
 use style::Stylesheet;
 use style::Stylist;
+use style::cascade;
 
 /// Information needed by the layout task.
 struct LayoutTask {
@@ -77,14 +87,34 @@ struct LayoutTask {
     /// The local image cache.
     local_image_cache: MutexArc<LocalImageCache>,
 
-    /// The size of the viewport.
+    /// The size of the viewport, or of the page area when printing.
     screen_size: Option<Size2D<Au>>,
 
+    /// The media type that the document was last laid out for.
+    media_type: MediaType,
+
+    /// The compatibility mode of the document.
+    quirks_mode: QuirksMode,
+
     /// A cached display list.
     display_list: Option<Arc<DisplayList<AbstractNode<()>>>>,
 
+    /// The cached display list of the fixed positioned flows, if there are any on screen.
+    fixed_display_list: Option<Arc<DisplayList<AbstractNode<()>>>>,
+
+    /// The flow tree of the last reflow. The next reflow reuses the flows of the nodes that have
+    /// not been damaged since.
+    flow_tree: Option<~Flow:>,
+
+    /// The ID of the next flow to construct. It is kept across reflows so that new flows never
+    /// get the ID of a flow reused from an earlier reflow.
+    next_flow_id: int,
+
     stylist: RWArc<Stylist>,
 
+    /// The browsing history, which links are matched against for `:visited`.
+    history: SharedHistoryStore,
+
     /// The channel on which messages can be sent to the profiler.
     profiler_chan: ProfilerChan,
 
@@ -105,9 +135,16 @@ impl PostorderFlowTraversal for ComputeDamageTraversal {
         flow::mut_base(flow).restyle_damage = damage;
         true
     }
+
+    /// A flow without damage has none under it either: it was reused whole from the last reflow.
+    #[inline]
+    fn should_prune(&mut self, flow: &mut Flow) -> bool {
+        flow::base(flow).restyle_damage.is_empty()
+    }
 }
 
-/// Propagates restyle damage up and down the tree as appropriate.
+/// Propagates restyle damage down the tree to the parts of tables, which are laid out together,
+/// and marks every flow if the whole document has to be laid out again.
 ///
 /// FIXME(pcwalton): Merge this with flow tree building and/or other traversals.
 struct PropagateDamageTraversal {
@@ -122,14 +159,39 @@ impl PreorderFlowTraversal for PropagateDamageTraversal {
         }
         debug!("restyle damage = {:?}", flow::base(flow).restyle_damage);
 
+        let lays_out_children = match flow.class() {
+            TableFlowClass | TableRowGroupFlowClass | TableRowFlowClass => true,
+            _ => false,
+        };
         let prop = flow::base(flow).restyle_damage.propagate_down();
-        if prop.is_nonempty() {
+        if lays_out_children && prop.is_nonempty() {
             for kid_ctx in flow::child_iter(flow) {
                 flow::mut_base(*kid_ctx).restyle_damage.union_in_place(prop)
             }
         }
         true
     }
+
+    #[inline]
+    fn should_prune(&mut self, flow: &mut Flow) -> bool {
+        !self.all_style_damage && flow::base(flow).restyle_damage.is_empty()
+    }
+}
+
+/// Clears the restyle damage of the flows once they have been laid out.
+pub struct ClearDamageTraversal;
+
+impl PreorderFlowTraversal for ClearDamageTraversal {
+    #[inline]
+    fn process(&mut self, flow: &mut Flow) -> bool {
+        flow::mut_base(flow).restyle_damage = RestyleDamage::none();
+        true
+    }
+
+    #[inline]
+    fn should_prune(&mut self, flow: &mut Flow) -> bool {
+        flow::base(flow).restyle_damage.is_empty()
+    }
 }
 
 /// The bubble-widths traversal, the first part of layout computation. This computes preferred
@@ -143,30 +205,51 @@ impl<'self> PostorderFlowTraversal for BubbleWidthsTraversal<'self> {
         true
     }
 
-    // FIXME: We can't prune until we start reusing flows
-    /*
+    /// The intrinsic widths of a flow that lacks the damage are those of the last reflow.
     #[inline]
     fn should_prune(&mut self, flow: &mut Flow) -> bool {
-        flow::mut_base(flow).restyle_damage.lacks(BubbleWidths)
+        flow::base(flow).restyle_damage.lacks(BubbleWidths)
     }
-    */
 }
 
 /// The assign-widths traversal. In Gecko this corresponds to `Reflow`.
-struct AssignWidthsTraversal<'self>(&'self mut LayoutContext);
+pub struct AssignWidthsTraversal<'self>(&'self mut LayoutContext);
 
 impl<'self> PreorderFlowTraversal for AssignWidthsTraversal<'self> {
     #[inline]
     fn process(&mut self, flow: &mut Flow) -> bool {
+        let old_kids: ~[(Au, u8)] = flow::child_iter(flow).map(|kid| {
+            let kid_base = flow::base(*kid);
+            (kid_base.position.size.width, *kid_base.flags)
+        }).collect();
+
         flow.assign_widths(**self);
+
+        // Children that got another width or other flags have to be laid out again, and so do
+        // those that lay out in order with their siblings, since the floats around them may
+        // have moved.
+        for (kid, &(old_width, old_flags)) in flow::child_iter(flow).zip(old_kids.iter()) {
+            let kid_base = flow::mut_base(*kid);
+            if kid_base.position.size.width != old_width || *kid_base.flags != old_flags ||
+                    kid_base.flags.inorder() {
+                kid_base.restyle_damage.union_in_place(RestyleDamage::none().add(Reflow))
+            }
+        }
         true
     }
+
+    /// Absolutely positioned flows are laid out by their containing blocks. Flows that lack the
+    /// damage keep the layout of the last reflow.
+    #[inline]
+    fn should_prune(&mut self, flow: &mut Flow) -> bool {
+        flow.is_absolutely_positioned() || flow::base(flow).restyle_damage.lacks(Reflow)
+    }
 }
 
 /// The assign-heights-and-store-overflow traversal, the last (and most expensive) part of layout
 /// computation. Determines the final heights for all layout objects, computes positions, and
 /// computes overflow regions. In Gecko this corresponds to `FinishAndStoreOverflow`.
-struct AssignHeightsAndStoreOverflowTraversal<'self>(&'self mut LayoutContext);
+pub struct AssignHeightsAndStoreOverflowTraversal<'self>(&'self mut LayoutContext);
 
 impl<'self> PostorderFlowTraversal for AssignHeightsAndStoreOverflowTraversal<'self> {
     #[inline]
@@ -180,6 +263,13 @@ impl<'self> PostorderFlowTraversal for AssignHeightsAndStoreOverflowTraversal<'s
     fn should_process(&mut self, flow: &mut Flow) -> bool {
         !flow::base(flow).flags.inorder()
     }
+
+    /// Absolutely positioned flows are laid out by their containing blocks. Flows that lack the
+    /// damage keep the layout of the last reflow.
+    #[inline]
+    fn should_prune(&mut self, flow: &mut Flow) -> bool {
+        flow.is_absolutely_positioned() || flow::base(flow).restyle_damage.lacks(Reflow)
+    }
 }
 
 struct LayoutImageResponder {
@@ -207,9 +297,10 @@ impl LayoutTask {
                   render_chan: RenderChan<AbstractNode<()>>,
                   img_cache_task: ImageCacheTask,
                   opts: Opts,
-                  profiler_chan: ProfilerChan) {
+                  profiler_chan: ProfilerChan,
+                  history: SharedHistoryStore) {
         spawn_with!(task::task(), [port, constellation_chan, script_chan,
-                                   render_chan, img_cache_task, profiler_chan], {
+                                   render_chan, img_cache_task, profiler_chan, history], {
             let mut layout = LayoutTask::new(id,
                                              port,
                                              constellation_chan,
@@ -217,7 +308,8 @@ impl LayoutTask {
                                              render_chan,
                                              img_cache_task,
                                              &opts,
-                                             profiler_chan);
+                                             profiler_chan,
+                                             history);
             layout.start();
         });
     }
@@ -230,7 +322,8 @@ impl LayoutTask {
            render_chan: RenderChan<AbstractNode<()>>, 
            image_cache_task: ImageCacheTask,
            opts: &Opts,
-           profiler_chan: ProfilerChan)
+           profiler_chan: ProfilerChan,
+           history: SharedHistoryStore)
            -> LayoutTask {
 
         LayoutTask {
@@ -242,10 +335,18 @@ impl LayoutTask {
             image_cache_task: image_cache_task.clone(),
             local_image_cache: MutexArc::new(LocalImageCache(image_cache_task)),
             screen_size: None,
+            media_type: Screen,
+            quirks_mode: NoQuirks,
 
             display_list: None,
-
-            stylist: RWArc::new(new_stylist()),
+            fixed_display_list: None,
+            flow_tree: None,
+            next_flow_id: 0,
+
+            // The real viewport size is only known at the first reflow.
+            stylist: RWArc::new(new_stylist(Device::new(Screen, Au(0), Au(0),
+                                                        opts.device_pixel_ratio))),
+            history: history,
             profiler_chan: profiler_chan,
             opts: opts.clone()
         }
@@ -269,6 +370,8 @@ impl LayoutTask {
             image_cache: image_cache,
             font_ctx: font_ctx,
             screen_size: Rect(Point2D(Au(0), Au(0)), screen_size),
+            device: do self.stylist.read |stylist| { *stylist.device() },
+            quirks_mode: self.quirks_mode,
             constellation_chan: self.constellation_chan.clone(),
         }
     }
@@ -356,9 +459,13 @@ impl LayoutTask {
     /// is intertwined with selector matching, making it difficult to compare directly. It is
     /// marked `#[inline(never)]` to aid benchmarking in sampling profilers.
     #[inline(never)]
-    fn construct_flow_tree(&self, layout_context: &mut LayoutContext, node: AbstractNode<LayoutView>)
+    fn construct_flow_tree(&mut self,
+                           layout_context: &mut LayoutContext,
+                           node: AbstractNode<LayoutView>)
                            -> ~Flow: {
-        node.traverse_postorder_mut(&mut FlowConstructor::init(layout_context));
+        let mut flow_constructor = FlowConstructor::init(layout_context, self.next_flow_id);
+        node.traverse_postorder_mut(&mut flow_constructor);
+        self.next_flow_id = flow_constructor.unused_flow_id();
 
         let result = match *node.mutate_layout_data().ptr {
             Some(ref mut layout_data) => {
@@ -374,7 +481,8 @@ impl LayoutTask {
         flow
     }
 
-    /// Performs layout constraint solving.
+    /// Performs layout constraint solving. Only the flows with restyle damage are laid out; the
+    /// others keep the layout of the last reflow.
     ///
     /// This corresponds to `Reflow()` in Gecko and `layout()` in WebKit/Blink and should be
     /// benchmarked against those two. It is marked `#[inline(never)]` to aid profiling.
@@ -384,16 +492,15 @@ impl LayoutTask {
                          layout_context: &mut LayoutContext) {
         let _ = layout_root.traverse_postorder(&mut BubbleWidthsTraversal(layout_context));
 
-        // FIXME(kmc): We want to prune nodes without the Reflow restyle damage
-        // bit, but FloatContext values can't be reused, so we need to
-        // recompute them every time.
-        // NOTE: this currently computes borders, so any pruning should separate that operation out.
+        // FloatContext values can't be reused, so the children that lay out in order with their
+        // siblings are marked to be laid out again along with their parents here.
         let _ = layout_root.traverse_preorder(&mut AssignWidthsTraversal(layout_context));
 
         // For now, this is an inorder traversal
-        // FIXME: prune this traversal as well
         let _ = layout_root.traverse_postorder(&mut
             AssignHeightsAndStoreOverflowTraversal(layout_context));
+
+        let _ = layout_root.traverse_preorder(&mut ClearDamageTraversal);
     }
 
     /// The high-level routine that performs layout tasks.
@@ -421,12 +528,43 @@ impl LayoutTask {
             _ => false
         };
 
-        let screen_size = Size2D(Au::from_px(data.window_size.width as int),
+        let window_size = Size2D(Au::from_px(data.window_size.width as int),
                                  Au::from_px(data.window_size.height as int));
-        if self.screen_size != Some(screen_size) {
+
+        // When printing, style sheets are evaluated against the print media and the document is
+        // laid out in the page area of the page box instead of the viewport.
+        let printing = self.opts.print || data.goal == ReflowForPrint;
+        let page_box = if printing {
+            Some(do self.stylist.read |stylist| {
+                stylist.page_box(&Device::new(Print, window_size.width, window_size.height,
+                                              self.opts.device_pixel_ratio))
+            })
+        } else {
+            None
+        };
+        let (media_type, screen_size) = match page_box {
+            Some(ref page_box) => {
+                (Print, Size2D(page_box.content_width(), page_box.content_height()))
+            }
+            None => (Screen, window_size),
+        };
+        // The media queries of the style sheets are only evaluated again when the device changed.
+        let viewport_changed = self.screen_size != Some(screen_size) ||
+            self.media_type != media_type;
+        let (media_queries_changed, quirks_mode_changed) = do self.stylist.write |stylist| {
+            let media_queries_changed = viewport_changed && {
+                let device = Device::new(media_type, screen_size.width, screen_size.height,
+                                         self.opts.device_pixel_ratio);
+                stylist.set_device(device)
+            };
+            (media_queries_changed, stylist.set_quirks_mode(data.quirks_mode))
+        };
+        self.quirks_mode = data.quirks_mode;
+        if viewport_changed {
             all_style_damage = true;
         }
         self.screen_size = Some(screen_size);
+        self.media_type = media_type;
 
         // Create a layout context for use throughout the following passes.
         let mut layout_ctx = self.build_layout_context();
@@ -438,14 +576,48 @@ impl LayoutTask {
             node.initialize_style_for_subtree();
         }
 
-        // Perform CSS selector matching if necessary.
-        match data.damage.level {
-            ReflowDocumentDamage => {}
+        // Perform CSS selector matching if necessary. A resize, which changes viewport-percentage
+        // lengths and may change which media queries match, or a new document in another
+        // compatibility mode, requires a re-cascade even if the document did not change.
+        //
+        // FIXME: A resize only needs a re-cascade if media queries or viewport units are used.
+        let restyle_needed = media_queries_changed || quirks_mode_changed || viewport_changed;
+        let restyled = match data.damage.level {
+            ReflowDocumentDamage if !restyle_needed => false,
             _ => {
+                // A change in the state of elements, such as :hover, only restyles the subtree
+                // that script reports.
+                let restyle_root = match data.damage.level {
+                    MatchSelectorsDocumentDamage if !restyle_needed => unsafe {
+                        data.damage.root.to_layout_view()
+                    },
+                    _ => *node,
+                };
                 do profile(time::LayoutSelectorMatchCategory, self.profiler_chan.clone()) {
-                    node.match_subtree(self.stylist.clone());
-                    node.cascade_subtree(None);
+                    restyle_root.match_and_cascade_subtree_in_parallel(self.stylist.clone(),
+                                                                       self.history.clone(),
+                                                                       &data.url,
+                                                                       self.opts.n_style_threads,
+                                                                       self.profiler_chan.clone());
+                    resolve_generated_content(*node);
+                }
+                true
+            }
+        };
+
+        // Blow apart the flow tree of the last reflow, so that flow construction reuses the flows
+        // of the nodes that have not been damaged since. A change to the content of the document
+        // throws all of them away.
+        //
+        // FIXME: Script reports the document element as the root of content changes, so we can't
+        // tell where the content changed.
+        match (util::replace(&mut self.flow_tree, None), data.damage.level) {
+            (Some(_), ContentChangedDocumentDamage) | (None, _) => {}
+            (Some(flow_tree), _) => {
+                if restyled {
+                    propagate_damage_to_ancestors(*node);
                 }
+                reclaim_flows(flow_tree)
             }
         }
 
@@ -455,10 +627,10 @@ impl LayoutTask {
                                       || self.construct_flow_tree(&mut layout_ctx, *node));
 
         // Propagate damage.
+        layout_root.traverse_postorder(&mut ComputeDamageTraversal.clone());
         layout_root.traverse_preorder(&mut PropagateDamageTraversal {
             all_style_damage: all_style_damage
         });
-        layout_root.traverse_postorder(&mut ComputeDamageTraversal.clone());
 
         // Perform the primary layout passes over the flow tree to compute the locations of all
         // the boxes.
@@ -470,51 +642,50 @@ impl LayoutTask {
         debug!("{:?}", layout_root.dump());
 
         // Build the display list if necessary, and send it to the renderer.
-        if data.goal == ReflowForDisplay {
-            do profile(time::LayoutDispListBuildCategory, self.profiler_chan.clone()) {
-                let root_size = flow::base(layout_root).position.size;
-                let display_list= ~Cell::new(DisplayList::<AbstractNode<()>>::new());
-                let dirty = flow::base(layout_root).position.clone();
-                layout_root.build_display_list(
-                    &DisplayListBuilder {
-                        ctx: &layout_ctx,
-                    },
-                    &dirty,
-                    display_list);
-
-                let display_list = Arc::new(display_list.take());
-
-                    let mut color = color::rgba(255.0, 255.0, 255.0, 255.0);
-
-                    for child in node.traverse_preorder() {
-                      if child.type_id() == ElementNodeTypeId(HTMLHtmlElementTypeId) || 
-                         child.type_id() == ElementNodeTypeId(HTMLBodyElementTypeId) {
-                             let element_bg_color = child.style().get().resolve_color(
-                                 child.style().get().Background.background_color
-                             ).to_gfx_color();
-                             match element_bg_color {
-                                 color::rgba(0., 0., 0., 0.) => {}
-                                 _ => {
-                                   color = element_bg_color;
-                                   break;
-                               }
-                             }
-                      }
-                    }
-
-                let render_layer = RenderLayer {
-                    display_list: display_list.clone(),
-                    size: Size2D(root_size.width.to_nearest_px() as uint,
-                                 root_size.height.to_nearest_px() as uint),
-                    color: color
-                };
-
-                self.display_list = Some(display_list.clone());
-
-                self.render_chan.send(RenderMsg(render_layer));
-            } // time(layout: display list building)
+        match page_box {
+            Some(ref page_box) => {
+                let display_list = self.print(layout_root, &layout_ctx, *node, page_box);
+                // In print mode there is nothing on screen, so queries are answered from the
+                // printed document.
+                if self.opts.print {
+                    self.display_list = Some(display_list);
+                    self.fixed_display_list = None;
+                }
+            }
+            None if data.goal == ReflowForDisplay => {
+                do profile(time::LayoutDispListBuildCategory, self.profiler_chan.clone()) {
+                    let root_size = flow::base(layout_root).position.size;
+                    let viewport_size = layout_ctx.screen_size.size;
+                    let (display_list, fixed_display_list) =
+                        self.build_display_lists(layout_root, &layout_ctx);
+                    let display_list = Arc::new(display_list);
+                    let fixed_display_list = if fixed_display_list.list.is_empty() {
+                        None
+                    } else {
+                        Some(Arc::new(fixed_display_list))
+                    };
+
+                    let render_layer = RenderLayer {
+                        display_list: display_list.clone(),
+                        size: Size2D(root_size.width.to_nearest_px() as uint,
+                                     root_size.height.to_nearest_px() as uint),
+                        color: self.background_color(*node),
+                        fixed_display_list: fixed_display_list.clone(),
+                        viewport_size: Size2D(viewport_size.width.to_nearest_px() as uint,
+                                              viewport_size.height.to_nearest_px() as uint),
+                    };
+
+                    self.display_list = Some(display_list.clone());
+                    self.fixed_display_list = fixed_display_list;
+
+                    self.render_chan.send(RenderMsg(render_layer));
+                } // time(layout: display list building)
+            }
+            None => {}
         }
 
+        self.flow_tree = Some(layout_root);
+
         // Tell script that we're done.
         //
         // FIXME(pcwalton): This should probably be *one* channel, but we can't fix this without
@@ -523,6 +694,74 @@ impl LayoutTask {
         data.script_chan.send(ReflowCompleteMsg(self.id, data.id));
     }
 
+    /// Builds the display list of the whole flow tree, and the display list of the fixed
+    /// positioned flows, which the renderer paints into a layer of their own.
+    fn build_display_lists(&self, layout_root: &mut Flow, layout_ctx: &LayoutContext)
+                           -> (DisplayList<AbstractNode<()>>, DisplayList<AbstractNode<()>>) {
+        let display_list = ~Cell::new(DisplayList::<AbstractNode<()>>::new());
+        let fixed_display_list = ~Cell::new(DisplayList::<AbstractNode<()>>::new());
+        let dirty = flow::base(layout_root).position.clone();
+        layout_root.build_display_list(
+            &DisplayListBuilder {
+                ctx: layout_ctx,
+            },
+            &dirty,
+            display_list,
+            fixed_display_list);
+
+        (display_list.take(), fixed_display_list.take())
+    }
+
+    /// The color of the canvas: the background color of the root element, or of the body
+    /// element if the root element has none (CSS 2.1 § 14.2).
+    fn background_color(&self, node: AbstractNode<LayoutView>) -> color::Color {
+        for child in node.traverse_preorder() {
+            if child.type_id() == ElementNodeTypeId(HTMLHtmlElementTypeId) ||
+                    child.type_id() == ElementNodeTypeId(HTMLBodyElementTypeId) {
+                let element_bg_color = child.style().get().resolve_color(
+                    child.style().get().Background.background_color
+                ).to_gfx_color();
+                if !element_bg_color.a.approx_eq(&0.0) {
+                    return element_bg_color
+                }
+            }
+        }
+        color::rgb(255, 255, 255)
+    }
+
+    /// Splits the document into pages and sends them to the renderer, which writes each of them
+    /// to its own output file. Returns the display list of the whole document.
+    fn print(&self,
+             layout_root: &mut Flow,
+             layout_ctx: &LayoutContext,
+             node: AbstractNode<LayoutView>,
+             page_box: &PageBox)
+             -> Arc<DisplayList<AbstractNode<()>>> {
+        do profile(time::LayoutDispListBuildCategory, self.profiler_chan.clone()) {
+            // Paper does not scroll, so the fixed positioned flows are printed along with the rest
+            // of the document.
+            let (display_list, fixed_display_list) =
+                self.build_display_lists(layout_root, layout_ctx);
+            let mut display_list = display_list;
+            display_list.list.push_all_move(fixed_display_list.list);
+            let display_list = Arc::new(display_list);
+            let pages = pagination::paginate(layout_root,
+                                             page_box.content_width(),
+                                             page_box.content_height());
+            debug!("layout: printing {:u} pages", pages.len());
+
+            self.render_chan.send(PrintMsg(PrintLayer {
+                display_list: display_list.clone(),
+                page_size: Size2D(page_box.width.to_nearest_px() as uint,
+                                  page_box.height.to_nearest_px() as uint),
+                content_origin: Point2D(page_box.margin_left, page_box.margin_top),
+                pages: pages,
+                color: self.background_color(node),
+            }));
+            display_list
+        }
+    }
+
     /// Handles a query from the script task. This is the main routine that DOM functions like
     /// `getClientRects()` or `getBoundingClientRect()` ultimately invoke.
     fn handle_query(&self, query: LayoutQuery) {
@@ -551,6 +790,9 @@ impl LayoutTask {
                 let mut rect = None;
                 let display_list = self.display_list.as_ref().unwrap().get();
                 union_boxes_for_node(&mut rect, display_list.iter(), node);
+                for fixed_display_list in self.fixed_display_list.iter() {
+                    union_boxes_for_node(&mut rect, fixed_display_list.get().iter(), node);
+                }
                 reply_chan.send(ContentBoxResponse(rect.unwrap_or(Au::zero_rect())))
             }
             ContentBoxesQuery(node, reply_chan) => {
@@ -574,6 +816,9 @@ impl LayoutTask {
                 let mut boxes = ~[];
                 let display_list = self.display_list.as_ref().unwrap().get();
                 add_boxes_for_node(&mut boxes, display_list.iter(), node);
+                for fixed_display_list in self.fixed_display_list.iter() {
+                    add_boxes_for_node(&mut boxes, fixed_display_list.get().iter(), node);
+                }
                 reply_chan.send(ContentBoxesResponse(boxes))
             }
             HitTestQuery(_, point, reply_chan) => {
@@ -587,13 +832,19 @@ impl LayoutTask {
                                     return ret;
                                 }
                             }
+                            OpacityDisplayItemClass(ref group) => {
+                                let ret = hit_test(x, y, group.child_list);
+                                if !ret.is_none() {
+                                    return ret;
+                                }
+                            }
                             _ => {}
                         }
                     }
 
                     for item in list.rev_iter() {
                         match *item {
-                            ClipDisplayItemClass(_) => continue,
+                            ClipDisplayItemClass(_) | OpacityDisplayItemClass(_) => continue,
                             _ => {}
                         }
                         let bounds = item.bounds();
@@ -619,7 +870,17 @@ impl LayoutTask {
                             let display_list = list.get();
                             let (x, y) = (Au::from_frac_px(point.x as f64),
                                           Au::from_frac_px(point.y as f64));
-                            let resp = hit_test(x,y,display_list.list);
+                            // The fixed positioned flows are painted above the document.
+                            //
+                            // FIXME: The point is relative to the document, and the fixed
+                            // positioned flows to the viewport, which may have been scrolled.
+                            let mut resp = None;
+                            for fixed_display_list in self.fixed_display_list.iter() {
+                                resp = hit_test(x, y, fixed_display_list.get().list);
+                            }
+                            if resp.is_none() {
+                                resp = hit_test(x,y,display_list.list);
+                            }
                             if resp.is_none() {
                                 Err(())
                             } else {
@@ -635,6 +896,49 @@ impl LayoutTask {
 
                 reply_chan.send(response)
             }
+            ComputedStyleQuery(node, pseudo_element, reply_chan) => {
+                // FIXME: Isolate this transmutation into a single "bridge" module.
+                let node: AbstractNode<LayoutView> = unsafe {
+                    transmute(node)
+                };
+
+                // Script must only see the style computed as if all links were unvisited.
+                let properties = match *node.mutate_layout_data().ptr {
+                    None => ~[],
+                    Some(ref mut layout_data) => {
+                        let style = match layout_data.unvisited_style {
+                            Some(ref style) => Some(style.clone()),
+                            None => layout_data.style.clone(),
+                        };
+                        match (style, pseudo_element) {
+                            (None, _) => ~[],
+                            (Some(style), None) => style.get().to_property_list(),
+                            (Some(style), Some(pseudo_element)) => {
+                                let visited = layout_data.unvisited_style.is_some();
+                                let pseudo_element_data =
+                                    layout_data.pseudo_element(pseudo_element);
+                                match pseudo_element_data.style {
+                                    None => ~[],
+                                    // Pseudo-elements inherit the colours of visited links, so
+                                    // cascade them again from the unvisited style.
+                                    Some(_) if visited => {
+                                        let device = do self.stylist.read |stylist| {
+                                            *stylist.device()
+                                        };
+                                        cascade(pseudo_element_data.applicable_declarations,
+                                                Some(style.get()),
+                                                &device).to_property_list()
+                                    }
+                                    Some(ref pseudo_style) => {
+                                        pseudo_style.get().to_property_list()
+                                    }
+                                }
+                            }
+                        }
+                    }
+                };
+                reply_chan.send(ComputedStyleResponse(properties))
+            }
         }
     }
 
@@ -5,6 +5,7 @@
 //! Borders, padding, and margins.
 
 use servo_util::geometry::Au;
+use servo_util::geometry;
 use computed = style::computed_values;
 
 /// Useful helper data type when computing values for blocks and positioned elements.
@@ -20,6 +21,7 @@ impl MaybeAuto {
         match length {
             computed::LPA_Auto => Auto,
             computed::LPA_Percentage(percent) => Specified(containing_length.scale_by(percent)),
+            computed::LPA_Calc(calc) => Specified(calc.resolve(containing_length)),
             computed::LPA_Length(length) => Specified(length)
         }
     }
@@ -42,6 +44,7 @@ pub fn specified_or_none(length: computed::LengthOrPercentageOrNone, containing_
     match length {
         computed::LPN_None => None,
         computed::LPN_Percentage(percent) => Some(containing_length.scale_by(percent)),
+        computed::LPN_Calc(calc) => Some(calc.resolve(containing_length)),
         computed::LPN_Length(length) => Some(length),
     }
 }
@@ -49,6 +52,14 @@ pub fn specified_or_none(length: computed::LengthOrPercentageOrNone, containing_
 pub fn specified(length: computed::LengthOrPercentage, containing_length: Au) -> Au {
     match length {
         computed::LP_Length(length) => length,
-        computed::LP_Percentage(p) => containing_length.scale_by(p)
+        computed::LP_Percentage(p) => containing_length.scale_by(p),
+        computed::LP_Calc(calc) => calc.resolve(containing_length),
     }
 }
+
+/// The shrink-to-fit width of CSS 2.1 § 10.3.5, given the minimum and preferred widths of the
+/// contents and the available width.
+#[inline]
+pub fn shrink_to_fit(min_width: Au, pref_width: Au, available_width: Au) -> Au {
+    geometry::min(pref_width, geometry::max(min_width, available_width))
+}
@@ -0,0 +1,218 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Splits a laid-out flow tree into pages for print media, per CSS 2.1 § 13.3.
+//!
+//! Pages may break between the block-level children of a block flow and between the line boxes
+//! of an inline flow. Floats are never split.
+
+use layout::flow::{AbsoluteFlowClass, BlockFlowClass, Flow, InlineFlowClass};
+use layout::flow;
+
+use extra::sort::quick_sort;
+use geom::point::Point2D;
+use geom::rect::Rect;
+use geom::size::Size2D;
+use servo_util::geometry::Au;
+use style::computed_values::{page_break_after, page_break_before, page_break_inside};
+
+#[deriving(Eq)]
+enum BreakKind {
+    /// 'page-break-before' or 'page-break-after' force a page break here.
+    ForcedBreak,
+    /// A page break is allowed here.
+    AllowedBreak,
+    /// A page break should be avoided here, so it is only used if there is no other choice.
+    AvoidedBreak,
+}
+
+/// A position in the document between two blocks or two lines where pages may be split.
+struct BreakPoint {
+    y: Au,
+    kind: BreakKind,
+}
+
+/// Splits the document laid out in `layout_root` into pages. Returns, for each page, the part of
+/// the document that is shown in its page area.
+pub fn paginate(layout_root: &mut Flow, page_width: Au, page_height: Au) -> ~[Rect<Au>] {
+    let mut break_points = ~[];
+    collect_break_points(layout_root, Au(0), false, &mut break_points);
+    quick_sort(break_points, |a, b| a.y <= b.y);
+
+    // Always make progress, even with an empty page area.
+    let page_height = Au::max(page_height, Au::from_px(1));
+    let document_height = flow::base(layout_root).position.size.height;
+    let pages = choose_page_breaks(break_points, document_height, page_height);
+    pages.iter().map(|&(top, bottom)| {
+        Rect(Point2D(Au(0), top), Size2D(page_width, bottom - top))
+    }).collect()
+}
+
+/// Returns the top and bottom of each page. Each page ends at a forced break if there is one,
+/// otherwise at the last break that fits on it, preferring breaks that are not avoided.
+fn choose_page_breaks(break_points: &[BreakPoint], document_height: Au, page_height: Au)
+                      -> ~[(Au, Au)] {
+    let mut pages = ~[];
+    let mut top = Au(0);
+    let mut next_break_point = 0;
+    while top < document_height {
+        let limit = top + page_height;
+        let mut forced = None;
+        let mut allowed = None;
+        let mut avoided = None;
+        for break_point in break_points.slice_from(next_break_point).iter() {
+            if break_point.y > limit {
+                break
+            }
+            if break_point.y <= top {
+                continue
+            }
+            match break_point.kind {
+                ForcedBreak => {
+                    forced = Some(break_point.y);
+                    break
+                }
+                AllowedBreak => allowed = Some(break_point.y),
+                AvoidedBreak => avoided = Some(break_point.y),
+            }
+        }
+        let bottom = match forced {
+            Some(y) => y,
+            None if limit >= document_height => document_height,
+            None => allowed.or(avoided).unwrap_or(limit),
+        };
+        pages.push((top, bottom));
+        top = bottom;
+        while next_break_point < break_points.len() && break_points[next_break_point].y <= top {
+            next_break_point += 1;
+        }
+    }
+    if pages.is_empty() {
+        // An empty document still prints a blank page.
+        pages.push((Au(0), Au(0)));
+    }
+    pages
+}
+
+fn is_forced(before: page_break_before::T, after: page_break_after::T) -> bool {
+    match (before, after) {
+        (page_break_before::always, _) | (page_break_before::left, _) |
+        (page_break_before::right, _) | (_, page_break_after::always) |
+        (_, page_break_after::left) | (_, page_break_after::right) => true,
+        _ => false,
+    }
+}
+
+/// Adds the break points inside `flow`, whose top is at `top` in the document. `avoid_inside` is
+/// true if 'page-break-inside: avoid' applies to an ancestor.
+fn collect_break_points(flow: &mut Flow, top: Au, avoid_inside: bool,
+                        break_points: &mut ~[BreakPoint]) {
+    match flow.class() {
+        BlockFlowClass => {
+            let mut avoid_inside = avoid_inside;
+            {
+                let block = flow.as_block();
+                if block.is_float() {
+                    return
+                }
+                for box in block.box.iter() {
+                    avoid_inside = avoid_inside ||
+                        box.style().Box.page_break_inside == page_break_inside::avoid;
+                }
+            }
+
+            // The top and page-break properties of each in-flow child.
+            let mut children = ~[];
+            for kid in flow::child_iter(flow) {
+                let kid_top = top + flow::base(*kid).position.origin.y;
+                collect_break_points(*kid, kid_top, avoid_inside, break_points);
+                let kid_bottom = kid_top + flow::base(*kid).position.size.height;
+                let (before, after) = match kid.class() {
+                    BlockFlowClass => {
+                        let block = kid.as_block();
+                        if block.is_float() {
+                            continue
+                        }
+                        match block.box {
+                            Some(ref box) => (box.style().Box.page_break_before,
+                                              box.style().Box.page_break_after),
+                            None => (page_break_before::auto, page_break_after::auto),
+                        }
+                    }
+                    AbsoluteFlowClass => continue,
+                    _ => (page_break_before::auto, page_break_after::auto),
+                };
+                children.push((kid_top, kid_bottom, before, after));
+            }
+
+            for (i, &(kid_top, kid_bottom, before, after)) in children.iter().enumerate() {
+                // Forced breaks before the first child and after the last one apply to the
+                // edges of this flow's content.
+                if i == 0 && is_forced(before, page_break_after::auto) {
+                    break_points.push(BreakPoint { y: kid_top, kind: ForcedBreak })
+                }
+                if i == children.len() - 1 {
+                    if is_forced(page_break_before::auto, after) {
+                        break_points.push(BreakPoint { y: kid_bottom, kind: ForcedBreak })
+                    }
+                    continue
+                }
+                let (next_top, _, next_before, _) = children[i + 1];
+                let kind = if is_forced(next_before, after) {
+                    ForcedBreak
+                } else if avoid_inside || next_before == page_break_before::avoid ||
+                        after == page_break_after::avoid {
+                    AvoidedBreak
+                } else {
+                    AllowedBreak
+                };
+                break_points.push(BreakPoint { y: next_top, kind: kind })
+            }
+        }
+        InlineFlowClass => {
+            let kind = if avoid_inside { AvoidedBreak } else { AllowedBreak };
+            for line in flow.as_inline().lines.iter().skip(1) {
+                break_points.push(BreakPoint { y: top + line.bounds.origin.y, kind: kind })
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AllowedBreak, AvoidedBreak, BreakPoint, ForcedBreak, choose_page_breaks};
+    use servo_util::geometry::Au;
+
+    fn px(value: int) -> Au {
+        Au::from_px(value)
+    }
+
+    #[test]
+    fn test_short_document_fits_on_one_page() {
+        let pages = choose_page_breaks([BreakPoint { y: px(50), kind: AllowedBreak }],
+                                       px(80), px(100));
+        assert_eq!(pages, ~[(px(0), px(80))]);
+    }
+
+    #[test]
+    fn test_breaks_at_last_fitting_break_point() {
+        let break_points = [BreakPoint { y: px(40), kind: AllowedBreak },
+                            BreakPoint { y: px(90), kind: AllowedBreak },
+                            BreakPoint { y: px(95), kind: AvoidedBreak },
+                            BreakPoint { y: px(150), kind: AllowedBreak }];
+        let pages = choose_page_breaks(break_points, px(250), px(100));
+        assert_eq!(pages, ~[(px(0), px(90)), (px(90), px(150)), (px(150), px(250))]);
+    }
+
+    #[test]
+    fn test_forced_and_missing_breaks() {
+        let break_points = [BreakPoint { y: px(0), kind: ForcedBreak },
+                            BreakPoint { y: px(30), kind: ForcedBreak }];
+        // A forced break at the top of the document does not produce an empty page, and a
+        // document with no break point that fits is cut at the page height.
+        let pages = choose_page_breaks(break_points, px(150), px(100));
+        assert_eq!(pages, ~[(px(0), px(30)), (px(30), px(130)), (px(130), px(150))]);
+    }
+}
@@ -0,0 +1,996 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! CSS tables (CSS 2.1 § 17).
+//!
+//! A `TableFlow` is the table wrapper box: its children are the captions and the row groups of the
+//! table, and its box is the table box. The table places its cells in a grid of rows and columns,
+//! works out the widths of the columns with the automatic or the fixed table layout algorithm, and
+//! hands them down to the rows, which place their cells. The row groups work out the heights of
+//! their rows once the cells know their own, and the table stacks the row groups and captions.
+
+use layout::block::BlockFlow;
+use layout::box::Box;
+use layout::context::LayoutContext;
+use layout::display_list_builder::ToGfxColor;
+use layout::flow::{Flow, FlowClass, FlowData, TableCaptionFlowClass, TableCellFlowClass};
+use layout::flow::{ImmutableFlowUtils, TableFlowClass, TableRowFlowClass};
+use layout::flow::{TableRowGroupFlowClass};
+use layout::flow;
+use layout::model::{MaybeAuto, Specified, Auto, shrink_to_fit, specified};
+use layout::table_cell::TableCellFlow;
+
+use geom::SideOffsets2D;
+use gfx::color::{Color, rgb};
+use servo_util::geometry::Au;
+use servo_util::geometry;
+use std::cmp;
+use std::num::Zero;
+use std::vec;
+use style::ComputedValues;
+use style::computed_values::{LengthOrPercentageOrAuto, border_collapse, border_spacing};
+use style::computed_values::{border_style, caption_side, table_layout};
+
+/// A flow for a box with `display: table`.
+pub struct TableFlow {
+    /// The block flow that lays out the table box. This must be the first field: it starts with
+    /// the `FlowData` that `flow::base()` returns.
+    block: BlockFlow,
+
+    /// The widths that the columns and column groups of the table specify, one per column.
+    column_specs: ~[LengthOrPercentageOrAuto],
+
+    /// The minimum widths of the columns.
+    column_min_widths: ~[Au],
+
+    /// The preferred widths of the columns.
+    column_pref_widths: ~[Au],
+
+    /// The used widths of the columns.
+    column_widths: ~[Au],
+
+    /// The spacing between the cells, and between the cells and the edges of the table. It is
+    /// zero in the collapsing border model.
+    spacing: border_spacing::T,
+}
+
+impl TableFlow {
+    pub fn from_box(base: FlowData, box: Box, column_specs: ~[LengthOrPercentageOrAuto])
+                    -> TableFlow {
+        TableFlow {
+            block: BlockFlow::from_box(base, box),
+            column_specs: column_specs,
+            column_min_widths: ~[],
+            column_pref_widths: ~[],
+            column_widths: ~[],
+            spacing: border_spacing::T {
+                horizontal: Au(0),
+                vertical: Au(0),
+            },
+        }
+    }
+
+    pub fn teardown(&mut self) {
+        self.block.teardown();
+        self.column_specs = ~[];
+        self.column_min_widths = ~[];
+        self.column_pref_widths = ~[];
+        self.column_widths = ~[];
+    }
+
+    /// Places the cells in the grid of the table: gives each cell its row and first column, and
+    /// clips its row span to the rows left in its row group (CSS 2.1 § 17.5). Returns the number
+    /// of rows and columns of the grid.
+    fn assign_grid(&mut self) -> (uint, uint) {
+        let mut row_count = 0;
+        let mut column_count = self.column_specs.len();
+        for group in self.block.base.child_iter() {
+            match group.class() {
+                TableRowGroupFlowClass => {}
+                _ => continue,
+            }
+
+            let mut group_row_count = 0;
+            for row in flow::child_iter(*group) {
+                match row.class() {
+                    TableRowFlowClass => group_row_count += 1,
+                    _ => {}
+                }
+            }
+
+            // How many rows further down each column is still taken by a cell of a row above.
+            let mut rows_taken: ~[uint] = ~[];
+            let mut group_row = 0;
+            for row in flow::child_iter(*group) {
+                match row.class() {
+                    TableRowFlowClass => {}
+                    _ => continue,
+                }
+                let mut column = 0;
+                for cell in flow::child_iter(*row) {
+                    match cell.class() {
+                        TableCellFlowClass => {}
+                        _ => continue,
+                    }
+                    let cell = cell.as_table_cell();
+                    while column < rows_taken.len() && rows_taken[column] > 0 {
+                        column += 1
+                    }
+
+                    // A 'rowspan' of zero spans the rest of the row group.
+                    let rows_left = group_row_count - group_row;
+                    cell.spanned_rows = if cell.rowspan == 0 || cell.rowspan > rows_left {
+                        rows_left
+                    } else {
+                        cell.rowspan
+                    };
+                    cell.row = row_count + group_row;
+                    cell.column = column;
+
+                    for spanned_column in range(column, column + cell.colspan) {
+                        if spanned_column == rows_taken.len() {
+                            rows_taken.push(0)
+                        }
+                        rows_taken[spanned_column] = cell.spanned_rows
+                    }
+                    column += cell.colspan
+                }
+
+                column_count = cmp::max(column_count, rows_taken.len());
+                for rows in rows_taken.mut_iter() {
+                    if *rows > 0 {
+                        *rows -= 1
+                    }
+                }
+                group_row += 1
+            }
+            row_count += group_row_count
+        }
+        (row_count, column_count)
+    }
+
+    /// Resolves the borders of the cells, rows, row groups and the table that compete for each
+    /// line of the grid in the collapsing border model (CSS 2.1 § 17.6.2). Each cell gets the
+    /// inner halves of the borders around it, and the table gets the outer halves of those around
+    /// the grid.
+    fn collapse_borders(&mut self, row_count: uint, column_count: uint) {
+        // The winning border of each segment of the horizontal and vertical lines of the grid.
+        let mut horizontal = vec::from_fn(row_count + 1, |_| {
+            vec::from_elem(column_count, CollapsedBorder::none())
+        });
+        let mut vertical = vec::from_fn(row_count, |_| {
+            vec::from_elem(column_count + 1, CollapsedBorder::none())
+        });
+
+        // Borders compete from the outside in, so that those of the cells are the last to be
+        // considered, and they compete in document order, so that the top and left ones win ties.
+        let table_borders = CollapsedBorder::borders_of(self.block.box.get_ref().style(),
+                                                        TableBorder);
+        collapse_around(&mut horizontal, &mut vertical, &table_borders, 0, row_count, 0,
+                        column_count);
+
+        let mut first_row = 0;
+        for group in self.block.base.child_iter() {
+            match group.class() {
+                TableRowGroupFlowClass => {}
+                _ => continue,
+            }
+
+            let mut group_row_count = 0;
+            for row in flow::child_iter(*group) {
+                match row.class() {
+                    TableRowFlowClass => group_row_count += 1,
+                    _ => {}
+                }
+            }
+            let group_borders = CollapsedBorder::borders_of(group.as_block().box.get_ref().style(),
+                                                            RowGroupBorder);
+            collapse_around(&mut horizontal, &mut vertical, &group_borders, first_row,
+                            group_row_count, 0, column_count);
+
+            let mut row_index = first_row;
+            for row in flow::child_iter(*group) {
+                match row.class() {
+                    TableRowFlowClass => {}
+                    _ => continue,
+                }
+                let row_borders = CollapsedBorder::borders_of(row.as_block().box.get_ref().style(),
+                                                              RowBorder);
+                collapse_around(&mut horizontal, &mut vertical, &row_borders, row_index, 1, 0,
+                                column_count);
+
+                for cell in flow::child_iter(*row) {
+                    match cell.class() {
+                        TableCellFlowClass => {}
+                        _ => continue,
+                    }
+                    let cell = cell.as_table_cell();
+                    let cell_borders =
+                        CollapsedBorder::borders_of(cell.block.box.get_ref().style(), CellBorder);
+                    collapse_around(&mut horizontal, &mut vertical, &cell_borders, cell.row,
+                                    cell.spanned_rows, cell.column, cell.colspan);
+                }
+                row_index += 1
+            }
+            first_row += group_row_count
+        }
+
+        // Give the cells the inner halves of the winning borders around them.
+        do each_cell(self as &mut Flow) |cell| {
+            let (row, column) = (cell.row, cell.column);
+            let (last_row, last_column) = (row + cell.spanned_rows, column + cell.colspan);
+            let borders = SideOffsets2D::new(horizontal[row][column].clone(),
+                                             vertical[row][last_column].clone(),
+                                             horizontal[last_row][column].clone(),
+                                             vertical[row][column].clone());
+            cell.set_collapsed_borders(CollapsedBorders {
+                borders: borders,
+                paints_right: last_column == column_count,
+                paints_bottom: last_row == row_count,
+            })
+        }
+
+        // The table box gets the outer halves of the borders around the grid: the widest along
+        // the top and bottom, and those of the first row on the left and right.
+        let mut border = SideOffsets2D::new(Au(0), Au(0), Au(0), Au(0));
+        for column in range(0, column_count) {
+            border.top = geometry::max(border.top, outer_half(horizontal[0][column].width));
+            border.bottom = geometry::max(border.bottom,
+                                          inner_half(horizontal[row_count][column].width));
+        }
+        if row_count > 0 {
+            border.left = outer_half(vertical[0][0].width);
+            border.right = inner_half(vertical[0][column_count].width);
+        }
+        self.block.box.get_ref().border.set(border)
+    }
+
+    /// Returns the widths that the columns and the cells in the first row of the table specify,
+    /// for the fixed table layout algorithm (CSS 2.1 § 17.5.2.1). Percentages refer to `width`,
+    /// the width of the table.
+    fn fixed_layout_specified_widths(&mut self, width: Au) -> ~[Option<Au>] {
+        let column_count = self.column_min_widths.len();
+        let mut widths = vec::from_elem(column_count, None);
+        for (column, spec) in self.column_specs.iter().enumerate() {
+            widths[column] = match MaybeAuto::from_style(*spec, width) {
+                Specified(column_width) => Some(column_width),
+                Auto => None,
+            }
+        }
+
+        let spacing = self.spacing.horizontal;
+        let mut found_first_row = false;
+        for group in self.block.base.child_iter() {
+            match group.class() {
+                TableRowGroupFlowClass if !found_first_row => {}
+                _ => continue,
+            }
+            for row in flow::child_iter(*group) {
+                match row.class() {
+                    TableRowFlowClass if !found_first_row => found_first_row = true,
+                    _ => continue,
+                }
+                for cell in flow::child_iter(*row) {
+                    match cell.class() {
+                        TableCellFlowClass => {}
+                        _ => continue,
+                    }
+                    let cell = cell.as_table_cell();
+                    let box = cell.block.box.get_ref();
+                    let style = box.style();
+                    let cell_width = match MaybeAuto::from_style(style.Box.width, width) {
+                        Specified(cell_width) => cell_width,
+                        Auto => continue,
+                    };
+
+                    // The columns share the border box of the cell, less the spacing between them.
+                    let border = box.border.get();
+                    let noncontent_width = border.left + border.right +
+                        specified(style.Padding.padding_left, width) +
+                        specified(style.Padding.padding_right, width);
+                    let spanned_width = cell_width + noncontent_width -
+                        total_spacing(spacing, cell.colspan - 1);
+                    let share = Au(*spanned_width / (cell.colspan as i32));
+                    for column in range(cell.column, cell.column + cell.colspan) {
+                        if widths[column].is_none() {
+                            widths[column] = Some(share)
+                        }
+                    }
+                }
+            }
+        }
+        widths
+    }
+}
+
+impl Flow for TableFlow {
+    fn class(&self) -> FlowClass {
+        TableFlowClass
+    }
+
+    fn as_block<'a>(&'a mut self) -> &'a mut BlockFlow {
+        &mut self.block
+    }
+
+    fn as_table<'a>(&'a mut self) -> &'a mut TableFlow {
+        self
+    }
+
+    /// Places the cells in the grid, resolves collapsed borders, and works out the minimum and
+    /// preferred widths of the columns from those of the cells (CSS 2.1 § 17.5.2.2). Cells that
+    /// span several columns widen them evenly if they need to.
+    fn bubble_widths(&mut self, _: &mut LayoutContext) {
+        let collapse = {
+            let box = self.block.box.get_ref();
+            let style = box.style();
+            box.compute_borders(style);
+            style.Table.border_collapse == border_collapse::collapse
+        };
+        self.spacing = if collapse {
+            border_spacing::T {
+                horizontal: Au(0),
+                vertical: Au(0),
+            }
+        } else {
+            self.block.box.get_ref().style().Table.border_spacing
+        };
+
+        let (row_count, column_count) = self.assign_grid();
+        if collapse {
+            self.collapse_borders(row_count, column_count)
+        }
+
+        let mut min_widths = vec::from_elem(column_count, Au(0));
+        let mut pref_widths = vec::from_elem(column_count, Au(0));
+        for (column, spec) in self.column_specs.iter().enumerate() {
+            match MaybeAuto::from_style(*spec, Au(0)) {
+                Specified(width) if width > Au(0) => {
+                    min_widths[column] = width;
+                    pref_widths[column] = width;
+                }
+                _ => {}
+            }
+        }
+
+        let mut spanning_cells = ~[];
+        do each_cell(self as &mut Flow) |cell| {
+            let (min_width, pref_width) = (cell.block.base.min_width, cell.block.base.pref_width);
+            if cell.colspan == 1 {
+                let column = cell.column;
+                min_widths[column] = geometry::max(min_widths[column], min_width);
+                pref_widths[column] = geometry::max(pref_widths[column], pref_width);
+            } else {
+                spanning_cells.push((cell.column, cell.colspan, min_width, pref_width))
+            }
+        }
+        let spacing = self.spacing.horizontal;
+        for &(column, colspan, min_width, pref_width) in spanning_cells.iter() {
+            let spanned_spacing = total_spacing(spacing, colspan - 1);
+            distribute_excess_width(min_widths.mut_slice(column, column + colspan),
+                                    min_width - spanned_spacing);
+            distribute_excess_width(pref_widths.mut_slice(column, column + colspan),
+                                    pref_width - spanned_spacing);
+        }
+        for column in range(0, column_count) {
+            pref_widths[column] = geometry::max(pref_widths[column], min_widths[column])
+        }
+
+        let grid_spacing = if column_count == 0 {
+            Au(0)
+        } else {
+            total_spacing(spacing, column_count + 1)
+        };
+        let mut min_width = sum(min_widths) + grid_spacing;
+        let mut pref_width = sum(pref_widths) + grid_spacing;
+        self.column_min_widths = min_widths;
+        self.column_pref_widths = pref_widths;
+
+        // A specified width is a minimum for the table.
+        for box in self.block.box.iter() {
+            let style = box.style();
+            match MaybeAuto::from_style(style.Box.width, Au(0)) {
+                Specified(width) => {
+                    min_width = geometry::max(min_width, width);
+                    pref_width = geometry::max(pref_width, width);
+                }
+                Auto => {}
+            }
+            let border = box.border.get();
+            let mut noncontent_width = border.left + border.right +
+                MaybeAuto::from_style(style.Margin.margin_left, Au(0)).specified_or_zero() +
+                MaybeAuto::from_style(style.Margin.margin_right, Au(0)).specified_or_zero();
+            if !collapse {
+                noncontent_width = noncontent_width +
+                    specified(style.Padding.padding_left, Au(0)) +
+                    specified(style.Padding.padding_right, Au(0));
+            }
+            min_width = min_width + noncontent_width;
+            pref_width = pref_width + noncontent_width;
+        }
+
+        // The table is at least as wide as its captions.
+        for kid in self.block.base.child_iter() {
+            match kid.class() {
+                TableCaptionFlowClass => {}
+                _ => continue,
+            }
+            let kid_base = flow::base(*kid);
+            min_width = geometry::max(min_width, kid_base.min_width);
+            pref_width = geometry::max(pref_width, kid_base.pref_width);
+        }
+
+        // The cells lay out their own floats.
+        self.block.base.num_floats = 0;
+        self.block.base.min_width = min_width;
+        self.block.base.pref_width = pref_width;
+    }
+
+    /// Works out the width of the table and the widths of its columns, with the fixed table layout
+    /// algorithm if the table has `table-layout: fixed` and a width, or with the automatic one
+    /// otherwise (CSS 2.1 § 17.5.2), and gives the row groups and captions their widths.
+    fn assign_widths(&mut self, _: &mut LayoutContext) {
+        debug!("assign_widths_table: assigning width for flow {}", self.block.base.id);
+
+        // The position was set to the containing block by the flow's parent.
+        let containing_width = self.block.base.position.size.width;
+        let grid_spacing = if self.column_min_widths.is_empty() {
+            Au(0)
+        } else {
+            total_spacing(self.spacing.horizontal, self.column_min_widths.len() + 1)
+        };
+
+        let (fixed, table_width, noncontent_width, margin_top, margin_bottom, maybe_margin_left,
+             maybe_margin_right) = {
+            let box = self.block.box.get_ref();
+            let style = box.style();
+            if style.Table.border_collapse == border_collapse::collapse {
+                box.padding.set(Zero::zero())
+            } else {
+                box.compute_padding(style, containing_width)
+            }
+            let (border, padding) = (box.border.get(), box.padding.get());
+            let noncontent_width = border.left + border.right + padding.left + padding.right;
+
+            let margin_top = MaybeAuto::from_style(style.Margin.margin_top,
+                                                   containing_width).specified_or_zero();
+            let margin_bottom = MaybeAuto::from_style(style.Margin.margin_bottom,
+                                                      containing_width).specified_or_zero();
+            let maybe_margin_left = MaybeAuto::from_style(style.Margin.margin_left,
+                                                          containing_width);
+            let maybe_margin_right = MaybeAuto::from_style(style.Margin.margin_right,
+                                                           containing_width);
+
+            // An 'auto' width shrinks to fit, and a specified width is a minimum, except in the
+            // fixed table layout algorithm.
+            let (fixed, table_width) = match MaybeAuto::from_style(style.Box.width,
+                                                                   containing_width) {
+                Auto => {
+                    let margins = maybe_margin_left.specified_or_zero() +
+                        maybe_margin_right.specified_or_zero();
+                    let width = shrink_to_fit(self.block.base.min_width,
+                                              self.block.base.pref_width,
+                                              containing_width);
+                    (false, width - margins - noncontent_width)
+                }
+                Specified(width) if style.Table.table_layout == table_layout::fixed => {
+                    (true, width)
+                }
+                Specified(width) => {
+                    (false, geometry::max(width, sum(self.column_min_widths) + grid_spacing))
+                }
+            };
+            (fixed, table_width, noncontent_width, margin_top, margin_bottom, maybe_margin_left,
+             maybe_margin_right)
+        };
+
+        self.column_widths = if fixed {
+            let specified_widths = self.fixed_layout_specified_widths(table_width);
+            fixed_column_widths(specified_widths, table_width - grid_spacing)
+        } else {
+            auto_column_widths(self.column_min_widths,
+                               self.column_pref_widths,
+                               table_width - grid_spacing)
+        };
+        let table_width = geometry::max(table_width, sum(self.column_widths) + grid_spacing);
+
+        let (table_width, margin_left, margin_right) =
+            self.block.compute_horiz(Specified(table_width),
+                                     maybe_margin_left,
+                                     maybe_margin_right,
+                                     containing_width - noncontent_width);
+
+        let x_offset = {
+            let box = self.block.box.get_ref();
+            box.margin.set(SideOffsets2D::new(margin_top,
+                                              margin_right,
+                                              margin_bottom,
+                                              margin_left));
+
+            // The associated box is the table box.
+            let position_ref = box.position.mutate();
+            position_ref.ptr.origin.x = margin_left;
+            position_ref.ptr.size.width = table_width + noncontent_width;
+            box.offset()
+        };
+
+        // Like the root flow, a table lays out its floats by itself.
+        self.block.base.flags.set_inorder(false);
+
+        let spacing = self.spacing;
+        let flags = self.block.base.flags;
+        for kid in self.block.base.child_iter() {
+            let is_caption = match kid.class() {
+                TableCaptionFlowClass => true,
+                TableRowGroupFlowClass => {
+                    let group = kid.as_table_row_group();
+                    group.column_widths = self.column_widths.clone();
+                    group.spacing = spacing;
+                    false
+                }
+                _ => false,
+            };
+            let in_flow = !kid.is_absolutely_positioned();
+
+            // Captions are as wide as the table box, and the row groups as its content box.
+            let kid_base = flow::mut_base(*kid);
+            if is_caption {
+                kid_base.position.origin.x = margin_left;
+                kid_base.position.size.width = table_width + noncontent_width;
+            } else {
+                kid_base.position.origin.x = x_offset;
+                kid_base.position.size.width = table_width;
+            }
+            kid_base.flags.set_inorder(false);
+
+            // Per CSS 2.1 § 16.3.1, text decoration propagates to all children in flow.
+            if in_flow {
+                kid_base.flags.propagate_text_decoration_from_parent(flags)
+            }
+        }
+    }
+
+    /// Stacks the captions above and below the table box, and the row groups inside it, with the
+    /// vertical border spacing around them.
+    fn assign_height(&mut self, ctx: &mut LayoutContext) {
+        debug!("assign_height_table: assigning height for flow {}", self.block.base.id);
+
+        let (margin, border, padding) = {
+            let box = self.block.box.get_ref();
+            (box.margin.get(), box.border.get(), box.padding.get())
+        };
+        let spacing = self.spacing.vertical;
+
+        let mut cur_y = margin.top;
+        for kid in self.block.base.child_iter() {
+            if is_caption_on_side(*kid, caption_side::top) {
+                let kid_base = flow::mut_base(*kid);
+                kid_base.position.origin.y = cur_y;
+                cur_y = cur_y + kid_base.position.size.height;
+            }
+        }
+
+        let table_y = cur_y;
+        let content_y = table_y + border.top + padding.top;
+        let mut has_row_groups = false;
+        cur_y = content_y + spacing;
+        for kid in self.block.base.child_iter() {
+            let is_row_group = match kid.class() {
+                TableRowGroupFlowClass => true,
+                _ => false,
+            };
+            let in_flow = !kid.is_absolutely_positioned();
+            let kid_base = flow::mut_base(*kid);
+            if is_row_group {
+                has_row_groups = true;
+                kid_base.position.origin.y = cur_y;
+                cur_y = cur_y + kid_base.position.size.height + spacing;
+            } else if !in_flow {
+                // An absolutely positioned child takes no room: it is just given its static
+                // position here.
+                kid_base.position.origin.y = content_y;
+            }
+        }
+        let mut height = if has_row_groups {
+            cur_y - content_y
+        } else {
+            Au(0)
+        };
+
+        // FIXME: The extra height that the 'height' of the table calls for should go to the rows.
+        for box in self.block.box.iter() {
+            let style = box.style();
+            match MaybeAuto::from_style(style.Box.height, Au(0)) {
+                Specified(value) => height = geometry::max(height, value),
+                Auto => {}
+            }
+
+            // The associated box is the table box.
+            let position_ref = box.position.mutate();
+            position_ref.ptr.origin.y = table_y;
+            position_ref.ptr.size.height = height + border.top + border.bottom + padding.top +
+                padding.bottom;
+        }
+
+        cur_y = table_y + height + border.top + border.bottom + padding.top + padding.bottom;
+        for kid in self.block.base.child_iter() {
+            if is_caption_on_side(*kid, caption_side::bottom) {
+                let kid_base = flow::mut_base(*kid);
+                kid_base.position.origin.y = cur_y;
+                cur_y = cur_y + kid_base.position.size.height;
+            }
+        }
+
+        self.block.base.position.size.height = cur_y + margin.bottom;
+        self.block.base.floats_out = self.block.base.floats_in.clone();
+        self.block.lay_out_absolute_descendants(ctx);
+    }
+
+    /// A table has been laid out already, and it does not affect the floats around it.
+    fn assign_height_inorder(&mut self, _: &mut LayoutContext) {
+        self.block.base.floats_out = self.block.base.floats_in.clone();
+    }
+
+    fn collapse_margins(&mut self,
+                        top_margin_collapsible: bool,
+                        first_in_flow: &mut bool,
+                        margin_top: &mut Au,
+                        top_offset: &mut Au,
+                        collapsing: &mut Au,
+                        collapsible: &mut Au) {
+        self.block.collapse_margins(top_margin_collapsible,
+                                    first_in_flow,
+                                    margin_top,
+                                    top_offset,
+                                    collapsing,
+                                    collapsible)
+    }
+
+    fn debug_str(&self) -> ~str {
+        let txt = ~"TableFlow: ";
+        txt.append(match self.block.box {
+            Some(ref rb) => rb.debug_str(),
+            None => ~"",
+        })
+    }
+}
+
+/// The kind of box that a border belongs to. When borders are equally wide and equally styled,
+/// that of the innermost box wins (CSS 2.1 § 17.6.2.1).
+#[deriving(Clone, Eq)]
+pub enum BorderOrigin {
+    TableBorder,
+    RowGroupBorder,
+    RowBorder,
+    CellBorder,
+}
+
+/// A border that competes for a segment of a line of the grid in the collapsing border model.
+#[deriving(Clone)]
+pub struct CollapsedBorder {
+    style: border_style::T,
+    width: Au,
+    color: Color,
+    origin: BorderOrigin,
+}
+
+impl CollapsedBorder {
+    /// A missing border, which loses to any other.
+    pub fn none() -> CollapsedBorder {
+        CollapsedBorder {
+            style: border_style::none,
+            width: Au(0),
+            color: rgb(0, 0, 0),
+            origin: TableBorder,
+        }
+    }
+
+    /// Returns the top, right, bottom and left borders of a box with the given style.
+    pub fn borders_of(style: &ComputedValues, origin: BorderOrigin)
+                      -> SideOffsets2D<CollapsedBorder> {
+        let border = |border_style: border_style::T, width: Au, color: Color| {
+            CollapsedBorder {
+                style: border_style,
+                width: if border_style == border_style::none { Au(0) } else { width },
+                color: color,
+                origin: origin,
+            }
+        };
+        let borders = &style.Border;
+        SideOffsets2D::new(border(borders.border_top_style,
+                                  borders.border_top_width,
+                                  style.resolve_color(borders.border_top_color).to_gfx_color()),
+                           border(borders.border_right_style,
+                                  borders.border_right_width,
+                                  style.resolve_color(borders.border_right_color).to_gfx_color()),
+                           border(borders.border_bottom_style,
+                                  borders.border_bottom_width,
+                                  style.resolve_color(borders.border_bottom_color).to_gfx_color()),
+                           border(borders.border_left_style,
+                                  borders.border_left_width,
+                                  style.resolve_color(borders.border_left_color).to_gfx_color()))
+    }
+
+    /// Returns true if this border wins over `other`: 'hidden' wins over everything, then wider
+    /// borders win, then the more solid style, then the border of the innermost box.
+    pub fn beats(&self, other: &CollapsedBorder) -> bool {
+        match (self.style, other.style) {
+            (_, border_style::hidden) => return false,
+            (border_style::hidden, _) => return true,
+            _ => {}
+        }
+        if self.width != other.width {
+            return self.width > other.width
+        }
+        let (rank, other_rank) = (style_rank(self.style), style_rank(other.style));
+        if rank != other_rank {
+            return rank > other_rank
+        }
+        (self.origin as uint) > (other.origin as uint)
+    }
+
+    /// Replaces this border with `other` if it wins.
+    fn collapse_with(&mut self, other: &CollapsedBorder) {
+        if other.beats(self) {
+            *self = other.clone()
+        }
+    }
+}
+
+/// The winning borders around a cell in the collapsing border model. A cell paints the borders
+/// above and to the left of it, so that later cells do not paint over them, and those below and to
+/// the right of it only on the edges of the table.
+#[deriving(Clone)]
+pub struct CollapsedBorders {
+    borders: SideOffsets2D<CollapsedBorder>,
+    paints_right: bool,
+    paints_bottom: bool,
+}
+
+/// Returns how solid a border style is, for collapsing borders.
+fn style_rank(style: border_style::T) -> uint {
+    match style {
+        border_style::solid => 3,
+        border_style::dashed => 2,
+        border_style::dotted => 1,
+        border_style::none | border_style::hidden => 0,
+    }
+}
+
+/// Makes `borders`, the borders of a box covering `row_count` rows from `row` and `column_count`
+/// columns from `column`, compete for the lines of the grid around it.
+fn collapse_around(horizontal: &mut ~[~[CollapsedBorder]],
+                   vertical: &mut ~[~[CollapsedBorder]],
+                   borders: &SideOffsets2D<CollapsedBorder>,
+                   row: uint,
+                   row_count: uint,
+                   column: uint,
+                   column_count: uint) {
+    if row_count == 0 || column_count == 0 {
+        return
+    }
+    for spanned_column in range(column, column + column_count) {
+        horizontal[row][spanned_column].collapse_with(&borders.top);
+        horizontal[row + row_count][spanned_column].collapse_with(&borders.bottom);
+    }
+    for spanned_row in range(row, row + row_count) {
+        vertical[spanned_row][column].collapse_with(&borders.left);
+        vertical[spanned_row][column + column_count].collapse_with(&borders.right);
+    }
+}
+
+/// The part of a collapsed border of the given width that lies above or to the left of its grid
+/// line.
+#[inline]
+pub fn outer_half(width: Au) -> Au {
+    Au(*width / 2)
+}
+
+/// The part of a collapsed border of the given width that lies below or to the right of its grid
+/// line.
+#[inline]
+pub fn inner_half(width: Au) -> Au {
+    width - outer_half(width)
+}
+
+/// Returns true if `flow` is a caption on the given side of its table.
+fn is_caption_on_side(flow: &mut Flow, side: caption_side::T) -> bool {
+    match flow.class() {
+        TableCaptionFlowClass => {
+            flow.as_block().box.get_ref().style().Table.caption_side == side
+        }
+        _ => false,
+    }
+}
+
+/// Calls `f` on each cell of the table whose flow is `table`, in document order.
+fn each_cell(table: &mut Flow, f: &fn(&mut TableCellFlow)) {
+    for group in flow::child_iter(table) {
+        match group.class() {
+            TableRowGroupFlowClass => {}
+            _ => continue,
+        }
+        for row in flow::child_iter(*group) {
+            match row.class() {
+                TableRowFlowClass => {}
+                _ => continue,
+            }
+            for cell in flow::child_iter(*row) {
+                match cell.class() {
+                    TableCellFlowClass => f(cell.as_table_cell()),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Returns the total width of `count` gaps of `spacing`.
+#[inline]
+pub fn total_spacing(spacing: Au, count: uint) -> Au {
+    Au(*spacing * (count as i32))
+}
+
+/// Returns the sum of the given widths.
+fn sum(widths: &[Au]) -> Au {
+    widths.iter().fold(Au(0), |total, &width| total + width)
+}
+
+/// Returns `width` scaled by `numerator / denominator`, without floating point rounding errors.
+fn scale_by_ratio(width: Au, numerator: Au, denominator: Au) -> Au {
+    Au(((*width as i64) * (*numerator as i64) / (*denominator as i64)) as i32)
+}
+
+/// Widens `widths` evenly so that they add up to at least `width`.
+fn distribute_excess_width(widths: &mut [Au], width: Au) {
+    let total = sum(widths);
+    if widths.is_empty() || width <= total {
+        return
+    }
+    let count = widths.len() as i32;
+    let share = Au(*(width - total) / count);
+    for column_width in widths.mut_iter() {
+        *column_width = *column_width + share
+    }
+
+    // Rounding errors go to the last column.
+    let last = widths.len() - 1;
+    widths[last] = widths[last] + width - total - Au(*share * count);
+}
+
+/// Shares `width` between columns with the given minimum and preferred widths, for the automatic
+/// table layout algorithm. Below their total preferred width, each column gets the same fraction
+/// of the room between its minimum and preferred widths; above it, the columns widen in
+/// proportion to their preferred widths. The columns are never narrower than their minimum widths.
+pub fn auto_column_widths(min_widths: &[Au], pref_widths: &[Au], width: Au) -> ~[Au] {
+    let (total_min_width, total_pref_width) = (sum(min_widths), sum(pref_widths));
+    if min_widths.is_empty() || width <= total_min_width {
+        return min_widths.to_owned()
+    }
+
+    let mut widths: ~[Au] = if width < total_pref_width {
+        min_widths.iter().zip(pref_widths.iter()).map(|(&min_width, &pref_width)| {
+            min_width + scale_by_ratio(pref_width - min_width,
+                                       width - total_min_width,
+                                       total_pref_width - total_min_width)
+        }).collect()
+    } else if total_pref_width == Au(0) {
+        let share = Au(*width / (pref_widths.len() as i32));
+        pref_widths.iter().map(|_| share).collect()
+    } else {
+        let excess_width = width - total_pref_width;
+        pref_widths.iter().map(|&pref_width| {
+            pref_width + scale_by_ratio(excess_width, pref_width, total_pref_width)
+        }).collect()
+    };
+
+    // Rounding errors go to the last column.
+    let last = widths.len() - 1;
+    widths[last] = widths[last] + width - sum(widths);
+    widths
+}
+
+/// Shares `width` between columns, some of which have specified widths, for the fixed table layout
+/// algorithm (CSS 2.1 § 17.5.2.1). The columns without a specified width share the width left
+/// evenly; if every column has one, they all widen in proportion to them. The columns may add up
+/// to more than `width`.
+pub fn fixed_column_widths(specified_widths: &[Option<Au>], width: Au) -> ~[Au] {
+    if specified_widths.is_empty() {
+        return ~[]
+    }
+    let mut total_specified_width = Au(0);
+    let mut auto_count = 0;
+    for specified_width in specified_widths.iter() {
+        match *specified_width {
+            Some(column_width) => total_specified_width = total_specified_width + column_width,
+            None => auto_count += 1,
+        }
+    }
+    let width_left = geometry::max(Au(0), width - total_specified_width);
+
+    let mut widths: ~[Au] = if auto_count > 0 {
+        let share = Au(*width_left / auto_count);
+        specified_widths.iter().map(|specified_width| {
+            specified_width.unwrap_or(share)
+        }).collect()
+    } else if total_specified_width > Au(0) {
+        specified_widths.iter().map(|specified_width| {
+            let column_width = specified_width.unwrap();
+            column_width + scale_by_ratio(width_left, column_width, total_specified_width)
+        }).collect()
+    } else {
+        let share = Au(*width_left / (specified_widths.len() as i32));
+        specified_widths.iter().map(|_| share).collect()
+    };
+
+    // Rounding errors go to the last column.
+    let total_width = sum(widths);
+    if total_width < width {
+        let last = widths.len() - 1;
+        widths[last] = widths[last] + width - total_width;
+    }
+    widths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BorderOrigin, CellBorder, CollapsedBorder, RowBorder, TableBorder};
+    use super::{auto_column_widths, fixed_column_widths};
+    use gfx::color::rgb;
+    use servo_util::geometry::Au;
+    use style::computed_values::border_style;
+
+    fn px(value: int) -> Au {
+        Au::from_px(value)
+    }
+
+    fn border(style: border_style::T, width: int, origin: BorderOrigin) -> CollapsedBorder {
+        CollapsedBorder {
+            style: style,
+            width: px(width),
+            color: rgb(0, 0, 0),
+            origin: origin,
+        }
+    }
+
+    #[test]
+    fn test_auto_column_widths() {
+        let (min_widths, pref_widths) = ([px(10), px(20)], [px(30), px(60)]);
+        // Too narrow: the columns keep their minimum widths.
+        assert_eq!(auto_column_widths(min_widths, pref_widths, px(20)), ~[px(10), px(20)]);
+        // Between the minimum and preferred widths.
+        assert_eq!(auto_column_widths(min_widths, pref_widths, px(60)), ~[px(20), px(40)]);
+        // Wider than the preferred widths: the excess goes in proportion to them.
+        assert_eq!(auto_column_widths(min_widths, pref_widths, px(120)), ~[px(40), px(80)]);
+        // Empty columns share the width evenly.
+        assert_eq!(auto_column_widths([px(0), px(0)], [px(0), px(0)], px(50)),
+                   ~[px(25), px(25)]);
+    }
+
+    #[test]
+    fn test_fixed_column_widths() {
+        // The columns without a width share what is left.
+        assert_eq!(fixed_column_widths([Some(px(20)), None, None], px(100)),
+                   ~[px(20), px(40), px(40)]);
+        // Specified widths widen in proportion when every column has one.
+        assert_eq!(fixed_column_widths([Some(px(10)), Some(px(30))], px(80)),
+                   ~[px(20), px(60)]);
+        // Specified widths may overflow the table.
+        assert_eq!(fixed_column_widths([Some(px(60)), Some(px(60)), None], px(100)),
+                   ~[px(60), px(60), px(0)]);
+    }
+
+    #[test]
+    fn test_collapsed_border_precedence() {
+        let hidden = border(border_style::hidden, 0, TableBorder);
+        let wide = border(border_style::dotted, 5, TableBorder);
+        let solid = border(border_style::solid, 3, TableBorder);
+        let dashed = border(border_style::dashed, 3, CellBorder);
+        assert!(hidden.beats(&wide));
+        assert!(!wide.beats(&hidden));
+        assert!(wide.beats(&solid));
+        assert!(solid.beats(&dashed));
+        assert!(border(border_style::solid, 3, RowBorder).beats(&solid));
+        assert!(!solid.beats(&solid));
+    }
+}
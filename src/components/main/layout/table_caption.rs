@@ -0,0 +1,79 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! CSS table captions (CSS 2.1 § 17.4).
+//!
+//! A `TableCaptionFlow` is a child of a `TableFlow`. It lays out its contents like a block that
+//! establishes a new block formatting context, as wide as the table box, and the table places it
+//! above or below the table box according to 'caption-side'.
+
+use layout::block::BlockFlow;
+use layout::box::Box;
+use layout::context::LayoutContext;
+use layout::float_context::FloatContext;
+use layout::flow::{Flow, FlowClass, FlowData, TableCaptionFlowClass};
+
+/// A flow for a box with `display: table-caption`.
+pub struct TableCaptionFlow {
+    /// The block flow that lays out the contents of this flow. This must be the first field: it
+    /// starts with the `FlowData` that `flow::base()` returns.
+    block: BlockFlow,
+}
+
+impl TableCaptionFlow {
+    pub fn from_box(base: FlowData, box: Box) -> TableCaptionFlow {
+        TableCaptionFlow {
+            block: BlockFlow::from_box(base, box),
+        }
+    }
+
+    pub fn teardown(&mut self) {
+        self.block.teardown()
+    }
+}
+
+impl Flow for TableCaptionFlow {
+    fn class(&self) -> FlowClass {
+        TableCaptionFlowClass
+    }
+
+    fn as_block<'a>(&'a mut self) -> &'a mut BlockFlow {
+        &mut self.block
+    }
+
+    fn bubble_widths(&mut self, ctx: &mut LayoutContext) {
+        self.block.bubble_widths(ctx)
+    }
+
+    /// The table gave this caption the width of the table box as its containing block.
+    fn assign_widths(&mut self, ctx: &mut LayoutContext) {
+        debug!("assign_widths_table_caption: assigning width for flow {}", self.block.base.id);
+
+        // Like the root flow, a caption lays out its floats by itself.
+        self.block.base.floats_in = FloatContext::new(self.block.base.num_floats);
+        self.block.base.flags.set_inorder(false);
+
+        self.block.assign_widths(ctx)
+    }
+
+    fn assign_height(&mut self, ctx: &mut LayoutContext) {
+        debug!("assign_height_table_caption: assigning height for flow {}", self.block.base.id);
+        let has_floats = self.block.base.num_floats > 0;
+        self.block.assign_height_block_base(ctx, has_floats);
+        self.block.lay_out_absolute_descendants(ctx);
+    }
+
+    /// A caption has been laid out already, and it does not affect the floats around it.
+    fn assign_height_inorder(&mut self, _: &mut LayoutContext) {
+        self.block.base.floats_out = self.block.base.floats_in.clone();
+    }
+
+    fn debug_str(&self) -> ~str {
+        let txt = ~"TableCaptionFlow: ";
+        txt.append(match self.block.box {
+            Some(ref rb) => rb.debug_str(),
+            None => ~"",
+        })
+    }
+}
@@ -0,0 +1,319 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! CSS table cells (CSS 2.1 § 17.5).
+//!
+//! A `TableCellFlow` is a child of a `TableRowFlow`. It lays out its contents like a block that
+//! establishes a new block formatting context, in the width of the columns it spans. The row group
+//! then stretches it to the height of the rows it spans and aligns its contents vertically.
+
+use layout::block::BlockFlow;
+use layout::box::Box;
+use layout::context::LayoutContext;
+use layout::display_list_builder::{DisplayListBuilder, ExtraDisplayListData};
+use layout::float_context::FloatContext;
+use layout::flow::{BlockFlowClass, Flow, FlowClass, FlowData, ImmutableFlowUtils};
+use layout::flow::{InlineFlowClass, TableCellFlowClass};
+use layout::flow;
+use layout::model::specified;
+use layout::table::{CollapsedBorders, inner_half, outer_half};
+
+use geom::{Point2D, Rect, Size2D, SideOffsets2D};
+use gfx::display_list::{BaseDisplayItem, BorderDisplayItem, BorderDisplayItemClass};
+use gfx::display_list::DisplayList;
+use servo_util::geometry::Au;
+use servo_util::geometry;
+use std::cell::Cell;
+use std::num::Zero;
+use style::computed_values::{LPA_Length, border_collapse, empty_cells, visibility};
+
+/// A flow for a box with `display: table-cell`.
+pub struct TableCellFlow {
+    /// The block flow that lays out the contents of this flow. This must be the first field: it
+    /// starts with the `FlowData` that `flow::base()` returns.
+    block: BlockFlow,
+
+    /// The number of columns that this cell spans, from its 'colspan' attribute.
+    colspan: uint,
+
+    /// The number of rows that this cell spans, from its 'rowspan' attribute. Zero spans the rest
+    /// of the row group.
+    rowspan: uint,
+
+    /// The number of rows that this cell actually spans in its row group.
+    spanned_rows: uint,
+
+    /// The row of the table that this cell is in.
+    row: uint,
+
+    /// The first column of the table that this cell is in.
+    column: uint,
+
+    /// The distance from the top of this cell to its baseline: that of its first line box, or the
+    /// bottom of its content box if it has none (CSS 2.1 § 17.5.3).
+    baseline: Au,
+
+    /// The borders around this cell in the collapsing border model.
+    collapsed_borders: Option<CollapsedBorders>,
+}
+
+impl TableCellFlow {
+    pub fn from_box(base: FlowData, box: Box, colspan: uint, rowspan: uint) -> TableCellFlow {
+        TableCellFlow {
+            block: BlockFlow::from_box(base, box),
+            colspan: colspan,
+            rowspan: rowspan,
+            spanned_rows: rowspan,
+            row: 0,
+            column: 0,
+            baseline: Au(0),
+            collapsed_borders: None,
+        }
+    }
+
+    pub fn teardown(&mut self) {
+        self.block.teardown();
+        self.collapsed_borders = None;
+    }
+
+    /// Gives this cell the inner halves of the collapsed borders around it as its own borders.
+    /// Its minimum and preferred widths change with them.
+    pub fn set_collapsed_borders(&mut self, collapsed_borders: CollapsedBorders) {
+        for box in self.block.box.iter() {
+            let old_border = box.border.get();
+            let borders = &collapsed_borders.borders;
+            let border = SideOffsets2D::new(inner_half(borders.top.width),
+                                            outer_half(borders.right.width),
+                                            outer_half(borders.bottom.width),
+                                            inner_half(borders.left.width));
+            box.border.set(border);
+
+            let extra_width = border.left + border.right - old_border.left - old_border.right;
+            self.block.base.min_width = self.block.base.min_width + extra_width;
+            self.block.base.pref_width = self.block.base.pref_width + extra_width;
+        }
+        self.collapsed_borders = Some(collapsed_borders)
+    }
+
+    /// Returns true if the borders and background of this cell are hidden because it has no
+    /// content (CSS 2.1 § 17.6.1.1).
+    fn is_hidden_empty_cell(&mut self) -> bool {
+        let hides = self.block.box.as_ref().map_default(false, |box| {
+            let style = box.style();
+            style.Table.empty_cells == empty_cells::hide &&
+                style.Table.border_collapse == border_collapse::separate
+        });
+        hides && self.block.base.child_iter().all(|kid| kid.is_absolutely_positioned())
+    }
+
+    pub fn build_display_list_table_cell<E:ExtraDisplayListData>(
+                                         &mut self,
+                                         builder: &DisplayListBuilder,
+                                         dirty: &Rect<Au>,
+                                         list: &Cell<DisplayList<E>>)
+                                         -> bool {
+        if self.is_hidden_empty_cell() {
+            return true
+        }
+        if self.block.build_display_list_block(builder, dirty, list) {
+            return true
+        }
+        for collapsed_borders in self.collapsed_borders.iter() {
+            self.paint_collapsed_borders(collapsed_borders, list)
+        }
+        false
+    }
+
+    /// Paints the collapsed borders above and to the left of this cell, and those below and to
+    /// the right of it on the edges of the table, across the grid lines.
+    fn paint_collapsed_borders<E:ExtraDisplayListData>(&self,
+                                                       collapsed_borders: &CollapsedBorders,
+                                                       list: &Cell<DisplayList<E>>) {
+        let box = self.block.box.get_ref();
+        if box.style().Box.visibility != visibility::visible {
+            return
+        }
+        let borders = &collapsed_borders.borders;
+        let border = box.border.get();
+        let outer = SideOffsets2D::new(borders.top.width - border.top,
+                                       borders.right.width - border.right,
+                                       borders.bottom.width - border.bottom,
+                                       borders.left.width - border.left);
+        let bounds = box.position.get().translate(&self.block.base.abs_position);
+        let bounds = Rect(Point2D(bounds.origin.x - outer.left, bounds.origin.y - outer.top),
+                          Size2D(bounds.size.width + outer.left + outer.right,
+                                 bounds.size.height + outer.top + outer.bottom));
+        let right_width = if collapsed_borders.paints_right {
+            borders.right.width
+        } else {
+            Au(0)
+        };
+        let bottom_width = if collapsed_borders.paints_bottom {
+            borders.bottom.width
+        } else {
+            Au(0)
+        };
+
+        do list.with_mut_ref |list| {
+            let border_display_item = ~BorderDisplayItem {
+                base: BaseDisplayItem {
+                    bounds: bounds,
+                    extra: ExtraDisplayListData::new(box),
+                },
+                border: SideOffsets2D::new(borders.top.width,
+                                           right_width,
+                                           bottom_width,
+                                           borders.left.width),
+                color: SideOffsets2D::new(borders.top.color,
+                                          borders.right.color,
+                                          borders.bottom.color,
+                                          borders.left.color),
+                style: SideOffsets2D::new(borders.top.style,
+                                          borders.right.style,
+                                          borders.bottom.style,
+                                          borders.left.style)
+            };
+            list.append_item(BorderDisplayItemClass(border_display_item))
+        }
+    }
+}
+
+impl Flow for TableCellFlow {
+    fn class(&self) -> FlowClass {
+        TableCellFlowClass
+    }
+
+    fn as_block<'a>(&'a mut self) -> &'a mut BlockFlow {
+        &mut self.block
+    }
+
+    fn as_table_cell<'a>(&'a mut self) -> &'a mut TableCellFlow {
+        self
+    }
+
+    /// The minimum and preferred widths of a cell are those of its contents, or its 'width' if
+    /// that is larger, plus its borders and padding. Margins do not apply to cells.
+    fn bubble_widths(&mut self, _: &mut LayoutContext) {
+        let mut min_width = Au(0);
+        let mut pref_width = Au(0);
+        let mut num_floats = 0;
+        for kid in self.block.base.child_iter() {
+            if kid.is_absolutely_positioned() {
+                continue
+            }
+            let kid_base = flow::mut_base(*kid);
+            min_width = geometry::max(min_width, kid_base.min_width);
+            pref_width = geometry::max(pref_width, kid_base.pref_width);
+            num_floats = num_floats + kid_base.num_floats;
+        }
+
+        for box in self.block.box.iter() {
+            let style = box.style();
+            box.compute_borders(style);
+            match style.Box.width {
+                LPA_Length(width) => {
+                    min_width = geometry::max(min_width, width);
+                    pref_width = geometry::max(pref_width, width);
+                }
+                _ => {}
+            }
+            let border = box.border.get();
+            let noncontent_width = border.left + border.right +
+                specified(style.Padding.padding_left, Au(0)) +
+                specified(style.Padding.padding_right, Au(0));
+            min_width = min_width + noncontent_width;
+            pref_width = pref_width + noncontent_width;
+        }
+
+        self.block.base.num_floats = num_floats;
+        self.block.base.min_width = min_width;
+        self.block.base.pref_width = geometry::max(pref_width, min_width);
+    }
+
+    /// The row gave this cell the width of the columns it spans, which is its border box.
+    fn assign_widths(&mut self, _: &mut LayoutContext) {
+        debug!("assign_widths_table_cell: assigning width for flow {}", self.block.base.id);
+
+        let width = self.block.base.position.size.width;
+        let mut x_offset = Au(0);
+        let mut content_width = width;
+        for box in self.block.box.iter() {
+            box.compute_padding(box.style(), width);
+            box.margin.set(Zero::zero());
+
+            // The associated box is the border box of this flow.
+            let position_ref = box.position.mutate();
+            position_ref.ptr.origin.x = Au(0);
+            position_ref.ptr.size.width = width;
+
+            x_offset = box.offset();
+            content_width = width - box.noncontent_width();
+        }
+
+        // Like the root flow, a cell lays out its floats by itself.
+        self.block.base.floats_in = FloatContext::new(self.block.base.num_floats);
+        self.block.base.flags.set_inorder(false);
+
+        self.block.assign_widths_of_children(x_offset, content_width);
+    }
+
+    /// Lays out the contents of this cell. The row group stretches it to the height of its rows
+    /// later, and lays out its absolutely positioned descendants then.
+    fn assign_height(&mut self, ctx: &mut LayoutContext) {
+        debug!("assign_height_table_cell: assigning height for flow {}", self.block.base.id);
+        let has_floats = self.block.base.num_floats > 0;
+        self.block.assign_height_block_base(ctx, has_floats);
+
+        let content_bottom = self.block.box.as_ref().map_default(Au(0), |box| {
+            let position = box.position.get();
+            position.origin.y + position.size.height - box.border.get().bottom -
+                box.padding.get().bottom
+        });
+        self.baseline = first_line_baseline(self as &mut Flow).unwrap_or(content_bottom);
+    }
+
+    /// A cell has been laid out already, and it does not affect the floats around it.
+    fn assign_height_inorder(&mut self, _: &mut LayoutContext) {
+        self.block.base.floats_out = self.block.base.floats_in.clone();
+    }
+
+    fn debug_str(&self) -> ~str {
+        let txt = ~"TableCellFlow: ";
+        txt.append(match self.block.box {
+            Some(ref rb) => rb.debug_str(),
+            None => ~"",
+        })
+    }
+}
+
+/// Returns the baseline of the first line box in the normal flow inside `flow`, relative to the
+/// top of `flow`, if there is one. The line boxes of floats and of absolutely positioned flows do
+/// not count.
+fn first_line_baseline(flow: &mut Flow) -> Option<Au> {
+    for kid in flow::child_iter(flow) {
+        let kid_baseline = match kid.class() {
+            InlineFlowClass => {
+                let lines = &kid.as_inline().lines;
+                if lines.is_empty() {
+                    None
+                } else {
+                    Some(lines[0].bounds.origin.y + lines[0].baseline)
+                }
+            }
+            BlockFlowClass => {
+                if kid.as_block().is_float() {
+                    None
+                } else {
+                    first_line_baseline(*kid)
+                }
+            }
+            _ => None,
+        };
+        match kid_baseline {
+            Some(kid_baseline) => return Some(flow::base(*kid).position.origin.y + kid_baseline),
+            None => {}
+        }
+    }
+    None
+}
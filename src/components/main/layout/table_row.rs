@@ -0,0 +1,197 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! CSS table rows (CSS 2.1 § 17.2).
+//!
+//! A `TableRowFlow` is a child of a `TableRowGroupFlow`, and its children are the cells that start
+//! in the row. It places them in the columns that the table gives it and works out its own height
+//! from the cells that span only this row. The row group places the row and stretches its cells.
+
+use layout::block::{BlockFlow, constrain_height};
+use layout::box::Box;
+use layout::context::LayoutContext;
+use layout::float_context::FloatContext;
+use layout::flow::{Flow, FlowClass, FlowData, ImmutableFlowUtils, TableCellFlowClass};
+use layout::flow::{TableRowFlowClass};
+use layout::flow;
+use layout::model::{MaybeAuto, Specified, Auto};
+use layout::table::total_spacing;
+
+use servo_util::geometry::Au;
+use servo_util::geometry;
+use std::num::Zero;
+use style::computed_values::vertical_align;
+
+/// A flow for a box with `display: table-row`.
+pub struct TableRowFlow {
+    /// The block flow that holds the box of this row. This must be the first field: it starts
+    /// with the `FlowData` that `flow::base()` returns.
+    block: BlockFlow,
+
+    /// The used widths of the columns of the table.
+    column_widths: ~[Au],
+
+    /// The horizontal spacing between the cells, and between the cells and the edges of the table.
+    spacing: Au,
+
+    /// The distance from the top of this row to the baseline that its baseline-aligned cells
+    /// share (CSS 2.1 § 17.5.3).
+    baseline: Au,
+}
+
+impl TableRowFlow {
+    pub fn from_box(base: FlowData, box: Box) -> TableRowFlow {
+        TableRowFlow {
+            block: BlockFlow::from_box(base, box),
+            column_widths: ~[],
+            spacing: Au(0),
+            baseline: Au(0),
+        }
+    }
+
+    pub fn teardown(&mut self) {
+        self.block.teardown();
+        self.column_widths = ~[];
+    }
+}
+
+impl Flow for TableRowFlow {
+    fn class(&self) -> FlowClass {
+        TableRowFlowClass
+    }
+
+    fn as_block<'a>(&'a mut self) -> &'a mut BlockFlow {
+        &mut self.block
+    }
+
+    fn as_table_row<'a>(&'a mut self) -> &'a mut TableRowFlow {
+        self
+    }
+
+    /// The table works out the widths of the columns from the cells. Rows have no borders or
+    /// padding of their own, and the cells lay out their own floats.
+    fn bubble_widths(&mut self, _: &mut LayoutContext) {
+        self.block.base.num_floats = 0;
+    }
+
+    /// Gives each cell the width of the columns that it spans, and the spacing between them.
+    fn assign_widths(&mut self, _: &mut LayoutContext) {
+        debug!("assign_widths_table_row: assigning width for flow {}", self.block.base.id);
+
+        let width = self.block.base.position.size.width;
+        for box in self.block.box.iter() {
+            box.margin.set(Zero::zero());
+            let position_ref = box.position.mutate();
+            position_ref.ptr.origin.x = Au(0);
+            position_ref.ptr.size.width = width;
+        }
+
+        // The left edge of each column.
+        let mut column_x = ~[];
+        let mut x = self.spacing;
+        for &column_width in self.column_widths.iter() {
+            column_x.push(x);
+            x = x + column_width + self.spacing;
+        }
+
+        let flags = self.block.base.flags;
+        for kid in self.block.base.child_iter() {
+            let (x, width) = match kid.class() {
+                TableCellFlowClass => {
+                    let cell = kid.as_table_cell();
+                    let (column, colspan) = (cell.column, cell.colspan);
+                    let mut width = total_spacing(self.spacing, colspan - 1);
+                    for &column_width in self.column_widths.slice(column, column + colspan).iter() {
+                        width = width + column_width
+                    }
+                    (column_x[column], width)
+                }
+                _ => (Au(0), width),
+            };
+            let in_flow = !kid.is_absolutely_positioned();
+
+            let kid_base = flow::mut_base(*kid);
+            kid_base.position.origin.x = x;
+            kid_base.position.size.width = width;
+            kid_base.flags.set_inorder(false);
+            kid_base.floats_in = FloatContext::new(0);
+
+            // Per CSS 2.1 § 16.3.1, text decoration propagates to all children in flow.
+            if in_flow {
+                kid_base.flags.propagate_text_decoration_from_parent(flags)
+            }
+        }
+    }
+
+    /// Makes this row as tall as the tallest cell that spans only this row, with its
+    /// baseline-aligned cells sharing a baseline, or as its 'height' if that is larger. The row
+    /// group takes care of cells that span several rows.
+    fn assign_height(&mut self, _: &mut LayoutContext) {
+        debug!("assign_height_table_row: assigning height for flow {}", self.block.base.id);
+
+        let mut baseline = Au(0);
+        for kid in self.block.base.child_iter() {
+            match kid.class() {
+                TableCellFlowClass => {}
+                _ => continue,
+            }
+            let cell = kid.as_table_cell();
+            if cell.spanned_rows == 1 && is_baseline_aligned(cell.block.box.get_ref()) {
+                baseline = geometry::max(baseline, cell.baseline)
+            }
+        }
+
+        let mut height = Au(0);
+        for kid in self.block.base.child_iter() {
+            match kid.class() {
+                TableCellFlowClass => {}
+                _ => continue,
+            }
+            let cell = kid.as_table_cell();
+            if cell.spanned_rows != 1 {
+                continue
+            }
+            let cell_height = cell.block.base.position.size.height;
+            height = if is_baseline_aligned(cell.block.box.get_ref()) {
+                geometry::max(height, baseline + cell_height - cell.baseline)
+            } else {
+                geometry::max(height, cell_height)
+            }
+        }
+
+        for box in self.block.box.iter() {
+            let style = box.style();
+            match MaybeAuto::from_style(style.Box.height, Au(0)) {
+                Specified(value) => height = geometry::max(height, value),
+                Auto => {}
+            }
+            height = constrain_height(style, height, None);
+        }
+
+        self.baseline = baseline;
+        self.block.base.position.size.height = height;
+    }
+
+    /// A row has been laid out already, and it does not affect the floats around it.
+    fn assign_height_inorder(&mut self, _: &mut LayoutContext) {
+        self.block.base.floats_out = self.block.base.floats_in.clone();
+    }
+
+    fn debug_str(&self) -> ~str {
+        let txt = ~"TableRowFlow: ";
+        txt.append(match self.block.box {
+            Some(ref rb) => rb.debug_str(),
+            None => ~"",
+        })
+    }
+}
+
+/// Returns true if the given cell box has `vertical-align: baseline`, or any other value that
+/// does not apply to cells, which all align cells as 'baseline' does (CSS 2.1 § 17.5.3).
+pub fn is_baseline_aligned(box: &Box) -> bool {
+    match box.vertical_align() {
+        vertical_align::top | vertical_align::middle | vertical_align::bottom => false,
+        _ => true,
+    }
+}
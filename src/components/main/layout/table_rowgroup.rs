@@ -0,0 +1,264 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! CSS table row groups (CSS 2.1 § 17.2).
+//!
+//! A `TableRowGroupFlow` is a child of a `TableFlow`, and its children are its rows. Once the rows
+//! know their heights, the row group makes room for the cells that span several rows, stacks the
+//! rows, and stretches each cell to the height of the rows it spans, aligning its contents
+//! vertically (CSS 2.1 § 17.5.3).
+
+use layout::block::BlockFlow;
+use layout::box::Box;
+use layout::context::LayoutContext;
+use layout::float_context::FloatContext;
+use layout::flow::{Flow, FlowClass, FlowData, ImmutableFlowUtils, MutableFlowUtils};
+use layout::flow::{TableCellFlowClass, TableRowFlowClass, TableRowGroupFlowClass};
+use layout::flow;
+use layout::table::total_spacing;
+use layout::table_row::is_baseline_aligned;
+
+use servo_util::geometry::Au;
+use servo_util::geometry;
+use std::num::Zero;
+use style::computed_values::{border_spacing, vertical_align};
+
+/// A flow for a box with `display: table-row-group`, `table-header-group` or
+/// `table-footer-group`.
+pub struct TableRowGroupFlow {
+    /// The block flow that holds the box of this row group. This must be the first field: it
+    /// starts with the `FlowData` that `flow::base()` returns.
+    block: BlockFlow,
+
+    /// The used widths of the columns of the table.
+    column_widths: ~[Au],
+
+    /// The spacing between the cells, and between the cells and the edges of the table.
+    spacing: border_spacing::T,
+}
+
+impl TableRowGroupFlow {
+    pub fn from_box(base: FlowData, box: Box) -> TableRowGroupFlow {
+        TableRowGroupFlow {
+            block: BlockFlow::from_box(base, box),
+            column_widths: ~[],
+            spacing: border_spacing::T {
+                horizontal: Au(0),
+                vertical: Au(0),
+            },
+        }
+    }
+
+    pub fn teardown(&mut self) {
+        self.block.teardown();
+        self.column_widths = ~[];
+    }
+}
+
+impl Flow for TableRowGroupFlow {
+    fn class(&self) -> FlowClass {
+        TableRowGroupFlowClass
+    }
+
+    fn as_block<'a>(&'a mut self) -> &'a mut BlockFlow {
+        &mut self.block
+    }
+
+    fn as_table_row_group<'a>(&'a mut self) -> &'a mut TableRowGroupFlow {
+        self
+    }
+
+    /// The table works out the widths of the columns from the cells. Row groups have no borders
+    /// or padding of their own, and the cells lay out their own floats.
+    fn bubble_widths(&mut self, _: &mut LayoutContext) {
+        self.block.base.num_floats = 0;
+    }
+
+    /// Gives each row the width of the row group, and the widths of the columns.
+    fn assign_widths(&mut self, _: &mut LayoutContext) {
+        debug!("assign_widths_table_row_group: assigning width for flow {}", self.block.base.id);
+
+        let width = self.block.base.position.size.width;
+        for box in self.block.box.iter() {
+            box.margin.set(Zero::zero());
+            let position_ref = box.position.mutate();
+            position_ref.ptr.origin.x = Au(0);
+            position_ref.ptr.size.width = width;
+        }
+
+        let flags = self.block.base.flags;
+        for kid in self.block.base.child_iter() {
+            match kid.class() {
+                TableRowFlowClass => {
+                    let row = kid.as_table_row();
+                    row.column_widths = self.column_widths.clone();
+                    row.spacing = self.spacing.horizontal;
+                }
+                _ => {}
+            }
+            let in_flow = !kid.is_absolutely_positioned();
+
+            let kid_base = flow::mut_base(*kid);
+            kid_base.position.origin.x = Au(0);
+            kid_base.position.size.width = width;
+            kid_base.flags.set_inorder(false);
+            kid_base.floats_in = FloatContext::new(0);
+
+            // Per CSS 2.1 § 16.3.1, text decoration propagates to all children in flow.
+            if in_flow {
+                kid_base.flags.propagate_text_decoration_from_parent(flags)
+            }
+        }
+    }
+
+    /// Makes the last row that each cell spans tall enough for it, stacks the rows with the
+    /// vertical border spacing between them, and stretches and aligns the cells.
+    fn assign_height(&mut self, ctx: &mut LayoutContext) {
+        debug!("assign_height_table_row_group: assigning height for flow {}",
+               self.block.base.id);
+
+        let spacing = self.spacing.vertical;
+        let mut row_heights = ~[];
+        for kid in self.block.base.child_iter() {
+            match kid.class() {
+                TableRowFlowClass => row_heights.push(flow::base(*kid).position.size.height),
+                _ => {}
+            }
+        }
+
+        // Cells that span several rows grow the last of them if they need to.
+        let mut row_index = 0;
+        for kid in self.block.base.child_iter() {
+            match kid.class() {
+                TableRowFlowClass => {}
+                _ => continue,
+            }
+            for cell in flow::child_iter(*kid) {
+                match cell.class() {
+                    TableCellFlowClass => {}
+                    _ => continue,
+                }
+                let cell = cell.as_table_cell();
+                if cell.spanned_rows < 2 {
+                    continue
+                }
+                let last_row = row_index + cell.spanned_rows - 1;
+                let spanned_height = spanned_height(row_heights, spacing, row_index, last_row);
+                let cell_height = cell.block.base.position.size.height;
+                if cell_height > spanned_height {
+                    row_heights[last_row] = row_heights[last_row] + cell_height - spanned_height
+                }
+            }
+            row_index += 1
+        }
+
+        // Stack the rows, and stretch their cells.
+        let mut cur_y = Au(0);
+        row_index = 0;
+        for kid in self.block.base.child_iter() {
+            match kid.class() {
+                TableRowFlowClass => {}
+                _ => {
+                    // An absolutely positioned child takes no room: it is just given its static
+                    // position here.
+                    flow::mut_base(*kid).position.origin.y = cur_y;
+                    continue
+                }
+            }
+
+            let row = kid.as_table_row();
+            let row_baseline = row.baseline;
+            let row_height = row_heights[row_index];
+            for cell in row.block.base.child_iter() {
+                match cell.class() {
+                    TableCellFlowClass => {}
+                    _ => {
+                        flow::mut_base(*cell).position.origin.y = Au(0);
+                        continue
+                    }
+                }
+                let cell = cell.as_table_cell();
+                let last_row = row_index + cell.spanned_rows - 1;
+                let height = spanned_height(row_heights, spacing, row_index, last_row);
+                let content_height = cell.block.base.position.size.height;
+
+                let extra_height = height - content_height;
+                let offset = match cell.block.box.get_ref().vertical_align() {
+                    vertical_align::top => Au(0),
+                    vertical_align::middle => Au(*extra_height / 2),
+                    vertical_align::bottom => extra_height,
+                    _ => geometry::max(Au(0), row_baseline - cell.baseline),
+                };
+                for cell_kid in cell.block.base.child_iter() {
+                    let cell_kid_base = flow::mut_base(*cell_kid);
+                    cell_kid_base.position.origin.y = cell_kid_base.position.origin.y + offset;
+                }
+                cell.baseline = cell.baseline + offset;
+
+                // The associated box is the border box of the cell.
+                for box in cell.block.box.iter() {
+                    let position_ref = box.position.mutate();
+                    position_ref.ptr.origin.y = Au(0);
+                    position_ref.ptr.size.height = height;
+                }
+                cell.block.base.position.origin.y = Au(0);
+                cell.block.base.position.size.height = height;
+                cell.block.lay_out_absolute_descendants(ctx);
+            }
+
+            // The cells and the row stored their overflow before they were stretched and their
+            // absolutely positioned descendants were laid out.
+            for cell in row.block.base.child_iter() {
+                cell.store_overflow(ctx)
+            }
+
+            for box in row.block.box.iter() {
+                let position_ref = box.position.mutate();
+                position_ref.ptr.origin.y = Au(0);
+                position_ref.ptr.size.height = row_height;
+            }
+            row.block.base.position.origin.y = cur_y;
+            row.block.base.position.size.height = row_height;
+            (row as &mut Flow).store_overflow(ctx);
+
+            cur_y = cur_y + row_height + spacing;
+            row_index += 1
+        }
+        let height = if row_heights.is_empty() {
+            Au(0)
+        } else {
+            cur_y - spacing
+        };
+
+        for box in self.block.box.iter() {
+            let position_ref = box.position.mutate();
+            position_ref.ptr.origin.y = Au(0);
+            position_ref.ptr.size.height = height;
+        }
+        self.block.base.position.size.height = height;
+    }
+
+    /// A row group has been laid out already, and it does not affect the floats around it.
+    fn assign_height_inorder(&mut self, _: &mut LayoutContext) {
+        self.block.base.floats_out = self.block.base.floats_in.clone();
+    }
+
+    fn debug_str(&self) -> ~str {
+        let txt = ~"TableRowGroupFlow: ";
+        txt.append(match self.block.box {
+            Some(ref rb) => rb.debug_str(),
+            None => ~"",
+        })
+    }
+}
+
+/// Returns the height of the rows from `first_row` to `last_row` with the given heights, and the
+/// spacing between them.
+fn spanned_height(row_heights: &[Au], spacing: Au, first_row: uint, last_row: uint) -> Au {
+    let mut height = total_spacing(spacing, last_row - first_row);
+    for &row_height in row_heights.slice(first_row, last_row + 1).iter() {
+        height = height + row_height
+    }
+    height
+}
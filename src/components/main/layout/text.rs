@@ -9,9 +9,13 @@ use layout::context::LayoutContext;
 use layout::flow::Flow;
 
 use gfx::text::text_run::TextRun;
-use gfx::text::util::{CompressWhitespaceNewline, transform_text};
+use gfx::text::util::{CompressionMode, CompressNone, CompressWhitespace};
+use gfx::text::util::{CompressWhitespaceNewline, apply_text_transform, transform_text};
 use std::vec;
+use servo_util::geometry::Au;
 use servo_util::range::Range;
+use style::ComputedValues;
+use style::computed_values::white_space;
 
 /// A stack-allocated object for scanning an inline flow into `TextRun`-containing `TextBox`es.
 struct TextRunScanner {
@@ -108,13 +112,15 @@ impl TextRunScanner {
 
                 let font_style = old_box.font_style();
                 let decoration = old_box.text_decoration();
+                let (letter_spacing, word_spacing) = spacing(old_box);
 
-                // TODO(#115): Use the actual CSS `white-space` property of the relevant style.
-                let compression = CompressWhitespaceNewline;
-
+                let compression = compression_mode(old_box.white_space());
                 let (transformed_text, whitespace) = transform_text(*text,
                                                                     compression,
                                                                     last_whitespace);
+                let transformed_text = apply_text_transform(transformed_text.as_slice(),
+                                                            old_box.style().Text.text_transform,
+                                                            last_whitespace);
                 new_whitespace = whitespace;
 
                 if transformed_text.len() > 0 {
@@ -122,7 +128,10 @@ impl TextRunScanner {
                     // font group fonts. This is probably achieved by creating the font group above
                     // and then letting `FontGroup` decide which `Font` to stick into the text run.
                     let fontgroup = ctx.font_ctx.get_resolved_font_for_style(&font_style);
-                    let run = ~fontgroup.with_borrow(|fg| fg.create_textrun(transformed_text.clone(), decoration));
+                    let run = ~fontgroup.with_borrow(|fg| {
+                        fg.create_textrun(transformed_text.clone(), decoration, letter_spacing,
+                                          word_spacing)
+                    });
 
                     debug!("TextRunScanner: pushing single text box in range: {} ({})",
                            self.clump,
@@ -136,8 +145,11 @@ impl TextRunScanner {
                 }
             },
             (false, true) => {
-                // TODO(#115): Use the actual CSS `white-space` property of the relevant style.
-                let compression = CompressWhitespaceNewline;
+                // Boxes are only coalesced if they have the same 'white-space',
+                // 'text-transform' and spacing, so the first one is representative.
+                let first_box = &in_boxes[self.clump.begin()];
+                let compression = compression_mode(first_box.white_space());
+                let text_transform = first_box.style().Text.text_transform;
 
                 // First, transform/compress text of all the nodes.
                 let mut last_whitespace_in_clump = new_whitespace;
@@ -154,6 +166,8 @@ impl TextRunScanner {
                     let (new_str, new_whitespace) = transform_text(*in_box,
                                                                    compression,
                                                                    last_whitespace_in_clump);
+                    let new_str = apply_text_transform(new_str.as_slice(), text_transform,
+                                                       last_whitespace_in_clump);
                     last_whitespace_in_clump = new_whitespace;
                     new_str
                 };
@@ -180,6 +194,7 @@ impl TextRunScanner {
                 let font_style = in_box.font_style();
                 let fontgroup = ctx.font_ctx.get_resolved_font_for_style(&font_style);
                 let decoration = in_box.text_decoration();
+                let (letter_spacing, word_spacing) = spacing(in_box);
 
                 // TextRuns contain a cycle which is usually resolved by the teardown
                 // sequence. If no clump takes ownership, however, it will leak.
@@ -187,7 +202,8 @@ impl TextRunScanner {
                 let run = if clump.length() != 0 && run_str.len() > 0 {
                     fontgroup.with_borrow( |fg| {
                         fg.fonts[0].with_mut_borrow( |font| {
-                            Some(Arc::new(~TextRun::new(font, run_str.clone(), decoration)))
+                            Some(Arc::new(~TextRun::new(font, run_str.clone(), decoration,
+                                                        letter_spacing, word_spacing)))
                         })
                     })
                 } else {
@@ -238,3 +254,52 @@ impl TextRunScanner {
         new_whitespace
     } // End of `flush_clump_to_list`.
 }
+
+/// Shapes the text of a scanned text box again with another style, as for the boxes on the first
+/// line of a block that has a ::first-line style. Whitespace has already been processed, so only
+/// 'text-transform' is applied again. The new run has the same characters at the same indices
+/// as the range of the old box.
+pub fn restyle_text_box(ctx: &mut LayoutContext, box: &Box, style: Arc<ComputedValues>) -> Box {
+    let text = match box.specific {
+        ScannedTextBox(ref text_box_info) => {
+            let range = &text_box_info.range;
+            text_box_info.run.get().text.get().slice_chars(range.begin(), range.end()).to_owned()
+        }
+        _ => fail!("Expected a scanned text box!"),
+    };
+    let text_transform = style.get().Text.text_transform;
+    let text = if text_transform != box.style().Text.text_transform {
+        apply_text_transform(text, text_transform, true)
+    } else {
+        text
+    };
+
+    let mut new_box = Box::from_style(box.node, style, box.specific.clone());
+    new_box.pseudo_element = box.pseudo_element;
+    let font_style = new_box.font_style();
+    let decoration = new_box.text_decoration();
+    let (letter_spacing, word_spacing) = spacing(&new_box);
+    let fontgroup = ctx.font_ctx.get_resolved_font_for_style(&font_style);
+    let run = ~fontgroup.with_borrow(|fg| {
+        fg.create_textrun(text.clone(), decoration, letter_spacing, word_spacing)
+    });
+    let range = Range::new(0, run.char_len());
+    let new_metrics = run.metrics_for_range(&range);
+    let new_text_box_info = ScannedTextBoxInfo::new(Arc::new(run), range);
+    new_box.transform(new_metrics.bounding_box.size, ScannedTextBox(new_text_box_info))
+}
+
+/// Returns how whitespace is compressed according to 'white-space'.
+fn compression_mode(white_space: white_space::T) -> CompressionMode {
+    match white_space {
+        white_space::normal | white_space::nowrap => CompressWhitespaceNewline,
+        white_space::pre | white_space::pre_wrap => CompressNone,
+        white_space::pre_line => CompressWhitespace,
+    }
+}
+
+/// Returns the used 'letter-spacing' and 'word-spacing' of a box. 'normal' is zero.
+fn spacing(box: &Box) -> (Au, Au) {
+    let text = &box.style().Text;
+    (text.letter_spacing.unwrap_or(Au(0)), text.word_spacing.unwrap_or(Au(0)))
+}
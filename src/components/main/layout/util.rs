@@ -13,7 +13,8 @@ use servo_util::tree::TreeNodeRef;
 use std::cast;
 use std::iter::Enumerate;
 use std::vec::VecIterator;
-use style::{ComputedValues, PropertyDeclaration};
+use style::{ComputedValues, PropertyDeclaration, PseudoElement};
+use style::{Before, After, FirstLine, FirstLetter};
 
 /// A range of nodes.
 pub struct NodeRange {
@@ -126,15 +127,34 @@ pub struct LayoutData {
     /// The results of CSS matching for this node.
     applicable_declarations: ~[Arc<~[PropertyDeclaration]>],
 
+    /// The results of CSS matching for this node as if the nearest link were visited, if it is.
+    visited_applicable_declarations: Option<~[Arc<~[PropertyDeclaration]>]>,
+
     /// The results of CSS styling for this node.
     style: Option<Arc<ComputedValues>>,
 
+    /// The style of this node as if all links were unvisited, if it differs from `style`. This is
+    /// the style that script may observe, so that it can not find out which links are visited.
+    unvisited_style: Option<Arc<ComputedValues>>,
+
     /// Description of how to account for recent style changes.
     restyle_damage: Option<int>,
 
     /// The current results of flow construction for this node. This is either a flow or a
     /// `ConstructionItem`. See comments in `construct.rs` for more details.
     flow_construction_result: ConstructionResult,
+
+    /// Data for the ::before pseudo-element of this node.
+    before: PseudoElementData,
+
+    /// Data for the ::after pseudo-element of this node.
+    after: PseudoElementData,
+
+    /// Data for the ::first-line pseudo-element of this node.
+    first_line: PseudoElementData,
+
+    /// Data for the ::first-letter pseudo-element of this node.
+    first_letter: PseudoElementData,
 }
 
 impl LayoutData {
@@ -142,9 +162,50 @@ impl LayoutData {
     pub fn new() -> LayoutData {
         LayoutData {
             applicable_declarations: ~[],
+            visited_applicable_declarations: None,
             style: None,
+            unvisited_style: None,
             restyle_damage: None,
             flow_construction_result: NoConstructionResult,
+            before: PseudoElementData::new(),
+            after: PseudoElementData::new(),
+            first_line: PseudoElementData::new(),
+            first_letter: PseudoElementData::new(),
+        }
+    }
+
+    /// Returns the data for the given pseudo-element of this node.
+    pub fn pseudo_element<'a>(&'a mut self, pseudo_element: PseudoElement)
+                              -> &'a mut PseudoElementData {
+        match pseudo_element {
+            Before => &mut self.before,
+            After => &mut self.after,
+            FirstLine => &mut self.first_line,
+            FirstLetter => &mut self.first_letter,
+        }
+    }
+}
+
+/// Data that layout associates with the ::before or ::after pseudo-element of a node.
+pub struct PseudoElementData {
+    /// The results of CSS matching for this pseudo-element.
+    applicable_declarations: ~[Arc<~[PropertyDeclaration]>],
+
+    /// The results of CSS styling for this pseudo-element. This is `None` if no rule applies to
+    /// it.
+    style: Option<Arc<ComputedValues>>,
+
+    /// The text generated by the 'content' property, if this pseudo-element generates a box. This
+    /// is always `None` for ::first-line and ::first-letter.
+    content: Option<~str>,
+}
+
+impl PseudoElementData {
+    pub fn new() -> PseudoElementData {
+        PseudoElementData {
+            applicable_declarations: ~[],
+            style: None,
+            content: None,
         }
     }
 }
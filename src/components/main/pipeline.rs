@@ -17,6 +17,7 @@ use script::script_task;
 use servo_msg::constellation_msg::{ConstellationChan, FailureMsg, PipelineId, SubpageId};
 use servo_net::image_cache_task::ImageCacheTask;
 use servo_net::resource_task::ResourceTask;
+use servo_util::history::SharedHistoryStore;
 use servo_util::time::ProfilerChan;
 use std::task;
 
@@ -41,6 +42,7 @@ impl Pipeline {
                        compositor_chan: CompositorChan,
                        image_cache_task: ImageCacheTask,
                        profiler_chan: ProfilerChan,
+                       history: SharedHistoryStore,
                        opts: Opts,
                        script_pipeline: &Pipeline)
                        -> Pipeline {
@@ -61,7 +63,8 @@ impl Pipeline {
                            render_chan.clone(),
                            image_cache_task.clone(),
                            opts.clone(),
-                           profiler_chan);
+                           profiler_chan,
+                           history);
 
         let new_layout_info = NewLayoutInfo {
             old_id: script_pipeline.id.clone(),
@@ -85,6 +88,7 @@ impl Pipeline {
                   image_cache_task: ImageCacheTask,
                   resource_task: ResourceTask,
                   profiler_chan: ProfilerChan,
+                  history: SharedHistoryStore,
                   opts: Opts)
                   -> Pipeline {
         let (script_port, script_chan) = special_stream!(ScriptChan);
@@ -111,7 +115,8 @@ impl Pipeline {
                     layout_port,
                     constellation_chan,
                     image_cache_task,
-                    profiler_chan
+                    profiler_chan,
+                    history
                 ], {
             ScriptTask::create(id,
                                compositor_chan.clone(),
@@ -136,7 +141,8 @@ impl Pipeline {
                                render_chan.clone(),
                                image_cache_task,
                                opts.clone(),
-                               profiler_chan);
+                               profiler_chan,
+                               history);
         });
 
         spawn_with!(task::task(), [failure_chan], {
@@ -8,6 +8,7 @@ use windowing::{ApplicationMethods, WindowEvent, WindowMethods};
 use windowing::{IdleWindowEvent, ResizeWindowEvent, LoadUrlWindowEvent, MouseWindowEventClass};
 use windowing::{ScrollWindowEvent, ZoomWindowEvent, NavigationWindowEvent, FinishedWindowEvent};
 use windowing::{QuitWindowEvent, MouseWindowClickEvent, MouseWindowMouseDownEvent, MouseWindowMouseUpEvent};
+use windowing::MouseWindowMoveEvent;
 use windowing::{Forward, Back};
 
 use alert::{Alert, AlertMethods};
@@ -109,6 +110,14 @@ impl WindowMethods<Application> for Window {
                 local_window().handle_mouse(button, action, x as i32, y as i32);
             }
         }
+        do window.glfw_window.set_cursor_pos_callback |win, x, y| {
+            //handle hidpi displays, since GLFW returns non-hi-def coordinates.
+            let (backing_size, _) = win.get_framebuffer_size();
+            let (window_size, _) = win.get_size();
+            let hidpi = (backing_size as f32) / (window_size as f32);
+            let point = Point2D(x as f32 * hidpi, y as f32 * hidpi);
+            local_window().event_queue.push(MouseWindowEventClass(MouseWindowMoveEvent(point)))
+        }
         do window.glfw_window.set_scroll_callback |win, x_offset, y_offset| {
             let dx = (x_offset as f32) * 30.0;
             let dy = (y_offset as f32) * 30.0;
@@ -44,6 +44,16 @@ impl LayerBufferSet {
     }
 }
 
+/// The layers that the renderer paints a page into.
+#[deriving(Eq)]
+pub enum LayerKind {
+    /// The layer of the document, which scrolls.
+    DocumentLayer,
+    /// The layer of the fixed positioned boxes, which stays in place in the viewport while the
+    /// document scrolls under it.
+    FixedLayer,
+}
+
 /// The status of the renderer.
 #[deriving(Eq)]
 pub enum RenderState {
@@ -79,9 +89,12 @@ pub trait RenderListener {
     fn get_graphics_metadata(&self) -> Option<NativeGraphicsMetadata>;
     fn new_layer(&self, PipelineId, Size2D<uint>);
     fn set_layer_page_size_and_color(&self, PipelineId, Size2D<uint>, Epoch, Color);
+    /// Sets the size of the fixed layer of a page, or removes it if the page has no fixed
+    /// positioned boxes.
+    fn set_fixed_layer_size(&self, PipelineId, Option<Size2D<uint>>, Epoch);
     fn set_layer_clip_rect(&self, PipelineId, Rect<uint>);
     fn delete_layer(&self, PipelineId);
-    fn paint(&self, id: PipelineId, layer_buffer_set: ~LayerBufferSet, Epoch);
+    fn paint(&self, id: PipelineId, kind: LayerKind, layer_buffer_set: ~LayerBufferSet, Epoch);
     fn set_render_state(&self, render_state: RenderState);
 }
 
@@ -812,7 +812,8 @@ pub enum Error {
     InvalidCharacter,
     NotSupported,
     InvalidState,
-    NamespaceError
+    NamespaceError,
+    NoModificationAllowed,
 }
 
 pub type Fallible<T> = Result<T, Error>;
@@ -0,0 +1,103 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::CSSRuleBinding;
+use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::cssrulelist::CSSRuleList;
+use dom::cssstyledeclaration::{CSSStyleDeclaration, StyleRuleOwner};
+use dom::cssstylesheet::CSSStyleSheet;
+use dom::window::Window;
+use style::{CSSStyleRule, CSSMediaRule, CSSImportRule, CSSPageRule};
+use style;
+
+pub struct CSSRule {
+    reflector_: Reflector,
+    window: @mut Window,
+    rule: style::CSSRule,
+    /// The objects for the declarations, the imported style sheet or the nested rules of this
+    /// rule, created when they are first asked for.
+    style: Option<@mut CSSStyleDeclaration>,
+    stylesheet: Option<@mut CSSStyleSheet>,
+    rules: Option<@mut CSSRuleList>,
+}
+
+impl CSSRule {
+    pub fn new_inherited(window: @mut Window, rule: style::CSSRule) -> CSSRule {
+        CSSRule {
+            reflector_: Reflector::new(),
+            window: window,
+            rule: rule,
+            style: None,
+            stylesheet: None,
+            rules: None,
+        }
+    }
+
+    pub fn new(window: @mut Window, rule: style::CSSRule) -> @mut CSSRule {
+        reflect_dom_object(@mut CSSRule::new_inherited(window, rule),
+                           window, CSSRuleBinding::Wrap)
+    }
+
+    pub fn Type(&self) -> u16 {
+        match self.rule {
+            CSSStyleRule(_) => 1,
+            CSSImportRule(_) => 3,
+            CSSMediaRule(_) => 4,
+            CSSPageRule(_) => 6,
+        }
+    }
+
+    pub fn GetStyle(&mut self) -> Option<@mut CSSStyleDeclaration> {
+        let window = self.window;
+        match self.rule {
+            CSSStyleRule(ref rule) => {
+                if self.style.is_none() {
+                    self.style = Some(CSSStyleDeclaration::new(
+                        window, StyleRuleOwner(rule.declarations.clone())));
+                }
+                self.style
+            }
+            CSSImportRule(_) | CSSMediaRule(_) | CSSPageRule(_) => None,
+        }
+    }
+
+    /// The imported style sheet, if it could be loaded.
+    pub fn GetStyleSheet(&mut self) -> Option<@mut CSSStyleSheet> {
+        let window = self.window;
+        match self.rule {
+            CSSImportRule(ref rule) => {
+                if self.stylesheet.is_none() {
+                    self.stylesheet = rule.stylesheet.as_ref().map(|stylesheet| {
+                        CSSStyleSheet::new(window, (**stylesheet).clone())
+                    });
+                }
+                self.stylesheet
+            }
+            CSSStyleRule(_) | CSSMediaRule(_) | CSSPageRule(_) => None,
+        }
+    }
+
+    pub fn GetCssRules(&mut self) -> Option<@mut CSSRuleList> {
+        let window = self.window;
+        match self.rule {
+            CSSMediaRule(ref rule) => {
+                if self.rules.is_none() {
+                    self.rules = Some(CSSRuleList::new(window, rule.rules.clone()));
+                }
+                self.rules
+            }
+            CSSStyleRule(_) | CSSImportRule(_) | CSSPageRule(_) => None,
+        }
+    }
+}
+
+impl Reflectable for CSSRule {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        &self.reflector_
+    }
+
+    fn mut_reflector<'a>(&'a mut self) -> &'a mut Reflector {
+        &mut self.reflector_
+    }
+}
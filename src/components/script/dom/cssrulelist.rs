@@ -0,0 +1,64 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::CSSRuleListBinding;
+use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::cssrule::CSSRule;
+use dom::window::Window;
+use style;
+
+use std::vec;
+
+pub struct CSSRuleList {
+    reflector_: Reflector,
+    window: @mut Window,
+    rules: ~[style::CSSRule],
+    /// The objects for the rules, created when they are first asked for.
+    items: ~[Option<@mut CSSRule>],
+}
+
+impl CSSRuleList {
+    pub fn new_inherited(window: @mut Window, rules: ~[style::CSSRule]) -> CSSRuleList {
+        CSSRuleList {
+            reflector_: Reflector::new(),
+            items: vec::from_elem(rules.len(), None),
+            rules: rules,
+            window: window,
+        }
+    }
+
+    pub fn new(window: @mut Window, rules: ~[style::CSSRule]) -> @mut CSSRuleList {
+        reflect_dom_object(@mut CSSRuleList::new_inherited(window, rules),
+                           window, CSSRuleListBinding::Wrap)
+    }
+
+    pub fn Length(&self) -> u32 {
+        self.rules.len() as u32
+    }
+
+    pub fn Item(&mut self, index: u32) -> Option<@mut CSSRule> {
+        if index >= self.rules.len() as u32 {
+            return None
+        }
+        if self.items[index].is_none() {
+            self.items[index] = Some(CSSRule::new(self.window, self.rules[index].clone()));
+        }
+        self.items[index]
+    }
+
+    pub fn IndexedGetter(&mut self, index: u32, found: &mut bool) -> Option<@mut CSSRule> {
+        *found = index < self.rules.len() as u32;
+        self.Item(index)
+    }
+}
+
+impl Reflectable for CSSRuleList {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        &self.reflector_
+    }
+
+    fn mut_reflector<'a>(&'a mut self) -> &'a mut Reflector {
+        &mut self.reflector_
+    }
+}
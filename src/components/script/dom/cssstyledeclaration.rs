@@ -0,0 +1,188 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::CSSStyleDeclarationBinding;
+use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::bindings::utils::{DOMString, ErrorResult, Fallible, NoModificationAllowed};
+use dom::node::{AbstractNode, ScriptView};
+use dom::window::Window;
+use style::{PropertyDeclarationBlock, ToCssText};
+
+use std::ascii::StrAsciiExt;
+
+/// Where the declarations of a `CSSStyleDeclaration` come from.
+pub enum DeclarationsOwner {
+    /// The style attribute of an element. Changes are made to the element.
+    StyleAttributeOwner(AbstractNode<ScriptView>),
+    /// The declarations of a style rule.
+    ///
+    /// FIXME: Style sheets cannot be changed from script yet, so these are read-only.
+    StyleRuleOwner(PropertyDeclarationBlock),
+    /// The names and values of all longhands of an element, from `getComputedStyle()`.
+    ///
+    /// FIXME: These are the values at the time of the call, but they should be live.
+    ComputedValuesOwner(~[(~str, ~str)]),
+}
+
+pub struct CSSStyleDeclaration {
+    reflector_: Reflector,
+    window: @mut Window,
+    owner: DeclarationsOwner,
+}
+
+impl CSSStyleDeclaration {
+    pub fn new_inherited(window: @mut Window, owner: DeclarationsOwner) -> CSSStyleDeclaration {
+        CSSStyleDeclaration {
+            reflector_: Reflector::new(),
+            window: window,
+            owner: owner,
+        }
+    }
+
+    pub fn new(window: @mut Window, owner: DeclarationsOwner) -> @mut CSSStyleDeclaration {
+        reflect_dom_object(@mut CSSStyleDeclaration::new_inherited(window, owner),
+                           window, CSSStyleDeclarationBinding::Wrap)
+    }
+
+    /// The declared longhands, with their values and whether they are important.
+    fn declarations(&self) -> ~[(~str, ~str, bool)] {
+        fn from_block(block: &PropertyDeclarationBlock) -> ~[(~str, ~str, bool)] {
+            block.declarations().move_iter().map(|(name, value, important)| {
+                (name.to_owned(), value, important)
+            }).collect()
+        }
+
+        match self.owner {
+            StyleAttributeOwner(element) => {
+                do element.with_imm_element |element| {
+                    match element.style_attribute {
+                        None => ~[],
+                        Some(ref block) => from_block(block),
+                    }
+                }
+            }
+            StyleRuleOwner(ref block) => from_block(block),
+            ComputedValuesOwner(ref values) => {
+                values.iter().map(|&(ref name, ref value)| {
+                    (name.clone(), value.clone(), false)
+                }).collect()
+            }
+        }
+    }
+
+    /// The value of the given longhand or shorthand, if declared, and whether it is important.
+    fn find(&self, property: &str) -> Option<(~str, bool)> {
+        match self.owner {
+            StyleAttributeOwner(element) => {
+                do element.with_imm_element |element| {
+                    element.style_attribute.as_ref().and_then(|block| {
+                        block.property_value(property)
+                    })
+                }
+            }
+            StyleRuleOwner(ref block) => block.property_value(property),
+            ComputedValuesOwner(ref values) => {
+                let property = property.to_ascii_lower();
+                values.iter().find(|&&(ref name, _)| *name == property)
+                    .map(|&(_, ref value)| (value.clone(), false))
+            }
+        }
+    }
+
+    /// The declarations, with longhands collapsed into shorthands where possible.
+    /// Computed values have no textual representation.
+    pub fn CssText(&self) -> DOMString {
+        match self.owner {
+            StyleAttributeOwner(element) => {
+                do element.with_imm_element |element| {
+                    match element.style_attribute {
+                        None => ~"",
+                        Some(ref block) => block.to_css_text(),
+                    }
+                }
+            }
+            StyleRuleOwner(ref block) => block.to_css_text(),
+            ComputedValuesOwner(_) => ~"",
+        }
+    }
+
+    pub fn Length(&self) -> u32 {
+        self.declarations().len() as u32
+    }
+
+    pub fn Item(&self, index: u32) -> DOMString {
+        let mut found = false;
+        self.IndexedGetter(index, &mut found)
+    }
+
+    pub fn IndexedGetter(&self, index: u32, found: &mut bool) -> DOMString {
+        let declarations = self.declarations();
+        *found = index < declarations.len() as u32;
+        if *found {
+            let (ref name, _, _) = declarations[index];
+            name.clone()
+        } else {
+            ~""
+        }
+    }
+
+    pub fn GetPropertyValue(&self, property: DOMString) -> DOMString {
+        match self.find(property.as_slice()) {
+            Some((value, _)) => value,
+            None => ~"",
+        }
+    }
+
+    pub fn GetPropertyPriority(&self, property: DOMString) -> DOMString {
+        match self.find(property.as_slice()) {
+            Some((_, true)) => ~"important",
+            _ => ~"",
+        }
+    }
+
+    pub fn SetProperty(&mut self, property: DOMString, value: DOMString, priority: DOMString)
+                       -> ErrorResult {
+        let element = match self.owner {
+            StyleAttributeOwner(element) => element,
+            StyleRuleOwner(_) | ComputedValuesOwner(_) => return Err(NoModificationAllowed),
+        };
+        if value.is_empty() {
+            return self.RemoveProperty(property).map(|_| ())
+        }
+        let important = if priority.is_empty() {
+            false
+        } else if priority.eq_ignore_ascii_case("important") {
+            true
+        } else {
+            return Ok(())
+        };
+        // Unsupported properties and invalid values are ignored.
+        do element.as_mut_element |elem| {
+            elem.set_style_property(element, property.as_slice(), value.as_slice(), important)
+        }
+        Ok(())
+    }
+
+    pub fn RemoveProperty(&mut self, property: DOMString) -> Fallible<DOMString> {
+        let element = match self.owner {
+            StyleAttributeOwner(element) => element,
+            StyleRuleOwner(_) | ComputedValuesOwner(_) => return Err(NoModificationAllowed),
+        };
+        let value = self.GetPropertyValue(property.clone());
+        do element.as_mut_element |elem| {
+            elem.remove_style_property(element, property.as_slice())
+        }
+        Ok(value)
+    }
+}
+
+impl Reflectable for CSSStyleDeclaration {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        &self.reflector_
+    }
+
+    fn mut_reflector<'a>(&'a mut self) -> &'a mut Reflector {
+        &mut self.reflector_
+    }
+}
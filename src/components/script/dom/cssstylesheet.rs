@@ -0,0 +1,58 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::CSSStyleSheetBinding;
+use dom::bindings::utils::{Reflectable, Reflector, reflect_dom_object};
+use dom::bindings::utils::DOMString;
+use dom::cssrulelist::CSSRuleList;
+use dom::window::Window;
+use style::Stylesheet;
+
+/// A style sheet of the document, as script sees it. Layout has its own copy.
+///
+/// FIXME: Style sheets cannot be changed from script yet.
+pub struct CSSStyleSheet {
+    reflector_: Reflector,
+    window: @mut Window,
+    stylesheet: Stylesheet,
+    /// The object for the rules, created when it is first asked for.
+    rules: Option<@mut CSSRuleList>,
+}
+
+impl CSSStyleSheet {
+    pub fn new_inherited(window: @mut Window, stylesheet: Stylesheet) -> CSSStyleSheet {
+        CSSStyleSheet {
+            reflector_: Reflector::new(),
+            window: window,
+            stylesheet: stylesheet,
+            rules: None,
+        }
+    }
+
+    pub fn new(window: @mut Window, stylesheet: Stylesheet) -> @mut CSSStyleSheet {
+        reflect_dom_object(@mut CSSStyleSheet::new_inherited(window, stylesheet),
+                           window, CSSStyleSheetBinding::Wrap)
+    }
+
+    pub fn Type(&self) -> DOMString {
+        ~"text/css"
+    }
+
+    pub fn CssRules(&mut self) -> @mut CSSRuleList {
+        if self.rules.is_none() {
+            self.rules = Some(CSSRuleList::new(self.window, self.stylesheet.rules.clone()));
+        }
+        self.rules.unwrap()
+    }
+}
+
+impl Reflectable for CSSStyleSheet {
+    fn reflector<'a>(&'a self) -> &'a Reflector {
+        &self.reflector_
+    }
+
+    fn mut_reflector<'a>(&'a mut self) -> &'a mut Reflector {
+        &mut self.reflector_
+    }
+}
@@ -5,9 +5,11 @@
 use dom::comment::Comment;
 use dom::bindings::codegen::DocumentBinding;
 use dom::bindings::utils::{Reflectable, Reflector, Traceable, reflect_dom_object};
+use dom::bindings::utils::trace_reflector;
 use dom::bindings::utils::{ErrorResult, Fallible, NotSupported, InvalidCharacter};
 use dom::bindings::utils::DOMString;
 use dom::bindings::utils::{xml_name_type, InvalidXMLName};
+use dom::cssstylesheet::CSSStyleSheet;
 use dom::documentfragment::DocumentFragment;
 use dom::element::{Element};
 use dom::element::{HTMLHeadElementTypeId, HTMLTitleElementTypeId};
@@ -16,14 +18,17 @@ use dom::htmlcollection::HTMLCollection;
 use dom::htmldocument::HTMLDocument;
 use dom::mouseevent::MouseEvent;
 use dom::node::{AbstractNode, ScriptView, Node, ElementNodeTypeId, DocumentNodeTypeId};
+use dom::stylesheetlist::StyleSheetList;
 use dom::text::Text;
 use dom::uievent::UIEvent;
 use dom::window::Window;
 use dom::htmltitleelement::HTMLTitleElement;
 use html::hubbub_html_parser::build_element_from_tag;
 use js::jsapi::{JSObject, JSContext, JSTracer};
+use servo_util::atom::Atom;
 use servo_util::tree::{TreeNodeRef, ElementLike};
 use layout_interface::{DocumentDamageLevel, ContentChangedDocumentDamage};
+use style::{CSSError, QuirksMode, NoQuirks, Stylesheet};
 
 use std::hashmap::HashMap;
 
@@ -93,7 +98,13 @@ pub struct Document {
     window: @mut Window,
     doctype: DocumentType,
     title: ~str,
-    idmap: HashMap<DOMString, AbstractNode<ScriptView>>
+    idmap: HashMap<DOMString, AbstractNode<ScriptView>>,
+    /// The compatibility mode, set by the HTML parser from the doctype.
+    quirks_mode: QuirksMode,
+    /// The errors found while parsing the style sheets and style attributes of the document.
+    css_errors: ~[CSSError],
+    /// The style sheets of the document, in the order they were found by the parser.
+    stylesheets: ~[@mut CSSStyleSheet],
 }
 
 impl Document {
@@ -125,7 +136,10 @@ impl Document {
             window: window,
             doctype: doctype,
             title: ~"",
-            idmap: HashMap::new()
+            idmap: HashMap::new(),
+            quirks_mode: NoQuirks,
+            css_errors: ~[],
+            stylesheets: ~[],
         }
     }
 
@@ -173,7 +187,7 @@ impl Document {
     }
 
     pub fn GetElementsByTagName(&self, tag: DOMString) -> @mut HTMLCollection {
-        self.createHTMLCollection(|elem| eq_slice(elem.tag_name, tag))
+        self.createHTMLCollection(|elem| eq_slice(elem.tag_name.as_slice(), tag))
     }
 
     pub fn GetElementsByTagNameNS(&self, _ns: Option<DOMString>, _tag: DOMString) -> @mut HTMLCollection {
@@ -184,6 +198,10 @@ impl Document {
         HTMLCollection::new(self.window, ~[])
     }
 
+    pub fn StyleSheets(&self) -> @mut StyleSheetList {
+        StyleSheetList::new(self.window, self.stylesheets.clone())
+    }
+
     pub fn GetElementById(&self, id: DOMString) -> Option<AbstractNode<ScriptView>> {
         // TODO: "in tree order, within the context object's tree"
         // http://dom.spec.whatwg.org/#dom-document-getelementbyid.
@@ -199,7 +217,7 @@ impl Document {
             return Err(InvalidCharacter);
         }
         let local_name = local_name.to_ascii_lower();
-        Ok(build_element_from_tag(local_name, abstract_self))
+        Ok(build_element_from_tag(local_name.as_slice(), abstract_self))
     }
 
     pub fn CreateDocumentFragment(&self, abstract_self: AbstractDocument) -> AbstractNode<ScriptView> {
@@ -282,7 +300,7 @@ impl Document {
                                 break;
                             }
                             if !has_title {
-                                let new_title = HTMLTitleElement::new(~"title", abstract_self);
+                                let new_title = HTMLTitleElement::new(Atom::from_slice("title"), abstract_self);
                                 new_title.AppendChild(self.CreateTextNode(abstract_self, title.clone()));
                                 node.AppendChild(new_title);
                             }
@@ -331,6 +349,24 @@ impl Document {
         self.window.wait_until_safe_to_modify_dom();
     }
 
+    /// Logs CSS errors to stderr, and keeps them so that they can be read back with
+    /// `css_errors`.
+    pub fn add_css_errors(&mut self, errors: ~[CSSError]) {
+        for error in errors.iter() {
+            error!("{:s}", error.to_str());
+        }
+        self.css_errors.push_all_move(errors);
+    }
+
+    pub fn css_errors<'a>(&'a self) -> &'a [CSSError] {
+        self.css_errors.as_slice()
+    }
+
+    /// Makes a style sheet, which is also given to layout, visible to script.
+    pub fn add_stylesheet(&mut self, stylesheet: Stylesheet) {
+        self.stylesheets.push(CSSStyleSheet::new(self.window, stylesheet));
+    }
+
     pub fn register_nodes_with_id(&mut self, root: &AbstractNode<ScriptView>) {
         foreach_ided_elements(root, |id: &DOMString, abstract_node: &AbstractNode<ScriptView>| {
             // TODO: "in tree order, within the context object's tree"
@@ -393,5 +429,8 @@ impl Traceable for Document {
     #[fixed_stack_segment]
     fn trace(&self, tracer: *mut JSTracer) {
         self.node.trace(tracer);
+        for stylesheet in self.stylesheets.iter() {
+            trace_reflector(tracer, "stylesheet", stylesheet.reflector());
+        }
     }
 }
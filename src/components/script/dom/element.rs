@@ -11,6 +11,7 @@ use dom::bindings::utils::{InvalidCharacter, QName, Name, InvalidXMLName, xml_na
 use dom::htmlcollection::HTMLCollection;
 use dom::clientrect::ClientRect;
 use dom::clientrectlist::ClientRectList;
+use dom::cssstyledeclaration::{CSSStyleDeclaration, StyleAttributeOwner};
 use dom::document::AbstractDocument;
 use dom::node::{ElementNodeTypeId, Node, ScriptView, AbstractNode};
 use dom::attr:: Attr;
@@ -21,8 +22,12 @@ use layout_interface::{ContentBoxQuery, ContentBoxResponse, ContentBoxesQuery};
 use layout_interface::{ContentBoxesResponse, ContentChangedDocumentDamage};
 use layout_interface::{MatchSelectorsDocumentDamage};
 use style;
+use style::ToCssText;
+use servo_util::atom::Atom;
+use servo_util::url::make_url;
 use servo_util::tree::{TreeNodeRef, ElementLike};
 
+use extra::url::Url;
 use std::comm;
 use std::hashmap::HashMap;
 use std::str::{eq, eq_slice};
@@ -30,12 +35,24 @@ use std::ascii::StrAsciiExt;
 
 pub struct Element {
     node: Node<ScriptView>,
-    tag_name: ~str,     // TODO: This should be an atom, not a ~str.
+    tag_name: Atom,
     namespace: Namespace,
+    /// The value of the id attribute, interned for selector matching.
+    id: Option<Atom>,
+    /// The classes in the class attribute, interned for selector matching.
+    classes: ~[Atom],
     attrs: HashMap<~str, ~[@mut Attr]>,
     attrs_insert_order: ~[(~str, Namespace)], // store an order of attributes.
     style_attribute: Option<style::PropertyDeclarationBlock>,
-    attr_list: Option<@mut AttrList>
+    /// The object for the style attribute, created when it is first asked for.
+    style_declaration: Option<@mut CSSStyleDeclaration>,
+    attr_list: Option<@mut AttrList>,
+    /// Whether the pointer is over this element or one of its descendants (:hover).
+    hover: bool,
+    /// Whether this element or one of its descendants is being activated (:active).
+    active: bool,
+    /// Whether this element has the focus (:focus).
+    focus: bool,
 }
 
 impl Reflectable for Element {
@@ -126,14 +143,22 @@ pub enum ElementTypeId {
 //
 
 impl ElementLike for Element {
-    fn get_local_name<'a>(&'a self) -> &'a str {
-        self.tag_name.as_slice()
+    fn get_local_name(&self) -> Atom {
+        self.tag_name
     }
 
     fn get_namespace_url<'a>(&'a self) -> &'a str {
         self.namespace.to_str().unwrap_or("")
     }
 
+    fn get_id(&self) -> Option<Atom> {
+        self.id
+    }
+
+    fn get_classes<'a>(&'a self) -> &'a [Atom] {
+        self.classes.as_slice()
+    }
+
     fn get_attr(&self, ns_url: Option<~str>, name: &str) -> Option<~str> {
         self.get_attribute(ns_url, name).map(|attr| attr.value.clone())
     }
@@ -149,18 +174,76 @@ impl ElementLike for Element {
             _ => None,
         }
     }
+
+    fn is_empty(&self) -> bool {
+        do self.node.children().all |kid| {
+            kid.is_comment() ||
+                (kid.is_text() && kid.with_imm_text(|text| text.element.data.is_empty()))
+        }
+    }
+
+    fn is_hovered(&self) -> bool {
+        self.hover
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn is_focused(&self) -> bool {
+        self.focus
+    }
+
+    fn get_disabled_state(&self) -> Option<bool> {
+        // FIXME: Descendants of a disabled fieldset and options in a disabled optgroup are
+        // disabled too.
+        match self.node.type_id {
+            // http://www.whatwg.org/specs/web-apps/current-work/multipage/selectors.html#selector-disabled
+            ElementNodeTypeId(HTMLButtonElementTypeId) |
+            ElementNodeTypeId(HTMLInputElementTypeId) |
+            ElementNodeTypeId(HTMLSelectElementTypeId) |
+            ElementNodeTypeId(HTMLTextAreaElementTypeId) |
+            ElementNodeTypeId(HTMLOptGroupElementTypeId) |
+            ElementNodeTypeId(HTMLOptionElementTypeId) |
+            ElementNodeTypeId(HTMLFieldSetElementTypeId)
+            => Some(self.get_attr(None, "disabled").is_some()),
+            _ => None,
+        }
+    }
+
+    fn is_checked(&self) -> bool {
+        // FIXME: This is the default checkedness; changes made by the user are not tracked.
+        match self.node.type_id {
+            // http://www.whatwg.org/specs/web-apps/current-work/multipage/selectors.html#selector-checked
+            ElementNodeTypeId(HTMLInputElementTypeId) => {
+                match self.get_attr(None, "type").map(|type_| type_.to_ascii_lower()) {
+                    Some(ref type_) if "checkbox" == *type_ || "radio" == *type_
+                    => self.get_attr(None, "checked").is_some(),
+                    _ => false,
+                }
+            }
+            ElementNodeTypeId(HTMLOptionElementTypeId) => self.get_attr(None, "selected").is_some(),
+            _ => false,
+        }
+    }
 }
 
 impl<'self> Element {
-    pub fn new_inherited(type_id: ElementTypeId, tag_name: ~str, namespace: Namespace, document: AbstractDocument) -> Element {
+    pub fn new_inherited(type_id: ElementTypeId, tag_name: Atom, namespace: Namespace, document: AbstractDocument) -> Element {
         Element {
             node: Node::new_inherited(ElementNodeTypeId(type_id), document),
             tag_name: tag_name,
             namespace: namespace,
+            id: None,
+            classes: ~[],
             attrs: HashMap::new(),
             attrs_insert_order: ~[],
             attr_list: None,
             style_attribute: None,
+            style_declaration: None,
+            hover: false,
+            active: false,
+            focus: false,
         }
     }
 
@@ -267,13 +350,25 @@ impl<'self> Element {
 
         match local_name.as_slice() {
             "style" => {
-                self.style_attribute = Some(style::parse_style_attribute(value))
+                let doc = self.node.owner_doc();
+                let base_url = self.style_base_url();
+                let mut errors = style::ErrorCollector::new();
+                self.style_attribute = Some(style::parse_style_attribute(value, &base_url,
+                                                                         &mut errors));
+                doc.mut_document().add_css_errors(errors.errors);
             }
             "id" => {
+                self.id = Some(self.id_or_class_atom(value.as_slice()));
                 let doc = self.node.owner_doc();
                 let doc = doc.mut_document();
                 doc.update_idmap(abstract_self, value.clone(), old_value);
             }
+            "class" => {
+                static WHITESPACE: &'static [char] = &'static [' ', '\t', '\n', '\r', '\x0C'];
+                let classes = value.split_iter(WHITESPACE).filter(|class| !class.is_empty())
+                                   .map(|class| self.id_or_class_atom(class)).collect();
+                self.classes = classes;
+            }
             _ => ()
         }
 
@@ -305,8 +400,83 @@ impl<'self> Element {
 }
 
 impl Element {
+    /// Interns an id or class for selector matching. Ids and classes are matched
+    /// case-insensitively in quirks mode, so they are lowercased once here rather than on every
+    /// lookup. The parser sets the quirks mode before creating any element.
+    fn id_or_class_atom(&self, name: &str) -> Atom {
+        let doc = self.node.owner_doc();
+        if doc.document().quirks_mode.has_case_insensitive_ids_and_classes() {
+            Atom::from_slice(name.to_ascii_lower())
+        } else {
+            Atom::from_slice(name)
+        }
+    }
+
+    /// Returns the URL that relative URLs in the style attribute resolve against. A document
+    /// that has not loaded a URL yet has no base URL, so `about:blank` stands in for it.
+    fn style_base_url(&self) -> Url {
+        let doc = self.node.owner_doc();
+        match doc.document().window.page.url {
+            Some((ref url, _)) => url.clone(),
+            None => make_url(~"about:blank", None),
+        }
+    }
+
+    /// The declarations of the style attribute, as `element.style`.
+    pub fn style_declaration(&mut self, abstract_self: AbstractNode<ScriptView>)
+                             -> @mut CSSStyleDeclaration {
+        match self.style_declaration {
+            None => {
+                let window = self.node.owner_doc().document().window;
+                let declaration = CSSStyleDeclaration::new(window,
+                                                           StyleAttributeOwner(abstract_self));
+                self.style_declaration = Some(declaration);
+                declaration
+            }
+            Some(declaration) => declaration
+        }
+    }
+
+    /// Sets a property in the style attribute, as with `element.style.setProperty()`.
+    /// Unsupported properties and invalid values are ignored.
+    pub fn set_style_property(&mut self, abstract_self: AbstractNode<ScriptView>, name: &str,
+                              value: &str, important: bool) {
+        let base_url = self.style_base_url();
+        let mut block = match self.style_attribute.take() {
+            Some(block) => block,
+            None => style::PropertyDeclarationBlock::new(),
+        };
+        let changed = block.set_property(name, value, important, &base_url);
+        self.style_attribute = Some(block);
+        if changed {
+            self.style_attribute_changed(abstract_self);
+        }
+    }
+
+    /// Removes a property from the style attribute, as with `element.style.removeProperty()`.
+    pub fn remove_style_property(&mut self, abstract_self: AbstractNode<ScriptView>,
+                                 name: &str) {
+        let removed = match self.style_attribute {
+            Some(ref mut block) => block.remove_property(name),
+            None => false,
+        };
+        if removed {
+            self.style_attribute_changed(abstract_self);
+        }
+    }
+
+    /// Serializes the declarations of the style attribute back into it. Setting the attribute
+    /// parses them again, which gives the same declarations, and restyles the element.
+    fn style_attribute_changed(&mut self, abstract_self: AbstractNode<ScriptView>) {
+        let css_text = match self.style_attribute {
+            Some(ref block) => block.to_css_text(),
+            None => ~"",
+        };
+        let _ = self.set_attribute(abstract_self, namespace::Null, ~"style", css_text);
+    }
+
     pub fn TagName(&self) -> DOMString {
-        self.tag_name.to_ascii_upper()
+        self.tag_name.as_slice().to_ascii_upper()
     }
 
     pub fn Id(&self, _abstract_self: AbstractNode<ScriptView>) -> DOMString {
@@ -8,19 +8,20 @@ use dom::document::AbstractDocument;
 use dom::element::HTMLAnchorElementTypeId;
 use dom::htmlelement::HTMLElement;
 use dom::node::{AbstractNode, Node, ScriptView};
+use servo_util::atom::Atom;
 
 pub struct HTMLAnchorElement {
     htmlelement: HTMLElement
 }
 
 impl HTMLAnchorElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLAnchorElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLAnchorElement {
         HTMLAnchorElement {
             htmlelement: HTMLElement::new_inherited(HTMLAnchorElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLAnchorElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLAnchorElementBinding::Wrap)
     }
@@ -8,19 +8,20 @@ use dom::document::AbstractDocument;
 use dom::element::HTMLAppletElementTypeId;
 use dom::htmlelement::HTMLElement;
 use dom::node::{AbstractNode, Node, ScriptView};
+use servo_util::atom::Atom;
 
 pub struct HTMLAppletElement {
     htmlelement: HTMLElement
 }
 
 impl HTMLAppletElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLAppletElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLAppletElement {
         HTMLAppletElement {
             htmlelement: HTMLElement::new_inherited(HTMLAppletElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLAppletElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLAppletElementBinding::Wrap)
     }
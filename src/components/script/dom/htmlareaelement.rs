@@ -8,19 +8,20 @@ use dom::document::AbstractDocument;
 use dom::element::HTMLAreaElementTypeId;
 use dom::htmlelement::HTMLElement;
 use dom::node::{AbstractNode, Node, ScriptView};
+use servo_util::atom::Atom;
 
 pub struct HTMLAreaElement {
     htmlelement: HTMLElement
 }
 
 impl HTMLAreaElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLAreaElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLAreaElement {
         HTMLAreaElement {
             htmlelement: HTMLElement::new_inherited(HTMLAreaElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLAreaElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLAreaElementBinding::Wrap)
     }
@@ -7,19 +7,20 @@ use dom::document::AbstractDocument;
 use dom::element::HTMLAudioElementTypeId;
 use dom::htmlmediaelement::HTMLMediaElement;
 use dom::node::{AbstractNode, Node, ScriptView};
+use servo_util::atom::Atom;
 
 pub struct HTMLAudioElement {
     htmlmediaelement: HTMLMediaElement
 }
 
 impl HTMLAudioElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLAudioElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLAudioElement {
         HTMLAudioElement {
             htmlmediaelement: HTMLMediaElement::new_inherited(HTMLAudioElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLAudioElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLAudioElementBinding::Wrap)
     }
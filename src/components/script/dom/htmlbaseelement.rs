@@ -8,19 +8,20 @@ use dom::document::AbstractDocument;
 use dom::element::HTMLBaseElementTypeId;
 use dom::htmlelement::HTMLElement;
 use dom::node::{AbstractNode, Node, ScriptView};
+use servo_util::atom::Atom;
 
 pub struct HTMLBaseElement {
     htmlelement: HTMLElement
 }
 
 impl HTMLBaseElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLBaseElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLBaseElement {
         HTMLBaseElement {
             htmlelement: HTMLElement::new_inherited(HTMLBaseElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLBaseElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLBaseElementBinding::Wrap)
     }
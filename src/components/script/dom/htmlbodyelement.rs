@@ -8,19 +8,20 @@ use dom::document::AbstractDocument;
 use dom::element::HTMLBodyElementTypeId;
 use dom::htmlelement::HTMLElement;
 use dom::node::{AbstractNode, Node, ScriptView};
+use servo_util::atom::Atom;
 
 pub struct HTMLBodyElement {
     htmlelement: HTMLElement
 }
 
 impl HTMLBodyElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLBodyElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLBodyElement {
         HTMLBodyElement {
             htmlelement: HTMLElement::new_inherited(HTMLBodyElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLBodyElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLBodyElementBinding::Wrap)
     }
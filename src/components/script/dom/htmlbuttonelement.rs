@@ -9,19 +9,20 @@ use dom::element::HTMLButtonElementTypeId;
 use dom::htmlelement::HTMLElement;
 use dom::node::{AbstractNode, Node, ScriptView};
 use dom::validitystate::ValidityState;
+use servo_util::atom::Atom;
 
 pub struct HTMLButtonElement {
     htmlelement: HTMLElement
 }
 
 impl HTMLButtonElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLButtonElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLButtonElement {
         HTMLButtonElement {
             htmlelement: HTMLElement::new_inherited(HTMLButtonElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLButtonElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLButtonElementBinding::Wrap)
     }
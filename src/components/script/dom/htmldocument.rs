@@ -44,11 +44,11 @@ impl HTMLDocument {
     }
 
     pub fn Images(&self) -> @mut HTMLCollection {
-        self.parent.createHTMLCollection(|elem| eq_slice(elem.tag_name, "img"))
+        self.parent.createHTMLCollection(|elem| eq_slice(elem.tag_name.as_slice(), "img"))
     }
 
     pub fn Embeds(&self) -> @mut HTMLCollection {
-        self.parent.createHTMLCollection(|elem| eq_slice(elem.tag_name, "embed"))
+        self.parent.createHTMLCollection(|elem| eq_slice(elem.tag_name.as_slice(), "embed"))
     }
 
     pub fn Plugins(&self) -> @mut HTMLCollection {
@@ -57,26 +57,26 @@ impl HTMLDocument {
 
     pub fn Links(&self) -> @mut HTMLCollection {
         self.parent.createHTMLCollection(|elem|
-            (eq_slice(elem.tag_name, "a") || eq_slice(elem.tag_name, "area"))
+            (eq_slice(elem.tag_name.as_slice(), "a") || eq_slice(elem.tag_name.as_slice(), "area"))
             && elem.get_attr(None, "href").is_some())
     }
 
     pub fn Forms(&self) -> @mut HTMLCollection {
-        self.parent.createHTMLCollection(|elem| eq_slice(elem.tag_name, "form"))
+        self.parent.createHTMLCollection(|elem| eq_slice(elem.tag_name.as_slice(), "form"))
     }
 
     pub fn Scripts(&self) -> @mut HTMLCollection {
-        self.parent.createHTMLCollection(|elem| eq_slice(elem.tag_name, "script"))
+        self.parent.createHTMLCollection(|elem| eq_slice(elem.tag_name.as_slice(), "script"))
     }
 
     pub fn Anchors(&self) -> @mut HTMLCollection {
         self.parent.createHTMLCollection(|elem|
-            eq_slice(elem.tag_name, "a") && elem.get_attr(None, "name").is_some())
+            eq_slice(elem.tag_name.as_slice(), "a") && elem.get_attr(None, "name").is_some())
     }
 
     pub fn Applets(&self) -> @mut HTMLCollection {
         // FIXME: This should be return OBJECT elements containing applets.
-        self.parent.createHTMLCollection(|elem| eq_slice(elem.tag_name, "applet"))
+        self.parent.createHTMLCollection(|elem| eq_slice(elem.tag_name.as_slice(), "applet"))
     }
 }
 
@@ -4,25 +4,27 @@
 
 use dom::bindings::codegen::HTMLElementBinding;
 use dom::bindings::utils::{DOMString, ErrorResult, Fallible};
+use dom::cssstyledeclaration::CSSStyleDeclaration;
 use dom::document::AbstractDocument;
 use dom::element::{Element, ElementTypeId, HTMLElementTypeId};
 use dom::node::{AbstractNode, Node, ScriptView};
 use js::jsapi::{JSContext, JSVal};
 use js::JSVAL_NULL;
 use dom::namespace;
+use servo_util::atom::Atom;
 
 pub struct HTMLElement {
     element: Element
 }
 
 impl HTMLElement {
-    pub fn new_inherited(type_id: ElementTypeId, tag_name: ~str, document: AbstractDocument) -> HTMLElement {
+    pub fn new_inherited(type_id: ElementTypeId, tag_name: Atom, document: AbstractDocument) -> HTMLElement {
         HTMLElement {
             element: Element::new_inherited(type_id, tag_name, namespace::HTML, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLElement::new_inherited(HTMLElementTypeId, localName, document);
         Node::reflect_node(@mut element, document, HTMLElementBinding::Wrap)
     }
@@ -43,6 +45,11 @@ impl HTMLElement {
     pub fn SetLang(&mut self, _lang: DOMString) {
     }
 
+    pub fn Style(&mut self, abstract_self: AbstractNode<ScriptView>)
+                 -> @mut CSSStyleDeclaration {
+        self.element.style_declaration(abstract_self)
+    }
+
     pub fn Dir(&self) -> DOMString {
         ~""
     }
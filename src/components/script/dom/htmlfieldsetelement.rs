@@ -10,19 +10,20 @@ use dom::htmlcollection::HTMLCollection;
 use dom::htmlelement::HTMLElement;
 use dom::node::{AbstractNode, Node, ScriptView};
 use dom::validitystate::ValidityState;
+use servo_util::atom::Atom;
 
 pub struct HTMLFieldSetElement {
     htmlelement: HTMLElement
 }
 
 impl HTMLFieldSetElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLFieldSetElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLFieldSetElement {
         HTMLFieldSetElement {
             htmlelement: HTMLElement::new_inherited(HTMLFieldSetElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLFieldSetElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLFieldSetElementBinding::Wrap)
     }
@@ -8,19 +8,20 @@ use dom::document::AbstractDocument;
 use dom::element::HTMLFontElementTypeId;
 use dom::htmlelement::HTMLElement;
 use dom::node::{AbstractNode, Node, ScriptView};
+use servo_util::atom::Atom;
 
 pub struct HTMLFontElement {
     htmlelement: HTMLElement
 }
 
 impl HTMLFontElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLFontElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLFontElement {
         HTMLFontElement {
             htmlelement: HTMLElement::new_inherited(HTMLFontElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLFontElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLFontElementBinding::Wrap)
     }
@@ -9,19 +9,20 @@ use dom::element::HTMLFrameElementTypeId;
 use dom::htmlelement::HTMLElement;
 use dom::node::{AbstractNode, Node, ScriptView};
 use dom::windowproxy::WindowProxy;
+use servo_util::atom::Atom;
 
 pub struct HTMLFrameElement {
     htmlelement: HTMLElement
 }
 
 impl HTMLFrameElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLFrameElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLFrameElement {
         HTMLFrameElement {
             htmlelement: HTMLElement::new_inherited(HTMLFrameElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLFrameElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLFrameElementBinding::Wrap)
     }
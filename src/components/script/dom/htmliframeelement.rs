@@ -12,6 +12,7 @@ use dom::windowproxy::WindowProxy;
 
 use extra::url::Url;
 use servo_msg::constellation_msg::{PipelineId, SubpageId};
+use servo_util::atom::Atom;
 use std::ascii::StrAsciiExt;
 
 enum SandboxAllowance {
@@ -43,7 +44,7 @@ impl HTMLIFrameElement {
 }
 
 impl HTMLIFrameElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLIFrameElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLIFrameElement {
         HTMLIFrameElement {
             htmlelement: HTMLElement::new_inherited(HTMLIframeElementTypeId, localName, document),
             frame: None,
@@ -52,7 +53,7 @@ impl HTMLIFrameElement {
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLIFrameElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLIFrameElementBinding::Wrap)
     }
@@ -15,6 +15,7 @@ use servo_net::image_cache_task;
 use servo_net::image_cache_task::ImageCacheTask;
 use servo_util::url::make_url;
 use servo_util::tree::ElementLike;
+use servo_util::atom::Atom;
 
 pub struct HTMLImageElement {
     htmlelement: HTMLElement,
@@ -22,14 +23,14 @@ pub struct HTMLImageElement {
 }
 
 impl HTMLImageElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLImageElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLImageElement {
         HTMLImageElement {
             htmlelement: HTMLElement::new_inherited(HTMLImageElementTypeId, localName, document),
             image: None,
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLImageElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLImageElementBinding::Wrap)
     }
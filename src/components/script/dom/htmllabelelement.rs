@@ -8,19 +8,20 @@ use dom::document::AbstractDocument;
 use dom::element::HTMLLabelElementTypeId;
 use dom::htmlelement::HTMLElement;
 use dom::node::{AbstractNode, Node, ScriptView};
+use servo_util::atom::Atom;
 
 pub struct HTMLLabelElement {
     htmlelement: HTMLElement,
 }
 
 impl HTMLLabelElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLLabelElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLLabelElement {
         HTMLLabelElement {
             htmlelement: HTMLElement::new_inherited(HTMLLabelElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLLabelElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLLabelElementBinding::Wrap)
     }
@@ -8,19 +8,20 @@ use dom::document::AbstractDocument;
 use dom::element::HTMLLIElementTypeId;
 use dom::htmlelement::HTMLElement;
 use dom::node::{AbstractNode, Node, ScriptView};
+use servo_util::atom::Atom;
 
 pub struct HTMLLIElement {
     htmlelement: HTMLElement,
 }
 
 impl HTMLLIElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLLIElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLLIElement {
         HTMLLIElement {
             htmlelement: HTMLElement::new_inherited(HTMLLIElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLLIElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLLIElementBinding::Wrap)
     }
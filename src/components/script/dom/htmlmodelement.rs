@@ -8,19 +8,20 @@ use dom::document::AbstractDocument;
 use dom::element::HTMLModElementTypeId;
 use dom::htmlelement::HTMLElement;
 use dom::node::{AbstractNode, Node, ScriptView};
+use servo_util::atom::Atom;
 
 pub struct HTMLModElement {
     htmlelement: HTMLElement
 }
 
 impl HTMLModElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLModElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLModElement {
         HTMLModElement {
             htmlelement: HTMLElement::new_inherited(HTMLModElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLModElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLModElementBinding::Wrap)
     }
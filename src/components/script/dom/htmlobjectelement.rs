@@ -10,19 +10,20 @@ use dom::htmlelement::HTMLElement;
 use dom::node::{AbstractNode, Node, ScriptView};
 use dom::validitystate::ValidityState;
 use dom::windowproxy::WindowProxy;
+use servo_util::atom::Atom;
 
 pub struct HTMLObjectElement {
     htmlelement: HTMLElement
 }
 
 impl HTMLObjectElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLObjectElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLObjectElement {
         HTMLObjectElement {
             htmlelement: HTMLElement::new_inherited(HTMLObjectElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLObjectElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLObjectElementBinding::Wrap)
     }
@@ -8,19 +8,20 @@ use dom::document::AbstractDocument;
 use dom::element::HTMLOptGroupElementTypeId;
 use dom::htmlelement::HTMLElement;
 use dom::node::{AbstractNode, Node, ScriptView};
+use servo_util::atom::Atom;
 
 pub struct HTMLOptGroupElement {
     htmlelement: HTMLElement
 }
 
 impl HTMLOptGroupElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLOptGroupElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLOptGroupElement {
         HTMLOptGroupElement {
             htmlelement: HTMLElement::new_inherited(HTMLOptGroupElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLOptGroupElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLOptGroupElementBinding::Wrap)
     }
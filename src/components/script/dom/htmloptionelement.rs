@@ -8,19 +8,20 @@ use dom::document::AbstractDocument;
 use dom::element::HTMLOptionElementTypeId;
 use dom::htmlelement::HTMLElement;
 use dom::node::{AbstractNode, Node, ScriptView};
+use servo_util::atom::Atom;
 
 pub struct HTMLOptionElement {
     htmlelement: HTMLElement
 }
 
 impl HTMLOptionElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLOptionElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLOptionElement {
         HTMLOptionElement {
             htmlelement: HTMLElement::new_inherited(HTMLOptionElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLOptionElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLOptionElementBinding::Wrap)
     }
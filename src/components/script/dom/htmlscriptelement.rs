@@ -9,19 +9,20 @@ use dom::element::HTMLScriptElementTypeId;
 use dom::htmlelement::HTMLElement;
 use dom::node::{AbstractNode, Node, ScriptView};
 use servo_util::tree::ElementLike;
+use servo_util::atom::Atom;
 
 pub struct HTMLScriptElement {
     htmlelement: HTMLElement,
 }
 
 impl HTMLScriptElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLScriptElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLScriptElement {
         HTMLScriptElement {
             htmlelement: HTMLElement::new_inherited(HTMLScriptElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLScriptElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLScriptElementBinding::Wrap)
     }
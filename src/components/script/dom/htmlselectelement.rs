@@ -9,19 +9,20 @@ use dom::element::HTMLSelectElementTypeId;
 use dom::htmlelement::HTMLElement;
 use dom::node::{AbstractNode, Node, ScriptView};
 use dom::validitystate::ValidityState;
+use servo_util::atom::Atom;
 
 pub struct HTMLSelectElement {
     htmlelement: HTMLElement
 }
 
 impl HTMLSelectElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLSelectElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLSelectElement {
         HTMLSelectElement {
             htmlelement: HTMLElement::new_inherited(HTMLSelectElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLSelectElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLSelectElementBinding::Wrap)
     }
@@ -8,19 +8,20 @@ use dom::document::AbstractDocument;
 use dom::element::HTMLStyleElementTypeId;
 use dom::htmlelement::HTMLElement;
 use dom::node::{AbstractNode, Node, ScriptView};
+use servo_util::atom::Atom;
 
 pub struct HTMLStyleElement {
     htmlelement: HTMLElement,
 }
 
 impl HTMLStyleElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLStyleElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLStyleElement {
         HTMLStyleElement {
             htmlelement: HTMLElement::new_inherited(HTMLStyleElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLStyleElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLStyleElementBinding::Wrap)
     }
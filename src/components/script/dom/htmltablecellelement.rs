@@ -6,13 +6,14 @@ use dom::bindings::utils::{DOMString, ErrorResult};
 use dom::document::AbstractDocument;
 use dom::element::ElementTypeId;
 use dom::htmlelement::HTMLElement;
+use servo_util::atom::Atom;
 
 pub struct HTMLTableCellElement {
     htmlelement: HTMLElement,
 }
 
 impl HTMLTableCellElement {
-    pub fn new_inherited(type_id: ElementTypeId, tag_name: ~str, document: AbstractDocument) -> HTMLTableCellElement {
+    pub fn new_inherited(type_id: ElementTypeId, tag_name: Atom, document: AbstractDocument) -> HTMLTableCellElement {
         HTMLTableCellElement {
             htmlelement: HTMLElement::new_inherited(type_id, tag_name, document)
         }
@@ -8,19 +8,20 @@ use dom::document::AbstractDocument;
 use dom::element::HTMLTableElementTypeId;
 use dom::htmlelement::HTMLElement;
 use dom::node::{AbstractNode, Node, ScriptView};
+use servo_util::atom::Atom;
 
 pub struct HTMLTableElement {
     htmlelement: HTMLElement,
 }
 
 impl HTMLTableElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLTableElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLTableElement {
         HTMLTableElement {
             htmlelement: HTMLElement::new_inherited(HTMLTableElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLTableElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLTableElementBinding::Wrap)
     }
@@ -8,19 +8,20 @@ use dom::document::AbstractDocument;
 use dom::element::HTMLTableRowElementTypeId;
 use dom::htmlelement::HTMLElement;
 use dom::node::{AbstractNode, Node, ScriptView};
+use servo_util::atom::Atom;
 
 pub struct HTMLTableRowElement {
     htmlelement: HTMLElement,
 }
 
 impl HTMLTableRowElement {
-    pub fn new_inherited(localName: ~str, document: AbstractDocument) -> HTMLTableRowElement {
+    pub fn new_inherited(localName: Atom, document: AbstractDocument) -> HTMLTableRowElement {
         HTMLTableRowElement {
             htmlelement: HTMLElement::new_inherited(HTMLTableRowElementTypeId, localName, document)
         }
     }
 
-    pub fn new(localName: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    pub fn new(localName: Atom, document: AbstractDocument) -> AbstractNode<ScriptView> {
         let element = HTMLTableRowElement::new_inherited(localName, document);
         Node::reflect_node(@mut element, document, HTMLTableRowElementBinding::Wrap)
     }
@@ -553,6 +553,14 @@ impl<'self, View> AbstractNode<View> {
 }
 
 impl AbstractNode<ScriptView> {
+    /// Returns the layout task's view of this node, for the nodes that script hands to layout.
+    /// This is unsafe because layout must not mutate the node.
+    pub unsafe fn to_layout_view(self) -> AbstractNode<LayoutView> {
+        AbstractNode {
+            obj: self.obj as *mut Box<Node<LayoutView>>,
+        }
+    }
+
     pub fn AppendChild(self, node: AbstractNode<ScriptView>) -> Fallible<AbstractNode<ScriptView>> {
         self.node().AppendChild(self, node)
     }
@@ -1232,6 +1240,14 @@ pub trait PostorderNodeMutTraversal {
 
 
 impl AbstractNode<LayoutView> {
+    /// Returns the script task's view of this node, for the nodes that layout hands back to
+    /// script, such as the results of hit tests.
+    pub fn to_script_view(self) -> AbstractNode<ScriptView> {
+        AbstractNode {
+            obj: self.obj as *mut Box<Node<ScriptView>>,
+        }
+    }
+
     /// Traverses the tree in postorder.
     ///
     /// TODO(pcwalton): Offer a parallel version with a compatible API.
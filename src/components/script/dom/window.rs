@@ -6,16 +6,19 @@ use dom::bindings::codegen::WindowBinding;
 use dom::bindings::utils::{Reflectable, Reflector, Traceable};
 use dom::bindings::utils::{trace_option, trace_reflector};
 use dom::bindings::utils::DOMString;
+use dom::cssstyledeclaration::{CSSStyleDeclaration, ComputedValuesOwner};
 use dom::document::AbstractDocument;
 use dom::eventtarget::{EventTarget, WindowTypeId};
 use dom::node::{AbstractNode, ScriptView};
 use dom::location::Location;
 use dom::navigator::Navigator;
 
-use layout_interface::{ReflowForDisplay, DocumentDamageLevel};
+use layout_interface::{ReflowForDisplay, ReflowForPrint, DocumentDamageLevel};
+use layout_interface::{ComputedStyleQuery, ComputedStyleResponse, ReflowDocumentDamage};
 use script_task::{ExitWindowMsg, FireTimerMsg, Page, ScriptChan};
 use servo_msg::compositor_msg::ScriptListener;
 use servo_net::image_cache_task::ImageCacheTask;
+use style;
 
 use js::glue::*;
 use js::jsapi::{JSObject, JSContext, JS_DefineProperty};
@@ -141,6 +144,12 @@ impl Window {
     }
 
     pub fn Print(&self) {
+        // Layout evaluates media queries against the print media by itself, and restyles if
+        // that changes which rules apply.
+        self.page.damage(ReflowDocumentDamage);
+        self.page.reflow(ReflowForPrint, self.script_chan.clone(), self.compositor);
+        // Then lay the document out for the screen again.
+        self.damage_and_reflow(ReflowDocumentDamage);
     }
 
     pub fn ShowModalDialog(&self, _cx: *JSContext, _url: DOMString, _argument: JSVal) -> JSVal {
@@ -185,6 +194,34 @@ impl Window {
         self.active_timers.remove(&handle);
     }
 
+    pub fn GetComputedStyle(&self, elt: AbstractNode<ScriptView>, pseudoElt: DOMString)
+                            -> @mut CSSStyleDeclaration {
+        // Both the CSS 2.1 (:before) and the CSS 3 (::before) syntaxes are accepted.
+        let pseudo_element = if pseudoElt.is_empty() {
+            Some(None)
+        } else if pseudoElt.starts_with("::") {
+            style::parse_pseudo_element(pseudoElt.slice_from(2)).map(|p| Some(p))
+        } else if pseudoElt.starts_with(":") {
+            style::parse_pseudo_element(pseudoElt.slice_from(1)).map(|p| Some(p))
+        } else {
+            None
+        };
+
+        let properties = match pseudo_element {
+            // Unknown pseudo-elements have no style.
+            None => ~[],
+            Some(pseudo_element) => {
+                let (port, chan) = comm::stream();
+                match self.page.query_layout(ComputedStyleQuery(elt, pseudo_element, chan),
+                                             port) {
+                    ComputedStyleResponse(properties) => properties,
+                }
+            }
+        };
+        CSSStyleDeclaration::new(self.page.frame.get_ref().window,
+                                 ComputedValuesOwner(properties))
+    }
+
     pub fn damage_and_reflow(&self, damage: DocumentDamageLevel) {
         // FIXME This should probably be ReflowForQuery, not Display. All queries currently
         // currently rely on the display list, which means we can't destroy it by
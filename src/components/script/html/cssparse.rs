@@ -8,8 +8,8 @@ use std::cell::Cell;
 use std::comm;
 use std::comm::Port;
 use std::task;
-use style::Stylesheet;
-use servo_net::resource_task::{Load, ProgressMsg, Payload, Done, ResourceTask};
+use style::{Stylesheet, CSSError, ErrorCollector};
+use servo_net::resource_task::{Load, LoadResponse, ProgressMsg, Payload, Done, ResourceTask};
 use extra::url::Url;
 
 /// Where a style sheet comes from.
@@ -18,35 +18,33 @@ pub enum StylesheetProvenance {
     InlineProvenance(Url, ~str),
 }
 
+/// Parses a style sheet and the style sheets it imports in a new task, and sends it back with
+/// the errors found while parsing them.
 pub fn spawn_css_parser(provenance: StylesheetProvenance,
                         resource_task: ResourceTask)
-                     -> Port<Stylesheet> {
+                     -> Port<(Stylesheet, ~[CSSError])> {
     let (result_port, result_chan) = comm::stream();
 
     let provenance_cell = Cell::new(provenance);
     do task::spawn {
-        // TODO: CSS parsing should take a base URL.
-        let _url = do provenance_cell.with_ref |p| {
-            match *p {
-                UrlProvenance(ref the_url) => (*the_url).clone(),
-                InlineProvenance(ref the_url, _) => (*the_url).clone()
-            }
-        };
-
-        let sheet = match provenance_cell.take() {
+        let mut errors = ErrorCollector::new();
+        let mut sheet = match provenance_cell.take() {
             UrlProvenance(url) => {
                 debug!("cssparse: loading style sheet at {:s}", url.to_str());
                 let (input_port, input_chan) = comm::stream();
                 resource_task.send(Load(url, input_chan));
+                let LoadResponse { metadata: metadata, progress_port: progress_port } =
+                    input_port.recv();
                 Stylesheet::from_iter(ProgressMsgPortIterator {
-                    progress_port: input_port.recv().progress_port
-                })
+                    progress_port: progress_port
+                }, metadata.final_url, &mut errors)
             }
-            InlineProvenance(_, data) => {
-                Stylesheet::from_str(data)
+            InlineProvenance(base_url, data) => {
+                Stylesheet::from_str(data, base_url, &mut errors)
             }
         };
-        result_chan.send(sheet);
+        sheet.load_imports(&resource_task, &mut errors);
+        result_chan.send((sheet, errors.errors));
     }
 
     return result_port;
@@ -20,6 +20,7 @@ use js::jsapi::JSContext;
 use servo_msg::constellation_msg::SubpageId;
 use servo_net::image_cache_task::ImageCacheTask;
 use servo_net::resource_task::{Load, Payload, Done, ResourceTask, load_whole_resource};
+use servo_util::atom::Atom;
 use servo_util::tree::{TreeNodeRef, ElementLike};
 use servo_util::url::make_url;
 use std::cast;
@@ -29,7 +30,7 @@ use std::comm;
 use std::from_str::FromStr;
 use std::str::eq_slice;
 use std::str;
-use style::Stylesheet;
+use style::{CSSError, Stylesheet, NoQuirks, LimitedQuirks, FullQuirks};
 
 macro_rules! handle_element(
     ($document: expr,
@@ -37,7 +38,7 @@ macro_rules! handle_element(
      $string: expr,
      $ctor: ident
      $(, $arg:expr )*) => (
-        if eq_slice($localName, $string) {
+        if eq_slice($localName.as_slice(), $string) {
             return $ctor::new($localName, $document $(, $arg)*);
         }
     )
@@ -64,7 +65,8 @@ enum JSMessage {
 
 /// Messages generated by the HTML parser upon discovery of additional resources
 pub enum HtmlDiscoveryMessage {
-    HtmlDiscoveredStyle(Stylesheet),
+    /// A style sheet, and the errors found while parsing it.
+    HtmlDiscoveredStyle(Stylesheet, ~[CSSError]),
     HtmlDiscoveredIFrame((Url, SubpageId, bool)),
     HtmlDiscoveredScript(JSResult)
 }
@@ -121,7 +123,8 @@ fn css_link_listener(to_parent: SharedChan<HtmlDiscoveryMessage>,
     // Send the sheets back in order
     // FIXME: Shouldn't wait until after we've recieved CSSTaskExit to start sending these
     for port in result_vec.iter() {
-        to_parent.send(HtmlDiscoveredStyle(port.recv()));
+        let (sheet, errors) = port.recv();
+        to_parent.send(HtmlDiscoveredStyle(sheet, errors));
     }
 }
 
@@ -157,11 +160,12 @@ fn js_script_listener(to_parent: SharedChan<HtmlDiscoveryMessage>,
     to_parent.send(HtmlDiscoveredScript(result_vec));
 }
 
-// Silly macros to handle constructing      DOM nodes. This produces bad code and should be optimized
-// via atomization (issue #85).
+// Silly macros to handle constructing      DOM nodes. This produces bad code: the tag name is
+// interned once and shared with the element, but it is still compared to each known name as a
+// string.
 
-pub fn build_element_from_tag(tag: ~str, document: AbstractDocument) -> AbstractNode<ScriptView> {
-    // TODO (Issue #85): use atoms
+pub fn build_element_from_tag(tag: &str, document: AbstractDocument) -> AbstractNode<ScriptView> {
+    let tag = Atom::from_slice(tag);
     handle_element!(document, tag, "a",         HTMLAnchorElement);
     handle_element!(document, tag, "applet",    HTMLAppletElement);
     handle_element!(document, tag, "area",      HTMLAreaElement);
@@ -331,7 +335,7 @@ pub fn parse_html(cx: *JSContext,
         },
         create_element: |tag: ~hubbub::Tag| {
             debug!("create element");
-            let node = build_element_from_tag(tag.name.clone(), document);
+            let node = build_element_from_tag(tag.name.as_slice(), document);
 
             debug!("-- attach attrs");
             do node.as_mut_element |element| {
@@ -453,8 +457,13 @@ pub fn parse_html(cx: *JSContext,
         add_attributes: |_node, _attributes| {
             debug!("add attributes");
         },
-        set_quirks_mode: |_mode| {
+        set_quirks_mode: |mode| {
             debug!("set quirks mode");
+            document.mut_document().quirks_mode = match mode {
+                hubbub::NoQuirks => NoQuirks,
+                hubbub::LimitedQuirks => LimitedQuirks,
+                hubbub::FullQuirks => FullQuirks,
+            };
         },
         encoding_change: |_encname| {
             debug!("encoding change");
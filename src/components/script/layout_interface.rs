@@ -16,7 +16,7 @@ use script_task::{ScriptChan};
 use servo_util::geometry::Au;
 use std::comm::{Chan, SharedChan};
 use std::cmp;
-use style::Stylesheet;
+use style::{PseudoElement, QuirksMode, Stylesheet};
 
 /// Asynchronous messages that script can send to layout.
 ///
@@ -56,11 +56,18 @@ pub enum LayoutQuery {
     ContentBoxesQuery(AbstractNode<ScriptView>, Chan<ContentBoxesResponse>),
     /// Requests the node containing the point of interest
     HitTestQuery(AbstractNode<ScriptView>, Point2D<f32>, Chan<Result<HitTestResponse, ()>>),
+    /// Requests the computed values of an element or of one of its pseudo-elements, as in the
+    /// `getComputedStyle()` call.
+    ComputedStyleQuery(AbstractNode<ScriptView>, Option<PseudoElement>,
+                       Chan<ComputedStyleResponse>),
 }
 
 pub struct ContentBoxResponse(Rect<Au>);
 pub struct ContentBoxesResponse(~[Rect<Au>]);
 pub struct HitTestResponse(AbstractNode<LayoutView>);
+/// The names and serialized values of all longhands. Empty if the element is not styled, e.g.
+/// because it is not in the document.
+pub struct ComputedStyleResponse(~[(~str, ~str)]);
 
 /// Determines which part of the 
 #[deriving(Eq, Ord)]
@@ -97,6 +104,9 @@ pub enum ReflowGoal {
     ReflowForDisplay,
     /// We're reflowing in order to satisfy a script query. No display list will be created.
     ReflowForScriptQuery,
+    /// We're reflowing in order to print the document, as with `window.print()`: style is
+    /// computed against the print media and the pages are written to the output file.
+    ReflowForPrint,
 }
 
 /// Information needed for a reflow.
@@ -109,6 +119,8 @@ pub struct Reflow {
     goal: ReflowGoal,
     /// The URL of the page.
     url: Url,
+    /// The compatibility mode of the document.
+    quirks_mode: QuirksMode,
     /// The channel through which messages can be sent back to the script task.
     script_chan: ScriptChan,
     /// The current window size.
@@ -10,6 +10,7 @@ use dom::bindings::utils::{Reflectable, GlobalStaticData};
 use dom::document::AbstractDocument;
 use dom::element::Element;
 use dom::event::{Event_, ResizeEvent, ReflowEvent, ClickEvent, MouseDownEvent, MouseUpEvent};
+use dom::event::MouseMoveEvent;
 use dom::event::Event;
 use dom::eventtarget::AbstractEventTarget;
 use dom::htmldocument::HTMLDocument;
@@ -132,8 +133,21 @@ pub struct Page {
     /// Pending resize event, if any.
     resize_event: Option<Size2D<uint>>,
 
+    /// Pending mouse move event, if any. Only the last position matters, and the hit test it
+    /// needs waits for layout to be idle, like resizes.
+    mouse_move_event: Option<Point2D<f32>>,
+
     /// Pending scroll to fragment event, if any
-    fragment_node: Option<AbstractNode<ScriptView>>
+    fragment_node: Option<AbstractNode<ScriptView>>,
+
+    /// The element under the pointer. It matches :hover, and so do its ancestors.
+    hover_node: Option<AbstractNode<ScriptView>>,
+
+    /// The element being clicked. It matches :active, and so do its ancestors.
+    active_node: Option<AbstractNode<ScriptView>>,
+
+    /// The element that has the focus and matches :focus.
+    focus_node: Option<AbstractNode<ScriptView>>,
 }
 
 pub struct PageTree {
@@ -159,7 +173,11 @@ impl PageTree {
                 url: None,
                 next_subpage_id: SubpageId(0),
                 resize_event: None,
+                mouse_move_event: None,
                 fragment_node: None,
+                hover_node: None,
+                active_node: None,
+                focus_node: None,
                 last_reflow_id: 0
             },
             inner: ~[],
@@ -221,31 +239,44 @@ impl<'self> Iterator<@mut Page> for PageTreeIterator<'self> {
 }
 
 impl Page {
-    /// Adds the given damage.
+    /// Adds the given damage to the whole document.
     pub fn damage(&mut self, level: DocumentDamageLevel) {
         let root = match self.frame {
             None => return,
             Some(ref frame) => frame.document.document().GetDocumentElement()
         };
-        match root {
-            None => {},
-            Some(root) => {
-                match self.damage {
-                    None => {}
-                    Some(ref mut damage) => {
-                        // FIXME(pcwalton): This is wrong. We should trace up to the nearest ancestor.
-                        damage.root = root;
-                        damage.level.add(level);
-                        return
-                    }
-                }
+        for &root in root.iter() {
+            self.damage_subtree(root, level)
+        }
+    }
 
-                self.damage = Some(DocumentDamage {
-                    root: root,
-                    level: level,
-                })
-            }
+    /// Adds the given damage to the subtree rooted at `root`. Subtrees that are not in the
+    /// document are not displayed, so damage to them is ignored.
+    pub fn damage_subtree(&mut self, root: AbstractNode<ScriptView>, level: DocumentDamageLevel) {
+        let document_element = match self.frame {
+            None => return,
+            Some(ref frame) => frame.document.document().GetDocumentElement()
         };
+        let document_element = match document_element {
+            None => return,
+            Some(document_element) => document_element,
+        };
+        if root != document_element && !root.ancestors().any(|node| node == document_element) {
+            return
+        }
+
+        match self.damage {
+            None => {}
+            Some(ref mut damage) => {
+                damage.root = nearest_common_ancestor(damage.root, root);
+                damage.level.add(level);
+                return
+            }
+        }
+        self.damage = Some(DocumentDamage {
+            root: root,
+            level: level,
+        })
     }
 
     /// Sends a ping to layout and waits for the response. The response will arrive when the
@@ -289,10 +320,11 @@ impl Page {
                   goal: ReflowGoal,
                   script_chan: ScriptChan,
                   compositor: @ScriptListener) {
-        let root = match self.frame {
+        let (root, quirks_mode) = match self.frame {
             None => return,
             Some(ref frame) => {
-                frame.document.document().GetDocumentElement()
+                let document = frame.document.document();
+                (document.GetDocumentElement(), document.quirks_mode)
             }
         };
 
@@ -325,6 +357,7 @@ impl Page {
                 let reflow = ~Reflow {
                     document_root: root,
                     url: self.url.get_ref().first().clone(),
+                    quirks_mode: quirks_mode,
                     goal: goal,
                     window_size: window_size,
                     script_chan: script_chan,
@@ -507,22 +540,30 @@ impl ScriptTask {
 
     /// Handle incoming control messages.
     fn handle_msgs(&mut self) -> bool {
-        // Handle pending resize events.
+        // Handle pending resize and mouse move events.
         // Gather them first to avoid a double mut borrow on self.
         let mut resizes = ~[];
+        let mut mouse_moves = ~[];
         for page in self.page_tree.iter() {
-            // Only process a resize if layout is idle.
+            // Only process a resize or a mouse move if layout is idle.
             if page.layout_join_port.is_none() {
                 match page.resize_event.take() {
                     Some(size) => resizes.push((page.id, size)),
                     None => ()
                 }
+                match page.mouse_move_event.take() {
+                    Some(point) => mouse_moves.push((page.id, point)),
+                    None => ()
+                }
             }
         }
 
         for (id, Size2D { width, height }) in resizes.move_iter() {
             self.handle_event(id, ResizeEvent(width, height));
         }
+        for (id, point) in mouse_moves.move_iter() {
+            self.handle_event(id, MouseMoveEvent(point));
+        }
 
         // Store new resizes, and gather all other events.
         let mut sequential = ~[];
@@ -535,6 +576,11 @@ impl ScriptTask {
                     let page = self.page_tree.find(id).expect("resize sent to nonexistent pipeline").page;
                     page.resize_event = Some(size);
                 }
+                SendEventMsg(id, MouseMoveEvent(point)) => {
+                    let page = self.page_tree.find(id)
+                                   .expect("mouse move sent to nonexistent pipeline").page;
+                    page.mouse_move_event = Some(point);
+                }
                 _ => {
                     sequential.push(event);
                 }
@@ -718,6 +764,10 @@ impl ScriptTask {
         // Note: We can parse the next document in parallel with any previous documents.
         let document = HTMLDocument::new(window);
 
+        // Relative URLs in the document, e.g. in style attributes, are resolved against this
+        // while parsing. No more reflow required.
+        page.url = Some((url.clone(), false));
+
         let html_parsing_result = hubbub_html_parser::parse_html(cx.ptr,
                                                                  document,
                                                                  url.clone(),
@@ -729,6 +779,11 @@ impl ScriptTask {
             discovery_port
         } = html_parsing_result;
 
+        // The elements of the previous document no longer have any state.
+        page.hover_node = None;
+        page.active_node = None;
+        page.focus_node = None;
+
         // Create the root frame.
         page.frame = Some(Frame {
             document: document,
@@ -747,8 +802,10 @@ impl ScriptTask {
                     assert!(js_scripts.is_none());
                     js_scripts = Some(scripts);
                 }
-                Some(HtmlDiscoveredStyle(sheet)) => {
-                    page.layout_chan.send(AddStylesheetMsg(sheet));
+                Some(HtmlDiscoveredStyle(sheet, errors)) => {
+                    page.layout_chan.send(AddStylesheetMsg(sheet.clone()));
+                    document.mut_document().add_stylesheet(sheet);
+                    document.mut_document().add_css_errors(errors);
                 }
                 Some(HtmlDiscoveredIFrame((iframe_url, subpage_id, sandboxed))) => {
                     page.next_subpage_id = SubpageId(*subpage_id + 1);
@@ -771,9 +828,6 @@ impl ScriptTask {
 
         let fragment = url.fragment.as_ref().map(|ref fragment| fragment.to_owned());
 
-        // No more reflow required
-        page.url = Some((url, false));
-
         // Receive the JavaScript scripts.
         assert!(js_scripts.is_some());
         let js_scripts = js_scripts.take_unwrap();
@@ -858,12 +912,15 @@ impl ScriptTask {
                 }
             }
 
+            // An image has arrived, which changes the size of its box although no style changed,
+            // so layout cannot reuse any flows.
+            //
             // FIXME(pcwalton): This reflows the entire document and is not incremental-y.
             ReflowEvent => {
                 debug!("script got reflow event");
 
                 if page.frame.is_some() {
-                    page.damage(MatchSelectorsDocumentDamage);
+                    page.damage(ContentChangedDocumentDamage);
                     page.reflow(ReflowForDisplay, self.chan.clone(), self.compositor)
                 }
             }
@@ -871,42 +928,90 @@ impl ScriptTask {
             ClickEvent(_button, point) => {
                 debug!("ClickEvent: clicked at {:?}", point);
 
-                let document = page.frame.expect("root frame is None").document;
-                let root = document.document().GetDocumentElement();
-                if root.is_none() {
-                    return;
-                }
-                let (port, chan) = comm::stream();
-                match page.query_layout(HitTestQuery(root.unwrap(), point, chan), port) {
-                    Ok(node) => match node {
-                        HitTestResponse(node) => {
-                            debug!("clicked on {:s}", node.debug_str());
-                            let mut node = node;
-                            // traverse node generations until a node that is an element is found
-                            while !node.is_element() {
-                                match node.parent_node() {
-                                    Some(parent) => {
-                                        node = parent;
-                                    }
-                                    None => break
-                                }
-                            }
-                            if node.is_element() {
-                                do node.with_imm_element |element| {
-                                    if "a" == element.tag_name {
-                                        self.load_url_from_element(page, element)
-                                    }
-                                }
+                match self.hit_test_element(page, point) {
+                    Some(node) => {
+                        debug!("clicked on {:s}", node.debug_str());
+                        do node.with_imm_element |element| {
+                            if "a" == element.tag_name.as_slice() {
+                                self.load_url_from_element(page, element)
                             }
                         }
-                    },
-                    Err(()) => {
-                        debug!("layout query error");
                     }
+                    None => {}
                 }
             }
-            MouseDownEvent(*) => {}
-            MouseUpEvent(*) => {}
+
+            // The element under the pointer becomes active until the button is released, and
+            // the focus moves to it if it can be focused.
+            MouseDownEvent(_button, point) => {
+                let node = self.hit_test_element(page, point);
+                let focus = node.and_then(|node| focusable_ancestor(node));
+                let mut changed = update_element_state(&mut page.active_node, node, true,
+                                                       |element, value| element.active = value);
+                let focus_changed = update_element_state(&mut page.focus_node, focus, false,
+                                                         |element, value| element.focus = value);
+                changed.push_all_move(focus_changed);
+                self.restyle(page, changed)
+            }
+            MouseUpEvent(*) => {
+                let changed = update_element_state(&mut page.active_node, None, true,
+                                                   |element, value| element.active = value);
+                self.restyle(page, changed)
+            }
+            MouseMoveEvent(point) => {
+                let node = self.hit_test_element(page, point);
+                let changed = update_element_state(&mut page.hover_node, node, true,
+                                                   |element, value| element.hover = value);
+                self.restyle(page, changed)
+            }
+        }
+    }
+
+    /// Returns the element at the given point of the page, which is the nearest element
+    /// ancestor of the node that layout finds there.
+    fn hit_test_element(&self, page: &mut Page, point: Point2D<f32>)
+                        -> Option<AbstractNode<ScriptView>> {
+        let root = match page.frame {
+            Some(ref frame) => frame.document.document().GetDocumentElement(),
+            None => return None,
+        };
+        let root = match root {
+            Some(root) => root,
+            None => return None,
+        };
+        let (port, chan) = comm::stream();
+        match page.query_layout(HitTestQuery(root, point, chan), port) {
+            Ok(HitTestResponse(node)) => {
+                // Layout hands back its own view of the node.
+                let mut node = node.to_script_view();
+                while !node.is_element() {
+                    match node.parent_node() {
+                        Some(parent) => node = parent,
+                        None => return None,
+                    }
+                }
+                Some(node)
+            }
+            Err(()) => {
+                debug!("layout query error");
+                None
+            }
+        }
+    }
+
+    /// Matches selectors again after a change in the state of the given elements, such as
+    /// :hover. Only the subtrees of their parents are restyled, since their descendants and their
+    /// siblings are the only other elements that may match different selectors now.
+    fn restyle(&self, page: @mut Page, changed: ~[AbstractNode<ScriptView>]) {
+        for &element in changed.iter() {
+            let root = match element.parent_node() {
+                Some(parent) if parent.is_element() => parent,
+                _ => element,
+            };
+            page.damage_subtree(root, MatchSelectorsDocumentDamage)
+        }
+        if page.damage.is_some() {
+            page.reflow(ReflowForDisplay, self.chan.clone(), self.compositor)
         }
     }
 
@@ -934,6 +1039,94 @@ impl ScriptTask {
     }
 }
 
+/// Moves a dynamic state, such as being hovered, from the element in `current` to `new`. If
+/// `with_ancestors` is true, the ancestors of the element have the state too, so the elements
+/// that the old and the new element share keep it. Returns the elements whose state changed,
+/// which need selectors matched again.
+fn update_element_state(current: &mut Option<AbstractNode<ScriptView>>,
+                        new: Option<AbstractNode<ScriptView>>,
+                        with_ancestors: bool,
+                        set: &fn(&mut Element, bool))
+                        -> ~[AbstractNode<ScriptView>] {
+    if *current == new {
+        return ~[]
+    }
+    let old_elements = elements_with_state(*current, with_ancestors);
+    let new_elements = elements_with_state(new, with_ancestors);
+    let mut changed = ~[];
+    for &node in old_elements.iter() {
+        if !new_elements.contains(&node) {
+            do node.as_mut_element |element| {
+                set(element, false)
+            }
+            changed.push(node)
+        }
+    }
+    for &node in new_elements.iter() {
+        if !old_elements.contains(&node) {
+            do node.as_mut_element |element| {
+                set(element, true)
+            }
+            changed.push(node)
+        }
+    }
+    *current = new;
+    changed
+}
+
+/// Returns the elements that have a state when `node` has it: `node` itself and, if
+/// `with_ancestors` is true, its element ancestors.
+fn elements_with_state(node: Option<AbstractNode<ScriptView>>, with_ancestors: bool)
+                       -> ~[AbstractNode<ScriptView>] {
+    let mut elements = ~[];
+    for &node in node.iter() {
+        if node.is_element() {
+            elements.push(node)
+        }
+        if with_ancestors {
+            for ancestor in node.ancestors() {
+                if ancestor.is_element() {
+                    elements.push(ancestor)
+                }
+            }
+        }
+    }
+    elements
+}
+
+/// Returns the nearest node that is `a` or one of its ancestors and `b` or one of its ancestors.
+/// The nodes must be in the same tree.
+fn nearest_common_ancestor(a: AbstractNode<ScriptView>, b: AbstractNode<ScriptView>)
+                           -> AbstractNode<ScriptView> {
+    let a_ancestors: ~[AbstractNode<ScriptView>] = a.ancestors().collect();
+    let mut b_ancestors = Some(b).move_iter().chain(b.ancestors());
+    match b_ancestors.find(|&node| node == a || a_ancestors.contains(&node)) {
+        Some(ancestor) => ancestor,
+        None => fail!("nodes in different trees"),
+    }
+}
+
+/// Returns the nearest ancestor-or-self of `node` that can take the focus: links, form controls
+/// that are not disabled, and elements with a `tabindex` attribute.
+fn focusable_ancestor(node: AbstractNode<ScriptView>) -> Option<AbstractNode<ScriptView>> {
+    let mut node = node;
+    loop {
+        if node.is_element() {
+            let focusable = do node.with_imm_element |element| {
+                element.get_attr(None, "tabindex").is_some() || element.get_link().is_some() ||
+                    element.get_disabled_state() == Some(false)
+            };
+            if focusable {
+                return Some(node)
+            }
+        }
+        match node.parent_node() {
+            Some(parent) => node = parent,
+            None => return None,
+        }
+    }
+}
+
 /// Shuts down layout for the given page.
 fn shut_down_layout(page: @mut Page) {
     page.join_layout();
@@ -0,0 +1,247 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! A counting Bloom filter of the local names, ids and classes of the ancestors of the element
+//! being matched. A selector that needs an ancestor with a name, id or class that is not in the
+//! filter can not match, which is found out without walking up the tree.
+
+use std::hash::Hash;
+use std::vec;
+
+use selector_matching::QuirksMode;
+use selectors::*;
+use servo_util::atom::Atom;
+use servo_util::tree::{TreeNodeRefAsElement, TreeNode, ElementLike};
+
+/// Each hash gives two keys of this many bits into the filter.
+static KEY_BITS: uint = 12;
+static KEY_MASK: u32 = 0xfff;
+
+/// How many of the names, ids and classes that ancestors need are checked for each selector.
+static MAX_ANCESTOR_HASHES: uint = 4;
+
+/// A counter that reaches this value is stuck, since its exact count is lost.
+static SATURATED: u8 = 0xff;
+
+/// The hashes of the names, ids and classes that ancestors need to have for a selector to match.
+#[deriving(Clone)]
+pub struct AncestorHashes {
+    priv hashes: [u32, ..MAX_ANCESTOR_HASHES],
+    priv len: uint,
+}
+
+impl AncestorHashes {
+    pub fn new(selector: &Selector, quirks_mode: QuirksMode) -> AncestorHashes {
+        // Ids and classes that are matched case-insensitively may not have the same hash as
+        // those of the ancestors, so only local names are checked.
+        let local_names_only = quirks_mode.has_case_insensitive_ids_and_classes();
+        let mut ancestor_hashes = AncestorHashes {
+            hashes: [0, ..MAX_ANCESTOR_HASHES],
+            len: 0,
+        };
+        let mut compound_selector = &selector.compound_selectors;
+        loop {
+            let (next, combinator) = match compound_selector.next {
+                Some((ref next, combinator)) => (next, combinator),
+                None => break,
+            };
+            // Only compound selectors on the left of a child or descendant combinator apply to
+            // ancestors. The others apply to siblings of the element or of its ancestors.
+            match combinator {
+                Child | Descendant => {
+                    for simple_selector in next.simple_selectors.iter() {
+                        let ancestor_hash = match *simple_selector {
+                            LocalNameSelector(ref name) => hash(name),
+                            IDSelector(ref id) if !local_names_only => hash(id),
+                            ClassSelector(ref class) if !local_names_only => hash(class),
+                            _ => continue,
+                        };
+                        if ancestor_hashes.len == MAX_ANCESTOR_HASHES {
+                            return ancestor_hashes
+                        }
+                        ancestor_hashes.hashes[ancestor_hashes.len] = ancestor_hash;
+                        ancestor_hashes.len += 1;
+                    }
+                }
+                NextSibling | LaterSibling => {}
+            }
+            compound_selector = &**next;
+        }
+        ancestor_hashes
+    }
+}
+
+/// The filter of the ancestors of the element being matched. Elements are pushed before their
+/// descendants are matched and popped afterwards.
+pub struct AncestorFilter {
+    priv counters: ~[u8],
+    /// The number of selectors that were rejected by the filter.
+    rejected: uint,
+    /// The number of selectors that the filter was asked about.
+    lookups: uint,
+    /// The number of selectors that were not rejected, and so were matched in full.
+    matched: uint,
+    /// The time spent matching those selectors in full, in nanoseconds. Divided by `matched`, it
+    /// estimates the time that each rejection saved.
+    matching_time: u64,
+}
+
+impl AncestorFilter {
+    pub fn new() -> AncestorFilter {
+        AncestorFilter {
+            counters: vec::from_elem(1 << KEY_BITS, 0u8),
+            rejected: 0,
+            lookups: 0,
+            matched: 0,
+            matching_time: 0,
+        }
+    }
+
+    /// Creates a filter that contains the ancestors of `element`.
+    pub fn for_ancestors_of<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: ElementLike>(
+            element: &T) -> AncestorFilter {
+        let mut filter = AncestorFilter::new();
+        let mut node = element.node().parent_node();
+        loop {
+            match node {
+                None => break,
+                Some(ancestor) => {
+                    if ancestor.is_element() {
+                        filter.push(&ancestor);
+                    }
+                    node = ancestor.node().parent_node();
+                }
+            }
+        }
+        filter
+    }
+
+    /// Adds `element` to the filter.
+    pub fn push<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: ElementLike>(
+            &mut self, element: &T) {
+        for hash in element_hashes(element).iter() {
+            let (first, second) = keys(*hash);
+            self.increment(first);
+            self.increment(second);
+        }
+    }
+
+    /// Removes `element`, which must be the last element that was added, from the filter.
+    pub fn pop<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: ElementLike>(
+            &mut self, element: &T) {
+        for hash in element_hashes(element).iter() {
+            let (first, second) = keys(*hash);
+            self.decrement(first);
+            self.decrement(second);
+        }
+    }
+
+    /// Returns false if the ancestors in the filter can not have all the given hashes, or true if
+    /// they might.
+    pub fn might_have_all(&mut self, ancestor_hashes: &AncestorHashes) -> bool {
+        if ancestor_hashes.len == 0 {
+            self.matched += 1;
+            return true
+        }
+        self.lookups += 1;
+        for hash in ancestor_hashes.hashes.slice_to(ancestor_hashes.len).iter() {
+            let (first, second) = keys(*hash);
+            if self.counters[first] == 0 || self.counters[second] == 0 {
+                self.rejected += 1;
+                return false
+            }
+        }
+        self.matched += 1;
+        true
+    }
+
+    #[inline]
+    fn increment(&mut self, key: uint) {
+        if self.counters[key] != SATURATED {
+            self.counters[key] += 1;
+        }
+    }
+
+    #[inline]
+    fn decrement(&mut self, key: uint) {
+        if self.counters[key] != SATURATED {
+            self.counters[key] -= 1;
+        }
+    }
+}
+
+#[inline]
+fn hash(atom: &Atom) -> u32 {
+    atom.hash() as u32
+}
+
+#[inline]
+fn keys(hash: u32) -> (uint, uint) {
+    ((hash & KEY_MASK) as uint, ((hash >> KEY_BITS) & KEY_MASK) as uint)
+}
+
+/// The hashes of the local name, id and classes of `element`.
+fn element_hashes<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: ElementLike>(
+        element: &T) -> ~[u32] {
+    do element.with_imm_element_like |element: &E| {
+        let mut hashes = ~[hash(&element.get_local_name())];
+        for id in element.get_id().iter() {
+            hashes.push(hash(id));
+        }
+        for class in element.get_classes().iter() {
+            hashes.push(hash(class));
+        }
+        hashes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AncestorFilter, AncestorHashes, hash, keys};
+    use selector_matching::{NoQuirks, FullQuirks};
+    use selectors::parse_selector_list;
+    use cssparser;
+    use namespaces::NamespaceMap;
+    use servo_util::atom::Atom;
+
+    fn ancestor_hashes(input: &str) -> AncestorHashes {
+        let input = cssparser::tokenize(input).map(|(v, _)| v).to_owned_vec();
+        let selectors = parse_selector_list(input.as_slice(), &NamespaceMap::new()).unwrap();
+        AncestorHashes::new(&selectors[0], NoQuirks)
+    }
+
+    fn add(filter: &mut AncestorFilter, value: &str) {
+        let (first, second) = keys(hash(&Atom::from_slice(value)));
+        filter.increment(first);
+        filter.increment(second);
+    }
+
+    #[test]
+    fn test_ancestor_hashes() {
+        assert_eq!(ancestor_hashes("p").len, 0);
+        assert_eq!(ancestor_hashes("DIV > p.intro").len, 1);
+        assert_eq!(ancestor_hashes("div#main .content p").len, 3);
+        // Siblings are not ancestors.
+        assert_eq!(ancestor_hashes("h1 + p").len, 0);
+        assert_eq!(ancestor_hashes("div h1 ~ p").len, 1);
+        assert_eq!(ancestor_hashes("a b c d e f").len, 4);
+
+        let input = cssparser::tokenize("div#main .content p").map(|(v, _)| v).to_owned_vec();
+        let selectors = parse_selector_list(input.as_slice(), &NamespaceMap::new()).unwrap();
+        assert_eq!(AncestorHashes::new(&selectors[0], FullQuirks).len, 1);
+    }
+
+    #[test]
+    fn test_might_have_all() {
+        let mut filter = AncestorFilter::new();
+        add(&mut filter, "div");
+        add(&mut filter, "main");
+        assert!(filter.might_have_all(&ancestor_hashes("DIV p")));
+        assert!(filter.might_have_all(&ancestor_hashes("div#main > p")));
+        assert!(filter.might_have_all(&ancestor_hashes("p")));
+        assert!(!filter.might_have_all(&ancestor_hashes("ul li")));
+        assert_eq!((filter.rejected, filter.lookups), (1, 3));
+        assert_eq!(filter.matched, 3);
+    }
+}
@@ -12,6 +12,7 @@ pub mod specified {
     use cssparser::ast;
     use cssparser::ast::*;
     use super::{Au, CSSFloat};
+    use serialize::{ToCssText, push_number, push_percentage};
     pub use CSSColor = cssparser::Color;
 
     #[deriving(Clone)]
@@ -19,13 +20,13 @@ pub mod specified {
         Au_(Au),  // application units
         Em(CSSFloat),
         Ex(CSSFloat),
-        // XXX uncomment when supported:
-//        Ch(CSSFloat),
-//        Rem(CSSFloat),
-//        Vw(CSSFloat),
-//        Vh(CSSFloat),
-//        Vmin(CSSFloat),
-//        Vmax(CSSFloat),
+        Ch(CSSFloat),
+        Rem(CSSFloat),
+        Vw(CSSFloat),
+        Vh(CSSFloat),
+        Vmin(CSSFloat),
+        Vmax(CSSFloat),
+        Calc_(Calc),  // without a percentage
     }
     static AU_PER_PX: CSSFloat = 60.;
     static AU_PER_IN: CSSFloat = AU_PER_PX * 96.;
@@ -40,6 +41,11 @@ pub mod specified {
                 &Dimension(ref value, ref unit) if negative_ok || value.value >= 0.
                 => Length::parse_dimension(value.value, unit.as_slice()),
                 &Number(ref value) if value.value == 0. =>  Some(Au_(Au(0))),
+                &Function(ref name, ref arguments) if name.eq_ignore_ascii_case("calc")
+                => match Calc::parse(*arguments) {
+                    Some(calc) if calc.percentage.is_none() => Some(Calc_(calc)),
+                    _ => None,
+                },
                 _ => None
             }
         }
@@ -61,6 +67,12 @@ pub mod specified {
                 "pc" => Some(Au_(Au((value * AU_PER_PC) as i32))),
                 "em" => Some(Em(value)),
                 "ex" => Some(Ex(value)),
+                "ch" => Some(Ch(value)),
+                "rem" => Some(Rem(value)),
+                "vw" => Some(Vw(value)),
+                "vh" => Some(Vh(value)),
+                "vmin" => Some(Vmin(value)),
+                "vmax" => Some(Vmax(value)),
                 _ => None
             }
         }
@@ -69,11 +81,241 @@ pub mod specified {
             Au_(Au((px_value * AU_PER_PX) as i32))
         }
     }
+    impl ToCssText for Length {
+        fn push_css_text(&self, css: &mut ~str) {
+            let (value, unit) = match *self {
+                Au_(value) => return value.push_css_text(css),
+                Em(value) => (value, "em"),
+                Ex(value) => (value, "ex"),
+                Ch(value) => (value, "ch"),
+                Rem(value) => (value, "rem"),
+                Vw(value) => (value, "vw"),
+                Vh(value) => (value, "vh"),
+                Vmin(value) => (value, "vmin"),
+                Vmax(value) => (value, "vmax"),
+                Calc_(ref calc) => return calc.push_css_text(css),
+            };
+            push_number(value, css);
+            css.push_str(unit);
+        }
+    }
+
+    /// A `calc()` expression, as the sum of its terms in each unit. Absolute lengths are added
+    /// up in application units. `percentage` is `None` if there is no percentage at all, and
+    /// percentages are only allowed where the property takes them.
+    ///
+    /// Negative values are not clamped where the property only takes non-negative values.
+    #[deriving(Clone)]
+    pub struct Calc {
+        au: CSSFloat,
+        em: CSSFloat,
+        ex: CSSFloat,
+        ch: CSSFloat,
+        rem: CSSFloat,
+        vw: CSSFloat,
+        vh: CSSFloat,
+        vmin: CSSFloat,
+        vmax: CSSFloat,
+        percentage: Option<CSSFloat>,
+    }
+
+    /// The value of a `calc()` sub-expression.
+    enum CalcValue {
+        CalcNumber(CSSFloat),
+        CalcSum(Calc),
+    }
+
+    impl Calc {
+        /// Parses the arguments of a `calc()` function.
+        pub fn parse(arguments: &[ComponentValue]) -> Option<Calc> {
+            match parse_calc_expression(arguments) {
+                Some(CalcSum(calc)) => Some(calc),
+                _ => None,  // A number is not a length.
+            }
+        }
+
+        fn zero() -> Calc {
+            Calc { au: 0., em: 0., ex: 0., ch: 0., rem: 0., vw: 0., vh: 0., vmin: 0., vmax: 0.,
+                   percentage: None }
+        }
+
+        fn from_length(length: Length) -> Calc {
+            match length {
+                Au_(Au(value)) => Calc { au: value as CSSFloat, .. Calc::zero() },
+                Em(value) => Calc { em: value, .. Calc::zero() },
+                Ex(value) => Calc { ex: value, .. Calc::zero() },
+                Ch(value) => Calc { ch: value, .. Calc::zero() },
+                Rem(value) => Calc { rem: value, .. Calc::zero() },
+                Vw(value) => Calc { vw: value, .. Calc::zero() },
+                Vh(value) => Calc { vh: value, .. Calc::zero() },
+                Vmin(value) => Calc { vmin: value, .. Calc::zero() },
+                Vmax(value) => Calc { vmax: value, .. Calc::zero() },
+                Calc_(calc) => calc,
+            }
+        }
+
+        fn add(&self, other: &Calc) -> Calc {
+            let percentage = match (self.percentage, other.percentage) {
+                (None, None) => None,
+                (a, b) => Some(a.unwrap_or(0.) + b.unwrap_or(0.)),
+            };
+            Calc {
+                au: self.au + other.au,
+                em: self.em + other.em,
+                ex: self.ex + other.ex,
+                ch: self.ch + other.ch,
+                rem: self.rem + other.rem,
+                vw: self.vw + other.vw,
+                vh: self.vh + other.vh,
+                vmin: self.vmin + other.vmin,
+                vmax: self.vmax + other.vmax,
+                percentage: percentage,
+            }
+        }
+
+        fn scale_by(&self, factor: CSSFloat) -> Calc {
+            Calc {
+                au: self.au * factor,
+                em: self.em * factor,
+                ex: self.ex * factor,
+                ch: self.ch * factor,
+                rem: self.rem * factor,
+                vw: self.vw * factor,
+                vh: self.vh * factor,
+                vmin: self.vmin * factor,
+                vmax: self.vmax * factor,
+                percentage: self.percentage.map(|percentage| percentage * factor),
+            }
+        }
+    }
+
+    /// Serialized as the sum of its non-zero terms, e.g. `calc(10px - 2em + 50%)`.
+    impl ToCssText for Calc {
+        fn push_css_text(&self, css: &mut ~str) {
+            let mut terms = ~[];
+            if self.au != 0. { terms.push((self.au / AU_PER_PX, "px")) }
+            if self.em != 0. { terms.push((self.em, "em")) }
+            if self.ex != 0. { terms.push((self.ex, "ex")) }
+            if self.ch != 0. { terms.push((self.ch, "ch")) }
+            if self.rem != 0. { terms.push((self.rem, "rem")) }
+            if self.vw != 0. { terms.push((self.vw, "vw")) }
+            if self.vh != 0. { terms.push((self.vh, "vh")) }
+            if self.vmin != 0. { terms.push((self.vmin, "vmin")) }
+            if self.vmax != 0. { terms.push((self.vmax, "vmax")) }
+            match self.percentage {
+                // Kept even if zero, so that the value still takes percentages once reparsed.
+                Some(percentage) => terms.push((percentage * 100., "%")),
+                None => (),
+            }
+            if terms.is_empty() {
+                terms.push((0., "px"))
+            }
+            css.push_str("calc(");
+            for (i, &(value, unit)) in terms.iter().enumerate() {
+                if i == 0 {
+                    push_number(value, css)
+                } else {
+                    css.push_str(if value < 0. { " - " } else { " + " });
+                    push_number(value.abs(), css)
+                }
+                css.push_str(unit);
+            }
+            css.push_char(')');
+        }
+    }
+
+    /// Parses a whole `calc()` expression, given without the function or the parentheses.
+    fn parse_calc_expression(input: &[ComponentValue]) -> Option<CalcValue> {
+        let tokens: ~[&ComponentValue] = input.skip_whitespace().collect();
+        let mut position = 0;
+        match parse_calc_sum(tokens, &mut position) {
+            Some(value) if position == tokens.len() => Some(value),
+            _ => None,
+        }
+    }
+
+    /// sum: product [ [ '+' | '-' ] product ]*
+    fn parse_calc_sum(tokens: &[&ComponentValue], position: &mut uint) -> Option<CalcValue> {
+        let mut sum = match parse_calc_product(tokens, position) {
+            Some(value) => value,
+            None => return None,
+        };
+        while *position < tokens.len() {
+            let sign = match *tokens[*position] {
+                Delim('+') => 1.,
+                Delim('-') => -1.,
+                _ => break,
+            };
+            *position += 1;
+            let term = match parse_calc_product(tokens, position) {
+                Some(value) => value,
+                None => return None,
+            };
+            sum = match (sum, term) {
+                (CalcNumber(a), CalcNumber(b)) => CalcNumber(a + sign * b),
+                (CalcSum(a), CalcSum(b)) => CalcSum(a.add(&b.scale_by(sign))),
+                _ => return None,  // A number can not be added to a length.
+            };
+        }
+        Some(sum)
+    }
+
+    /// product: value [ '*' value | '/' value ]*, where one side of each operation is a number.
+    fn parse_calc_product(tokens: &[&ComponentValue], position: &mut uint) -> Option<CalcValue> {
+        let mut product = match parse_calc_value(tokens, position) {
+            Some(value) => value,
+            None => return None,
+        };
+        while *position < tokens.len() {
+            let is_division = match *tokens[*position] {
+                Delim('*') => false,
+                Delim('/') => true,
+                _ => break,
+            };
+            *position += 1;
+            let factor = match parse_calc_value(tokens, position) {
+                Some(value) => value,
+                None => return None,
+            };
+            product = match (product, factor, is_division) {
+                (CalcNumber(a), CalcNumber(b), false) => CalcNumber(a * b),
+                (CalcNumber(a), CalcSum(b), false) | (CalcSum(b), CalcNumber(a), false)
+                => CalcSum(b.scale_by(a)),
+                (CalcNumber(a), CalcNumber(b), true) if b != 0. => CalcNumber(a / b),
+                (CalcSum(a), CalcNumber(b), true) if b != 0. => CalcSum(a.scale_by(1. / b)),
+                _ => return None,
+            };
+        }
+        Some(product)
+    }
+
+    /// value: <number> | <length> | <percentage> | '(' sum ')' | calc( sum )
+    fn parse_calc_value(tokens: &[&ComponentValue], position: &mut uint) -> Option<CalcValue> {
+        if *position >= tokens.len() {
+            return None
+        }
+        let token = tokens[*position];
+        *position += 1;
+        match *token {
+            Number(ref value) => Some(CalcNumber(value.value)),
+            Dimension(ref value, ref unit) => Length::parse_dimension(value.value, unit.as_slice())
+                                                  .map(|length| CalcSum(Calc::from_length(length))),
+            ast::Percentage(ref value) => Some(CalcSum(Calc {
+                percentage: Some(value.value / 100.),
+                .. Calc::zero()
+            })),
+            ParenthesisBlock(ref arguments) => parse_calc_expression(*arguments),
+            Function(ref name, ref arguments) if name.eq_ignore_ascii_case("calc")
+            => parse_calc_expression(*arguments),
+            _ => None,
+        }
+    }
 
     #[deriving(Clone)]
     pub enum LengthOrPercentage {
         LP_Length(Length),
         LP_Percentage(CSSFloat),  // [0 .. 100%] maps to [0.0 .. 1.0]
+        LP_Calc(Calc),  // with a percentage
     }
     impl LengthOrPercentage {
         fn parse_internal(input: &ComponentValue, negative_ok: bool)
@@ -84,6 +326,10 @@ pub mod specified {
                 &ast::Percentage(ref value) if negative_ok || value.value >= 0.
                 => Some(LP_Percentage(value.value / 100.)),
                 &Number(ref value) if value.value == 0. =>  Some(LP_Length(Au_(Au(0)))),
+                &Function(ref name, ref arguments) if name.eq_ignore_ascii_case("calc")
+                => do Calc::parse(*arguments).map |calc| {
+                    if calc.percentage.is_some() { LP_Calc(calc) } else { LP_Length(Calc_(calc)) }
+                },
                 _ => None
             }
         }
@@ -95,12 +341,35 @@ pub mod specified {
         pub fn parse_non_negative(input: &ComponentValue) -> Option<LengthOrPercentage> {
             LengthOrPercentage::parse_internal(input, /* negative_ok = */ false)
         }
+        /// For properties where percentages are the same as em, e.g. 'font-size'.
+        pub fn percentage_as_em(self) -> Length {
+            match self {
+                LP_Length(length) => length,
+                LP_Percentage(value) => Em(value),
+                LP_Calc(calc) => Calc_(Calc {
+                    em: calc.em + calc.percentage.unwrap_or(0.),
+                    percentage: None,
+                    .. calc
+                }),
+            }
+        }
+    }
+
+    impl ToCssText for LengthOrPercentage {
+        fn push_css_text(&self, css: &mut ~str) {
+            match *self {
+                LP_Length(ref value) => value.push_css_text(css),
+                LP_Percentage(value) => push_percentage(value, css),
+                LP_Calc(ref value) => value.push_css_text(css),
+            }
+        }
     }
 
     #[deriving(Clone)]
     pub enum LengthOrPercentageOrAuto {
         LPA_Length(Length),
         LPA_Percentage(CSSFloat),  // [0 .. 100%] maps to [0.0 .. 1.0]
+        LPA_Calc(Calc),  // with a percentage
         LPA_Auto,
     }
     impl LengthOrPercentageOrAuto {
@@ -112,6 +381,10 @@ pub mod specified {
                 &ast::Percentage(ref value) if negative_ok || value.value >= 0.
                 => Some(LPA_Percentage(value.value / 100.)),
                 &Number(ref value) if value.value == 0. => Some(LPA_Length(Au_(Au(0)))),
+                &Function(ref name, ref arguments) if name.eq_ignore_ascii_case("calc")
+                => do Calc::parse(*arguments).map |calc| {
+                    if calc.percentage.is_some() { LPA_Calc(calc) } else { LPA_Length(Calc_(calc)) }
+                },
                 &Ident(ref value) if value.eq_ignore_ascii_case("auto") => Some(LPA_Auto),
                 _ => None
             }
@@ -126,10 +399,22 @@ pub mod specified {
         }
     }
 
+    impl ToCssText for LengthOrPercentageOrAuto {
+        fn push_css_text(&self, css: &mut ~str) {
+            match *self {
+                LPA_Length(ref value) => value.push_css_text(css),
+                LPA_Percentage(value) => push_percentage(value, css),
+                LPA_Calc(ref value) => value.push_css_text(css),
+                LPA_Auto => css.push_str("auto"),
+            }
+        }
+    }
+
     #[deriving(Clone)]
     pub enum LengthOrPercentageOrNone {
         LPN_Length(Length),
         LPN_Percentage(CSSFloat),  // [0 .. 100%] maps to [0.0 .. 1.0]
+        LPN_Calc(Calc),  // with a percentage
         LPN_None,
     }
     impl LengthOrPercentageOrNone {
@@ -141,6 +426,10 @@ pub mod specified {
                 &ast::Percentage(ref value) if negative_ok || value.value >= 0.
                 => Some(LPN_Percentage(value.value / 100.)),
                 &Number(ref value) if value.value == 0. => Some(LPN_Length(Au_(Au(0)))),
+                &Function(ref name, ref arguments) if name.eq_ignore_ascii_case("calc")
+                => do Calc::parse(*arguments).map |calc| {
+                    if calc.percentage.is_some() { LPN_Calc(calc) } else { LPN_Length(Calc_(calc)) }
+                },
                 &Ident(ref value) if value.eq_ignore_ascii_case("none") => Some(LPN_None),
                 _ => None
             }
@@ -154,6 +443,17 @@ pub mod specified {
             LengthOrPercentageOrNone::parse_internal(input, /* negative_ok = */ false)
         }
     }
+
+    impl ToCssText for LengthOrPercentageOrNone {
+        fn push_css_text(&self, css: &mut ~str) {
+            match *self {
+                LPN_Length(ref value) => value.push_css_text(css),
+                LPN_Percentage(value) => push_percentage(value, css),
+                LPN_Calc(ref value) => value.push_css_text(css),
+                LPN_None => css.push_str("none"),
+            }
+        }
+    }
 }
 
 pub mod computed {
@@ -162,6 +462,7 @@ pub mod computed {
     pub use compute_CSSColor = super::super::longhands::computed_as_specified;
     use super::*;
     use super::super::longhands;
+    use serialize::{ToCssText, push_percentage};
     pub use servo_util::geometry::Au;
 
     pub struct Context {
@@ -175,7 +476,10 @@ pub mod computed {
         has_border_right: bool,
         has_border_bottom: bool,
         has_border_left: bool,
-        // TODO, as needed: root font size, viewport size, etc.
+        /// The computed 'font-size' of the root element, for 'rem'.
+        root_font_size: Au,
+        viewport_width: Au,
+        viewport_height: Au,
     }
 
     pub fn compute_Au(value: specified::Length, context: &Context) -> Au {
@@ -186,6 +490,80 @@ pub mod computed {
                 let x_height = 0.5;  // TODO: find that from the font
                 context.font_size.scale_by(value * x_height)
             },
+            specified::Ch(value) => {
+                let zero_advance = 0.5;  // TODO: find that from the font
+                context.font_size.scale_by(value * zero_advance)
+            },
+            specified::Rem(value) => context.root_font_size.scale_by(value),
+            specified::Vw(value) => context.viewport_width.scale_by(value / 100.),
+            specified::Vh(value) => context.viewport_height.scale_by(value / 100.),
+            specified::Vmin(value) => {
+                Au::min(context.viewport_width, context.viewport_height).scale_by(value / 100.)
+            },
+            specified::Vmax(value) => {
+                Au::max(context.viewport_width, context.viewport_height).scale_by(value / 100.)
+            },
+            specified::Calc_(calc) => sum_calc_terms(&calc, |length| compute_Au(length, context)),
+        }
+    }
+
+    /// Computes a length that does not belong to any element, e.g. in media queries or
+    /// @page rules. Relative units are based on the initial value of 'font-size'.
+    ///
+    /// FIXME: Viewport units are zero, lengths are computed when parsing, before the viewport
+    /// is known.
+    pub fn compute_Au_without_element(value: specified::Length) -> Au {
+        let initial_font_size = longhands::font_size::get_initial_value();
+        match value {
+            specified::Au_(value) => value,
+            specified::Em(value) | specified::Rem(value) => initial_font_size.scale_by(value),
+            specified::Ex(value) | specified::Ch(value) => initial_font_size.scale_by(value * 0.5),
+            specified::Vw(_) | specified::Vh(_) | specified::Vmin(_) | specified::Vmax(_) => Au(0),
+            specified::Calc_(calc) => sum_calc_terms(&calc, compute_Au_without_element),
+        }
+    }
+
+    /// Adds up the lengths of a `calc()` expression, computed with `compute_length`. The
+    /// percentage, if any, is left out.
+    fn sum_calc_terms(calc: &specified::Calc, compute_length: &fn(specified::Length) -> Au)
+                      -> Au {
+        Au(calc.au as i32) +
+            compute_length(specified::Em(calc.em)) +
+            compute_length(specified::Ex(calc.ex)) +
+            compute_length(specified::Ch(calc.ch)) +
+            compute_length(specified::Rem(calc.rem)) +
+            compute_length(specified::Vw(calc.vw)) +
+            compute_length(specified::Vh(calc.vh)) +
+            compute_length(specified::Vmin(calc.vmin)) +
+            compute_length(specified::Vmax(calc.vmax))
+    }
+
+    /// A `calc()` expression with a percentage, which can only be resolved during layout.
+    #[deriving(Eq, Clone)]
+    pub struct Calc {
+        length: Au,
+        percentage: CSSFloat,
+    }
+    impl Calc {
+        /// Returns the used value, given the length that percentages refer to.
+        #[inline]
+        pub fn resolve(&self, reference: Au) -> Au {
+            self.length + reference.scale_by(self.percentage)
+        }
+    }
+    impl ToCssText for Calc {
+        fn push_css_text(&self, css: &mut ~str) {
+            css.push_str("calc(");
+            self.length.push_css_text(css);
+            css.push_str(if self.percentage < 0. { " - " } else { " + " });
+            push_percentage(self.percentage.abs(), css);
+            css.push_char(')');
+        }
+    }
+    pub fn compute_Calc(value: specified::Calc, context: &Context) -> Calc {
+        Calc {
+            length: sum_calc_terms(&value, |length| compute_Au(length, context)),
+            percentage: value.percentage.unwrap_or(0.),
         }
     }
 
@@ -193,12 +571,23 @@ pub mod computed {
     pub enum LengthOrPercentage {
         LP_Length(Au),
         LP_Percentage(CSSFloat),
+        LP_Calc(Calc),
+    }
+    impl ToCssText for LengthOrPercentage {
+        fn push_css_text(&self, css: &mut ~str) {
+            match *self {
+                LP_Length(ref value) => value.push_css_text(css),
+                LP_Percentage(value) => push_percentage(value, css),
+                LP_Calc(ref value) => value.push_css_text(css),
+            }
+        }
     }
     pub fn compute_LengthOrPercentage(value: specified::LengthOrPercentage, context: &Context)
                                    -> LengthOrPercentage {
         match value {
             specified::LP_Length(value) => LP_Length(compute_Au(value, context)),
             specified::LP_Percentage(value) => LP_Percentage(value),
+            specified::LP_Calc(value) => LP_Calc(compute_Calc(value, context)),
         }
     }
 
@@ -206,13 +595,25 @@ pub mod computed {
     pub enum LengthOrPercentageOrAuto {
         LPA_Length(Au),
         LPA_Percentage(CSSFloat),
+        LPA_Calc(Calc),
         LPA_Auto,
     }
+    impl ToCssText for LengthOrPercentageOrAuto {
+        fn push_css_text(&self, css: &mut ~str) {
+            match *self {
+                LPA_Length(ref value) => value.push_css_text(css),
+                LPA_Percentage(value) => push_percentage(value, css),
+                LPA_Calc(ref value) => value.push_css_text(css),
+                LPA_Auto => css.push_str("auto"),
+            }
+        }
+    }
     pub fn compute_LengthOrPercentageOrAuto(value: specified::LengthOrPercentageOrAuto,
                                             context: &Context) -> LengthOrPercentageOrAuto {
         match value {
             specified::LPA_Length(value) => LPA_Length(compute_Au(value, context)),
             specified::LPA_Percentage(value) => LPA_Percentage(value),
+            specified::LPA_Calc(value) => LPA_Calc(compute_Calc(value, context)),
             specified::LPA_Auto => LPA_Auto,
         }
     }
@@ -221,13 +622,25 @@ pub mod computed {
     pub enum LengthOrPercentageOrNone {
         LPN_Length(Au),
         LPN_Percentage(CSSFloat),
+        LPN_Calc(Calc),
         LPN_None,
     }
+    impl ToCssText for LengthOrPercentageOrNone {
+        fn push_css_text(&self, css: &mut ~str) {
+            match *self {
+                LPN_Length(ref value) => value.push_css_text(css),
+                LPN_Percentage(value) => push_percentage(value, css),
+                LPN_Calc(ref value) => value.push_css_text(css),
+                LPN_None => css.push_str("none"),
+            }
+        }
+    }
     pub fn compute_LengthOrPercentageOrNone(value: specified::LengthOrPercentageOrNone,
                                             context: &Context) -> LengthOrPercentageOrNone {
         match value {
             specified::LPN_Length(value) => LPN_Length(compute_Au(value, context)),
             specified::LPN_Percentage(value) => LPN_Percentage(value),
+            specified::LPN_Calc(value) => LPN_Calc(compute_Calc(value, context)),
             specified::LPN_None => LPN_None,
         }
     }
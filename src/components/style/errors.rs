@@ -2,38 +2,116 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use std::local_data;
 use cssparser::ast::{SyntaxError, SourceLocation};
+use extra::url::Url;
 
 
-pub struct ErrorLoggerIterator<I>(I);
+/// The kinds of errors found while parsing CSS.
+#[deriving(Eq, Clone)]
+pub enum CSSErrorCategory {
+    /// Input that is not a rule or a declaration at all.
+    InvalidSyntax,
+    InvalidSelector,
+    UnknownProperty,
+    InvalidValue,
+    /// An at-rule that is invalid, in the wrong place or not supported.
+    InvalidAtRule,
+    /// An imported style sheet that could not be loaded.
+    InvalidImport,
+}
 
-impl<T, I: Iterator<Result<T, SyntaxError>>> Iterator<T> for ErrorLoggerIterator<I> {
-    fn next(&mut self) -> Option<T> {
-        for result in **self {
-            match result {
-                Ok(v) => return Some(v),
-                Err(error) => log_css_error(error.location, format!("{:?}", error.reason))
-            }
-        }
-        None
+
+#[deriving(Clone)]
+pub struct CSSError {
+    /// The style sheet, or the document of the style attribute, where the error is.
+    url: Url,
+    line: uint,
+    column: uint,
+    category: CSSErrorCategory,
+    message: ~str,
+}
+
+impl ToStr for CSSError {
+    fn to_str(&self) -> ~str {
+        format!("{:s}:{:u}:{:u} {:s}", self.url.to_str(), self.line, self.column, self.message)
     }
 }
 
 
-local_data_key!(silence_errors: ())
+/// Receives the errors found while parsing a style sheet or a style attribute.
+pub trait ErrorReporter {
+    fn report_error(&mut self, error: CSSError);
+}
+
+/// Logs errors to stderr.
+pub struct LogErrorReporter;
 
-pub fn log_css_error(location: SourceLocation, message: &str) {
-    // TODO eventually this will got into a "web console" or something.
-    if local_data::get(silence_errors, |silenced| silenced.is_none()) {
-        error!("{:u}:{:u} {:s}", location.line, location.column, message)
+impl ErrorReporter for LogErrorReporter {
+    fn report_error(&mut self, error: CSSError) {
+        error!("{:s}", error.to_str())
     }
 }
 
+/// Ignores errors, e.g. those of the user agent style sheet, which uses properties that are not
+/// supported yet.
+pub struct SilentErrorReporter;
+
+impl ErrorReporter for SilentErrorReporter {
+    fn report_error(&mut self, _error: CSSError) {}
+}
+
+/// Keeps errors so that they can be read back after parsing.
+pub struct ErrorCollector {
+    errors: ~[CSSError],
+}
+
+impl ErrorCollector {
+    pub fn new() -> ErrorCollector {
+        ErrorCollector {
+            errors: ~[],
+        }
+    }
+}
 
-pub fn with_errors_silenced<T>(f: &fn() -> T) -> T {
-    local_data::set(silence_errors, ());
-    let result = f();
-    local_data::pop(silence_errors);
-    result
+impl ErrorReporter for ErrorCollector {
+    fn report_error(&mut self, error: CSSError) {
+        self.errors.push(error)
+    }
+}
+
+
+/// The error reporter of the style sheet or style attribute being parsed, and the URL that its
+/// errors are reported with.
+pub struct ErrorContext<'a> {
+    priv url: Url,
+    priv reporter: &'a mut ErrorReporter,
+}
+
+impl<'a> ErrorContext<'a> {
+    pub fn new(url: Url, reporter: &'a mut ErrorReporter) -> ErrorContext<'a> {
+        ErrorContext {
+            url: url,
+            reporter: reporter,
+        }
+    }
+
+    pub fn report(&mut self, location: SourceLocation, category: CSSErrorCategory,
+                  message: &str) {
+        self.reporter.report_error(CSSError {
+            url: self.url.clone(),
+            line: location.line,
+            column: location.column,
+            category: category,
+            message: message.to_owned(),
+        })
+    }
+
+    pub fn report_syntax_error(&mut self, error: SyntaxError) {
+        self.report(error.location, InvalidSyntax, format!("{:?}", error.reason))
+    }
+
+    /// The error reporter itself, e.g. to give to the style sheets imported by this one.
+    pub fn reporter<'b>(&'b mut self) -> &'b mut ErrorReporter {
+        &mut *self.reporter
+    }
 }
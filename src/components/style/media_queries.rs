@@ -3,66 +3,161 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use std::ascii::StrAsciiExt;
+use extra::url::Url;
 use cssparser::parse_rule_list;
 use cssparser::ast::*;
 
-use errors::{ErrorLoggerIterator, log_css_error};
+use errors::{ErrorContext, InvalidAtRule};
 use stylesheets::{CSSRule, CSSMediaRule, parse_style_rule, parse_nested_at_rule};
 use namespaces::NamespaceMap;
+use properties::common_types::{Au, CSSFloat, specified, computed};
+use serialize::{ToCssText, push_identifier, push_number};
 
 
+#[deriving(Clone)]
 pub struct MediaRule {
     media_queries: MediaQueryList,
     rules: ~[CSSRule],
 }
 
 
+#[deriving(Clone)]
 pub struct MediaQueryList {
     // "not all" is omitted from the list.
     // An empty list never matches.
     media_queries: ~[MediaQuery]
 }
 
-// For now, this is a "Level 2 MQ", ie. a media type.
-struct MediaQuery {
+/// A Level 3 media query: an optionally negated media type and a list of expressions
+/// that must all be true.
+#[deriving(Clone)]
+pub struct MediaQuery {
+    negated: bool,
     media_type: MediaQueryType,
-    // TODO: Level 3 MQ expressions
+    expressions: ~[Expression],
 }
 
 
+#[deriving(Clone)]
 enum MediaQueryType {
     All,  // Always true
     MediaType(MediaType),
+    UnknownType(~str),  // Never true, but "not" may apply to it. Lowercased.
 }
 
-#[deriving(Eq)]
+#[deriving(Eq, Clone)]
 pub enum MediaType {
     Screen,
     Print,
 }
 
+#[deriving(Eq, Clone)]
+pub enum Orientation {
+    Portrait,
+    Landscape,
+}
+
+/// The "min-", "max-" or unprefixed form of a range media feature.
+#[deriving(Eq, Clone)]
+pub enum Range<T> {
+    Min(T),
+    Max(T),
+    Exact(T),
+}
+
+impl<T: Ord> Range<T> {
+    fn evaluate(&self, value: T) -> bool {
+        match *self {
+            Min(ref width) => value >= *width,
+            Max(ref width) => value <= *width,
+            Exact(ref width) => value == *width,
+        }
+    }
+}
+
+#[deriving(Eq, Clone)]
+pub enum Expression {
+    Width(Range<Au>),
+    Height(Range<Au>),
+    DeviceOrientation(Orientation),
+    /// Width and height of the ratio, both strictly positive.
+    AspectRatio(Range<(u32, u32)>),
+    /// In dots per CSS pixel (dppx).
+    Resolution(Range<CSSFloat>),
+}
+
+impl Expression {
+    fn evaluate(&self, device: &Device) -> bool {
+        match *self {
+            Width(ref range) => range.evaluate(device.viewport_width),
+            Height(ref range) => range.evaluate(device.viewport_height),
+            DeviceOrientation(orientation) => orientation == device.orientation(),
+            AspectRatio(ref range) => {
+                // Compare width / height with a / b as width * b with height * a,
+                // to avoid rounding errors.
+                let width = (*device.viewport_width) as i64;
+                let height = (*device.viewport_height) as i64;
+                match *range {
+                    Min((a, b)) => width * (b as i64) >= height * (a as i64),
+                    Max((a, b)) => width * (b as i64) <= height * (a as i64),
+                    Exact((a, b)) => width * (b as i64) == height * (a as i64),
+                }
+            }
+            Resolution(ref range) => range.evaluate(device.device_pixel_ratio),
+        }
+    }
+}
+
 pub struct Device {
     media_type: MediaType,
-    // TODO: Level 3 MQ data: viewport size, etc.
+    viewport_width: Au,
+    viewport_height: Au,
+    /// Number of device pixels per CSS pixel.
+    device_pixel_ratio: CSSFloat,
+}
+
+impl Device {
+    pub fn new(media_type: MediaType, viewport_width: Au, viewport_height: Au,
+               device_pixel_ratio: CSSFloat) -> Device {
+        Device {
+            media_type: media_type,
+            viewport_width: viewport_width,
+            viewport_height: viewport_height,
+            device_pixel_ratio: device_pixel_ratio,
+        }
+    }
+
+    #[inline]
+    pub fn orientation(&self) -> Orientation {
+        if self.viewport_height >= self.viewport_width { Portrait } else { Landscape }
+    }
 }
 
 
 pub fn parse_media_rule(rule: AtRule, parent_rules: &mut ~[CSSRule],
-                        namespaces: &NamespaceMap) {
+                        namespaces: &NamespaceMap, base_url: &Url, errors: &mut ErrorContext) {
     let media_queries = parse_media_query_list(rule.prelude);
     let block = match rule.block {
         Some(block) => block,
         None => {
-            log_css_error(rule.location, "Invalid @media rule");
+            errors.report(rule.location, InvalidAtRule, "Invalid @media rule");
             return
         }
     };
     let mut rules = ~[];
-    for rule in ErrorLoggerIterator(parse_rule_list(block.move_iter())) {
+    for result in parse_rule_list(block.move_iter()) {
+        let rule = match result {
+            Ok(rule) => rule,
+            Err(error) => {
+                errors.report_syntax_error(error);
+                continue
+            }
+        };
         match rule {
-            QualifiedRule(rule) => parse_style_rule(rule, &mut rules, namespaces),
+            QualifiedRule(rule) => parse_style_rule(rule, &mut rules, namespaces, base_url,
+                                                    errors),
             AtRule(rule) => parse_nested_at_rule(
-                rule.name.to_ascii_lower(), rule, &mut rules, namespaces),
+                rule.name.to_ascii_lower(), rule, &mut rules, namespaces, base_url, errors),
         }
     }
     parent_rules.push(CSSMediaRule(MediaRule {
@@ -72,49 +167,156 @@ pub fn parse_media_rule(rule: AtRule, parent_rules: &mut ~[CSSRule],
 }
 
 
+/// media_query_list: S* [media_query [ ',' S* media_query ]* ]?
 pub fn parse_media_query_list(input: &[ComponentValue]) -> MediaQueryList {
-    let iter = &mut input.skip_whitespace();
-    let mut next = iter.next();
-    if next.is_none() {
-        return MediaQueryList{ media_queries: ~[MediaQuery{media_type: All}] }
+    if input.skip_whitespace().next().is_none() {
+        return MediaQueryList{ media_queries: ~[MediaQuery{
+            negated: false, media_type: All, expressions: ~[] }] }
     }
     let mut queries = ~[];
+    for part in input.split_iter(|component_value| *component_value == Comma) {
+        // Invalid queries are "not all", ie. omitted from the list.
+        for mq in parse_media_query(part).move_iter() {
+            queries.push(mq);
+        }
+    }
+    MediaQueryList{ media_queries: queries }
+}
+
+
+/// media_query: [ONLY | NOT]? S* media_type S* [ AND S* expression ]*
+///            | expression [ AND S* expression ]*
+fn parse_media_query(input: &[ComponentValue]) -> Option<MediaQuery> {
+    let iter = &mut input.skip_whitespace();
+    let mut negated = false;
+    let mut expressions = ~[];
+    let media_type = match iter.next() {
+        Some(&Ident(ref value)) => {
+            // FIXME: Workaround for https://github.com/mozilla/rust/issues/10683
+            let mut value_lower = value.to_ascii_lower();
+            if "not" == value_lower || "only" == value_lower {
+                negated = "not" == value_lower;
+                value_lower = match iter.next() {
+                    Some(&Ident(ref value)) => value.to_ascii_lower(),
+                    _ => return None,
+                };
+            }
+            match value_lower.as_slice() {
+                "screen" => MediaType(Screen),
+                "print" => MediaType(Print),
+                "all" => All,
+                // "not", "only", "and" are not valid media types.
+                "not" | "only" | "and" => return None,
+                _ => UnknownType(value_lower.clone()),
+            }
+        },
+        Some(&ParenthesisBlock(ref block)) => {
+            match parse_expression(block.as_slice()) {
+                Some(expression) => expressions.push(expression),
+                None => return None,
+            }
+            All
+        },
+        _ => return None,
+    };
     loop {
-        let mq = match next {
-            Some(&Ident(ref value)) => {
-                // FIXME: Workaround for https://github.com/mozilla/rust/issues/10683
-                let value_lower = value.to_ascii_lower(); 
-                match value_lower.as_slice() {
-                    "screen" => Some(MediaQuery{ media_type: MediaType(Screen) }),
-                    "print" => Some(MediaQuery{ media_type: MediaType(Print) }),
-                    "all" => Some(MediaQuery{ media_type: All }),
-                    _ => None
-                }
+        match iter.next() {
+            None => break,
+            Some(&Ident(ref value)) if value.eq_ignore_ascii_case("and") => (),
+            _ => return None,
+        }
+        match iter.next() {
+            Some(&ParenthesisBlock(ref block)) => match parse_expression(block.as_slice()) {
+                Some(expression) => expressions.push(expression),
+                None => return None,
             },
-            _ => None
+            _ => return None,
+        }
+    }
+    Some(MediaQuery{ negated: negated, media_type: media_type, expressions: expressions })
+}
+
+
+/// expression: '(' S* media_feature S* [ ':' S* expr ]? ')' S*
+///
+/// Only the features we support are accepted, and all of them require a value.
+fn parse_expression(input: &[ComponentValue]) -> Option<Expression> {
+    let iter = &mut input.skip_whitespace();
+    let name = match iter.next() {
+        Some(&Ident(ref name)) => name.to_ascii_lower(),
+        _ => return None,
+    };
+    match iter.next() {
+        Some(&Colon) => (),
+        _ => return None,
+    }
+    let value: ~[&ComponentValue] = iter.collect();
+    let value = value.as_slice();
+    let (feature, range_kind) = if name.starts_with("min-") {
+        (name.slice_from(4), Some(true))
+    } else if name.starts_with("max-") {
+        (name.slice_from(4), Some(false))
+    } else {
+        (name.as_slice(), None)
+    };
+    macro_rules! range(
+        ($value: expr) => {
+            match range_kind {
+                Some(true) => Min($value),
+                Some(false) => Max($value),
+                None => Exact($value),
+            }
         };
-        match iter.next() {
-            None => {
-                for mq in mq.move_iter() {
-                    queries.push(mq);
+    )
+    match feature {
+        "width" => parse_length(value).map(|length| Width(range!(length))),
+        "height" => parse_length(value).map(|length| Height(range!(length))),
+        "orientation" if range_kind.is_none() => match value {
+            [&Ident(ref value)] => {
+                // FIXME: Workaround for https://github.com/mozilla/rust/issues/10683
+                let value_lower = value.to_ascii_lower();
+                match value_lower.as_slice() {
+                    "portrait" => Some(DeviceOrientation(Portrait)),
+                    "landscape" => Some(DeviceOrientation(Landscape)),
+                    _ => None,
                 }
-                return MediaQueryList{ media_queries: queries }
             },
-            Some(&Comma) => {
-                for mq in mq.move_iter() {
-                    queries.push(mq);
-                }
+            _ => None,
+        },
+        "aspect-ratio" => match value {
+            [&Number(ref a), &Delim('/'), &Number(ref b)] => match (a.int_value, b.int_value) {
+                (Some(a), Some(b)) if a > 0 && b > 0
+                => Some(AspectRatio(range!((a as u32, b as u32)))),
+                _ => None,
             },
-            // Ingnore this comma-separated part
-            _ => loop {
-                match iter.next() {
-                    Some(&Comma) => break,
-                    None => return MediaQueryList{ media_queries: queries },
-                    _ => (),
-                }
+            _ => None,
+        },
+        "resolution" => match value {
+            [&Dimension(ref value, ref unit)] if value.value > 0. => {
+                // FIXME: Workaround for https://github.com/mozilla/rust/issues/10683
+                let unit_lower = unit.to_ascii_lower();
+                let dppx = match unit_lower.as_slice() {
+                    "dppx" => value.value,
+                    "dpi" => value.value / 96.,
+                    "dpcm" => value.value * 2.54 / 96.,
+                    _ => return None,
+                };
+                Some(Resolution(range!(dppx)))
             },
-        }
-        next = iter.next();
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+
+/// Lengths in media queries are non-negative, and relative units are based on the
+/// initial value of the 'font-size' property.
+fn parse_length(value: &[&ComponentValue]) -> Option<Au> {
+    match value {
+        [value] => specified::Length::parse_non_negative(value)
+                       .map(computed::compute_Au_without_element),
+        _ => None,
     }
 }
 
@@ -122,11 +324,156 @@ pub fn parse_media_query_list(input: &[ComponentValue]) -> MediaQueryList {
 impl MediaQueryList {
     pub fn evaluate(&self, device: &Device) -> bool {
         do self.media_queries.iter().any |mq| {
-            match mq.media_type {
+            let media_type_matches = match mq.media_type {
                 MediaType(media_type) => media_type == device.media_type,
                 All => true,
+                UnknownType(_) => false,
+            };
+            let matches = media_type_matches &&
+                mq.expressions.iter().all(|expression| expression.evaluate(device));
+            matches != mq.negated
+        }
+    }
+}
+
+
+/// An empty list, which never matches, is serialized as "not all".
+impl ToCssText for MediaQueryList {
+    fn push_css_text(&self, css: &mut ~str) {
+        if self.media_queries.is_empty() {
+            css.push_str("not all");
+            return
+        }
+        for (i, mq) in self.media_queries.iter().enumerate() {
+            if i > 0 { css.push_str(", ") }
+            mq.push_css_text(css)
+        }
+    }
+}
+
+impl ToCssText for MediaQuery {
+    fn push_css_text(&self, css: &mut ~str) {
+        if self.negated { css.push_str("not ") }
+        let mut first = match self.media_type {
+            // "all and" is implied before an expression.
+            All if !self.negated && !self.expressions.is_empty() => true,
+            All => { css.push_str("all"); false }
+            MediaType(Screen) => { css.push_str("screen"); false }
+            MediaType(Print) => { css.push_str("print"); false }
+            UnknownType(ref name) => { push_identifier(name.as_slice(), css); false }
+        };
+        for expression in self.expressions.iter() {
+            if !first { css.push_str(" and ") }
+            expression.push_css_text(css);
+            first = false;
+        }
+    }
+}
+
+impl ToCssText for Orientation {
+    fn push_css_text(&self, css: &mut ~str) {
+        css.push_str(match *self { Portrait => "portrait", Landscape => "landscape" })
+    }
+}
+
+impl ToCssText for Expression {
+    fn push_css_text(&self, css: &mut ~str) {
+        fn push_range<T>(feature: &str, range: &Range<T>, css: &mut ~str,
+                         push_value: &fn(&T, &mut ~str)) {
+            css.push_char('(');
+            let value = match *range {
+                Min(ref value) => { css.push_str("min-"); value }
+                Max(ref value) => { css.push_str("max-"); value }
+                Exact(ref value) => value,
+            };
+            css.push_str(feature);
+            css.push_str(": ");
+            push_value(value, css);
+            css.push_char(')');
+        }
+        match *self {
+            Width(ref range) => push_range("width", range, css, |v, css| v.push_css_text(css)),
+            Height(ref range) => push_range("height", range, css, |v, css| v.push_css_text(css)),
+            DeviceOrientation(orientation) => {
+                css.push_str("(orientation: ");
+                orientation.push_css_text(css);
+                css.push_char(')');
             }
-            // TODO: match Level 3 expressions
+            AspectRatio(ref range) => push_range("aspect-ratio", range, css, |&(a, b), css| {
+                css.push_str(format!("{}/{}", a, b))
+            }),
+            Resolution(ref range) => push_range("resolution", range, css, |&dppx, css| {
+                push_number(dppx as f64, css);
+                css.push_str("dppx");
+            }),
         }
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use cssparser;
+    use super::*;
+    use properties::common_types::Au;
+    use serialize::ToCssText;
+
+    fn parse(input: &str) -> MediaQueryList {
+        let input = cssparser::tokenize(input).map(|(v, _)| v).to_owned_vec();
+        parse_media_query_list(input)
+    }
+
+    fn evaluate(input: &str, device: &Device) -> bool {
+        parse(input).evaluate(device)
+    }
+
+    #[test]
+    fn test_media_types() {
+        let screen = &Device::new(Screen, Au::from_px(800), Au::from_px(600), 1.);
+        assert!(evaluate("", screen));
+        assert!(evaluate("all", screen));
+        assert!(evaluate("screen, print", screen));
+        assert!(!evaluate("print", screen));
+        assert!(evaluate("not print", screen));
+        assert!(evaluate("only screen", screen));
+        assert!(!evaluate("tv", screen));
+        assert!(evaluate("not tv", screen));
+        assert!(!evaluate("screen and", screen));
+    }
+
+    #[test]
+    fn test_expressions() {
+        let screen = &Device::new(Screen, Au::from_px(800), Au::from_px(600), 2.);
+        assert!(evaluate("(min-width: 800px)", screen));
+        assert!(!evaluate("(min-width: 801px)", screen));
+        assert!(evaluate("screen and (max-width: 50em) and (min-height: 500px)", screen));
+        assert!(!evaluate("print and (max-width: 50em)", screen));
+        assert!(evaluate("not screen and (max-height: 400px)", screen));
+        assert!(evaluate("(orientation: landscape)", screen));
+        assert!(!evaluate("(orientation: portrait)", screen));
+        assert!(evaluate("(aspect-ratio: 4/3)", screen));
+        assert!(evaluate("(min-aspect-ratio: 1/1)", screen));
+        assert!(!evaluate("(min-aspect-ratio: 16/9)", screen));
+        assert!(evaluate("(min-resolution: 2dppx)", screen));
+        assert!(evaluate("(resolution: 192dpi)", screen));
+        assert!(!evaluate("(min-resolution: 300dpi)", screen));
+        // Unknown features and invalid values make the query "not all".
+        assert!(!evaluate("(min-color: 8)", screen));
+        assert!(!evaluate("(min-width: -1px)", screen));
+        assert!(evaluate("(min-color: 8), (max-width: 1000px)", screen));
+    }
+
+    #[test]
+    fn test_serialization() {
+        assert_eq!(parse("").to_css_text(), ~"all");
+        assert_eq!(parse("SCREEN, only print").to_css_text(), ~"screen, print");
+        assert_eq!(parse("not tv").to_css_text(), ~"not tv");
+        assert_eq!(parse("screen and").to_css_text(), ~"not all");
+        assert_eq!(parse("(min-width: 50em) and (orientation: portrait)").to_css_text(),
+                   ~"(min-width: 800px) and (orientation: portrait)");
+        assert_eq!(parse("not all and (max-aspect-ratio: 16/9)").to_css_text(),
+                   ~"not all and (max-aspect-ratio: 16/9)");
+        assert_eq!(parse("print and (resolution: 192dpi)").to_css_text(),
+                   ~"print and (resolution: 2dppx)");
+    }
+}
@@ -3,9 +3,12 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use std::hashmap::HashMap;
+use extra::sort::tim_sort;
 use cssparser::ast::*;
-use errors::log_css_error;
+use errors::{ErrorContext, InvalidAtRule};
+use serialize::{ToCssText, push_identifier, push_string};
 
+#[deriving(Clone)]
 pub struct NamespaceMap {
     default: Option<~str>,  // Optional URL
     prefix_map: HashMap<~str, ~str>,  // prefix -> URL
@@ -19,11 +22,37 @@ impl NamespaceMap {
 }
 
 
-pub fn parse_namespace_rule(rule: AtRule, namespaces: &mut NamespaceMap) {
+/// Serialized as @namespace rules, one per line: the default namespace first, then the
+/// prefixes in alphabetical order.
+impl ToCssText for NamespaceMap {
+    fn push_css_text(&self, css: &mut ~str) {
+        let mut rules = ~[];
+        for url in self.default.iter() {
+            let mut rule = ~"@namespace url(";
+            push_string(url.as_slice(), &mut rule);
+            rule.push_str(");");
+            rules.push(rule)
+        }
+        let mut prefixes: ~[~str] = self.prefix_map.keys().map(|prefix| prefix.clone()).collect();
+        tim_sort(prefixes);
+        for prefix in prefixes.iter() {
+            let mut rule = ~"@namespace ";
+            push_identifier(prefix.as_slice(), &mut rule);
+            rule.push_str(" url(");
+            push_string(self.prefix_map.get(prefix).as_slice(), &mut rule);
+            rule.push_str(");");
+            rules.push(rule)
+        }
+        css.push_str(rules.connect("\n"))
+    }
+}
+
+pub fn parse_namespace_rule(rule: AtRule, namespaces: &mut NamespaceMap,
+                            errors: &mut ErrorContext) {
     let location = rule.location;
     macro_rules! syntax_error(
         () => {{
-            log_css_error(location, "Invalid @namespace rule");
+            errors.report(location, InvalidAtRule, "Invalid @namespace rule");
             return
         }};
     );
@@ -49,12 +78,12 @@ pub fn parse_namespace_rule(rule: AtRule, namespaces: &mut NamespaceMap) {
     match (prefix, url) {
         (Some(prefix), Some(url)) => {
             if namespaces.prefix_map.swap(prefix, url).is_some() {
-                log_css_error(location, "Duplicate @namespace rule");
+                errors.report(location, InvalidAtRule, "Duplicate @namespace rule");
             }
         },
         (None, Some(url)) => {
             if namespaces.default.is_some() {
-                log_css_error(location, "Duplicate @namespace rule");
+                errors.report(location, InvalidAtRule, "Duplicate @namespace rule");
             }
             namespaces.default = Some(url);
         },
@@ -0,0 +1,309 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::ascii::StrAsciiExt;
+use cssparser::{parse_declaration_list, ToCss};
+use cssparser::ast::*;
+
+use errors::{ErrorContext, InvalidAtRule, InvalidValue};
+use stylesheets::{CSSRule, CSSPageRule};
+use media_queries::{Orientation, Portrait, Landscape};
+use parsing_utils::get_ident_lower;
+use properties::common_types::{Au, specified, computed};
+use serialize::ToCssText;
+
+
+/// An @page rule. Page selectors (:first, :left, :right) are not supported.
+#[deriving(Clone)]
+pub struct PageRule {
+    size: Option<PageSize>,
+    margin_top: Option<specified::LengthOrPercentageOrAuto>,
+    margin_right: Option<specified::LengthOrPercentageOrAuto>,
+    margin_bottom: Option<specified::LengthOrPercentageOrAuto>,
+    margin_left: Option<specified::LengthOrPercentageOrAuto>,
+}
+
+
+#[deriving(Eq, Clone)]
+pub enum PageSize {
+    /// The size of the output device, rotated to the given orientation if any.
+    AutoSize(Option<Orientation>),
+    /// Width and height.
+    ExplicitSize(Au, Au),
+}
+
+
+/// The used size and margins of the page box.
+#[deriving(Eq, Clone)]
+pub struct PageBox {
+    width: Au,
+    height: Au,
+    margin_top: Au,
+    margin_right: Au,
+    margin_bottom: Au,
+    margin_left: Au,
+}
+
+impl PageBox {
+    /// Cascades @page rules given in order of increasing precedence. `device_width` and
+    /// `device_height` give the page size when 'size' is 'auto'.
+    pub fn cascade(rules: &[&PageRule], device_width: Au, device_height: Au) -> PageBox {
+        let mut size = AutoSize(None);
+        let mut margins = [None, None, None, None];
+        for rule in rules.iter() {
+            for value in rule.size.iter() {
+                size = value.clone()
+            }
+            for (margin, value) in margins.mut_iter().zip(
+                    [&rule.margin_top, &rule.margin_right,
+                     &rule.margin_bottom, &rule.margin_left].iter()) {
+                if value.is_some() {
+                    *margin = (**value).clone()
+                }
+            }
+        }
+        let (width, height) = match size {
+            AutoSize(None) => (device_width, device_height),
+            AutoSize(Some(orientation)) => {
+                let (short, long) = if device_width < device_height {
+                    (device_width, device_height)
+                } else {
+                    (device_height, device_width)
+                };
+                match orientation {
+                    Portrait => (short, long),
+                    Landscape => (long, short),
+                }
+            }
+            ExplicitSize(width, height) => (width, height),
+        };
+        // Percentages refer to the width of the page box for horizontal margins and to its
+        // height for vertical ones. 'auto' margins are zero.
+        let compute = |margin: &Option<specified::LengthOrPercentageOrAuto>, reference: Au| {
+            match *margin {
+                Some(specified::LPA_Length(length)) => computed::compute_Au_without_element(length),
+                Some(specified::LPA_Percentage(percentage)) => reference.scale_by(percentage),
+                Some(specified::LPA_Calc(calc)) => {
+                    computed::compute_Au_without_element(specified::Calc_(calc)) +
+                        reference.scale_by(calc.percentage.unwrap_or(0.))
+                }
+                Some(specified::LPA_Auto) | None => Au(0),
+            }
+        };
+        PageBox {
+            width: width,
+            height: height,
+            margin_top: compute(&margins[0], height),
+            margin_right: compute(&margins[1], width),
+            margin_bottom: compute(&margins[2], height),
+            margin_left: compute(&margins[3], width),
+        }
+    }
+
+    /// The width of the page area, where the content of the document is laid out.
+    pub fn content_width(&self) -> Au {
+        Au::max(self.width - self.margin_left - self.margin_right, Au(0))
+    }
+
+    /// The height of the page area, where the content of the document is laid out.
+    pub fn content_height(&self) -> Au {
+        Au::max(self.height - self.margin_top - self.margin_bottom, Au(0))
+    }
+}
+
+
+/// @page S* '{' S* declaration? [ ';' S* declaration? ]* '}' S*
+pub fn parse_page_rule(rule: AtRule, parent_rules: &mut ~[CSSRule], errors: &mut ErrorContext) {
+    let location = rule.location;
+    if rule.prelude.skip_whitespace().next().is_some() {
+        errors.report(location, InvalidAtRule, "Unsupported @page selector");
+        return
+    }
+    let block = match rule.block {
+        Some(block) => block,
+        None => {
+            errors.report(location, InvalidAtRule, "Invalid @page rule");
+            return
+        }
+    };
+    let mut page_rule = PageRule {
+        size: None,
+        margin_top: None,
+        margin_right: None,
+        margin_bottom: None,
+        margin_left: None,
+    };
+    for result in parse_declaration_list(block.move_iter()) {
+        let item = match result {
+            Ok(item) => item,
+            Err(error) => {
+                errors.report_syntax_error(error);
+                continue
+            }
+        };
+        match item {
+            Decl_AtRule(rule) => errors.report(rule.location, InvalidAtRule, format!(
+                "Unsupported at-rule in @page: @{:s}", rule.name)),
+            Declaration(Declaration{ location: l, name: n, value: v, important: _ }) => {
+                if !page_rule.parse_descriptor(n, v) {
+                    errors.report(l, InvalidValue, format!("Invalid @page descriptor: {}:{}",
+                                                           n, v.iter().to_css()))
+                }
+            }
+        }
+    }
+    parent_rules.push(CSSPageRule(page_rule))
+}
+
+
+impl PageRule {
+    /// Returns false if the descriptor is not supported or its value is invalid.
+    fn parse_descriptor(&mut self, name: &str, value: &[ComponentValue]) -> bool {
+        // FIXME: local variable to work around Rust #10683
+        let name_lower = name.to_ascii_lower();
+        let margin = one_margin(value);
+        match name_lower.as_slice() {
+            "size" => match parse_size(value) {
+                Some(size) => self.size = Some(size),
+                None => return false,
+            },
+            "margin" => {
+                let mut iter = value.skip_whitespace().map(
+                    specified::LengthOrPercentageOrAuto::parse);
+                // Same as the 'margin' shorthand: one to four values.
+                let top = iter.next().unwrap_or(None);
+                let right = iter.next().unwrap_or(top);
+                let bottom = iter.next().unwrap_or(top);
+                let left = iter.next().unwrap_or(right);
+                if top.is_none() || right.is_none() || bottom.is_none() || left.is_none()
+                        || iter.next().is_some() {
+                    return false
+                }
+                self.margin_top = top;
+                self.margin_right = right;
+                self.margin_bottom = bottom;
+                self.margin_left = left;
+            },
+            "margin-top" if margin.is_some() => self.margin_top = margin,
+            "margin-right" if margin.is_some() => self.margin_right = margin,
+            "margin-bottom" if margin.is_some() => self.margin_bottom = margin,
+            "margin-left" if margin.is_some() => self.margin_left = margin,
+            _ => return false,
+        }
+        true
+    }
+}
+
+
+/// Only the descriptors that were declared are serialized, in a fixed order.
+impl ToCssText for PageRule {
+    fn push_css_text(&self, css: &mut ~str) {
+        css.push_str("@page {");
+        for size in self.size.iter() {
+            css.push_str(" size: ");
+            size.push_css_text(css);
+            css.push_char(';');
+        }
+        for &(name, margin) in [("margin-top", &self.margin_top),
+                                     ("margin-right", &self.margin_right),
+                                     ("margin-bottom", &self.margin_bottom),
+                                     ("margin-left", &self.margin_left)].iter() {
+            for value in margin.iter() {
+                css.push_char(' ');
+                css.push_str(name);
+                css.push_str(": ");
+                value.push_css_text(css);
+                css.push_char(';');
+            }
+        }
+        css.push_str(" }");
+    }
+}
+
+impl ToCssText for PageSize {
+    fn push_css_text(&self, css: &mut ~str) {
+        match *self {
+            AutoSize(None) => css.push_str("auto"),
+            AutoSize(Some(orientation)) => orientation.push_css_text(css),
+            ExplicitSize(width, height) => {
+                width.push_css_text(css);
+                css.push_char(' ');
+                height.push_css_text(css);
+            }
+        }
+    }
+}
+
+
+fn one_margin(value: &[ComponentValue]) -> Option<specified::LengthOrPercentageOrAuto> {
+    let mut iter = value.skip_whitespace();
+    match (iter.next(), iter.next()) {
+        (Some(value), None) => specified::LengthOrPercentageOrAuto::parse(value),
+        _ => None,
+    }
+}
+
+
+/// <length>{1,2} | auto | [ <page-size> || [ portrait | landscape ] ]
+fn parse_size(input: &[ComponentValue]) -> Option<PageSize> {
+    let values: ~[&ComponentValue] = input.skip_whitespace().collect();
+    let lengths: ~[Au] = values.iter().filter_map(|value| {
+        specified::Length::parse_non_negative(*value).map(computed::compute_Au_without_element)
+    }).collect();
+    if lengths.len() == values.len() {
+        return match lengths.as_slice() {
+            [width] => Some(ExplicitSize(width, width)),
+            [width, height] => Some(ExplicitSize(width, height)),
+            _ => None,
+        }
+    }
+    let mut page_size = None;
+    let mut orientation = None;
+    for value in values.iter() {
+        let keyword = match get_ident_lower(*value) {
+            Some(keyword) => keyword,
+            None => return None,
+        };
+        match keyword.as_slice() {
+            "auto" if values.len() == 1 => return Some(AutoSize(None)),
+            "portrait" if orientation.is_none() => orientation = Some(Portrait),
+            "landscape" if orientation.is_none() => orientation = Some(Landscape),
+            _ if page_size.is_none() => match named_page_size(keyword) {
+                Some(size) => page_size = Some(size),
+                None => return None,
+            },
+            _ => return None,
+        }
+    }
+    match page_size {
+        None => Some(AutoSize(orientation)),
+        // Named page sizes are given in portrait orientation.
+        Some((width, height)) => match orientation {
+            Some(Landscape) => Some(ExplicitSize(height, width)),
+            _ => Some(ExplicitSize(width, height)),
+        },
+    }
+}
+
+
+/// Width and height of the page sizes defined in CSS Paged Media Level 3.
+fn named_page_size(name: &str) -> Option<(Au, Au)> {
+    let (width, height, unit) = match name {
+        "a5" => (148., 210., "mm"),
+        "a4" => (210., 297., "mm"),
+        "a3" => (297., 420., "mm"),
+        "b5" => (176., 250., "mm"),
+        "b4" => (250., 353., "mm"),
+        "letter" => (8.5, 11., "in"),
+        "legal" => (8.5, 14., "in"),
+        "ledger" => (11., 17., "in"),
+        _ => return None,
+    };
+    match (specified::Length::parse_dimension(width, unit),
+           specified::Length::parse_dimension(height, unit)) {
+        (Some(width), Some(height)) => Some((computed::compute_Au_without_element(width),
+                                             computed::compute_Au_without_element(height))),
+        _ => None,
+    }
+}
@@ -4,7 +4,7 @@
 
 
 use std::ascii::StrAsciiExt;
-use cssparser::ast::{ComponentValue, Ident, SkipWhitespaceIterable};
+use cssparser::ast::{ComponentValue, Ident, URL, Function, String, SkipWhitespaceIterable};
 
 
 pub fn one_component_value<'a>(input: &'a [ComponentValue]) -> Option<&'a ComponentValue> {
@@ -19,3 +19,19 @@ pub fn get_ident_lower(component_value: &ComponentValue) -> Option<~str> {
         _ => None,
     }
 }
+
+
+/// Returns the unresolved URL of a url() value.
+pub fn get_url(component_value: &ComponentValue) -> Option<~str> {
+    match component_value {
+        &URL(ref value) => Some(value.clone()),
+        &Function(ref name, ref arguments) if name.eq_ignore_ascii_case("url") => {
+            let mut iter = arguments.as_slice().skip_whitespace();
+            match (iter.next(), iter.next()) {
+                (Some(&String(ref value)), None) => Some(value.clone()),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
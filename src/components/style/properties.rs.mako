@@ -6,11 +6,15 @@
 
 use std::ascii::StrAsciiExt;
 pub use extra::arc::Arc;
+pub use extra::url::Url;
 pub use cssparser::*;
 pub use cssparser::ast::*;
 
-use errors::{ErrorLoggerIterator, log_css_error};
+use errors;
+use errors::{ErrorContext, ErrorReporter, InvalidAtRule};
+use media_queries::Device;
 pub use parsing_utils::*;
+pub use serialize::*;
 pub use self::common_types::*;
 
 pub mod common_types;
@@ -58,6 +62,15 @@ pub mod longhands {
 
     pub fn computed_as_specified<T>(value: T, _context: &computed::Context) -> T { value }
 
+    <%def name="serialized_with_to_css_text()">
+        pub fn specified_to_css(value: &SpecifiedValue, css: &mut ~str) {
+            value.push_css_text(css)
+        }
+        pub fn computed_to_css(value: &computed_value::T, css: &mut ~str) {
+            value.push_css_text(css)
+        }
+    </%def>
+
     <%def name="raw_longhand(name, inherited=False, no_super=False)">
     <%
         property = Longhand(name, inherited)
@@ -71,13 +84,13 @@ pub mod longhands {
             % endif
             pub use self::computed_value::*;
             ${caller.body()}
-            pub fn parse_declared(input: &[ComponentValue])
+            pub fn parse_declared(input: &[ComponentValue], base_url: &Url)
                                -> Option<DeclaredValue<SpecifiedValue>> {
                 match CSSWideKeyword::parse(input) {
                     Some(Left(keyword)) => Some(CSSWideKeyword(keyword)),
                     Some(Right(Unset)) => Some(CSSWideKeyword(${
                         "Inherit" if inherited else "Initial"})),
-                    None => parse_specified(input),
+                    None => parse_specified(input, base_url),
                 }
             }
         }
@@ -86,7 +99,7 @@ pub mod longhands {
     <%def name="longhand(name, inherited=False, no_super=False)">
         <%self:raw_longhand name="${name}" inherited="${inherited}">
             ${caller.body()}
-            pub fn parse_specified(input: &[ComponentValue])
+            pub fn parse_specified(input: &[ComponentValue], _base_url: &Url)
                                -> Option<DeclaredValue<SpecifiedValue>> {
                 parse(input).map(super::SpecifiedValue)
             }
@@ -127,6 +140,17 @@ pub mod longhands {
                     }
                 }
             }
+            pub fn specified_to_css(value: &SpecifiedValue, css: &mut ~str) {
+                css.push_str(match *value {
+                    % for value in values.split():
+                        ${to_rust_ident(value)} => "${value}",
+                    % endfor
+                })
+            }
+            #[inline]
+            pub fn computed_to_css(value: &computed_value::T, css: &mut ~str) {
+                specified_to_css(value, css)
+            }
         </%self:single_component_value>
     </%def>
 
@@ -148,6 +172,7 @@ pub mod longhands {
             #[inline] pub fn from_component_value(v: &ComponentValue) -> Option<SpecifiedValue> {
                 specified::${type}::${parse_method}(v)
             }
+            ${serialized_with_to_css_text()}
         </%self:single_component_value>
     </%def>
 
@@ -220,6 +245,7 @@ pub mod longhands {
                 if context.has_border_${side} { computed::compute_Au(value, context) }
                 else { Au(0) }
             }
+            ${serialized_with_to_css_text()}
         </%self:longhand>
     % endfor
 
@@ -245,11 +271,11 @@ pub mod longhands {
             };
             if positioned || context.float != float::none || context.is_root_element {
                 match value {
-//                    inline_table => table,
+                    inline_table => table,
                     inline | inline_block
-//                    | table_row_group | table_column | table_column_group
-//                    | table_header_group | table_footer_group | table_row
-//                    | table_cell | table_caption
+                    | table_row_group | table_column | table_column_group
+                    | table_header_group | table_footer_group | table_row
+                    | table_cell | table_caption
                     => block,
                     _ => value,
                 }
@@ -263,6 +289,47 @@ pub mod longhands {
     ${single_keyword("float", "none left right")}
     ${single_keyword("clear", "none left right both")}
 
+    % for side in ["top", "right", "bottom", "left"]:
+        ${predefined_type(side, "LengthOrPercentageOrAuto", "computed::LPA_Auto")}
+    % endfor
+
+    <%self:single_component_value name="z-index">
+        pub use to_computed_value = super::computed_as_specified;
+        pub type SpecifiedValue = computed_value::T;
+        pub mod computed_value {
+            #[deriving(Eq, Clone)]
+            pub enum T {
+                Auto,
+                Number(i32),
+            }
+        }
+        #[inline] pub fn get_initial_value() -> computed_value::T { Auto }
+        /// auto | <integer>
+        ///
+        /// Integers out of the range of `i32` are clamped to it.
+        pub fn from_component_value(input: &ComponentValue) -> Option<SpecifiedValue> {
+            use std::i32;
+            match input {
+                &Ident(ref value) if value.eq_ignore_ascii_case("auto") => Some(Auto),
+                &ast::Number(ref value) => do value.int_value.map |value| {
+                    Number(value.max(&(i32::min_value as i64)).min(&(i32::max_value as i64))
+                           as i32)
+                },
+                _ => None,
+            }
+        }
+        pub fn specified_to_css(value: &SpecifiedValue, css: &mut ~str) {
+            match *value {
+                Auto => css.push_str("auto"),
+                Number(value) => css.push_str(value.to_str()),
+            }
+        }
+        #[inline]
+        pub fn computed_to_css(value: &computed_value::T, css: &mut ~str) {
+            specified_to_css(value, css)
+        }
+    </%self:single_component_value>
+
     // CSS 2.1, Section 10 - Visual formatting model details
 
     ${predefined_type("width", "LengthOrPercentageOrAuto",
@@ -279,6 +346,13 @@ pub mod longhands {
                       "computed::LPN_None",
                       "parse_non_negative")}
 
+    ${predefined_type("min-height", "LengthOrPercentage",
+                      "computed::LP_Length(Au(0))",
+                      "parse_non_negative")}
+    ${predefined_type("max-height", "LengthOrPercentageOrNone",
+                      "computed::LPN_None",
+                      "parse_non_negative")}
+
     <%self:single_component_value name="line-height">
         #[deriving(Clone)]
         pub enum SpecifiedValue {
@@ -297,6 +371,9 @@ pub mod longhands {
                 &Dimension(ref value, ref unit) if value.value >= 0.
                 => specified::Length::parse_dimension(value.value, unit.as_slice())
                     .map(SpecifiedLength),
+                &Function(ref name, _) if name.eq_ignore_ascii_case("calc")
+                => specified::LengthOrPercentage::parse_non_negative(input)
+                    .map(|value| SpecifiedLength(value.percentage_as_em())),
                 &Ident(ref value) if value.eq_ignore_ascii_case("normal")
                 => Some(SpecifiedNormal),
                 _ => None,
@@ -320,6 +397,20 @@ pub mod longhands {
                 SpecifiedNumber(value) => Number(value),
             }
         }
+        pub fn specified_to_css(value: &SpecifiedValue, css: &mut ~str) {
+            match *value {
+                SpecifiedNormal => css.push_str("normal"),
+                SpecifiedLength(ref value) => value.push_css_text(css),
+                SpecifiedNumber(value) => push_number(value, css),
+            }
+        }
+        pub fn computed_to_css(value: &computed_value::T, css: &mut ~str) {
+            match *value {
+                Normal => css.push_str("normal"),
+                Length(value) => value.push_css_text(css),
+                Number(value) => push_number(value, css),
+            }
+        }
     </%self:single_component_value>
 
     <%self:single_component_value name="vertical-align">
@@ -351,7 +442,7 @@ pub mod longhands {
             }
         }
         pub mod computed_value {
-            use super::super::{Au, CSSFloat};
+            use super::super::{Au, CSSFloat, computed};
             #[deriving(Eq, Clone)]
             pub enum T {
                 % for keyword in vertical_align_keywords:
@@ -359,6 +450,7 @@ pub mod longhands {
                 % endfor
                 Length(Au),
                 Percentage(CSSFloat),
+                Calc(computed::Calc),
             }
         }
         #[inline] pub fn get_initial_value() -> computed_value::T { baseline }
@@ -371,23 +463,376 @@ pub mod longhands {
                 SpecifiedLengthOrPercentage(value)
                 => match computed::compute_LengthOrPercentage(value, context) {
                     computed::LP_Length(value) => Length(value),
-                    computed::LP_Percentage(value) => Percentage(value)
+                    computed::LP_Percentage(value) => Percentage(value),
+                    computed::LP_Calc(value) => Calc(value),
                 }
             }
         }
+        pub fn specified_to_css(value: &SpecifiedValue, css: &mut ~str) {
+            match *value {
+                % for keyword in vertical_align_keywords:
+                    Specified_${to_rust_ident(keyword)} => css.push_str("${keyword}"),
+                % endfor
+                SpecifiedLengthOrPercentage(ref value) => value.push_css_text(css),
+            }
+        }
+        pub fn computed_to_css(value: &computed_value::T, css: &mut ~str) {
+            match *value {
+                % for keyword in vertical_align_keywords:
+                    ${to_rust_ident(keyword)} => css.push_str("${keyword}"),
+                % endfor
+                Length(value) => value.push_css_text(css),
+                Percentage(value) => push_percentage(value, css),
+                Calc(ref value) => value.push_css_text(css),
+            }
+        }
     </%self:single_component_value>
 
 
     // CSS 2.1, Section 11 - Visual effects
-    ${single_keyword("overflow", "visible hidden", inherited=False)} // TODO: scroll auto
+    ${single_keyword("overflow", "visible hidden scroll auto", inherited=False)}
 
     // TODO: collapse. Well, do tables first.
     ${single_keyword("visibility", "visible hidden", inherited=True)}
 
+    // CSS Color Module Level 3, Section 3.2 - Transparency
+
+    <%self:single_component_value name="opacity">
+        pub use to_computed_value = super::computed_as_specified;
+        pub type SpecifiedValue = computed_value::T;
+        pub mod computed_value {
+            use super::super::CSSFloat;
+            pub type T = CSSFloat;
+        }
+        #[inline] pub fn get_initial_value() -> computed_value::T { 1.0 }
+        /// <number>, clamped to [0, 1]
+        pub fn from_component_value(input: &ComponentValue) -> Option<SpecifiedValue> {
+            match input {
+                &ast::Number(ref value) => {
+                    if value.value < 0. {
+                        Some(0.)
+                    } else if value.value > 1. {
+                        Some(1.)
+                    } else {
+                        Some(value.value)
+                    }
+                }
+                _ => None,
+            }
+        }
+        ${serialized_with_to_css_text()}
+    </%self:single_component_value>
+
     // CSS 2.1, Section 12 - Generated content, automatic numbering, and lists
 
+    <%self:longhand name="content">
+        pub use to_computed_value = super::computed_as_specified;
+        pub mod computed_value {
+            #[deriving(Eq, Clone)]
+            pub enum CounterStyle {
+                Disc,
+                Circle,
+                Square,
+                Decimal,
+                LowerRoman,
+                UpperRoman,
+                LowerAlpha,
+                UpperAlpha,
+                NoCounterStyle,
+            }
+            #[deriving(Eq, Clone)]
+            pub enum ContentItem {
+                StringContent(~str),
+                /// The value of an attribute of the element, given by its name.
+                AttrContent(~str),
+                /// The innermost instance of a counter: counter(name, style).
+                CounterContent(~str, CounterStyle),
+                /// All the instances of a counter: counters(name, separator, style).
+                CountersContent(~str, ~str, CounterStyle),
+                OpenQuote,
+                CloseQuote,
+                NoOpenQuote,
+                NoCloseQuote,
+            }
+            #[deriving(Eq, Clone)]
+            pub enum T {
+                normal,
+                none,
+                Content(~[ContentItem]),
+            }
+        }
+        pub type SpecifiedValue = computed_value::T;
+        #[inline] pub fn get_initial_value() -> computed_value::T { normal }
+        /// normal | none | [ <string> | <counter> | attr(<identifier>) | open-quote | close-quote
+        ///                 | no-open-quote | no-close-quote ]+
+        pub fn parse(input: &[ComponentValue]) -> Option<SpecifiedValue> {
+            match one_component_value(input).and_then(get_ident_lower) {
+                Some(ref keyword) if "normal" == *keyword => return Some(normal),
+                Some(ref keyword) if "none" == *keyword => return Some(none),
+                _ => (),
+            }
+            let mut content = ~[];
+            for component_value in input.skip_whitespace() {
+                let item = match component_value {
+                    &String(ref value) => StringContent(value.to_owned()),
+                    &Function(ref name, ref arguments) => {
+                        // FIXME: Workaround for https://github.com/mozilla/rust/issues/10683
+                        let name_lower = name.to_ascii_lower();
+                        match parse_function(name_lower.as_slice(), arguments.as_slice()) {
+                            Some(item) => item,
+                            None => return None,
+                        }
+                    }
+                    _ => match get_ident_lower(component_value) {
+                        Some(keyword) => match keyword.as_slice() {
+                            "open-quote" => OpenQuote,
+                            "close-quote" => CloseQuote,
+                            "no-open-quote" => NoOpenQuote,
+                            "no-close-quote" => NoCloseQuote,
+                            _ => return None,
+                        },
+                        None => return None,
+                    },
+                };
+                content.push(item)
+            }
+            if content.is_empty() { None } else { Some(Content(content)) }
+        }
+        /// attr(<identifier>) | counter(<identifier>, <counter-style>?)
+        /// | counters(<identifier>, <string>, <counter-style>?)
+        fn parse_function(name: &str, arguments: &[ComponentValue]) -> Option<ContentItem> {
+            let arguments: ~[&[ComponentValue]] =
+                arguments.split_iter(|component_value| *component_value == Comma).collect();
+            fn ident(input: &[ComponentValue]) -> Option<~str> {
+                match one_component_value(input) {
+                    Some(&Ident(ref value)) => Some(value.to_owned()),
+                    _ => None,
+                }
+            }
+            fn string(input: &[ComponentValue]) -> Option<~str> {
+                match one_component_value(input) {
+                    Some(&String(ref value)) => Some(value.to_owned()),
+                    _ => None,
+                }
+            }
+            match (name, arguments.as_slice()) {
+                ("attr", [attribute]) => ident(attribute).map(AttrContent),
+                ("counter", [counter]) => ident(counter).map(|name| CounterContent(name, Decimal)),
+                ("counter", [counter, style]) => match (ident(counter), parse_counter_style(style)) {
+                    (Some(name), Some(style)) => Some(CounterContent(name, style)),
+                    _ => None,
+                },
+                ("counters", [counter, separator]) => match (ident(counter), string(separator)) {
+                    (Some(name), Some(separator)) => Some(CountersContent(name, separator, Decimal)),
+                    _ => None,
+                },
+                ("counters", [counter, separator, style]) => {
+                    match (ident(counter), string(separator), parse_counter_style(style)) {
+                        (Some(name), Some(separator), Some(style))
+                        => Some(CountersContent(name, separator, style)),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        }
+        fn parse_counter_style(input: &[ComponentValue]) -> Option<CounterStyle> {
+            do one_component_value(input).and_then(get_ident_lower).and_then |keyword| {
+                match keyword.as_slice() {
+                    "disc" => Some(Disc),
+                    "circle" => Some(Circle),
+                    "square" => Some(Square),
+                    "decimal" => Some(Decimal),
+                    "lower-roman" => Some(LowerRoman),
+                    "upper-roman" => Some(UpperRoman),
+                    "lower-alpha" | "lower-latin" => Some(LowerAlpha),
+                    "upper-alpha" | "upper-latin" => Some(UpperAlpha),
+                    "none" => Some(NoCounterStyle),
+                    _ => None,
+                }
+            }
+        }
+        fn counter_style_to_css(style: CounterStyle, css: &mut ~str) {
+            css.push_str(match style {
+                Disc => "disc",
+                Circle => "circle",
+                Square => "square",
+                Decimal => "decimal",
+                LowerRoman => "lower-roman",
+                UpperRoman => "upper-roman",
+                LowerAlpha => "lower-alpha",
+                UpperAlpha => "upper-alpha",
+                NoCounterStyle => "none",
+            })
+        }
+        pub fn specified_to_css(value: &SpecifiedValue, css: &mut ~str) {
+            let items = match *value {
+                normal => return css.push_str("normal"),
+                none => return css.push_str("none"),
+                Content(ref items) => items,
+            };
+            for (i, item) in items.iter().enumerate() {
+                if i != 0 {
+                    css.push_char(' ')
+                }
+                match *item {
+                    StringContent(ref value) => push_string(value.as_slice(), css),
+                    AttrContent(ref name) => {
+                        css.push_str("attr(");
+                        push_identifier(name.as_slice(), css);
+                        css.push_char(')');
+                    }
+                    CounterContent(ref name, style) => {
+                        css.push_str("counter(");
+                        push_identifier(name.as_slice(), css);
+                        if style != Decimal {
+                            css.push_str(", ");
+                            counter_style_to_css(style, css);
+                        }
+                        css.push_char(')');
+                    }
+                    CountersContent(ref name, ref separator, style) => {
+                        css.push_str("counters(");
+                        push_identifier(name.as_slice(), css);
+                        css.push_str(", ");
+                        push_string(separator.as_slice(), css);
+                        if style != Decimal {
+                            css.push_str(", ");
+                            counter_style_to_css(style, css);
+                        }
+                        css.push_char(')');
+                    }
+                    OpenQuote => css.push_str("open-quote"),
+                    CloseQuote => css.push_str("close-quote"),
+                    NoOpenQuote => css.push_str("no-open-quote"),
+                    NoCloseQuote => css.push_str("no-close-quote"),
+                }
+            }
+        }
+        #[inline]
+        pub fn computed_to_css(value: &computed_value::T, css: &mut ~str) {
+            specified_to_css(value, css)
+        }
+    </%self:longhand>
+
+    % for name, default_value in [("counter-increment", 1), ("counter-reset", 0)]:
+        <%self:longhand name="${name}">
+            pub use to_computed_value = super::computed_as_specified;
+            pub mod computed_value {
+                /// Counter names, each with an integer.
+                pub type T = ~[(~str, i32)];
+            }
+            pub type SpecifiedValue = computed_value::T;
+            #[inline] pub fn get_initial_value() -> computed_value::T { ~[] }
+            /// none | [ <identifier> <integer>? ]+
+            pub fn parse(input: &[ComponentValue]) -> Option<SpecifiedValue> {
+                let values: ~[&ComponentValue] = input.skip_whitespace().collect();
+                match values.as_slice() {
+                    [&Ident(ref value)] if value.eq_ignore_ascii_case("none") => return Some(~[]),
+                    [] => return None,
+                    _ => (),
+                }
+                let mut counters = ~[];
+                let mut i = 0;
+                while i < values.len() {
+                    let name = match values[i] {
+                        &Ident(ref name) if !name.eq_ignore_ascii_case("none") => name.to_owned(),
+                        _ => return None,
+                    };
+                    i += 1;
+                    let mut value = ${default_value};
+                    if i < values.len() {
+                        match values[i] {
+                            &Number(ref number) => match number.int_value {
+                                Some(int_value) => {
+                                    value = int_value as i32;
+                                    i += 1;
+                                }
+                                None => return None,
+                            },
+                            _ => (),
+                        }
+                    }
+                    counters.push((name, value))
+                }
+                Some(counters)
+            }
+            pub fn specified_to_css(value: &SpecifiedValue, css: &mut ~str) {
+                if value.is_empty() {
+                    return css.push_str("none")
+                }
+                for (i, &(ref name, number)) in value.iter().enumerate() {
+                    if i != 0 {
+                        css.push_char(' ')
+                    }
+                    push_identifier(name.as_slice(), css);
+                    css.push_char(' ');
+                    css.push_str(number.to_str());
+                }
+            }
+            #[inline]
+            pub fn computed_to_css(value: &computed_value::T, css: &mut ~str) {
+                specified_to_css(value, css)
+            }
+        </%self:longhand>
+    % endfor
+
+    <%self:longhand name="quotes" inherited="True">
+        pub use to_computed_value = super::computed_as_specified;
+        pub mod computed_value {
+            /// Pairs of open and close quotes, from the outermost level of nesting inwards.
+            pub type T = ~[(~str, ~str)];
+        }
+        pub type SpecifiedValue = computed_value::T;
+        #[inline] pub fn get_initial_value() -> computed_value::T {
+            ~[(~"\u201c", ~"\u201d"), (~"\u2018", ~"\u2019")]
+        }
+        /// none | [ <string> <string> ]+
+        pub fn parse(input: &[ComponentValue]) -> Option<SpecifiedValue> {
+            let values: ~[&ComponentValue] = input.skip_whitespace().collect();
+            match values.as_slice() {
+                [&Ident(ref value)] if value.eq_ignore_ascii_case("none") => return Some(~[]),
+                [] => return None,
+                _ => (),
+            }
+            if values.len() % 2 != 0 {
+                return None
+            }
+            let mut quotes = ~[];
+            for pair in values.chunk_iter(2) {
+                match pair {
+                    [&String(ref open), &String(ref close)]
+                    => quotes.push((open.to_owned(), close.to_owned())),
+                    _ => return None,
+                }
+            }
+            Some(quotes)
+        }
+        pub fn specified_to_css(value: &SpecifiedValue, css: &mut ~str) {
+            if value.is_empty() {
+                return css.push_str("none")
+            }
+            for (i, &(ref open, ref close)) in value.iter().enumerate() {
+                if i != 0 {
+                    css.push_char(' ')
+                }
+                push_string(open.as_slice(), css);
+                css.push_char(' ');
+                push_string(close.as_slice(), css);
+            }
+        }
+        #[inline]
+        pub fn computed_to_css(value: &computed_value::T, css: &mut ~str) {
+            specified_to_css(value, css)
+        }
+    </%self:longhand>
+
     // CSS 2.1, Section 13 - Paged media
 
+    ${single_keyword("page-break-before", "auto always avoid left right")}
+    ${single_keyword("page-break-after", "auto always avoid left right")}
+    ${single_keyword("page-break-inside", "auto avoid")}
+
     // CSS 2.1, Section 14 - Colors and Backgrounds
 
     ${new_style_struct("Background")}
@@ -395,6 +840,161 @@ pub mod longhands {
     ${predefined_type("background-color", "CSSColor",
                       "RGBA(RGBA { red: 0., green: 0., blue: 0., alpha: 0. }) /* transparent */")}
 
+    <%self:raw_longhand name="background-image">
+        use servo_util::url::make_url;
+        pub use to_computed_value = super::computed_as_specified;
+        pub type SpecifiedValue = Option<Url>;
+        pub mod computed_value {
+            use super::super::Url;
+            pub type T = Option<Url>;
+        }
+        #[inline] pub fn get_initial_value() -> computed_value::T { None }
+        /// none | <url>
+        pub fn from_component_value(component_value: &ComponentValue, base_url: &Url)
+                                    -> Option<SpecifiedValue> {
+            match get_url(component_value) {
+                Some(url) => Some(Some(make_url(url, Some(base_url.clone())))),
+                None => match get_ident_lower(component_value) {
+                    Some(ref keyword) if "none" == *keyword => Some(None),
+                    _ => None,
+                },
+            }
+        }
+        pub fn parse_specified(input: &[ComponentValue], base_url: &Url)
+                               -> Option<DeclaredValue<SpecifiedValue>> {
+            match one_component_value(input) {
+                Some(component_value) => {
+                    from_component_value(component_value, base_url).map(super::SpecifiedValue)
+                }
+                None => None,
+            }
+        }
+        pub fn specified_to_css(value: &SpecifiedValue, css: &mut ~str) {
+            match *value {
+                Some(ref url) => url.push_css_text(css),
+                None => css.push_str("none"),
+            }
+        }
+        #[inline]
+        pub fn computed_to_css(value: &computed_value::T, css: &mut ~str) {
+            specified_to_css(value, css)
+        }
+    </%self:raw_longhand>
+
+    ${single_keyword("background-repeat", "repeat repeat-x repeat-y no-repeat")}
+    ${single_keyword("background-attachment", "scroll fixed")}
+
+    <%self:longhand name="background-position">
+        pub mod computed_value {
+            use super::super::computed::LengthOrPercentage;
+            #[deriving(Eq, Clone)]
+            pub struct T {
+                horizontal: LengthOrPercentage,
+                vertical: LengthOrPercentage,
+            }
+        }
+        #[deriving(Clone)]
+        pub struct SpecifiedValue {
+            horizontal: specified::LengthOrPercentage,
+            vertical: specified::LengthOrPercentage,
+        }
+        /// One of the (up to) two values of 'background-position'.
+        pub enum Component {
+            LengthComponent(specified::LengthOrPercentage),
+            KeywordCenter,
+            KeywordLeft,
+            KeywordRight,
+            KeywordTop,
+            KeywordBottom,
+        }
+        impl Component {
+            fn is_horizontal(&self) -> bool {
+                match *self { KeywordTop | KeywordBottom => false, _ => true }
+            }
+            fn is_vertical(&self) -> bool {
+                match *self { KeywordLeft | KeywordRight => false, _ => true }
+            }
+            fn is_keyword(&self) -> bool {
+                match *self { LengthComponent(_) => false, _ => true }
+            }
+            fn to_length_or_percentage(self) -> specified::LengthOrPercentage {
+                match self {
+                    LengthComponent(value) => value,
+                    KeywordLeft | KeywordTop => specified::LP_Percentage(0.),
+                    KeywordCenter => specified::LP_Percentage(0.5),
+                    KeywordRight | KeywordBottom => specified::LP_Percentage(1.),
+                }
+            }
+        }
+        #[inline] pub fn get_initial_value() -> computed_value::T {
+            computed_value::T {
+                horizontal: computed::LP_Percentage(0.),
+                vertical: computed::LP_Percentage(0.),
+            }
+        }
+        #[inline]
+        pub fn to_computed_value(value: SpecifiedValue, context: &computed::Context)
+                                 -> computed_value::T {
+            computed_value::T {
+                horizontal: computed::compute_LengthOrPercentage(value.horizontal, context),
+                vertical: computed::compute_LengthOrPercentage(value.vertical, context),
+            }
+        }
+        pub fn parse_component(input: &ComponentValue) -> Option<Component> {
+            match get_ident_lower(input) {
+                Some(keyword) => match keyword.as_slice() {
+                    "center" => Some(KeywordCenter),
+                    "left" => Some(KeywordLeft),
+                    "right" => Some(KeywordRight),
+                    "top" => Some(KeywordTop),
+                    "bottom" => Some(KeywordBottom),
+                    _ => None,
+                },
+                None => specified::LengthOrPercentage::parse(input).map(LengthComponent),
+            }
+        }
+        /// The horizontal value comes first, unless both values are keywords. A single value
+        /// leaves the other one centered.
+        pub fn from_components(first: Component, second: Option<Component>)
+                               -> Option<SpecifiedValue> {
+            let second = second.unwrap_or(KeywordCenter);
+            let (horizontal, vertical) = if first.is_horizontal() && second.is_vertical() {
+                (first, second)
+            } else if first.is_keyword() && second.is_keyword() &&
+                    first.is_vertical() && second.is_horizontal() {
+                (second, first)
+            } else {
+                return None
+            };
+            Some(SpecifiedValue {
+                horizontal: horizontal.to_length_or_percentage(),
+                vertical: vertical.to_length_or_percentage(),
+            })
+        }
+        /// [ [ <percentage> | <length> | left | center | right ]
+        ///   [ <percentage> | <length> | top | center | bottom ]? ] |
+        /// [ [ left | center | right ] || [ top | center | bottom ] ]
+        pub fn parse(input: &[ComponentValue]) -> Option<SpecifiedValue> {
+            let mut iter = input.skip_whitespace().map(parse_component);
+            match (iter.next(), iter.next(), iter.next()) {
+                (Some(Some(first)), None, None) => from_components(first, None),
+                (Some(Some(first)), Some(Some(second)), None)
+                => from_components(first, Some(second)),
+                _ => None,
+            }
+        }
+        pub fn specified_to_css(value: &SpecifiedValue, css: &mut ~str) {
+            value.horizontal.push_css_text(css);
+            css.push_char(' ');
+            value.vertical.push_css_text(css);
+        }
+        pub fn computed_to_css(value: &computed_value::T, css: &mut ~str) {
+            value.horizontal.push_css_text(css);
+            css.push_char(' ');
+            value.vertical.push_css_text(css);
+        }
+    </%self:longhand>
+
 
     ${new_style_struct("Color")}
 
@@ -407,13 +1007,15 @@ pub mod longhands {
         #[inline] pub fn get_initial_value() -> computed_value::T {
             RGBA { red: 0., green: 0., blue: 0., alpha: 1. }  /* black */
         }
-        pub fn parse_specified(input: &[ComponentValue]) -> Option<DeclaredValue<SpecifiedValue>> {
+        pub fn parse_specified(input: &[ComponentValue], _base_url: &Url)
+                               -> Option<DeclaredValue<SpecifiedValue>> {
             match one_component_value(input).and_then(Color::parse) {
                 Some(RGBA(rgba)) => Some(SpecifiedValue(rgba)),
                 Some(CurrentColor) => Some(CSSWideKeyword(Inherit)),
                 None => None,
             }
         }
+        ${serialized_with_to_css_text()}
     </%self:raw_longhand>
 
     // CSS 2.1, Section 15 - Fonts
@@ -495,6 +1097,39 @@ pub mod longhands {
             }
             Some(result)
         }
+        /// A family name is serialized as a sequence of identifiers when that parses back to
+        /// the same name, and as a string otherwise.
+        pub fn specified_to_css(value: &SpecifiedValue, css: &mut ~str) {
+            for (i, family) in value.iter().enumerate() {
+                if i != 0 {
+                    css.push_str(", ")
+                }
+                match *family {
+                    FamilyName(ref name) => {
+                        let words: ~[&str] = name.split_iter(' ').collect();
+                        let name_lower = name.to_ascii_lower();
+                        let is_keyword = match name_lower.as_slice() {
+                            "initial" | "inherit" | "unset" | "default" => true,
+                            _ => false,
+                        };
+                        if is_keyword || words.iter().any(|word| word.is_empty()) {
+                            push_string(name.as_slice(), css)
+                        } else {
+                            for (j, word) in words.iter().enumerate() {
+                                if j != 0 {
+                                    css.push_char(' ')
+                                }
+                                push_identifier(*word, css)
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        #[inline]
+        pub fn computed_to_css(value: &computed_value::T, css: &mut ~str) {
+            specified_to_css(value, css)
+        }
     </%self:longhand>
 
 
@@ -578,6 +1213,22 @@ pub mod longhands {
                 },
             }
         }
+        pub fn specified_to_css(value: &SpecifiedValue, css: &mut ~str) {
+            css.push_str(match *value {
+                Bolder => "bolder",
+                Lighther => "lighter",
+                % for weight in range(100, 901, 100):
+                    SpecifiedWeight${weight} => "${weight}",
+                % endfor
+            })
+        }
+        pub fn computed_to_css(value: &computed_value::T, css: &mut ~str) {
+            css.push_str(match *value {
+                % for weight in range(100, 901, 100):
+                    Weight${weight} => "${weight}",
+                % endfor
+            })
+        }
     </%self:single_component_value>
 
     <%self:single_component_value name="font-size" inherited="True">
@@ -593,13 +1244,10 @@ pub mod longhands {
         /// <length> | <percentage>
         /// TODO: support <absolute-size> and <relative-size>
         pub fn from_component_value(input: &ComponentValue) -> Option<SpecifiedValue> {
-            do specified::LengthOrPercentage::parse_non_negative(input).map |value| {
-                match value {
-                    specified::LP_Length(value) => value,
-                    specified::LP_Percentage(value) => specified::Em(value),
-                }
-            }
+            specified::LengthOrPercentage::parse_non_negative(input)
+                .map(|value| value.percentage_as_em())
         }
+        ${serialized_with_to_css_text()}
     </%self:single_component_value>
 
     // CSS 2.1, Section 16 - Text
@@ -652,10 +1300,124 @@ pub mod longhands {
             }
             if !empty { Some(result) } else { None }
         }
+        pub fn specified_to_css(value: &SpecifiedValue, css: &mut ~str) {
+            let mut keywords = ~[];
+            if value.underline { keywords.push("underline") }
+            if value.overline { keywords.push("overline") }
+            if value.line_through { keywords.push("line-through") }
+            if keywords.is_empty() { keywords.push("none") }
+            css.push_str(keywords.connect(" "));
+        }
+        #[inline]
+        pub fn computed_to_css(value: &computed_value::T, css: &mut ~str) {
+            specified_to_css(value, css)
+        }
     </%self:longhand>
 
+    ${predefined_type("text-indent", "LengthOrPercentage", "computed::LP_Length(Au(0))",
+                      inherited=True)}
+
+    % for name in ["letter-spacing", "word-spacing"]:
+        <%self:single_component_value name="${name}" inherited="True">
+            /// None is 'normal'.
+            pub type SpecifiedValue = Option<specified::Length>;
+            pub mod computed_value {
+                use super::super::Au;
+                pub type T = Option<Au>;
+            }
+            #[inline] pub fn get_initial_value() -> computed_value::T { None }
+            #[inline]
+            pub fn to_computed_value(value: SpecifiedValue, context: &computed::Context)
+                                     -> computed_value::T {
+                value.map(|length| computed::compute_Au(length, context))
+            }
+            /// normal | <length>
+            pub fn from_component_value(input: &ComponentValue) -> Option<SpecifiedValue> {
+                match input {
+                    &Ident(ref value) if value.eq_ignore_ascii_case("normal") => Some(None),
+                    _ => specified::Length::parse(input).map(Some),
+                }
+            }
+            pub fn specified_to_css(value: &SpecifiedValue, css: &mut ~str) {
+                match *value {
+                    Some(ref length) => length.push_css_text(css),
+                    None => css.push_str("normal"),
+                }
+            }
+            pub fn computed_to_css(value: &computed_value::T, css: &mut ~str) {
+                match *value {
+                    Some(ref length) => length.push_css_text(css),
+                    None => css.push_str("normal"),
+                }
+            }
+        </%self:single_component_value>
+    % endfor
+
+    ${single_keyword("text-transform", "none capitalize uppercase lowercase", inherited=True)}
+    ${single_keyword("white-space", "normal pre nowrap pre-wrap pre-line", inherited=True)}
+
     // CSS 2.1, Section 17 - Tables
 
+    ${new_style_struct("Table")}
+
+    ${single_keyword("table-layout", "auto fixed")}
+    ${single_keyword("caption-side", "top bottom", inherited=True)}
+    ${single_keyword("border-collapse", "separate collapse", inherited=True)}
+    ${single_keyword("empty-cells", "show hide", inherited=True)}
+
+    <%self:longhand name="border-spacing" inherited="True">
+        pub mod computed_value {
+            use super::super::Au;
+            #[deriving(Eq, Clone)]
+            pub struct T {
+                horizontal: Au,
+                vertical: Au,
+            }
+        }
+        #[deriving(Clone)]
+        pub struct SpecifiedValue {
+            horizontal: specified::Length,
+            vertical: specified::Length,
+        }
+        #[inline] pub fn get_initial_value() -> computed_value::T {
+            computed_value::T {
+                horizontal: Au(0),
+                vertical: Au(0),
+            }
+        }
+        #[inline]
+        pub fn to_computed_value(value: SpecifiedValue, context: &computed::Context)
+                                 -> computed_value::T {
+            computed_value::T {
+                horizontal: computed::compute_Au(value.horizontal, context),
+                vertical: computed::compute_Au(value.vertical, context),
+            }
+        }
+        /// <length> <length>?, where a single length applies to both directions.
+        pub fn parse(input: &[ComponentValue]) -> Option<SpecifiedValue> {
+            let mut iter = input.skip_whitespace().map(specified::Length::parse_non_negative);
+            match (iter.next(), iter.next(), iter.next()) {
+                (Some(Some(length)), None, None) => {
+                    Some(SpecifiedValue { horizontal: length.clone(), vertical: length })
+                }
+                (Some(Some(horizontal)), Some(Some(vertical)), None) => {
+                    Some(SpecifiedValue { horizontal: horizontal, vertical: vertical })
+                }
+                _ => None,
+            }
+        }
+        pub fn specified_to_css(value: &SpecifiedValue, css: &mut ~str) {
+            value.horizontal.push_css_text(css);
+            css.push_char(' ');
+            value.vertical.push_css_text(css);
+        }
+        pub fn computed_to_css(value: &computed_value::T, css: &mut ~str) {
+            value.horizontal.push_css_text(css);
+            css.push_char(' ');
+            value.vertical.push_css_text(css);
+        }
+    </%self:longhand>
+
     // CSS 2.1, Section 18 - User interface
 }
 
@@ -664,7 +1426,7 @@ pub mod shorthands {
     pub use super::*;
     pub use super::longhands::*;
 
-    <%def name="shorthand(name, sub_properties)">
+    <%def name="shorthand(name, sub_properties, serializer='serialize_in_order')">
     <%
         shorthand = Shorthand(name, sub_properties.split())
         SHORTHANDS.append(shorthand)
@@ -676,16 +1438,43 @@ pub mod shorthands {
                     ${sub_property.ident}: Option<${sub_property.ident}::SpecifiedValue>,
                 % endfor
             }
-            pub fn parse(input: &[ComponentValue]) -> Option<Longhands> {
+            #[allow(unused_variable)]
+            pub fn parse(input: &[ComponentValue], base_url: &Url) -> Option<Longhands> {
                 ${caller.body()}
             }
+            /// Serializes this shorthand from the serialized values of its longhands, given in
+            /// the order of its sub-properties. None if they can not be expressed with it.
+            pub fn to_css(values: &[~str]) -> Option<~str> {
+                ${serializer}(values)
+            }
         }
     </%def>
 
+    /// For shorthands that take the values of their longhands in any order.
+    pub fn serialize_in_order(values: &[~str]) -> Option<~str> {
+        Some(values.connect(" "))
+    }
+
+    /// For 'margin'-like shorthands: the values for the top, right, bottom and left sides,
+    /// leaving out those that would be repeated when parsing.
+    pub fn serialize_four_sides(values: &[~str]) -> Option<~str> {
+        let (top, right, bottom, left) = (&values[0], &values[1], &values[2], &values[3]);
+        Some(if left != right {
+            format!("{} {} {} {}", *top, *right, *bottom, *left)
+        } else if bottom != top {
+            format!("{} {} {}", *top, *right, *bottom)
+        } else if right != top {
+            format!("{} {}", *top, *right)
+        } else {
+            top.clone()
+        })
+    }
+
     <%def name="four_sides_shorthand(name, sub_property_pattern, parser_function)">
         <%self:shorthand name="${name}" sub_properties="${
                 ' '.join(sub_property_pattern % side
-                         for side in ['top', 'right', 'bottom', 'left'])}">
+                         for side in ['top', 'right', 'bottom', 'left'])}"
+                serializer="serialize_four_sides">
             let mut iter = input.skip_whitespace().map(${parser_function});
             // zero or more than four values is invalid.
             // one value sets them all
@@ -710,11 +1499,75 @@ pub mod shorthands {
     </%def>
 
 
-    // TODO: other background-* properties
-    <%self:shorthand name="background" sub_properties="background-color">
-        do one_component_value(input).and_then(specified::CSSColor::parse).map |color| {
-            Longhands { background_color: Some(color) }
+    <%self:shorthand name="background" sub_properties="background-color background-image
+            background-repeat background-attachment background-position">
+        // The values may come in any order, but the one or two values for
+        // 'background-position' must be adjacent.
+        let values: ~[&ComponentValue] = input.skip_whitespace().collect();
+        let mut color = None;
+        let mut image = None;
+        let mut repeat = None;
+        let mut attachment = None;
+        let mut position = None;
+        let mut i = 0;
+        while i < values.len() {
+            let value = values[i];
+            i += 1;
+            if color.is_none() {
+                match specified::CSSColor::parse(value) {
+                    Some(c) => { color = Some(c); continue },
+                    None => ()
+                }
+            }
+            if image.is_none() {
+                match background_image::from_component_value(value, base_url) {
+                    Some(url) => { image = Some(url); continue },
+                    None => ()
+                }
+            }
+            if repeat.is_none() {
+                match background_repeat::from_component_value(value) {
+                    Some(r) => { repeat = Some(r); continue },
+                    None => ()
+                }
+            }
+            if attachment.is_none() {
+                match background_attachment::from_component_value(value) {
+                    Some(a) => { attachment = Some(a); continue },
+                    None => ()
+                }
+            }
+            if position.is_none() {
+                match background_position::parse_component(value) {
+                    Some(first) => {
+                        let second = if i < values.len() {
+                            background_position::parse_component(values[i])
+                        } else {
+                            None
+                        };
+                        if second.is_some() {
+                            i += 1;
+                        }
+                        position = background_position::from_components(first, second);
+                        if position.is_some() {
+                            continue
+                        }
+                    }
+                    None => ()
+                }
+            }
+            return None
         }
+        if values.is_empty() {
+            return None
+        }
+        Some(Longhands {
+            background_color: color,
+            background_image: image,
+            background_repeat: repeat,
+            background_attachment: attachment,
+            background_position: position,
+        })
     </%self:shorthand>
 
     ${four_sides_shorthand("margin", "margin-%s", "margin_top::from_component_value")}
@@ -757,12 +1610,34 @@ pub mod shorthands {
         if any { Some((color, style, width)) } else { None }
     }
 
+    /// The color, style and width of one side, as 'border-top'.
+    pub fn serialize_border_side(values: &[~str]) -> Option<~str> {
+        Some(format!("{} {} {}", values[2], values[1], values[0]))
+    }
+
+    /// The same color, style and width for all sides, given side by side as for
+    /// 'border-top', 'border-right', 'border-bottom' and 'border-left'.
+    pub fn serialize_border(values: &[~str]) -> Option<~str> {
+        let top = values.slice(0, 3);
+        if values.chunk_iter(3).all(|side| side == top) {
+            serialize_border_side(top)
+        } else {
+            None
+        }
+    }
+
+    /// font-style font-variant font-weight font-size/line-height font-family
+    pub fn serialize_font(values: &[~str]) -> Option<~str> {
+        Some(format!("{} {} {} {}/{} {}", values[0], values[1], values[2], values[3], values[4],
+                     values[5]))
+    }
+
 
     % for side in ["top", "right", "bottom", "left"]:
         <%self:shorthand name="border-${side}" sub_properties="${' '.join(
             'border-%s-%s' % (side, prop)
             for prop in ['color', 'style', 'width']
-        )}">
+        )}" serializer="serialize_border_side">
             do parse_border(input).map |(color, style, width)| {
                 Longhands {
                     % for prop in ["color", "style", "width"]:
@@ -777,7 +1652,7 @@ pub mod shorthands {
         'border-%s-%s' % (side, prop)
         for side in ['top', 'right', 'bottom', 'left']
         for prop in ['color', 'style', 'width']
-    )}">
+    )}" serializer="serialize_border">
         do parse_border(input).map |(color, style, width)| {
             Longhands {
                 % for side in ["top", "right", "bottom", "left"]:
@@ -790,7 +1665,8 @@ pub mod shorthands {
     </%self:shorthand>
 
     <%self:shorthand name="font" sub_properties="font-style font-variant font-weight
-                                                 font-size line-height font-family">
+                                                 font-size line-height font-family"
+            serializer="serialize_font">
         let mut iter = input.skip_whitespace();
         let mut nb_normals = 0u;
         let mut style = None;
@@ -867,31 +1743,191 @@ pub mod shorthands {
 }
 
 
+#[deriving(Clone)]
 pub struct PropertyDeclarationBlock {
     important: Arc<~[PropertyDeclaration]>,
     normal: Arc<~[PropertyDeclaration]>,
 }
 
+impl PropertyDeclarationBlock {
+    pub fn new() -> PropertyDeclarationBlock {
+        PropertyDeclarationBlock {
+            important: Arc::new(~[]),
+            normal: Arc::new(~[]),
+        }
+    }
+
+    /// The longhands declared in this block, with their values and whether they are important.
+    /// When a longhand is declared several times, only the declaration that wins is kept.
+    pub fn declarations(&self) -> ~[(&'static str, ~str, bool)] {
+        let mut result: ~[(&'static str, ~str, bool)] = ~[];
+        for &(list, important) in [(&self.normal, false), (&self.important, true)].iter() {
+            for declaration in list.get().iter() {
+                let name = declaration.name();
+                result.retain(|&(other_name, _, _)| other_name != name);
+                result.push((name, declaration.value(), important))
+            }
+        }
+        result
+    }
+
+    /// The declaration of the longhand `name` that wins in this block, if any, and whether it
+    /// is important.
+    fn find<'a>(&'a self, name: &str) -> Option<(&'a PropertyDeclaration, bool)> {
+        for &(list, important) in [(&self.important, true), (&self.normal, false)].iter() {
+            match list.get().rev_iter().find(|declaration| declaration.name() == name) {
+                Some(declaration) => return Some((declaration, important)),
+                None => (),
+            }
+        }
+        None
+    }
 
-pub fn parse_style_attribute(input: &str) -> PropertyDeclarationBlock {
-    parse_property_declaration_list(tokenize(input))
+    /// The value of the property `name`, a longhand or a shorthand, serialized to CSS, and
+    /// whether it is important. None if the property is not declared. A shorthand is only
+    /// found when all its longhands are declared, with the same importance, and their values
+    /// can be expressed with it.
+    pub fn property_value(&self, name: &str) -> Option<(~str, bool)> {
+        let name_lower = name.to_ascii_lower();
+        let longhands = longhands_of(name_lower);
+        let mut declarations = ~[];
+        let mut important = false;
+        for (i, longhand) in longhands.iter().enumerate() {
+            match self.find(*longhand) {
+                Some((declaration, longhand_important)) => {
+                    if i != 0 && longhand_important != important {
+                        return None
+                    }
+                    declarations.push(declaration);
+                    important = longhand_important;
+                }
+                None => return None,
+            }
+        }
+        if longhands.is_empty() {
+            None
+        } else if longhands.len() == 1 && longhands[0] == name_lower.as_slice() {
+            Some((declarations[0].value(), important))
+        } else {
+            serialize_shorthand(name_lower, declarations).map(|value| (value, important))
+        }
+    }
+
+    /// Sets the property `name`, a longhand or a shorthand, to `value`, replacing the previous
+    /// declarations of its longhands. Returns false, and leaves the block unchanged, if the
+    /// property is not supported or the value is invalid.
+    pub fn set_property(&mut self, name: &str, value: &str, important: bool, base_url: &Url)
+                        -> bool {
+        let value = tokenize(value).map(|(v, _)| v).to_owned_vec();
+        let mut declarations = ~[];
+        match PropertyDeclaration::parse(name, value, &mut declarations, base_url) {
+            ValidDeclaration => {}
+            UnknownProperty | InvalidValue => return false,
+        }
+        self.remove_property(name);
+        let list = if important { &mut self.important } else { &mut self.normal };
+        let mut new_list = list.get().clone();
+        new_list.push_all_move(declarations);
+        *list = Arc::new(new_list);
+        true
+    }
+
+    /// Removes the declarations of the property `name`, a longhand or a shorthand. Returns false
+    /// if there were none.
+    pub fn remove_property(&mut self, name: &str) -> bool {
+        fn without(list: &Arc<~[PropertyDeclaration]>, longhands: &[&'static str])
+                   -> Arc<~[PropertyDeclaration]> {
+            Arc::new(list.get().iter().filter(|declaration| {
+                !longhands.contains(&declaration.name())
+            }).map(|declaration| declaration.clone()).collect())
+        }
+        let longhands = longhands_of(name);
+        let declared = |list: &Arc<~[PropertyDeclaration]>| {
+            list.get().iter().any(|declaration| longhands.contains(&declaration.name()))
+        };
+        if !declared(&self.normal) && !declared(&self.important) {
+            return false
+        }
+        self.normal = without(&self.normal, longhands);
+        self.important = without(&self.important, longhands);
+        true
+    }
 }
 
+/// Serialized as `name: value; name: value !important;`, with shorthands instead of their
+/// longhands where possible.
+impl ToCssText for PropertyDeclarationBlock {
+    fn push_css_text(&self, css: &mut ~str) {
+        let mut serialized: ~[&'static str] = ~[];
+        let mut first = true;
+        for (name, value, important) in self.declarations().move_iter() {
+            if serialized.contains(&name) {
+                continue
+            }
+            let mut declaration = (name, value, important);
+            for &shorthand in shorthands_containing(name).iter() {
+                let longhands = longhands_of(shorthand);
+                if longhands.iter().any(|longhand| serialized.contains(longhand)) {
+                    continue
+                }
+                match self.property_value(shorthand) {
+                    Some((value, shorthand_important)) if shorthand_important == important => {
+                        declaration = (shorthand, value, important);
+                        break
+                    }
+                    _ => (),
+                }
+            }
+            let (name, value, important) = declaration;
+            serialized.push_all(longhands_of(name));
+            if !first {
+                css.push_char(' ');
+            }
+            first = false;
+            css.push_str(name);
+            css.push_str(": ");
+            css.push_str(value);
+            if important {
+                css.push_str(" !important");
+            }
+            css.push_char(';');
+        }
+    }
+}
+
+
+/// Parses the value of a style attribute. Its errors are given to `reporter`, with the URL of
+/// the document.
+pub fn parse_style_attribute(input: &str, base_url: &Url, reporter: &mut ErrorReporter)
+                             -> PropertyDeclarationBlock {
+    let mut errors = ErrorContext::new(base_url.clone(), reporter);
+    parse_property_declaration_list(tokenize(input), base_url, &mut errors)
+}
 
-pub fn parse_property_declaration_list<I: Iterator<Node>>(input: I) -> PropertyDeclarationBlock {
+
+pub fn parse_property_declaration_list<I: Iterator<Node>>(input: I, base_url: &Url,
+                                                          errors: &mut ErrorContext)
+                                                          -> PropertyDeclarationBlock {
     let mut important = ~[];
     let mut normal = ~[];
-    for item in ErrorLoggerIterator(parse_declaration_list(input)) {
+    for result in parse_declaration_list(input) {
+        let item = match result {
+            Ok(item) => item,
+            Err(error) => {
+                errors.report_syntax_error(error);
+                continue
+            }
+        };
         match item {
-            Decl_AtRule(rule) => log_css_error(
-                rule.location, format!("Unsupported at-rule in declaration list: @{:s}", rule.name)),
+            Decl_AtRule(rule) => errors.report(rule.location, InvalidAtRule, format!(
+                "Unsupported at-rule in declaration list: @{:s}", rule.name)),
             Declaration(Declaration{ location: l, name: n, value: v, important: i}) => {
                 // TODO: only keep the last valid declaration for a given name.
                 let list = if i { &mut important } else { &mut normal };
-                match PropertyDeclaration::parse(n, v, list) {
-                    UnknownProperty => log_css_error(l, format!(
+                match PropertyDeclaration::parse(n, v, list, base_url) {
+                    UnknownProperty => errors.report(l, errors::UnknownProperty, format!(
                         "Unsupported property: {}:{}", n, v.iter().to_css())),
-                    InvalidValue => log_css_error(l, format!(
+                    InvalidValue => errors.report(l, errors::InvalidValue, format!(
                         "Invalid value: {}:{}", n, v.iter().to_css())),
                     ValidDeclaration => (),
                 }
@@ -905,12 +1941,21 @@ pub fn parse_property_declaration_list<I: Iterator<Node>>(input: I) -> PropertyD
 }
 
 
-#[deriving(Clone)]
+#[deriving(Eq, Clone)]
 pub enum CSSWideKeyword {
     Initial,
     Inherit,
 }
 
+impl ToCssText for CSSWideKeyword {
+    fn push_css_text(&self, css: &mut ~str) {
+        css.push_str(match *self {
+            Initial => "initial",
+            Inherit => "inherit",
+        })
+    }
+}
+
 struct Unset;
 
 impl CSSWideKeyword {
@@ -949,13 +1994,14 @@ enum PropertyDeclarationParseResult {
 
 impl PropertyDeclaration {
     pub fn parse(name: &str, value: &[ComponentValue],
-                 result_list: &mut ~[PropertyDeclaration]) -> PropertyDeclarationParseResult {
+                 result_list: &mut ~[PropertyDeclaration],
+                 base_url: &Url) -> PropertyDeclarationParseResult {
         // FIXME: local variable to work around Rust #10683
         let name_lower = name.to_ascii_lower();
         match name_lower.as_slice() {
             % for property in LONGHANDS:
                 "${property.name}" => result_list.push(${property.ident}_declaration(
-                    match longhands::${property.ident}::parse_declared(value) {
+                    match longhands::${property.ident}::parse_declared(value, base_url) {
                         Some(value) => value,
                         None => return InvalidValue,
                     }
@@ -978,7 +2024,7 @@ impl PropertyDeclaration {
                             ));
                         % endfor
                     },
-                    None => match shorthands::${shorthand.ident}::parse(value) {
+                    None => match shorthands::${shorthand.ident}::parse(value, base_url) {
                         Some(result) => {
                             % for sub_property in shorthand.sub_properties:
                                 result_list.push(${sub_property.ident}_declaration(
@@ -997,6 +2043,120 @@ impl PropertyDeclaration {
         }
         ValidDeclaration
     }
+
+    /// The name of the longhand that this declares.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            % for property in LONGHANDS:
+                ${property.ident}_declaration(_) => "${property.name}",
+            % endfor
+        }
+    }
+
+    /// The declared value, serialized to CSS.
+    pub fn value(&self) -> ~str {
+        let mut css = ~"";
+        match *self {
+            % for property in LONGHANDS:
+                ${property.ident}_declaration(ref value) => match *value {
+                    SpecifiedValue(ref value)
+                    => longhands::${property.ident}::specified_to_css(value, &mut css),
+                    CSSWideKeyword(keyword) => keyword.push_css_text(&mut css),
+                },
+            % endfor
+        }
+        css
+    }
+
+    fn css_wide_keyword(&self) -> Option<CSSWideKeyword> {
+        match *self {
+            % for property in LONGHANDS:
+                ${property.ident}_declaration(CSSWideKeyword(keyword)) => Some(keyword),
+            % endfor
+            _ => None,
+        }
+    }
+
+    /// The declared value as part of a shorthand, where 'initial' is replaced by the initial
+    /// value of the longhand. None for 'inherit', which shorthands can only take for all their
+    /// longhands at once.
+    fn value_in_shorthand(&self) -> Option<~str> {
+        let mut css = ~"";
+        match *self {
+            % for property in LONGHANDS:
+                ${property.ident}_declaration(CSSWideKeyword(Initial)) => {
+                    longhands::${property.ident}::computed_to_css(
+                        &longhands::${property.ident}::get_initial_value(), &mut css)
+                }
+            % endfor
+            _ => match self.css_wide_keyword() {
+                Some(_) => return None,
+                None => css = self.value(),
+            },
+        }
+        Some(css)
+    }
+}
+
+
+/// Serializes the shorthand `name` from the declarations of its longhands, given in the order
+/// of its sub-properties. None if they can not be expressed with it.
+fn serialize_shorthand(name: &str, declarations: &[&PropertyDeclaration]) -> Option<~str> {
+    let keyword = declarations[0].css_wide_keyword();
+    if keyword.is_some() && declarations.iter().all(|d| d.css_wide_keyword() == keyword) {
+        return Some(keyword.unwrap().to_css_text())
+    }
+    let mut values = ~[];
+    for declaration in declarations.iter() {
+        match declaration.value_in_shorthand() {
+            Some(value) => values.push(value),
+            None => return None,
+        }
+    }
+    match name {
+        % for shorthand in SHORTHANDS:
+            "${shorthand.name}" => shorthands::${shorthand.ident}::to_css(values),
+        % endfor
+        _ => None,
+    }
+}
+
+
+/// The shorthands that set the longhand `name`, those with the most longhands first.
+fn shorthands_containing(name: &str) -> ~[&'static str] {
+    match name {
+        % for property in LONGHANDS:
+            "${property.name}" => ~[
+                % for shorthand in sorted(SHORTHANDS, key=lambda s: -len(s.sub_properties)):
+                    % if property in shorthand.sub_properties:
+                        "${shorthand.name}",
+                    % endif
+                % endfor
+            ],
+        % endfor
+        _ => ~[],
+    }
+}
+
+
+/// The longhands set by the property `name`, which is a longhand or a shorthand. Empty if the
+/// property is not supported.
+pub fn longhands_of(name: &str) -> ~[&'static str] {
+    // FIXME: local variable to work around Rust #10683
+    let name_lower = name.to_ascii_lower();
+    match name_lower.as_slice() {
+        % for property in LONGHANDS:
+            "${property.name}" => ~["${property.name}"],
+        % endfor
+        % for shorthand in SHORTHANDS:
+            "${shorthand.name}" => ~[
+                % for sub_property in shorthand.sub_properties:
+                    "${sub_property.name}",
+                % endfor
+            ],
+        % endfor
+        _ => ~[],
+    }
 }
 
 
@@ -1017,6 +2177,8 @@ pub struct ComputedValues {
     % for name, longhands in LONGHANDS_PER_STYLE_STRUCT:
         ${name}: style_structs::${name},
     % endfor
+    /// The computed 'font-size' of the root element, for 'rem'.
+    root_font_size: Au,
 }
 
 impl ComputedValues {
@@ -1033,6 +2195,37 @@ impl ComputedValues {
             CurrentColor => self.Color.color,
         }
     }
+
+    /// Returns a copy of these values with the colours of `visited`, the style computed as if the
+    /// nearest link were visited. No other property may depend on the visited state of links,
+    /// or it could be found out through layout.
+    pub fn with_visited_colors(&self, visited: &ComputedValues) -> ComputedValues {
+        let mut style = self.clone();
+        style.Color.color = visited.Color.color;
+        style.Background.background_color = visited.Background.background_color;
+        % for side in ["top", "right", "bottom", "left"]:
+            style.Border.border_${side}_color = visited.Border.border_${side}_color;
+        % endfor
+        style
+    }
+
+    /// The names and values, serialized to CSS, of all longhands, for script.
+    pub fn to_property_list(&self) -> ~[(~str, ~str)] {
+        fn serialize<T>(value: &T, to_css: &fn(&T, &mut ~str)) -> ~str {
+            let mut css = ~"";
+            to_css(value, &mut css);
+            css
+        }
+        ~[
+            % for style_struct, longhands in LONGHANDS_PER_STYLE_STRUCT:
+                % for longhand in longhands:
+                    (~"${longhand.name}",
+                     serialize(&self.${style_struct}.${longhand.ident},
+                               longhands::${longhand.ident}::computed_to_css)),
+                % endfor
+            % endfor
+        ]
+    }
 }
 
 #[inline]
@@ -1045,13 +2238,16 @@ fn get_initial_values() -> ComputedValues {
                 % endfor
             },
         % endfor
+        root_font_size: longhands::font_size::get_initial_value(),
     }
 }
 
 
 // Most specific/important declarations last
+// Viewport-percentage lengths are relative to the viewport of `device`.
 pub fn cascade(applicable_declarations: &[Arc<~[PropertyDeclaration]>],
-               parent_style: Option< &ComputedValues>)
+               parent_style: Option< &ComputedValues>,
+               device: &Device)
             -> ComputedValues {
     let initial_keep_alive;
     let (parent_style, is_root_element) = match parent_style {
@@ -1115,6 +2311,10 @@ pub fn cascade(applicable_declarations: &[Arc<~[PropertyDeclaration]>],
         has_border_right: has_border!(border_right_style),
         has_border_bottom: has_border!(border_bottom_style),
         has_border_left: has_border!(border_left_style),
+        // For the root element, this is the initial value until its own 'font-size' is known.
+        root_font_size: parent_style.root_font_size,
+        viewport_width: device.viewport_width,
+        viewport_height: device.viewport_height,
     };
     macro_rules! get_computed(
         ($style_struct: ident, $property: ident) => {
@@ -1128,6 +2328,9 @@ pub fn cascade(applicable_declarations: &[Arc<~[PropertyDeclaration]>],
         };
     )
     context.font_size = get_computed!(Font, font_size);
+    if is_root_element {
+        context.root_font_size = context.font_size;
+    }
     ComputedValues {
         % for style_struct, longhands in LONGHANDS_PER_STYLE_STRUCT:
             ${style_struct}: style_structs::${style_struct} {
@@ -1136,6 +2339,7 @@ pub fn cascade(applicable_declarations: &[Arc<~[PropertyDeclaration]>],
                 % endfor
             },
         % endfor
+        root_font_size: context.root_font_size,
     }
 }
 
@@ -1150,7 +2354,8 @@ pub mod computed_values {
 
     pub use cssparser::RGBA;
     pub use super::common_types::computed::{
-        LengthOrPercentage, LP_Length, LP_Percentage,
-        LengthOrPercentageOrAuto, LPA_Length, LPA_Percentage, LPA_Auto,
-        LengthOrPercentageOrNone, LPN_Length, LPN_Percentage, LPN_None};
+        Calc,
+        LengthOrPercentage, LP_Length, LP_Percentage, LP_Calc,
+        LengthOrPercentageOrAuto, LPA_Length, LPA_Percentage, LPA_Calc, LPA_Auto,
+        LengthOrPercentageOrNone, LPN_Length, LPN_Percentage, LPN_Calc, LPN_None};
 }
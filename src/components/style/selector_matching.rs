@@ -4,16 +4,32 @@
 
 use std::ascii::StrAsciiExt;
 use std::hashmap::HashMap;
+use std::util;
 use extra::arc::Arc;
 use extra::sort::tim_sort;
+use extra::time::precise_time_ns;
 
+use bloom::{AncestorFilter, AncestorHashes};
 use selectors::*;
-use stylesheets::{Stylesheet, iter_style_rules};
-use media_queries::{Device, Screen};
+use stylesheets::{Stylesheet, iter_style_rules, iter_page_rules, media_queries_changed};
+use media_queries::Device;
+use page::PageBox;
 use properties::{PropertyDeclaration, PropertyDeclarationBlock};
+use servo_util::atom::Atom;
 use servo_util::tree::{TreeNodeRefAsElement, TreeNode, ElementLike};
 
-use std::str;
+use std::ptr;
+
+#[cfg(test)]
+use std::from_str::FromStr;
+#[cfg(test)]
+use errors::LogErrorReporter;
+#[cfg(test)]
+use media_queries::Screen;
+#[cfg(test)]
+use servo_util::geometry::Au;
+#[cfg(test)]
+use servo_util::tree::TreeNodeRef;
 
 pub enum StylesheetOrigin {
     UserAgentOrigin,
@@ -21,6 +37,24 @@ pub enum StylesheetOrigin {
     UserOrigin,
 }
 
+/// The compatibility mode of a document, which the HTML parser finds out from its doctype.
+#[deriving(Eq, Clone)]
+pub enum QuirksMode {
+    NoQuirks,
+    /// Only changes the height of line boxes, which is not implemented yet, so documents in
+    /// limited quirks mode are styled like those in no quirks mode.
+    LimitedQuirks,
+    FullQuirks,
+}
+
+impl QuirksMode {
+    /// Ids and classes are matched ASCII case-insensitively in quirks mode.
+    #[inline]
+    pub fn has_case_insensitive_ids_and_classes(&self) -> bool {
+        *self == FullQuirks
+    }
+}
+
 
 /// Map node attributes to Rules whose last simple selector starts with them.
 ///
@@ -43,21 +77,33 @@ pub enum StylesheetOrigin {
 /// node.
 pub struct SelectorMap {
     // TODO: Tune the initial capacity of the HashMap
-    // FIXME: Use interned strings
-    priv id_hash: HashMap<~str, ~[Rule]>,
-    priv class_hash: HashMap<~str, ~[Rule]>,
-    priv element_hash: HashMap<~str, ~[Rule]>,
+    priv id_hash: HashMap<Atom, ~[Rule]>,
+    priv class_hash: HashMap<Atom, ~[Rule]>,
+    priv element_hash: HashMap<Atom, ~[Rule]>,
     // For Rules that don't have ID, class, or element selectors.
     priv universal_rules: ~[Rule],
+    priv quirks_mode: QuirksMode,
 }
 
 impl SelectorMap {
-    fn new() -> SelectorMap {
+    fn new(quirks_mode: QuirksMode) -> SelectorMap {
         SelectorMap {
             id_hash: HashMap::new(),
             class_hash: HashMap::new(),
             element_hash: HashMap::new(),
             universal_rules: ~[],
+            quirks_mode: quirks_mode,
+        }
+    }
+
+    /// Returns the key of the rules for an id or class. In quirks mode, ids and classes are
+    /// matched case-insensitively, so the keys are lowercased once here, and elements give
+    /// lowercased ids and classes (see `ElementLike::get_id`).
+    fn id_or_class_key(&self, name: Atom) -> Atom {
+        if self.quirks_mode.has_case_insensitive_ids_and_classes() {
+            Atom::from_slice(name.as_slice().to_ascii_lower())
+        } else {
+            name
         }
     }
 
@@ -68,36 +114,36 @@ impl SelectorMap {
     fn get_all_matching_rules<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: ElementLike>(
         &self, node: &T,
         pseudo_element: Option<PseudoElement>,
+        visited_link: Option<&T>,
+        ancestor_filter: &mut AncestorFilter,
         matching_rules_list: &mut [~[Rule]],
         list_index: uint) {
 
         let init_len = matching_rules_list[list_index].len();
-        static WHITESPACE: &'static [char] = &'static [' ', '\t', '\n', '\r', '\x0C'];
+        let quirks_mode = self.quirks_mode;
         do node.with_imm_element_like |element: &E| {
-            match element.get_attr(None, "id") {
+            match element.get_id() {
                 Some(id) => SelectorMap::get_matching_rules_from_hash(
-                    node, pseudo_element, &self.id_hash, id, &mut matching_rules_list[list_index]),
+                    node, pseudo_element, visited_link, ancestor_filter, quirks_mode,
+                    &self.id_hash, &id,
+                    &mut matching_rules_list[list_index]),
                 None => {}
             }
 
-            match element.get_attr(None, "class") {
-                Some(ref class_attr) => {
-                    for class in class_attr.split_iter(WHITESPACE) {
-                        SelectorMap::get_matching_rules_from_hash(
-                            node, pseudo_element, &self.class_hash, class, &mut matching_rules_list[list_index]);
-                    }
-                }
-                None => {}
+            for class in element.get_classes().iter() {
+                SelectorMap::get_matching_rules_from_hash(
+                    node, pseudo_element, visited_link, ancestor_filter, quirks_mode,
+                    &self.class_hash, class,
+                    &mut matching_rules_list[list_index]);
             }
 
             SelectorMap::get_matching_rules_from_hash(
-                node, pseudo_element, &self.element_hash,
-                // HTML elements in HTML documents must be matched case-insensitively
-                // TODO: case-sensitivity depends on the document type
-                element.get_local_name().to_ascii_lower(),
+                node, pseudo_element, visited_link, ancestor_filter, quirks_mode,
+                &self.element_hash, &element.get_local_name(),
                 &mut matching_rules_list[list_index]);
             SelectorMap::get_matching_rules(
-                node, pseudo_element, self.universal_rules, &mut matching_rules_list[list_index]);
+                node, pseudo_element, visited_link, ancestor_filter, quirks_mode,
+                self.universal_rules, &mut matching_rules_list[list_index]);
         }
 
         // Sort only the rules we just added.
@@ -107,11 +153,15 @@ impl SelectorMap {
     fn get_matching_rules_from_hash<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: ElementLike>(
         node: &T,
         pseudo_element: Option<PseudoElement>,
-        hash: &HashMap<~str, ~[Rule]>, 
-        key: &str,
+        visited_link: Option<&T>,
+        ancestor_filter: &mut AncestorFilter,
+        quirks_mode: QuirksMode,
+        hash: &HashMap<Atom, ~[Rule]>,
+        key: &Atom,
         matching_rules: &mut ~[Rule]) {
-        match hash.find(&key.to_str()) {
-            Some(rules) => SelectorMap::get_matching_rules(node, pseudo_element, *rules,
+        match hash.find(key) {
+            Some(rules) => SelectorMap::get_matching_rules(node, pseudo_element, visited_link,
+                                                           ancestor_filter, quirks_mode, *rules,
                                                            matching_rules),
             None => {}
         };
@@ -121,10 +171,22 @@ impl SelectorMap {
     fn get_matching_rules<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: ElementLike>(
         node: &T,
         pseudo_element: Option<PseudoElement>,
+        visited_link: Option<&T>,
+        ancestor_filter: &mut AncestorFilter,
+        quirks_mode: QuirksMode,
         rules: &[Rule],
         matching_rules: &mut ~[Rule]) {
         for rule in rules.iter() {
-            if matches_selector(rule.selector.get(), node, pseudo_element) {
+            if !ancestor_filter.might_have_all(&rule.ancestor_hashes) {
+                continue
+            }
+            // Only the selectors that the filter lets through are timed, so that the time of
+            // each full match estimates the time that each rejection saves.
+            let start_time = precise_time_ns();
+            let matches = matches_selector(rule.selector.get(), node, pseudo_element,
+                                           visited_link, quirks_mode);
+            ancestor_filter.matching_time += precise_time_ns() - start_time;
+            if matches {
                 // TODO: Is the cloning inefficient?
                 matching_rules.push(rule.clone());
             }
@@ -136,6 +198,7 @@ impl SelectorMap {
     fn insert(&mut self, rule: Rule) {
         match SelectorMap::get_id_name(&rule) {
             Some(id_name) => {
+                let id_name = self.id_or_class_key(id_name);
                 match self.id_hash.find_mut(&id_name) {
                     Some(rules) => {
                         rules.push(rule);
@@ -150,6 +213,7 @@ impl SelectorMap {
         }
         match SelectorMap::get_class_name(&rule) {
             Some(class_name) => {
+                let class_name = self.id_or_class_key(class_name);
                 match self.class_hash.find_mut(&class_name) {
                     Some(rules) => {
                         rules.push(rule);
@@ -182,12 +246,11 @@ impl SelectorMap {
     }
 
     /// Retrieve the first ID name in Rule, or None otherwise.
-    fn get_id_name(rule: &Rule) -> Option<~str> {
+    fn get_id_name(rule: &Rule) -> Option<Atom> {
         let simple_selector_sequence = &rule.selector.get().compound_selectors.simple_selectors;
         for ss in simple_selector_sequence.iter() {
             match *ss {
-                // TODO: Implement case-sensitivity based on the document type and quirks mode
-                IDSelector(ref id) => return Some(id.clone()),
+                IDSelector(id) => return Some(id),
                 _ => {}
             }
         }
@@ -195,12 +258,11 @@ impl SelectorMap {
     }
 
     /// Retrieve the FIRST class name in Rule, or None otherwise.
-    fn get_class_name(rule: &Rule) -> Option<~str> {
+    fn get_class_name(rule: &Rule) -> Option<Atom> {
         let simple_selector_sequence = &rule.selector.get().compound_selectors.simple_selectors;
         for ss in simple_selector_sequence.iter() {
             match *ss {
-                // TODO: Implement case-sensitivity based on the document type and quirks mode
-                ClassSelector(ref class) => return Some(class.clone()),
+                ClassSelector(class) => return Some(class),
                 _ => {}
             }
         }
@@ -208,13 +270,11 @@ impl SelectorMap {
     }
 
     /// Retrieve the name if it is a type selector, or None otherwise.
-    fn get_element_name(rule: &Rule) -> Option<~str> {
+    fn get_element_name(rule: &Rule) -> Option<Atom> {
         let simple_selector_sequence = &rule.selector.get().compound_selectors.simple_selectors;
         for ss in simple_selector_sequence.iter() {
             match *ss {
-                // HTML elements in HTML documents must be matched case-insensitively
-                // TODO: case-sensitivity depends on the document type
-                LocalNameSelector(ref name) => return Some(name.to_ascii_lower()),
+                LocalNameSelector(name) => return Some(name),
                 _ => {}
             }
         }
@@ -227,20 +287,114 @@ pub struct Stylist {
     priv author_rule_map: PerOriginSelectorMap,
     priv user_rule_map: PerOriginSelectorMap,
     priv stylesheet_index: uint,
+    /// The device media queries are evaluated against.
+    priv device: Device,
+    /// All the style sheets added so far, kept to rebuild the rule maps when a change of
+    /// device changes which media queries match.
+    priv stylesheets: ~[(Stylesheet, StylesheetOrigin)],
+    /// The compatibility mode of the document being styled.
+    priv quirks_mode: QuirksMode,
+    /// A user agent style sheet that only applies in quirks mode.
+    priv quirks_mode_stylesheet: Option<Stylesheet>,
 }
 
 impl Stylist {
     #[inline]
-    pub fn new() -> Stylist {
+    pub fn new(device: Device) -> Stylist {
         Stylist {
-            ua_rule_map: PerOriginSelectorMap::new(),
-            author_rule_map: PerOriginSelectorMap::new(),
-            user_rule_map: PerOriginSelectorMap::new(),
+            ua_rule_map: PerOriginSelectorMap::new(NoQuirks),
+            author_rule_map: PerOriginSelectorMap::new(NoQuirks),
+            user_rule_map: PerOriginSelectorMap::new(NoQuirks),
             stylesheet_index: 0u,
+            device: device,
+            stylesheets: ~[],
+            quirks_mode: NoQuirks,
+            quirks_mode_stylesheet: None,
         }
     }
 
     pub fn add_stylesheet(&mut self, stylesheet: Stylesheet, origin: StylesheetOrigin) {
+        self.add_rules(&stylesheet, origin);
+        self.stylesheets.push((stylesheet, origin));
+    }
+
+    #[inline]
+    pub fn device<'a>(&'a self) -> &'a Device {
+        &self.device
+    }
+
+    /// Changes the device media queries are evaluated against, e.g. when the viewport is
+    /// resized. Returns true if this changed which rules apply, in which case selectors
+    /// need to be matched again.
+    pub fn set_device(&mut self, device: Device) -> bool {
+        let changed = self.stylesheets.iter().any(|&(ref stylesheet, _)| {
+            media_queries_changed(stylesheet.rules.as_slice(), &self.device, &device)
+        });
+        self.device = device;
+        if changed {
+            self.rebuild_rule_maps();
+        }
+        changed
+    }
+
+    /// Sets the user agent style sheet that only applies to documents in quirks mode.
+    pub fn set_quirks_mode_stylesheet(&mut self, stylesheet: Stylesheet) {
+        self.quirks_mode_stylesheet = Some(stylesheet);
+        self.rebuild_rule_maps();
+    }
+
+    /// Changes the compatibility mode of the document being styled. Returns true if it changed,
+    /// in which case selectors need to be matched again.
+    pub fn set_quirks_mode(&mut self, quirks_mode: QuirksMode) -> bool {
+        if self.quirks_mode == quirks_mode {
+            return false
+        }
+        self.quirks_mode = quirks_mode;
+        self.rebuild_rule_maps();
+        true
+    }
+
+    fn rebuild_rule_maps(&mut self) {
+        self.ua_rule_map = PerOriginSelectorMap::new(self.quirks_mode);
+        self.author_rule_map = PerOriginSelectorMap::new(self.quirks_mode);
+        self.user_rule_map = PerOriginSelectorMap::new(self.quirks_mode);
+        self.stylesheet_index = 0u;
+        let stylesheets = util::replace(&mut self.stylesheets, ~[]);
+        for &(ref stylesheet, origin) in stylesheets.iter() {
+            self.add_rules(stylesheet, origin);
+        }
+        self.stylesheets = stylesheets;
+        if self.quirks_mode == FullQuirks {
+            let quirks_mode_stylesheet = util::replace(&mut self.quirks_mode_stylesheet, None);
+            for stylesheet in quirks_mode_stylesheet.iter() {
+                self.add_rules(stylesheet, UserAgentOrigin);
+            }
+            self.quirks_mode_stylesheet = quirks_mode_stylesheet;
+        }
+    }
+
+    /// Returns the page box for printing, from the @page rules matching the given print
+    /// device. The size of its viewport is used for pages whose 'size' is 'auto'.
+    pub fn page_box(&self, device: &Device) -> PageBox {
+        let mut rules = ~[];
+        // @page rules cascade like normal declarations: user agent, then user, then author.
+        for &wanted_origin in [UserAgentOrigin, UserOrigin, AuthorOrigin].iter() {
+            for &(ref stylesheet, origin) in self.stylesheets.iter() {
+                let same_origin = match (origin, wanted_origin) {
+                    (UserAgentOrigin, UserAgentOrigin) | (UserOrigin, UserOrigin)
+                    | (AuthorOrigin, AuthorOrigin) => true,
+                    _ => false,
+                };
+                if same_origin {
+                    iter_page_rules(stylesheet.rules.as_slice(), device,
+                                    |rule| rules.push(rule));
+                }
+            }
+        }
+        PageBox::cascade(rules, device.viewport_width, device.viewport_height)
+    }
+
+    fn add_rules(&mut self, stylesheet: &Stylesheet, origin: StylesheetOrigin) {
         let rule_map = match origin {
             UserAgentOrigin => &mut self.ua_rule_map,
             AuthorOrigin => &mut self.author_rule_map,
@@ -249,6 +403,7 @@ impl Stylist {
         let mut added_normal_declarations = false;
         let mut added_important_declarations = false;
         let mut style_rule_index = 0u;
+        let quirks_mode = self.quirks_mode;
 
         // Take apart the StyleRule into individual Rules and insert
         // them into the SelectorMap of that priority.
@@ -260,6 +415,7 @@ impl Stylist {
                         // TODO: avoid copying?
                         rule_map.$priority.insert(Rule {
                                 selector: Arc::new(selector.clone()),
+                                ancestor_hashes: AncestorHashes::new(selector, quirks_mode),
                                 declarations: style_rule.declarations.$priority.clone(),
                                 index: style_rule_index,
                                 stylesheet_index: self.stylesheet_index,
@@ -269,8 +425,7 @@ impl Stylist {
             };
         );
 
-        let device = &Device { media_type: Screen };  // TODO, use Print when printing
-        do iter_style_rules(stylesheet.rules.as_slice(), device) |style_rule| {
+        do iter_style_rules(stylesheet.rules.as_slice(), &self.device) |style_rule| {
             append!(normal, added_normal_declarations);
             append!(important, added_important_declarations);
             style_rule_index += 1u;
@@ -278,9 +433,33 @@ impl Stylist {
         self.stylesheet_index += 1;
     }
 
+    /// Returns the declarations that apply to `element`, in cascading order. All links are
+    /// matched as unvisited. `ancestor_filter` must contain the ancestors of `element`.
     pub fn get_applicable_declarations<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: ElementLike>(
             &self, element: &T, style_attribute: Option<&PropertyDeclarationBlock>,
-            pseudo_element: Option<PseudoElement>) -> ~[Arc<~[PropertyDeclaration]>] {
+            pseudo_element: Option<PseudoElement>, ancestor_filter: &mut AncestorFilter)
+            -> ~[Arc<~[PropertyDeclaration]>] {
+        self.get_declarations(element, style_attribute, pseudo_element, None, ancestor_filter)
+    }
+
+    /// Returns the declarations that apply to `element` if `visited_link`, the nearest link among
+    /// the element and its ancestors, points to a visited URL. `visited_link` is the only element
+    /// that matches `:visited`, and only through descendant and child combinators.
+    ///
+    /// To keep the browsing history private, only the colours of the resulting style may be used
+    /// (see `ComputedValues::with_visited_colors`).
+    pub fn get_visited_declarations<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: ElementLike>(
+            &self, element: &T, style_attribute: Option<&PropertyDeclarationBlock>,
+            pseudo_element: Option<PseudoElement>, visited_link: &T,
+            ancestor_filter: &mut AncestorFilter) -> ~[Arc<~[PropertyDeclaration]>] {
+        self.get_declarations(element, style_attribute, pseudo_element, Some(visited_link),
+                              ancestor_filter)
+    }
+
+    fn get_declarations<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: ElementLike>(
+            &self, element: &T, style_attribute: Option<&PropertyDeclarationBlock>,
+            pseudo_element: Option<PseudoElement>, visited_link: Option<&T>,
+            ancestor_filter: &mut AncestorFilter) -> ~[Arc<~[PropertyDeclaration]>] {
         assert!(element.is_element());
         assert!(style_attribute.is_none() || pseudo_element.is_none(),
                 "Style attributes do not apply to pseudo-elements");
@@ -295,7 +474,8 @@ impl Stylist {
 
         let mut matching_rules_list: [~[Rule], ..6] = [~[], ~[], ~[], ~[], ~[], ~[]];
         for (i, rule_map) in rule_map_list.iter().enumerate() {
-            rule_map.get_all_matching_rules(element, pseudo_element, matching_rules_list, i);
+            rule_map.get_all_matching_rules(element, pseudo_element, visited_link,
+                                            ancestor_filter, matching_rules_list, i);
         }
         
         // Keeping this as a separate step because we will need it for further
@@ -336,8 +516,11 @@ struct PerOriginSelectorMap {
 
 impl PerOriginSelectorMap {
     #[inline]
-    fn new() -> PerOriginSelectorMap {
-        PerOriginSelectorMap { normal: SelectorMap::new(), important:SelectorMap::new() }
+    fn new(quirks_mode: QuirksMode) -> PerOriginSelectorMap {
+        PerOriginSelectorMap {
+            normal: SelectorMap::new(quirks_mode),
+            important: SelectorMap::new(quirks_mode),
+        }
     }
 }
 
@@ -347,6 +530,9 @@ struct Rule {
     // that it matches. Selector contains an owned vector (through
     // CompoundSelector) and we want to avoid the allocation.
     selector: Arc<Selector>,
+    // The names, ids and classes that the ancestors of an element need for the selector to
+    // match, checked against the ancestor filter before the selector is matched.
+    ancestor_hashes: AncestorHashes,
     declarations: Arc<~[PropertyDeclaration]>,
     // Index of the parent StyleRule in the parent Stylesheet (useful for
     // breaking ties while cascading).
@@ -368,15 +554,19 @@ impl Ord for Rule {
 
 #[inline]
 fn matches_selector<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: ElementLike>(
-        selector: &Selector, element: &T, pseudo_element: Option<PseudoElement>) -> bool {
+        selector: &Selector, element: &T, pseudo_element: Option<PseudoElement>,
+        visited_link: Option<&T>, quirks_mode: QuirksMode) -> bool {
     selector.pseudo_element == pseudo_element &&
-        matches_compound_selector::<N, T, E>(&selector.compound_selectors, element)
+        matches_compound_selector::<N, T, E>(&selector.compound_selectors, element, visited_link,
+                                             quirks_mode)
 }
 
+/// `visited_link` is the link that matches `:visited` rather than `:link`, if any.
 fn matches_compound_selector<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: ElementLike>(
-        selector: &CompoundSelector, element: &T) -> bool {
+        selector: &CompoundSelector, element: &T, visited_link: Option<&T>,
+        quirks_mode: QuirksMode) -> bool {
     if !do selector.simple_selectors.iter().all |simple_selector| {
-            matches_simple_selector(simple_selector, element)
+            matches_simple_selector(simple_selector, element, visited_link, quirks_mode)
     } {
         return false
     }
@@ -389,6 +579,9 @@ fn matches_compound_selector<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: E
                 NextSibling => (true, true),
                 LaterSibling => (true, false),
             };
+            // Links reached through sibling combinators always match as unvisited, otherwise
+            // the visited state of a link could be found out from the style of its siblings.
+            let visited_link = if siblings { None } else { visited_link };
             let mut node = element.clone();
             loop {
                 let next_node = if siblings {
@@ -401,7 +594,8 @@ fn matches_compound_selector<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: E
                     Some(next_node) => node = next_node,
                 }
                 if node.is_element() {
-                    if matches_compound_selector(&**next_selector, &node) {
+                    if matches_compound_selector(&**next_selector, &node, visited_link,
+                                                 quirks_mode) {
                         return true
                     } else if just_one {
                         return false
@@ -412,17 +606,22 @@ fn matches_compound_selector<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: E
     }
 }
 
+#[inline]
+fn same_id_or_class(a: &Atom, b: &Atom, quirks_mode: QuirksMode) -> bool {
+    *a == *b || (quirks_mode.has_case_insensitive_ids_and_classes() &&
+                 a.as_slice().eq_ignore_ascii_case(b.as_slice()))
+}
+
 #[inline]
 fn matches_simple_selector<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: ElementLike>(
-        selector: &SimpleSelector, element: &T) -> bool {
+        selector: &SimpleSelector, element: &T, visited_link: Option<&T>,
+        quirks_mode: QuirksMode) -> bool {
     static WHITESPACE: &'static [char] = &'static [' ', '\t', '\n', '\r', '\x0C'];
 
     match *selector {
-        // TODO: case-sensitivity depends on the document type
-        // TODO: intern element names
         LocalNameSelector(ref name) => {
             do element.with_imm_element_like |element: &E| {
-                element.get_local_name().eq_ignore_ascii_case(name.as_slice())
+                element.get_local_name() == *name
             }
         }
         NamespaceSelector(ref url) => {
@@ -430,25 +629,19 @@ fn matches_simple_selector<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: Ele
                 element.get_namespace_url() == url.as_slice()
             }
         }
-        // TODO: case-sensitivity depends on the document type and quirks mode
-        // TODO: cache and intern IDs on elements.
         IDSelector(ref id) => {
             do element.with_imm_element_like |element: &E| {
-                match element.get_attr(None, "id") {
-                    Some(attr) => str::eq_slice(attr, *id),
-                    None => false
+                match element.get_id() {
+                    Some(ref element_id) => same_id_or_class(element_id, id, quirks_mode),
+                    None => false,
                 }
             }
         }
-        // TODO: cache and intern classe names on elements.
         ClassSelector(ref class) => {
             do element.with_imm_element_like |element: &E| {
-                match element.get_attr(None, "class") {
-                    None => false,
-                    // TODO: case-sensitivity depends on the document type and quirks mode
-                    Some(ref class_attr)
-                    => class_attr.split_iter(WHITESPACE).any(|c| c == class.as_slice()),
-                }
+                element.get_classes().iter().any(|element_class| {
+                    same_id_or_class(element_class, class, quirks_mode)
+                })
             }
         }
 
@@ -478,21 +671,12 @@ fn matches_simple_selector<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: Ele
             }
         }
         Link => {
-            do element.with_imm_element_like |element: &E| {
-                match element.get_link() {
-                    Some(url) => !url_is_visited(url),
-                    None => false,
-                }
-            }
-        }
-        Visited => {
-            do element.with_imm_element_like |element: &E| {
-                match element.get_link() {
-                    Some(url) => url_is_visited(url),
-                    None => false,
-                }
-            }
+            let is_link = do element.with_imm_element_like |element: &E| {
+                element.get_link().is_some()
+            };
+            is_link && !is_same_node(element, visited_link)
         }
+        Visited => is_same_node(element, visited_link),
 
         FirstChild => matches_first_child(element),
         LastChild  => matches_last_child(element),
@@ -501,6 +685,11 @@ fn matches_simple_selector<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: Ele
 
         Root => matches_root(element),
 
+        Empty => do element.with_imm_element_like |element: &E| {
+            element.is_empty()
+        },
+        Lang(ref lang) => matches_lang(element, lang.as_slice()),
+
         NthChild(a, b)      => matches_generic_nth_child(element, a, b, false, false),
         NthLastChild(a, b)  => matches_generic_nth_child(element, a, b, false, true),
         NthOfType(a, b)     => matches_generic_nth_child(element, a, b, true, false),
@@ -511,17 +700,38 @@ fn matches_simple_selector<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: Ele
         OnlyOfType  => matches_generic_nth_child(element, 0, 1, true, false) &&
                        matches_generic_nth_child(element, 0, 1, true, true),
 
+        Hover => do element.with_imm_element_like |element: &E| {
+            element.is_hovered()
+        },
+        Active => do element.with_imm_element_like |element: &E| {
+            element.is_active()
+        },
+        Focus => do element.with_imm_element_like |element: &E| {
+            element.is_focused()
+        },
+        Enabled => do element.with_imm_element_like |element: &E| {
+            element.get_disabled_state() == Some(false)
+        },
+        Disabled => do element.with_imm_element_like |element: &E| {
+            element.get_disabled_state() == Some(true)
+        },
+        Checked => do element.with_imm_element_like |element: &E| {
+            element.is_checked()
+        },
+
         Negation(ref negated) => {
-            !negated.iter().all(|s| matches_simple_selector(s, element))
+            !negated.iter().all(|s| matches_simple_selector(s, element, visited_link, quirks_mode))
         },
     }
 }
 
-fn url_is_visited(_url: &str) -> bool {
-    // FIXME: implement this.
-    // This function will probably need to take a "session"
-    // or something containing browsing history as an additional parameter.
-    false
+#[inline]
+fn is_same_node<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: ElementLike>(
+        element: &T, other: Option<&T>) -> bool {
+    match other {
+        Some(other) => ptr::to_unsafe_ptr(element.node()) == ptr::to_unsafe_ptr(other.node()),
+        None => false,
+    }
 }
 
 #[inline]
@@ -537,11 +747,11 @@ fn matches_generic_nth_child<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: E
         None => return false
     };
 
-    let mut element_local_name = "";
+    let mut element_local_name = None;
     let mut element_namespace = "";
     if is_of_type {
         do element.with_imm_element_like |element: &E| {
-            element_local_name = element.get_local_name();
+            element_local_name = Some(element.get_local_name());
             element_namespace = element.get_namespace_url();
         }
     }
@@ -563,7 +773,7 @@ fn matches_generic_nth_child<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: E
         if node.is_element() {
             if is_of_type {
                 do node.with_imm_element_like |node: &E| {
-                    if element_local_name == node.get_local_name() &&
+                    if element_local_name == Some(node.get_local_name()) &&
                        element_namespace == node.get_namespace_url() {
                         index += 1;
                     }
@@ -592,6 +802,32 @@ fn matches_root<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: ElementLike>(
     }
 }
 
+/// The language of an element is given by the nearest `xml:lang` or `lang` attribute on it or on
+/// an ancestor. `lang` is in lower case.
+fn matches_lang<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: ElementLike>(
+        element: &T, lang: &str) -> bool {
+    let mut node = element.clone();
+    loop {
+        let value = do node.with_imm_element_like |element: &E| {
+            element.get_attr(Some(~"http://www.w3.org/XML/1998/namespace"), "lang")
+                   .or(element.get_attr(None, "lang"))
+        };
+        match value {
+            Some(value) => {
+                // A prefix matches only up to a subtag boundary: :lang(en) matches "en-US".
+                let value = value.to_ascii_lower();
+                return value.as_slice() == lang ||
+                    (value.starts_with(lang) && value.char_at(lang.len()) == '-')
+            }
+            None => {}
+        }
+        match node.node().parent_node() {
+            Some(parent) if parent.is_element() => node = parent,
+            _ => return false,
+        }
+    }
+}
+
 #[inline]
 fn matches_first_child<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: ElementLike>(
         element: &T) -> bool {
@@ -650,13 +886,16 @@ fn match_attribute<N: TreeNode<T>, T: TreeNodeRefAsElement<N, E>, E: ElementLike
     }
 }
 fn get_rules(css_string: &str) -> ~[~[Rule]] {
-    let device = &Device { media_type: Screen };
-    let sheet = Stylesheet::from_str(css_string);
+    let device = &Device::new(Screen, Au::from_px(800), Au::from_px(600), 1.);
+    let sheet = Stylesheet::from_str(css_string,
+                                     FromStr::from_str("http://example.com/").unwrap(),
+                                     &mut LogErrorReporter);
     let mut index = 0u;
     let mut results = ~[];
     do iter_style_rules(sheet.rules.as_slice(), device) |style_rule| {
         results.push(style_rule.selectors.iter().map(|s| Rule {
                     selector: Arc::new(s.clone()),
+                    ancestor_hashes: AncestorHashes::new(s, NoQuirks),
                     declarations: style_rule.declarations.normal.clone(),
                     index: index,
                     stylesheet_index: 0u,
@@ -685,33 +924,272 @@ fn test_rule_ordering_same_specificity(){
 fn test_get_id_name(){
     let rules_list = get_mock_rules([".intro", "#top"]);
     assert_eq!(SelectorMap::get_id_name(&rules_list[0][0]), None);
-    assert_eq!(SelectorMap::get_id_name(&rules_list[1][0]), Some(~"top"));
+    assert_eq!(SelectorMap::get_id_name(&rules_list[1][0]), Some(Atom::from_slice("top")));
 }
 
 #[test]
 fn test_get_class_name(){
     let rules_list = get_mock_rules([".intro.foo", "#top"]);
-    assert_eq!(SelectorMap::get_class_name(&rules_list[0][0]), Some(~"intro"));
+    assert_eq!(SelectorMap::get_class_name(&rules_list[0][0]), Some(Atom::from_slice("intro")));
     assert_eq!(SelectorMap::get_class_name(&rules_list[1][0]), None);
 }
 
 #[test]
 fn test_get_element_name(){
     let rules_list = get_mock_rules(["img.foo", "#top", "IMG", "ImG"]);
-    assert_eq!(SelectorMap::get_element_name(&rules_list[0][0]), Some(~"img"));
+    assert_eq!(SelectorMap::get_element_name(&rules_list[0][0]), Some(Atom::from_slice("img")));
     assert_eq!(SelectorMap::get_element_name(&rules_list[1][0]), None);
-    assert_eq!(SelectorMap::get_element_name(&rules_list[2][0]), Some(~"img"));
-    assert_eq!(SelectorMap::get_element_name(&rules_list[3][0]), Some(~"img"));
+    assert_eq!(SelectorMap::get_element_name(&rules_list[2][0]), Some(Atom::from_slice("img")));
+    assert_eq!(SelectorMap::get_element_name(&rules_list[3][0]), Some(Atom::from_slice("img")));
 }
 
 #[test]
 fn test_insert(){
     let rules_list = get_mock_rules([".intro.foo", "#top"]);
-    let mut selector_map = SelectorMap::new();
+    let mut selector_map = SelectorMap::new(NoQuirks);
     selector_map.insert(rules_list[1][0].clone());
-    assert_eq!(1, selector_map.id_hash.find(&~"top").unwrap()[0].index);
+    assert_eq!(1, selector_map.id_hash.find(&Atom::from_slice("top")).unwrap()[0].index);
+    selector_map.insert(rules_list[0][0].clone());
+    assert_eq!(0, selector_map.class_hash.find(&Atom::from_slice("intro")).unwrap()[0].index);
+    assert!(selector_map.class_hash.find(&Atom::from_slice("foo")).is_none());
+}
+
+
+#[test]
+fn test_insert_quirks_mode(){
+    let rules_list = get_mock_rules([".Intro", "#Top"]);
+    let mut selector_map = SelectorMap::new(FullQuirks);
     selector_map.insert(rules_list[0][0].clone());
-    assert_eq!(0, selector_map.class_hash.find(&~"intro").unwrap()[0].index);
-    assert!(selector_map.class_hash.find(&~"foo").is_none());
+    selector_map.insert(rules_list[1][0].clone());
+    assert_eq!(0, selector_map.class_hash.find(&Atom::from_slice("intro")).unwrap()[0].index);
+    assert_eq!(1, selector_map.id_hash.find(&Atom::from_slice("top")).unwrap()[0].index);
+}
+
+/// An element of a document tree for the matching tests, which is its own `ElementLike`.
+#[cfg(test)]
+struct MockNode {
+    parent_node: Option<MockNodeRef>,
+    first_child: Option<MockNodeRef>,
+    last_child: Option<MockNodeRef>,
+    prev_sibling: Option<MockNodeRef>,
+    next_sibling: Option<MockNodeRef>,
+    local_name: Atom,
+    id: Option<Atom>,
+    classes: ~[Atom],
+    hovered: bool,
+}
+
+#[cfg(test)]
+#[deriving(Clone)]
+struct MockNodeRef {
+    node: *mut MockNode,
+}
+
+/// Owns the nodes of a mock document tree.
+#[cfg(test)]
+struct MockTree {
+    nodes: ~[~MockNode],
+}
+
+#[cfg(test)]
+impl MockTree {
+    fn new() -> MockTree {
+        MockTree {
+            nodes: ~[],
+        }
+    }
+
+    /// Adds an element as the last child of `parent`, or as the root if there is none.
+    fn add(&mut self, parent: Option<&MockNodeRef>, local_name: &str, id: Option<&str>,
+           classes: &[&str]) -> MockNodeRef {
+        let mut node = ~MockNode {
+            parent_node: None,
+            first_child: None,
+            last_child: None,
+            prev_sibling: None,
+            next_sibling: None,
+            local_name: Atom::from_slice(local_name),
+            id: id.map(|id| Atom::from_slice(id)),
+            classes: classes.map(|class| Atom::from_slice(*class)),
+            hovered: false,
+        };
+        let node_ref = MockNodeRef {
+            node: &mut *node as *mut MockNode,
+        };
+        self.nodes.push(node);
+        for parent in parent.iter() {
+            parent.add_child(node_ref.clone(), None)
+        }
+        node_ref
+    }
+}
+
+#[cfg(test)]
+impl TreeNodeRef<MockNode> for MockNodeRef {
+    fn node<'a>(&'a self) -> &'a MockNode {
+        unsafe {
+            &*self.node
+        }
+    }
+
+    fn mut_node<'a>(&'a self) -> &'a mut MockNode {
+        unsafe {
+            &mut *self.node
+        }
+    }
+
+    fn parent_node(node: &MockNode) -> Option<MockNodeRef> {
+        node.parent_node.clone()
+    }
+    fn first_child(node: &MockNode) -> Option<MockNodeRef> {
+        node.first_child.clone()
+    }
+    fn last_child(node: &MockNode) -> Option<MockNodeRef> {
+        node.last_child.clone()
+    }
+    fn prev_sibling(node: &MockNode) -> Option<MockNodeRef> {
+        node.prev_sibling.clone()
+    }
+    fn next_sibling(node: &MockNode) -> Option<MockNodeRef> {
+        node.next_sibling.clone()
+    }
+
+    fn set_parent_node(node: &mut MockNode, new_parent_node: Option<MockNodeRef>) {
+        node.parent_node = new_parent_node
+    }
+    fn set_first_child(node: &mut MockNode, new_first_child: Option<MockNodeRef>) {
+        node.first_child = new_first_child
+    }
+    fn set_last_child(node: &mut MockNode, new_last_child: Option<MockNodeRef>) {
+        node.last_child = new_last_child
+    }
+    fn set_prev_sibling(node: &mut MockNode, new_prev_sibling: Option<MockNodeRef>) {
+        node.prev_sibling = new_prev_sibling
+    }
+    fn set_next_sibling(node: &mut MockNode, new_next_sibling: Option<MockNodeRef>) {
+        node.next_sibling = new_next_sibling
+    }
+
+    fn is_element(&self) -> bool {
+        true
+    }
+
+    fn is_document(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+impl TreeNodeRefAsElement<MockNode, MockNode> for MockNodeRef {
+    fn with_imm_element_like<R>(&self, f: &fn(&MockNode) -> R) -> R {
+        f(self.node())
+    }
 }
 
+#[cfg(test)]
+impl TreeNode<MockNodeRef> for MockNode { }
+
+#[cfg(test)]
+impl ElementLike for MockNode {
+    fn get_local_name(&self) -> Atom {
+        self.local_name.clone()
+    }
+    fn get_namespace_url<'a>(&'a self) -> &'a str {
+        "http://www.w3.org/1999/xhtml"
+    }
+    fn get_id(&self) -> Option<Atom> {
+        self.id.clone()
+    }
+    fn get_classes<'a>(&'a self) -> &'a [Atom] {
+        self.classes.as_slice()
+    }
+    fn get_attr(&self, _: Option<~str>, _: &str) -> Option<~str> {
+        None
+    }
+    fn get_link(&self) -> Option<~str> {
+        None
+    }
+    fn is_empty(&self) -> bool {
+        self.first_child.is_none()
+    }
+    fn is_hovered(&self) -> bool {
+        self.hovered
+    }
+    fn is_active(&self) -> bool {
+        false
+    }
+    fn is_focused(&self) -> bool {
+        false
+    }
+    fn get_disabled_state(&self) -> Option<bool> {
+        None
+    }
+    fn is_checked(&self) -> bool {
+        false
+    }
+}
+
+/// Returns true if `selector` matches `element`, looking it up in a selector map as the stylist
+/// does.
+#[cfg(test)]
+fn selector_matches(selector: &str, element: &MockNodeRef, quirks_mode: QuirksMode) -> bool {
+    let mut rule = get_mock_rules([selector])[0][0].clone();
+    rule.ancestor_hashes = AncestorHashes::new(rule.selector.get(), quirks_mode);
+    let mut selector_map = SelectorMap::new(quirks_mode);
+    selector_map.insert(rule);
+    let mut ancestor_filter = AncestorFilter::for_ancestors_of(element);
+    let mut matching_rules_list: [~[Rule], ..1] = [~[]];
+    selector_map.get_all_matching_rules(element, None, None, &mut ancestor_filter,
+                                        matching_rules_list, 0);
+    !matching_rules_list[0].is_empty()
+}
+
+#[test]
+fn test_hover_matching() {
+    let mut tree = MockTree::new();
+    let html = tree.add(None, "html", None, []);
+    let div = tree.add(Some(&html), "div", None, ["menu"]);
+    let a = tree.add(Some(&div), "a", None, []);
+    let p = tree.add(Some(&html), "p", None, []);
+
+    // Script gives the state to the element under the pointer and to its ancestors.
+    for element in [&html, &div, &a].iter() {
+        element.mut_node().hovered = true
+    }
+    assert!(selector_matches("a:hover", &a, NoQuirks));
+    assert!(selector_matches(".menu:hover a", &a, NoQuirks));
+    assert!(selector_matches("div:hover > a:hover", &a, NoQuirks));
+    assert!(selector_matches("div:hover + p", &p, NoQuirks));
+    assert!(selector_matches("p:not(:hover)", &p, NoQuirks));
+    assert!(!selector_matches("p:hover", &p, NoQuirks));
+
+    a.mut_node().hovered = false;
+    assert!(!selector_matches("a:hover", &a, NoQuirks));
+    assert!(selector_matches("div:hover a", &a, NoQuirks));
+    assert!(selector_matches("a:not(:hover)", &a, NoQuirks));
+}
+
+#[test]
+fn test_quirks_mode_matching() {
+    let mut tree = MockTree::new();
+    let div = tree.add(None, "div", Some("Top"), ["Intro"]);
+    let p = tree.add(Some(&div), "p", None, []);
+    assert!(selector_matches(".Intro", &div, NoQuirks));
+    assert!(selector_matches("#Top", &div, NoQuirks));
+    assert!(!selector_matches(".intro", &div, NoQuirks));
+    assert!(!selector_matches("#TOP", &div, NoQuirks));
+    assert!(!selector_matches(".intro p", &p, NoQuirks));
+
+    // Script lowercases the ids and classes of elements in documents in quirks mode.
+    let mut tree = MockTree::new();
+    let div = tree.add(None, "div", Some("top"), ["intro"]);
+    let p = tree.add(Some(&div), "p", None, []);
+    assert!(selector_matches(".Intro", &div, FullQuirks));
+    assert!(selector_matches(".INTRO", &div, FullQuirks));
+    assert!(selector_matches("#Top", &div, FullQuirks));
+    assert!(selector_matches("div#TOP.intro", &div, FullQuirks));
+    assert!(selector_matches(".Intro p", &p, FullQuirks));
+    assert!(selector_matches("#TOP > p", &p, FullQuirks));
+    assert!(!selector_matches(".Outro", &div, FullQuirks));
+    assert!(!selector_matches(".Outro p", &p, FullQuirks));
+}
@@ -7,6 +7,8 @@ use std::ascii::StrAsciiExt;
 use cssparser::ast::*;
 use cssparser::parse_nth;
 use namespaces::NamespaceMap;
+use serialize::{ToCssText, push_identifier, push_string};
+use servo_util::atom::Atom;
 
 
 #[deriving(Eq, Clone)]
@@ -44,9 +46,11 @@ pub enum Combinator {
 
 #[deriving(Eq, Clone)]
 pub enum SimpleSelector {
-    IDSelector(~str),
-    ClassSelector(~str),
-    LocalNameSelector(~str),
+    IDSelector(Atom),
+    ClassSelector(Atom),
+    // Lowercased, since HTML elements in HTML documents are matched case-insensitively
+    // TODO: case-sensitivity depends on the document type
+    LocalNameSelector(Atom),
     NamespaceSelector(~str),
 
     // Attribute selectors
@@ -64,16 +68,24 @@ pub enum SimpleSelector {
     Link,
     Visited,
     FirstChild, LastChild, OnlyChild,
-//    Empty,
+    Empty,
     Root,
-//    Lang(~str),
+    Lang(~str),
     NthChild(i32, i32),
     NthLastChild(i32, i32),
     NthOfType(i32, i32),
     NthLastOfType(i32, i32),
     FirstOfType,
     LastOfType,
-    OnlyOfType
+    OnlyOfType,
+
+    // User action and UI element states
+    Hover,
+    Active,
+    Focus,
+    Enabled,
+    Disabled,
+    Checked,
     // ...
 }
 
@@ -84,16 +96,16 @@ pub struct AttrSelector {
 }
 
 
-type Iter = iter::Peekable<ComponentValue, vec::MoveIterator<ComponentValue>>;
+type Iter<'a> = iter::Peekable<&'a ComponentValue, vec::VecIterator<'a, ComponentValue>>;
 
 
 /// Parse a comma-separated list of Selectors.
 /// aka Selector Group in http://www.w3.org/TR/css3-selectors/#grouping
 ///
 /// Return the Selectors or None if there is an invalid selector.
-pub fn parse_selector_list(input: ~[ComponentValue], namespaces: &NamespaceMap)
+pub fn parse_selector_list(input: &[ComponentValue], namespaces: &NamespaceMap)
                            -> Option<~[Selector]> {
-    let iter = &mut input.move_iter().peekable();
+    let iter = &mut input.iter().peekable();
     let first = match parse_selector(iter, namespaces) {
         None => return None,
         Some(result) => result
@@ -104,7 +116,7 @@ pub fn parse_selector_list(input: ~[ComponentValue], namespaces: &NamespaceMap)
         skip_whitespace(iter);
         match iter.peek() {
             None => break,  // EOF
-            Some(&Comma) => {
+            Some(& &Comma) => {
                 iter.next();
             }
             _ => return None,
@@ -135,10 +147,10 @@ fn parse_selector(iter: &mut Iter, namespaces: &NamespaceMap)
         let any_whitespace = skip_whitespace(iter);
         let combinator = match iter.peek() {
             None => break,  // EOF
-            Some(&Comma) => break,
-            Some(&Delim('>')) => { iter.next(); Child },
-            Some(&Delim('+')) => { iter.next(); NextSibling },
-            Some(&Delim('~')) => { iter.next(); LaterSibling },
+            Some(& &Comma) => break,
+            Some(& &Delim('>')) => { iter.next(); Child },
+            Some(& &Delim('+')) => { iter.next(); NextSibling },
+            Some(& &Delim('~')) => { iter.next(); LaterSibling },
             Some(_) => {
                 if any_whitespace { Descendant }
                 else { return None }
@@ -199,10 +211,12 @@ fn compute_specificity(mut selector: &CompoundSelector,
                 | &AttrPrefixMatch(*) | &AttrSubstringMatch(*) | &AttrSuffixMatch(*)
                 | &AnyLink | &Link | &Visited
                 | &FirstChild | &LastChild | &OnlyChild | &Root
-//                | &Empty | &Lang(*)
+                | &Empty | &Lang(*)
                 | &NthChild(*) | &NthLastChild(*)
                 | &NthOfType(*) | &NthLastOfType(*)
                 | &FirstOfType | &LastOfType | &OnlyOfType
+                | &Hover | &Active | &Focus
+                | &Enabled | &Disabled | &Checked
                 => specificity.class_like_selectors += 1,
                 &NamespaceSelector(*) => (),
                 &Negation(ref negated)
@@ -265,7 +279,8 @@ fn parse_type_selector(iter: &mut Iter, namespaces: &NamespaceMap)
                 None => (),
             }
             match local_name {
-                Some(name) => simple_selectors.push(LocalNameSelector(name)),
+                Some(name) => simple_selectors.push(
+                    LocalNameSelector(Atom::from_slice(name.to_ascii_lower().as_slice()))),
                 None => (),
             }
             TypeSelector(simple_selectors)
@@ -285,29 +300,33 @@ enum SimpleSelectorParseResult {
 fn parse_one_simple_selector(iter: &mut Iter, namespaces: &NamespaceMap, inside_negation: bool)
                          -> SimpleSelectorParseResult {
     match iter.peek() {
-        Some(&IDHash(_)) => match iter.next() {
-            Some(IDHash(id)) => SimpleSelectorResult(IDSelector(id)),
+        Some(& &IDHash(_)) => match iter.next() {
+            Some(&IDHash(ref id)) => {
+                SimpleSelectorResult(IDSelector(Atom::from_slice(id.as_slice())))
+            }
             _ => fail!("Implementation error, this should not happen."),
         },
-        Some(&Delim('.')) => {
+        Some(& &Delim('.')) => {
             iter.next();
             match iter.next() {
-                Some(Ident(class)) => SimpleSelectorResult(ClassSelector(class)),
+                Some(&Ident(ref class)) => {
+                    SimpleSelectorResult(ClassSelector(Atom::from_slice(class.as_slice())))
+                }
                 _ => InvalidSimpleSelector,
             }
         }
-        Some(&SquareBracketBlock(_)) => match iter.next() {
-            Some(SquareBracketBlock(content))
-            => match parse_attribute_selector(content, namespaces) {
+        Some(& &SquareBracketBlock(_)) => match iter.next() {
+            Some(&SquareBracketBlock(ref content))
+            => match parse_attribute_selector(content.as_slice(), namespaces) {
                 None => InvalidSimpleSelector,
                 Some(simple_selector) => SimpleSelectorResult(simple_selector),
             },
             _ => fail!("Implementation error, this should not happen."),
         },
-        Some(&Colon) => {
+        Some(& &Colon) => {
             iter.next();
             match iter.next() {
-                Some(Ident(name)) => match parse_simple_pseudo_class(name) {
+                Some(&Ident(ref name)) => match parse_simple_pseudo_class(name.as_slice()) {
                     None => {
                         // FIXME: Workaround for https://github.com/mozilla/rust/issues/10683
                         let name_lower = name.to_ascii_lower(); 
@@ -323,14 +342,14 @@ fn parse_one_simple_selector(iter: &mut Iter, namespaces: &NamespaceMap, inside_
                     },
                     Some(result) => SimpleSelectorResult(result),
                 },
-                Some(Function(name, arguments)) => match parse_functional_pseudo_class(
-                        name, arguments, namespaces, inside_negation) {
+                Some(&Function(ref name, ref arguments)) => match parse_functional_pseudo_class(
+                        name.as_slice(), arguments.as_slice(), namespaces, inside_negation) {
                     None => InvalidSimpleSelector,
                     Some(simple_selector) => SimpleSelectorResult(simple_selector),
                 },
-                Some(Colon) => {
+                Some(&Colon) => {
                     match iter.next() {
-                        Some(Ident(name)) => match parse_pseudo_element(name) {
+                        Some(&Ident(ref name)) => match parse_pseudo_element(name.as_slice()) {
                             Some(pseudo_element) => PseudoElementResult(pseudo_element),
                             _ => InvalidSimpleSelector,
                         },
@@ -362,14 +381,14 @@ fn parse_qualified_name(iter: &mut Iter, allow_universal: bool, namespaces: &Nam
     #[inline]
     fn explicit_namespace(iter: &mut Iter, allow_universal: bool, namespace_url: Option<~str>)
                          -> QualifiedNameParseResult {
-        assert!(iter.next() == Some(Delim('|')),
+        assert!(iter.next() == Some(&Delim('|')),
                 "Implementation error, this should not happen.");
         match iter.peek() {
-            Some(&Delim('*')) if allow_universal => {
+            Some(& &Delim('*')) if allow_universal => {
                 iter.next();
                 QualifiedName(namespace_url, None)
             },
-            Some(&Ident(_)) => {
+            Some(& &Ident(_)) => {
                 let local_name = get_next_ident(iter);
                 QualifiedName(namespace_url, Some(local_name))
             },
@@ -378,10 +397,10 @@ fn parse_qualified_name(iter: &mut Iter, allow_universal: bool, namespaces: &Nam
     }
 
     match iter.peek() {
-        Some(&Ident(_)) => {
+        Some(& &Ident(_)) => {
             let value = get_next_ident(iter);
             match iter.peek() {
-                Some(&Delim('|')) => {
+                Some(& &Delim('|')) => {
                     let namespace_url = match namespaces.prefix_map.find(&value) {
                         None => return InvalidQualifiedName,  // Undeclared namespace prefix
                         Some(ref url) => url.to_owned(),
@@ -391,25 +410,25 @@ fn parse_qualified_name(iter: &mut Iter, allow_universal: bool, namespaces: &Nam
                 _ => default_namespace(namespaces, Some(value)),
             }
         },
-        Some(&Delim('*')) => {
+        Some(& &Delim('*')) => {
             iter.next();  // Consume '*'
             match iter.peek() {
-                Some(&Delim('|')) => explicit_namespace(iter, allow_universal, None),
+                Some(& &Delim('|')) => explicit_namespace(iter, allow_universal, None),
                 _ => {
                     if allow_universal { default_namespace(namespaces, None) }
                     else { InvalidQualifiedName }
                 },
             }
         },
-        Some(&Delim('|')) => explicit_namespace(iter, allow_universal, Some(~"")),
+        Some(& &Delim('|')) => explicit_namespace(iter, allow_universal, Some(~"")),
         _ => NotAQualifiedName,
     }
 }
 
 
-fn parse_attribute_selector(content: ~[ComponentValue], namespaces: &NamespaceMap)
+fn parse_attribute_selector(content: &[ComponentValue], namespaces: &NamespaceMap)
                             -> Option<SimpleSelector> {
-    let iter = &mut content.move_iter().peekable();
+    let iter = &mut content.iter().peekable();
     let attr = match parse_qualified_name(iter, /* allow_universal = */ false, namespaces) {
         InvalidQualifiedName | NotAQualifiedName => return None,
         QualifiedName(_, None) => fail!("Implementation error, this should not happen."),
@@ -423,22 +442,22 @@ fn parse_attribute_selector(content: ~[ComponentValue], namespaces: &NamespaceMa
     macro_rules! get_value( () => {{
         skip_whitespace(iter);
         match iter.next() {
-            Some(Ident(value)) | Some(String(value)) => value,
+            Some(&Ident(ref value)) | Some(&String(ref value)) => value.clone(),
             _ => return None,
         }
     }};)
     let result = match iter.next() {
         None => AttrExists(attr),  // [foo]
-        Some(Delim('=')) => AttrEqual(attr, get_value!()),  // [foo=bar]
-        Some(IncludeMatch) => AttrIncludes(attr, get_value!()),  // [foo~=bar]
-        Some(DashMatch) => {
+        Some(&Delim('=')) => AttrEqual(attr, get_value!()),  // [foo=bar]
+        Some(&IncludeMatch) => AttrIncludes(attr, get_value!()),  // [foo~=bar]
+        Some(&DashMatch) => {
             let value = get_value!();
             let dashing_value = value + "-";
             AttrDashMatch(attr, value, dashing_value)  // [foo|=bar]
         },
-        Some(PrefixMatch) => AttrPrefixMatch(attr, get_value!()),  // [foo^=bar]
-        Some(SubstringMatch) => AttrSubstringMatch(attr, get_value!()),  // [foo*=bar]
-        Some(SuffixMatch) => AttrSuffixMatch(attr, get_value!()),  // [foo$=bar]
+        Some(&PrefixMatch) => AttrPrefixMatch(attr, get_value!()),  // [foo^=bar]
+        Some(&SubstringMatch) => AttrSubstringMatch(attr, get_value!()),  // [foo*=bar]
+        Some(&SuffixMatch) => AttrSuffixMatch(attr, get_value!()),  // [foo$=bar]
         _ => return None
     };
     skip_whitespace(iter);
@@ -460,19 +479,25 @@ fn parse_simple_pseudo_class(name: &str) -> Option<SimpleSelector> {
         "first-of-type" => Some(FirstOfType),
         "last-of-type"  => Some(LastOfType),
         "only-of-type"  => Some(OnlyOfType),
-//        "empty" => Some(Empty),
+        "empty" => Some(Empty),
+        "hover" => Some(Hover),
+        "active" => Some(Active),
+        "focus" => Some(Focus),
+        "enabled" => Some(Enabled),
+        "disabled" => Some(Disabled),
+        "checked" => Some(Checked),
         _ => None
     }
 }
 
 
-fn parse_functional_pseudo_class(name: ~str, arguments: ~[ComponentValue],
+fn parse_functional_pseudo_class(name: &str, arguments: &[ComponentValue],
                                  namespaces: &NamespaceMap, inside_negation: bool)
                                  -> Option<SimpleSelector> {
     // FIXME: Workaround for https://github.com/mozilla/rust/issues/10683
     let name_lower = name.to_ascii_lower(); 
     match name_lower.as_slice() {
-//        "lang" => parse_lang(arguments),
+        "lang" => parse_lang(arguments),
         "nth-child"        => parse_nth(arguments).map(|(a, b)| NthChild(a, b)),
         "nth-last-child"   => parse_nth(arguments).map(|(a, b)| NthLastChild(a, b)),
         "nth-of-type"      => parse_nth(arguments).map(|(a, b)| NthOfType(a, b)),
@@ -483,7 +508,7 @@ fn parse_functional_pseudo_class(name: ~str, arguments: ~[ComponentValue],
 }
 
 
-fn parse_pseudo_element(name: ~str) -> Option<PseudoElement> {
+pub fn parse_pseudo_element(name: &str) -> Option<PseudoElement> {
     // FIXME: Workaround for https://github.com/mozilla/rust/issues/10683
     let name_lower = name.to_ascii_lower();  
     match name_lower.as_slice() {
@@ -497,22 +522,23 @@ fn parse_pseudo_element(name: ~str) -> Option<PseudoElement> {
 }
 
 
-//fn parse_lang(arguments: ~[ComponentValue]) -> Option<SimpleSelector> {
-//    let mut iter = arguments.move_skip_whitespace();
-//    match iter.next() {
-//        Some(Ident(value)) => {
-//            if "" == value || iter.next().is_some() { None }
-//            else { Some(Lang(value)) }
-//        },
-//        _ => None,
-//    }
-//}
+fn parse_lang(arguments: &[ComponentValue]) -> Option<SimpleSelector> {
+    let iter = &mut arguments.iter().peekable();
+    skip_whitespace(iter);
+    let value = match iter.next() {
+        Some(&Ident(ref value)) => value,
+        _ => return None,
+    };
+    skip_whitespace(iter);
+    if value.is_empty() || iter.next().is_some() { None }
+    else { Some(Lang(value.to_ascii_lower())) }
+}
 
 
 // Level 3: Parse ONE simple_selector
-fn parse_negation(arguments: ~[ComponentValue], namespaces: &NamespaceMap)
+fn parse_negation(arguments: &[ComponentValue], namespaces: &NamespaceMap)
                   -> Option<SimpleSelector> {
-    let iter = &mut arguments.move_iter().peekable();
+    let iter = &mut arguments.iter().peekable();
     Some(Negation(match parse_type_selector(iter, namespaces) {
         InvalidTypeSelector => return None,
         TypeSelector(s) => s,
@@ -530,7 +556,7 @@ fn parse_negation(arguments: ~[ComponentValue], namespaces: &NamespaceMap)
 #[inline]
 fn get_next_ident(iter: &mut Iter) -> ~str {
     match iter.next() {
-        Some(Ident(value)) => value,
+        Some(&Ident(ref value)) => value.clone(),
         _ => fail!("Implementation error, this should not happen."),
     }
 }
@@ -540,22 +566,225 @@ fn get_next_ident(iter: &mut Iter) -> ~str {
 fn skip_whitespace(iter: &mut Iter) -> bool {
     let mut any_whitespace = false;
     loop {
-        if iter.peek() != Some(&WhiteSpace) { return any_whitespace }
+        if iter.peek() != Some(& &WhiteSpace) { return any_whitespace }
         any_whitespace = true;
         iter.next();
     }
 }
 
 
+/// Serializes a selector list, e.g. the prelude of a style rule.
+/// Namespaces are written with the prefixes declared in `namespaces`.
+pub fn push_selector_list_css(selectors: &[Selector], namespaces: &NamespaceMap,
+                              css: &mut ~str) {
+    for (i, selector) in selectors.iter().enumerate() {
+        if i > 0 { css.push_str(", ") }
+        push_selector_css(selector, namespaces, css)
+    }
+}
+
+
+pub fn push_selector_css(selector: &Selector, namespaces: &NamespaceMap, css: &mut ~str) {
+    // c.next is left of c: collect the compound selectors right to left first.
+    let mut compound_selectors = ~[];
+    let mut compound_selector = &selector.compound_selectors;
+    loop {
+        match compound_selector.next {
+            Some((ref next, combinator)) => {
+                compound_selectors.push((compound_selector, Some(combinator)));
+                compound_selector = &**next;
+            }
+            None => {
+                compound_selectors.push((compound_selector, None));
+                break
+            }
+        }
+    }
+    for &(compound_selector, combinator) in compound_selectors.rev_iter() {
+        match combinator {
+            Some(Child) => css.push_str(" > "),
+            Some(Descendant) => css.push_char(' '),
+            Some(NextSibling) => css.push_str(" + "),
+            Some(LaterSibling) => css.push_str(" ~ "),
+            None => (),
+        }
+        push_simple_selectors_css(compound_selector.simple_selectors, namespaces, css)
+    }
+    match selector.pseudo_element {
+        Some(pseudo_element) => {
+            css.push_str("::");
+            pseudo_element.push_css_text(css)
+        }
+        None => (),
+    }
+}
+
+
+/// The type selector, if any, comes first: `ns|E` is `[NamespaceSelector, LocalNameSelector]`.
+fn push_simple_selectors_css(simple_selectors: &[SimpleSelector], namespaces: &NamespaceMap,
+                             css: &mut ~str) {
+    let (namespace, rest) = match simple_selectors {
+        [NamespaceSelector(ref url), ..rest] => (Some(url.as_slice()), rest),
+        _ => (None, simple_selectors),
+    };
+    let (local_name, rest) = match rest {
+        [LocalNameSelector(ref name), ..rest] => (Some(name.as_slice()), rest),
+        _ => (None, rest),
+    };
+    if namespace.is_some() || local_name.is_some() || simple_selectors.is_empty() {
+        push_qualified_name(namespace, local_name.unwrap_or("*"), namespaces, css)
+    }
+    for simple_selector in rest.iter() {
+        push_simple_selector_css(simple_selector, namespaces, css)
+    }
+}
+
+
+/// `namespace` is None for any namespace (`*|`).
+fn push_qualified_name(namespace: Option<&str>, local_name: &str, namespaces: &NamespaceMap,
+                       css: &mut ~str) {
+    let is_default = |url: Option<&str>| namespaces.default.as_ref().map(|d| d.as_slice()) == url;
+    match namespace {
+        _ if is_default(namespace) => (),
+        None => css.push_str("*|"),
+        Some("") => css.push_char('|'),
+        Some(url) => {
+            match namespaces.prefix_map.iter().find(|&(_, u)| u.as_slice() == url) {
+                Some((prefix, _)) => {
+                    push_identifier(prefix.as_slice(), css);
+                    css.push_char('|');
+                }
+                // Not declared in this style sheet: the namespace can not be expressed.
+                None => (),
+            }
+        }
+    }
+    if local_name == "*" { css.push_char('*') } else { push_identifier(local_name, css) }
+}
+
+
+fn push_simple_selector_css(simple_selector: &SimpleSelector, namespaces: &NamespaceMap,
+                            css: &mut ~str) {
+    macro_rules! attr(
+        ($attr: expr, $operator: expr, $value: expr) => {{
+            css.push_char('[');
+            push_qualified_name($attr.namespace.as_ref().map(|url| url.as_slice()),
+                                $attr.name.as_slice(), namespaces, css);
+            css.push_str($operator);
+            push_string($value.as_slice(), css);
+            css.push_char(']');
+        }};
+    );
+    macro_rules! nth(
+        ($name: expr, $a: expr, $b: expr) => {{
+            css.push_str($name);
+            css.push_char('(');
+            push_nth($a, $b, css);
+            css.push_char(')');
+        }};
+    );
+    match *simple_selector {
+        IDSelector(ref id) => {
+            css.push_char('#');
+            push_identifier(id.as_slice(), css)
+        }
+        ClassSelector(ref class) => {
+            css.push_char('.');
+            push_identifier(class.as_slice(), css)
+        }
+        LocalNameSelector(ref name) => push_identifier(name.as_slice(), css),
+        NamespaceSelector(ref url) => {
+            push_qualified_name(Some(url.as_slice()), "*", namespaces, css)
+        }
+        AttrExists(ref attr) => {
+            css.push_char('[');
+            push_qualified_name(attr.namespace.as_ref().map(|url| url.as_slice()),
+                                attr.name.as_slice(), namespaces, css);
+            css.push_char(']');
+        }
+        AttrEqual(ref attr, ref value) => attr!(attr, "=", value),
+        AttrIncludes(ref attr, ref value) => attr!(attr, "~=", value),
+        AttrDashMatch(ref attr, ref value, _) => attr!(attr, "|=", value),
+        AttrPrefixMatch(ref attr, ref value) => attr!(attr, "^=", value),
+        AttrSubstringMatch(ref attr, ref value) => attr!(attr, "*=", value),
+        AttrSuffixMatch(ref attr, ref value) => attr!(attr, "$=", value),
+        Negation(ref simple_selectors) => {
+            css.push_str(":not(");
+            push_simple_selectors_css(*simple_selectors, namespaces, css);
+            css.push_char(')');
+        }
+        AnyLink => css.push_str(":any-link"),
+        Link => css.push_str(":link"),
+        Visited => css.push_str(":visited"),
+        FirstChild => css.push_str(":first-child"),
+        LastChild => css.push_str(":last-child"),
+        OnlyChild => css.push_str(":only-child"),
+        Empty => css.push_str(":empty"),
+        Root => css.push_str(":root"),
+        Lang(ref lang) => {
+            css.push_str(":lang(");
+            push_identifier(lang.as_slice(), css);
+            css.push_char(')');
+        }
+        NthChild(a, b) => nth!(":nth-child", a, b),
+        NthLastChild(a, b) => nth!(":nth-last-child", a, b),
+        NthOfType(a, b) => nth!(":nth-of-type", a, b),
+        NthLastOfType(a, b) => nth!(":nth-last-of-type", a, b),
+        FirstOfType => css.push_str(":first-of-type"),
+        LastOfType => css.push_str(":last-of-type"),
+        OnlyOfType => css.push_str(":only-of-type"),
+        Hover => css.push_str(":hover"),
+        Active => css.push_str(":active"),
+        Focus => css.push_str(":focus"),
+        Enabled => css.push_str(":enabled"),
+        Disabled => css.push_str(":disabled"),
+        Checked => css.push_str(":checked"),
+    }
+}
+
+
+/// The `an+b` microsyntax, e.g. `2n+1`, `-n+3` or `5`.
+fn push_nth(a: i32, b: i32, css: &mut ~str) {
+    match a {
+        0 => { css.push_str(b.to_str()); return }
+        1 => css.push_char('n'),
+        -1 => css.push_str("-n"),
+        _ => {
+            css.push_str(a.to_str());
+            css.push_char('n');
+        }
+    }
+    if b > 0 {
+        css.push_char('+');
+        css.push_str(b.to_str());
+    } else if b < 0 {
+        css.push_str(b.to_str());
+    }
+}
+
+
+impl ToCssText for PseudoElement {
+    fn push_css_text(&self, css: &mut ~str) {
+        css.push_str(match *self {
+            Before => "before",
+            After => "after",
+            FirstLine => "first-line",
+            FirstLetter => "first-letter",
+        })
+    }
+}
+
+
 #[cfg(test)]
 mod tests {
     use cssparser;
     use namespaces::NamespaceMap;
+    use servo_util::atom::Atom;
     use super::*;
 
     fn parse(input: &str) -> Option<~[Selector]> {
         parse_selector_list(
-            cssparser::tokenize(input).map(|(v, _)| v).to_owned_vec(),
+            cssparser::tokenize(input).map(|(v, _)| v).to_owned_vec().as_slice(),
             &NamespaceMap::new())
     }
 
@@ -568,7 +797,7 @@ mod tests {
         assert_eq!(parse(""), None)
         assert_eq!(parse("e"), Some(~[Selector{
             compound_selectors: CompoundSelector {
-                simple_selectors: ~[LocalNameSelector(~"e")],
+                simple_selectors: ~[LocalNameSelector(Atom::from_slice("e"))],
                 next: None,
             },
             pseudo_element: None,
@@ -576,7 +805,7 @@ mod tests {
         }]))
         assert_eq!(parse(".foo"), Some(~[Selector{
             compound_selectors: CompoundSelector {
-                simple_selectors: ~[ClassSelector(~"foo")],
+                simple_selectors: ~[ClassSelector(Atom::from_slice("foo"))],
                 next: None,
             },
             pseudo_element: None,
@@ -584,7 +813,7 @@ mod tests {
         }]))
         assert_eq!(parse("#bar"), Some(~[Selector{
             compound_selectors: CompoundSelector {
-                simple_selectors: ~[IDSelector(~"bar")],
+                simple_selectors: ~[IDSelector(Atom::from_slice("bar"))],
                 next: None,
             },
             pseudo_element: None,
@@ -592,9 +821,9 @@ mod tests {
         }]))
         assert_eq!(parse("e.foo#bar"), Some(~[Selector{
             compound_selectors: CompoundSelector {
-                simple_selectors: ~[LocalNameSelector(~"e"),
-                                    ClassSelector(~"foo"),
-                                    IDSelector(~"bar")],
+                simple_selectors: ~[LocalNameSelector(Atom::from_slice("e")),
+                                    ClassSelector(Atom::from_slice("foo")),
+                                    IDSelector(Atom::from_slice("bar"))],
                 next: None,
             },
             pseudo_element: None,
@@ -602,15 +831,52 @@ mod tests {
         }]))
         assert_eq!(parse("e.foo #bar"), Some(~[Selector{
             compound_selectors: CompoundSelector {
-                simple_selectors: ~[IDSelector(~"bar")],
+                simple_selectors: ~[IDSelector(Atom::from_slice("bar"))],
                 next: Some((~CompoundSelector {
-                    simple_selectors: ~[LocalNameSelector(~"e"),
-                                        ClassSelector(~"foo")],
+                    simple_selectors: ~[LocalNameSelector(Atom::from_slice("e")),
+                                        ClassSelector(Atom::from_slice("foo"))],
                     next: None,
                 }, Descendant)),
             },
             pseudo_element: None,
             specificity: specificity(1, 1, 1),
         }]))
+        assert_eq!(parse("a:hover:lang(EN)"), Some(~[Selector{
+            compound_selectors: CompoundSelector {
+                simple_selectors: ~[LocalNameSelector(Atom::from_slice("a")), Hover, Lang(~"en")],
+                next: None,
+            },
+            pseudo_element: None,
+            specificity: specificity(0, 2, 1),
+        }]))
+        assert_eq!(parse(":empty:not(:disabled)"), Some(~[Selector{
+            compound_selectors: CompoundSelector {
+                simple_selectors: ~[Empty, Negation(~[Disabled])],
+                next: None,
+            },
+            pseudo_element: None,
+            specificity: specificity(0, 2, 0),
+        }]))
+        assert_eq!(parse(":lang()"), None)
+    }
+
+    #[test]
+    fn test_serialization() {
+        fn serialize(input: &str) -> ~str {
+            let mut css = ~"";
+            push_selector_list_css(parse(input).unwrap(), &NamespaceMap::new(), &mut css);
+            css
+        }
+        assert_eq!(serialize("E"), ~"e")
+        assert_eq!(serialize("*"), ~"*")
+        assert_eq!(serialize("e.foo#bar, a:HOVER"), ~"e.foo#bar, a:hover")
+        assert_eq!(serialize("ul>li  +p~ a  b::before"), ~"ul > li + p ~ a b::before")
+        assert_eq!(serialize("[href][lang|=en][title=\"a b\"]"),
+                   ~"[href][lang|=\"en\"][title=\"a b\"]")
+        assert_eq!(serialize("|e[*|foo]"), ~"|e[foo]")
+        assert_eq!(serialize(":nth-child(2n+1):nth-of-type(-n+3):nth-last-child(odd)"),
+                   ~":nth-child(2n+1):nth-of-type(-n+3):nth-last-child(2n+1)")
+        assert_eq!(serialize(":not(.foo):lang(en)"), ~":not(.foo):lang(en)")
+        assert_eq!(serialize(".\\31 0"), ~".\\31 0")
     }
 }
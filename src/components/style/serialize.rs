@@ -0,0 +1,133 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use extra::url::Url;
+use cssparser::{Color, RGBA, CurrentColor};
+use servo_util::geometry::{Au, to_frac_px};
+
+
+/// Values that can be serialized back to CSS text. Parsing the result gives the same value,
+/// though not necessarily the same text: e.g. absolute lengths are serialized in px.
+pub trait ToCssText {
+    fn to_css_text(&self) -> ~str {
+        let mut css = ~"";
+        self.push_css_text(&mut css);
+        css
+    }
+
+    fn push_css_text(&self, css: &mut ~str);
+}
+
+
+/// Serializes a number, without the floating point noise of unit conversions.
+pub fn push_number(value: f64, css: &mut ~str) {
+    let rounded = (value * 1e6).round() / 1e6;
+    if rounded == (rounded as i64) as f64 {
+        css.push_str((rounded as i64).to_str())
+    } else {
+        css.push_str(rounded.to_str())
+    }
+}
+
+/// Serializes a fraction, e.g. 0.5 for 50%, as a percentage.
+pub fn push_percentage(value: f64, css: &mut ~str) {
+    push_number(value * 100., css);
+    css.push_char('%');
+}
+
+fn push_hex_escape(c: char, css: &mut ~str) {
+    css.push_char('\\');
+    css.push_str((c as u32).to_str_radix(16));
+    css.push_char(' ');
+}
+
+/// Serializes an identifier, escaping what would not be parsed back as part of it.
+pub fn push_identifier(value: &str, css: &mut ~str) {
+    if value == "-" {
+        css.push_str("\\-");
+        return
+    }
+    let mut chars = value.chars();
+    if value.starts_with("-") {
+        css.push_char('-');
+        chars.next();
+    }
+    let mut first = true;
+    for c in chars {
+        match c {
+            '0'..'9' if first => push_hex_escape(c, css),
+            'a'..'z' | 'A'..'Z' | '0'..'9' | '_' | '-' => css.push_char(c),
+            _ if c as u32 >= 0x80 => css.push_char(c),
+            _ if c as u32 < 0x20 || c == '\x7f' => push_hex_escape(c, css),
+            _ => {
+                css.push_char('\\');
+                css.push_char(c);
+            }
+        }
+        first = false;
+    }
+}
+
+/// Serializes a string between double quotes.
+pub fn push_string(value: &str, css: &mut ~str) {
+    css.push_char('"');
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => {
+                css.push_char('\\');
+                css.push_char(c);
+            }
+            _ if c as u32 < 0x20 || c == '\x7f' => push_hex_escape(c, css),
+            _ => css.push_char(c),
+        }
+    }
+    css.push_char('"');
+}
+
+
+impl ToCssText for f64 {
+    fn push_css_text(&self, css: &mut ~str) {
+        push_number(*self, css)
+    }
+}
+
+/// Lengths in application units are serialized in px.
+impl ToCssText for Au {
+    fn push_css_text(&self, css: &mut ~str) {
+        push_number(to_frac_px(*self), css);
+        css.push_str("px");
+    }
+}
+
+impl ToCssText for RGBA {
+    fn push_css_text(&self, css: &mut ~str) {
+        fn component(value: f64) -> int { (value * 255.).round() as int }
+        if self.alpha >= 1. {
+            css.push_str(format!("rgb({}, {}, {})", component(self.red as f64),
+                                 component(self.green as f64), component(self.blue as f64)));
+        } else {
+            css.push_str(format!("rgba({}, {}, {}, ", component(self.red as f64),
+                                 component(self.green as f64), component(self.blue as f64)));
+            push_number(self.alpha as f64, css);
+            css.push_char(')');
+        }
+    }
+}
+
+impl ToCssText for Color {
+    fn push_css_text(&self, css: &mut ~str) {
+        match *self {
+            RGBA(ref rgba) => rgba.push_css_text(css),
+            CurrentColor => css.push_str("currentColor"),
+        }
+    }
+}
+
+impl ToCssText for Url {
+    fn push_css_text(&self, css: &mut ~str) {
+        css.push_str("url(");
+        push_string(self.to_str(), css);
+        css.push_char(')');
+    }
+}
@@ -5,38 +5,64 @@
 use std::str;
 use std::iter::Iterator;
 use std::ascii::StrAsciiExt;
+use extra::url::Url;
 use cssparser::{tokenize, parse_stylesheet_rules, ToCss};
 use cssparser::ast::*;
 use selectors;
 use properties;
-use errors::{ErrorLoggerIterator, log_css_error};
+use errors::{ErrorContext, ErrorReporter, InvalidAtRule, InvalidImport, InvalidSelector};
 use namespaces::{NamespaceMap, parse_namespace_rule};
-use media_queries::{MediaRule, parse_media_rule};
+use media_queries::{MediaRule, MediaQueryList, parse_media_rule, parse_media_query_list};
 use media_queries;
+use parsing_utils::get_url;
+use page::{PageRule, parse_page_rule};
+use serialize::ToCssText;
+use servo_net::resource_task::{ResourceTask, load_whole_resource};
+use servo_util::url::make_url;
 
 
+#[deriving(Clone)]
 pub struct Stylesheet {
     /// List of rules in the order they were found (important for
     /// cascading order)
     rules: ~[CSSRule],
     namespaces: NamespaceMap,
+    /// The URL relative URLs in this style sheet (e.g. in @import) are resolved against.
+    base_url: Url,
 }
 
 
+#[deriving(Clone)]
 pub enum CSSRule {
     CSSStyleRule(StyleRule),
     CSSMediaRule(MediaRule),
+    CSSImportRule(ImportRule),
+    CSSPageRule(PageRule),
 }
 
 
+#[deriving(Clone)]
 pub struct StyleRule {
     selectors: ~[selectors::Selector],
     declarations: properties::PropertyDeclarationBlock,
 }
 
 
+#[deriving(Clone)]
+pub struct ImportRule {
+    location: SourceLocation,
+    /// Absolute URL of the imported style sheet.
+    url: Url,
+    media_queries: MediaQueryList,
+    /// The imported style sheet, once it has been fetched with `Stylesheet::load_imports`.
+    /// None if it has not been fetched yet or could not be loaded.
+    stylesheet: Option<~Stylesheet>,
+}
+
+
 impl Stylesheet {
-    pub fn from_iter<I: Iterator<~[u8]>>(input: I) -> Stylesheet {
+    pub fn from_iter<I: Iterator<~[u8]>>(input: I, base_url: Url, reporter: &mut ErrorReporter)
+                                         -> Stylesheet {
         let mut string = ~"";
         let mut input = input;
         // TODO: incremental tokinization/parsing
@@ -45,10 +71,11 @@ impl Stylesheet {
             // TODO: support character encodings (use rust-encodings in rust-cssparser)
             string.push_str(str::from_utf8_owned(chunk))
         }
-        Stylesheet::from_str(string)
+        Stylesheet::from_str(string, base_url, reporter)
     }
 
-    pub fn from_str(css: &str) -> Stylesheet {
+    /// Parses a style sheet. Its errors are given to `reporter`.
+    pub fn from_str(css: &str, base_url: Url, reporter: &mut ErrorReporter) -> Stylesheet {
         static STATE_CHARSET: uint = 1;
         static STATE_IMPORTS: uint = 2;
         static STATE_NAMESPACES: uint = 3;
@@ -57,20 +84,29 @@ impl Stylesheet {
 
         let mut rules = ~[];
         let mut namespaces = NamespaceMap::new();
+        let mut errors = ErrorContext::new(base_url.clone(), reporter);
 
-        for rule in ErrorLoggerIterator(parse_stylesheet_rules(tokenize(css))) {
+        for result in parse_stylesheet_rules(tokenize(css)) {
+            let rule = match result {
+                Ok(rule) => rule,
+                Err(error) => {
+                    errors.report_syntax_error(error);
+                    continue
+                }
+            };
             let next_state;  // Unitialized to force each branch to set it.
             match rule {
                 QualifiedRule(rule) => {
                     next_state = STATE_BODY;
-                    parse_style_rule(rule, &mut rules, &namespaces)
+                    parse_style_rule(rule, &mut rules, &namespaces, &base_url, &mut errors)
                 },
                 AtRule(rule) => {
                     let lower_name = rule.name.to_ascii_lower();
                     match lower_name.as_slice() {
                         "charset" => {
                             if state > STATE_CHARSET {
-                                log_css_error(rule.location, "@charset must be the first rule")
+                                errors.report(rule.location, InvalidAtRule,
+                                              "@charset must be the first rule")
                             }
                             // Valid @charset rules are just ignored
                             next_state = STATE_IMPORTS;
@@ -78,62 +114,219 @@ impl Stylesheet {
                         "import" => {
                             if state > STATE_IMPORTS {
                                 next_state = state;
-                                log_css_error(rule.location,
+                                errors.report(rule.location, InvalidAtRule,
                                               "@import must be before any rule but @charset")
                             } else {
                                 next_state = STATE_IMPORTS;
-                                // TODO: support @import
-                                log_css_error(rule.location, "@import is not supported yet")
+                                parse_import_rule(rule, &mut rules, &base_url, &mut errors)
                             }
                         },
                         "namespace" => {
                             if state > STATE_NAMESPACES {
                                 next_state = state;
-                                log_css_error(
-                                    rule.location,
+                                errors.report(
+                                    rule.location, InvalidAtRule,
                                     "@namespace must be before any rule but @charset and @import"
                                 )
                             } else {
                                 next_state = STATE_NAMESPACES;
-                                parse_namespace_rule(rule, &mut namespaces)
+                                parse_namespace_rule(rule, &mut namespaces, &mut errors)
                             }
                         },
                         _ => {
                             next_state = STATE_BODY;
-                            parse_nested_at_rule(lower_name, rule, &mut rules, &namespaces)
+                            parse_nested_at_rule(lower_name, rule, &mut rules, &namespaces,
+                                                 &base_url, &mut errors)
                         },
                     }
                 },
             }
             state = next_state;
         }
-        Stylesheet{ rules: rules, namespaces: namespaces }
+        Stylesheet{ rules: rules, namespaces: namespaces, base_url: base_url }
+    }
+
+    /// Fetch the style sheets referenced by @import rules through the resource task,
+    /// recursively. Import cycles are reported and the offending rule is left empty. The errors
+    /// of the imported style sheets are given to `reporter`.
+    pub fn load_imports(&mut self, resource_task: &ResourceTask, reporter: &mut ErrorReporter) {
+        let mut loading = ~[self.base_url.clone()];
+        let mut errors = ErrorContext::new(self.base_url.clone(), reporter);
+        load_imports(self.rules, resource_task, &mut loading, &mut errors)
+    }
+}
+
+
+/// `loading` is the chain of style sheet URLs currently being loaded, used to detect cycles.
+/// `errors` is the error context of the style sheet that contains `rules`.
+fn load_imports(rules: &mut [CSSRule], resource_task: &ResourceTask, loading: &mut ~[Url],
+                errors: &mut ErrorContext) {
+    for rule in rules.mut_iter() {
+        match *rule {
+            CSSImportRule(ref mut rule) => rule.load(resource_task, loading, errors),
+            // @import rules are only allowed before any other rule.
+            _ => break,
+        }
     }
 }
 
 
+impl ImportRule {
+    fn load(&mut self, resource_task: &ResourceTask, loading: &mut ~[Url],
+            errors: &mut ErrorContext) {
+        if loading.iter().any(|url| *url == self.url) {
+            errors.report(self.location, InvalidImport, format!(
+                "@import cycle detected, ignoring {:s}", self.url.to_str()));
+            return
+        }
+        match load_whole_resource(resource_task, self.url.clone()) {
+            Ok((metadata, data)) => {
+                debug!("stylesheets: loading imported style sheet at {:s}", self.url.to_str());
+                let mut stylesheet = Stylesheet::from_iter(Some(data).move_iter(),
+                                                           metadata.final_url,
+                                                           errors.reporter());
+                loading.push(self.url.clone());
+                let mut imported_errors = ErrorContext::new(stylesheet.base_url.clone(),
+                                                            errors.reporter());
+                load_imports(stylesheet.rules, resource_task, loading, &mut imported_errors);
+                loading.pop();
+                self.stylesheet = Some(~stylesheet);
+            }
+            Err(()) => errors.report(self.location, InvalidImport, format!(
+                "Failed to load imported style sheet {:s}", self.url.to_str())),
+        }
+    }
+}
+
+
+/// Rules are serialized one per line. @namespace rules, which are not kept as rules,
+/// come right after the @import rules.
+impl ToCssText for Stylesheet {
+    fn push_css_text(&self, css: &mut ~str) {
+        let imports = self.rules.iter().position(|rule| {
+            match *rule { CSSImportRule(_) => false, _ => true }
+        }).unwrap_or(self.rules.len());
+        let mut lines = ~[];
+        for rule in self.rules.slice_to(imports).iter() {
+            lines.push(rule_to_css(rule, &self.namespaces))
+        }
+        let namespace_rules = self.namespaces.to_css_text();
+        if !namespace_rules.is_empty() {
+            lines.push(namespace_rules)
+        }
+        for rule in self.rules.slice_from(imports).iter() {
+            lines.push(rule_to_css(rule, &self.namespaces))
+        }
+        css.push_str(lines.connect("\n"))
+    }
+}
+
+
+fn rule_to_css(rule: &CSSRule, namespaces: &NamespaceMap) -> ~str {
+    let mut css = ~"";
+    push_rule_css(rule, namespaces, &mut css);
+    css
+}
+
+
+/// `namespaces` gives the prefixes to use in selectors.
+pub fn push_rule_css(rule: &CSSRule, namespaces: &NamespaceMap, css: &mut ~str) {
+    match *rule {
+        CSSStyleRule(ref rule) => {
+            selectors::push_selector_list_css(rule.selectors, namespaces, css);
+            let declarations = rule.declarations.to_css_text();
+            if declarations.is_empty() {
+                css.push_str(" { }")
+            } else {
+                css.push_str(" { ");
+                css.push_str(declarations);
+                css.push_str(" }");
+            }
+        }
+        CSSMediaRule(ref rule) => {
+            css.push_str("@media ");
+            rule.media_queries.push_css_text(css);
+            css.push_str(" {\n");
+            for nested_rule in rule.rules.iter() {
+                css.push_str("  ");
+                push_rule_css(nested_rule, namespaces, css);
+                css.push_char('\n');
+            }
+            css.push_char('}');
+        }
+        CSSImportRule(ref rule) => {
+            css.push_str("@import ");
+            rule.url.push_css_text(css);
+            let media_queries = rule.media_queries.to_css_text();
+            if media_queries.as_slice() != "all" {
+                css.push_char(' ');
+                css.push_str(media_queries);
+            }
+            css.push_char(';');
+        }
+        CSSPageRule(ref rule) => rule.push_css_text(css),
+    }
+}
+
+
+/// @import [ <string> | <url> ] <media-query-list>? ;
+pub fn parse_import_rule(rule: AtRule, parent_rules: &mut ~[CSSRule], base_url: &Url,
+                         errors: &mut ErrorContext) {
+    let location = rule.location;
+    if rule.block.is_some() {
+        errors.report(location, InvalidAtRule, "Invalid @import rule");
+        return
+    }
+    let start = match rule.prelude.iter().position(|v| *v != WhiteSpace) {
+        Some(start) => start,
+        None => {
+            errors.report(location, InvalidAtRule, "Invalid @import rule");
+            return
+        }
+    };
+    let url = match rule.prelude[start] {
+        String(ref value) => value.clone(),
+        ref value => match get_url(value) {
+            Some(url) => url,
+            None => {
+                errors.report(location, InvalidAtRule, "Invalid @import rule");
+                return
+            }
+        }
+    };
+    parent_rules.push(CSSImportRule(ImportRule {
+        location: location,
+        url: make_url(url, Some(base_url.clone())),
+        media_queries: parse_media_query_list(rule.prelude.slice_from(start + 1)),
+        stylesheet: None,
+    }))
+}
+
+
 pub fn parse_style_rule(rule: QualifiedRule, parent_rules: &mut ~[CSSRule],
-                        namespaces: &NamespaceMap) {
+                        namespaces: &NamespaceMap, base_url: &Url, errors: &mut ErrorContext) {
     let QualifiedRule{location: location, prelude: prelude, block: block} = rule;
-    // FIXME: avoid doing this for valid selectors
-    let serialized = prelude.iter().to_css();
-    match selectors::parse_selector_list(prelude, namespaces) {
+    match selectors::parse_selector_list(prelude.as_slice(), namespaces) {
         Some(selectors) => parent_rules.push(CSSStyleRule(StyleRule{
             selectors: selectors,
-            declarations: properties::parse_property_declaration_list(block.move_iter())
+            declarations: properties::parse_property_declaration_list(block.move_iter(),
+                                                                      base_url, errors)
         })),
-        None => log_css_error(location, format!(
-            "Invalid/unsupported selector: {}", serialized)),
+        None => errors.report(location, InvalidSelector, format!(
+            "Invalid/unsupported selector: {}", prelude.iter().to_css())),
     }
 }
 
 
 // lower_name is passed explicitly to avoid computing it twice.
 pub fn parse_nested_at_rule(lower_name: &str, rule: AtRule,
-                            parent_rules: &mut ~[CSSRule], namespaces: &NamespaceMap) {
+                            parent_rules: &mut ~[CSSRule], namespaces: &NamespaceMap,
+                            base_url: &Url, errors: &mut ErrorContext) {
     match lower_name {
-        "media" => parse_media_rule(rule, parent_rules, namespaces),
-        _ => log_css_error(rule.location, format!("Unsupported at-rule: @{:s}", lower_name))
+        "media" => parse_media_rule(rule, parent_rules, namespaces, base_url, errors),
+        "page" => parse_page_rule(rule, parent_rules, errors),
+        _ => errors.report(rule.location, InvalidAtRule,
+                           format!("Unsupported at-rule: @{:s}", lower_name))
     }
 }
 
@@ -145,7 +338,59 @@ pub fn iter_style_rules<'a>(rules: &[CSSRule], device: &media_queries::Device,
             CSSStyleRule(ref rule) => callback(rule),
             CSSMediaRule(ref rule) => if rule.media_queries.evaluate(device) {
                 iter_style_rules(rule.rules.as_slice(), device, |s| callback(s))
-            }
+            },
+            // Imported rules come before the rules of the importing style sheet
+            // in the cascade, which is their position in `rules`.
+            CSSImportRule(ref rule) => match rule.stylesheet {
+                Some(ref stylesheet) if rule.media_queries.evaluate(device) => {
+                    iter_style_rules(stylesheet.rules.as_slice(), device, |s| callback(s))
+                }
+                _ => (),
+            },
+            CSSPageRule(_) => (),
+        }
+    }
+}
+
+
+pub fn iter_page_rules<'a>(rules: &'a [CSSRule], device: &media_queries::Device,
+                           callback: &fn(&'a PageRule)) {
+    for rule in rules.iter() {
+        match *rule {
+            CSSPageRule(ref rule) => callback(rule),
+            CSSMediaRule(ref rule) => if rule.media_queries.evaluate(device) {
+                iter_page_rules(rule.rules.as_slice(), device, |r| callback(r))
+            },
+            CSSImportRule(ref rule) => match rule.stylesheet {
+                Some(ref stylesheet) if rule.media_queries.evaluate(device) => {
+                    iter_page_rules(stylesheet.rules.as_slice(), device, |r| callback(r))
+                }
+                _ => (),
+            },
+            CSSStyleRule(_) => (),
+        }
+    }
+}
+
+
+/// Whether any media query in `rules` evaluates differently for the two devices.
+pub fn media_queries_changed(rules: &[CSSRule], before: &media_queries::Device,
+                             after: &media_queries::Device) -> bool {
+    do rules.iter().any |rule| {
+        match *rule {
+            CSSStyleRule(_) | CSSPageRule(_) => false,
+            CSSMediaRule(ref rule) => {
+                rule.media_queries.evaluate(before) != rule.media_queries.evaluate(after) ||
+                    media_queries_changed(rule.rules.as_slice(), before, after)
+            },
+            CSSImportRule(ref rule) => {
+                rule.media_queries.evaluate(before) != rule.media_queries.evaluate(after) ||
+                    match rule.stylesheet {
+                        Some(ref stylesheet)
+                        => media_queries_changed(stylesheet.rules.as_slice(), before, after),
+                        None => false,
+                    }
+            },
         }
     }
 }
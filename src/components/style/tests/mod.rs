@@ -2,11 +2,315 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use super::stylesheets::Stylesheet;
+use std::from_str::FromStr;
+use servo_net::resource_task::{ResourceTask, Exit};
+use servo_util::geometry::Au;
+use super::stylesheets::{Stylesheet, CSSImportRule, CSSStyleRule};
+use super::selector_matching::{Stylist, AuthorOrigin};
+use super::media_queries::{Device, Print, Screen};
+use super::properties::{cascade, parse_style_attribute};
+use super::errors::{CSSErrorCategory, ErrorCollector, LogErrorReporter, InvalidSyntax};
+use super::errors::{InvalidSelector, UnknownProperty, InvalidValue, InvalidAtRule, InvalidImport};
+use super::properties::computed_values::{LP_Percentage, background_attachment, background_repeat};
+use super::properties::computed_values::{Calc, LP_Length, LPA_Length, LPA_Calc, LPN_Length};
+use super::properties::computed_values::{border_collapse, content, display};
+use super::serialize::ToCssText;
+
+fn screen() -> Device {
+    Device::new(Screen, Au::from_px(800), Au::from_px(600), 1.)
+}
 
 #[test]
 fn test_bootstrap() {
     // Test that parsing bootstrap does not trigger an assertion or otherwise fail.
-    let stylesheet = Stylesheet::from_str(include_str!("bootstrap-v3.0.0.css"));
+    let stylesheet = Stylesheet::from_str(include_str!("bootstrap-v3.0.0.css"),
+                                          FromStr::from_str("http://example.com/").unwrap(),
+                                          &mut LogErrorReporter);
     assert!(stylesheet.rules.len() > 100);  // This depends on whet selectors are supported.
 }
+
+#[test]
+fn test_import_rule() {
+    let stylesheet = Stylesheet::from_str(
+        "@import url(base.css) print; @import 'http://example.org/x.css'; p { color: red }",
+        FromStr::from_str("http://example.com/css/main.css").unwrap(), &mut LogErrorReporter);
+    assert_eq!(stylesheet.rules.len(), 3);
+    match stylesheet.rules[0] {
+        CSSImportRule(ref rule) => {
+            assert_eq!(rule.url.to_str(), ~"http://example.com/css/base.css");
+            assert_eq!(rule.media_queries.media_queries.len(), 1);
+        }
+        _ => fail!("expected an @import rule"),
+    }
+    match stylesheet.rules[1] {
+        CSSImportRule(ref rule) => assert_eq!(rule.url.to_str(), ~"http://example.org/x.css"),
+        _ => fail!("expected an @import rule"),
+    }
+}
+
+#[test]
+fn test_import_after_style_rule_is_ignored() {
+    let mut errors = ErrorCollector::new();
+    let stylesheet = Stylesheet::from_str("p { color: red } @import 'a.css';",
+                                          FromStr::from_str("http://example.com/").unwrap(),
+                                          &mut errors);
+    assert_eq!(stylesheet.rules.len(), 1);
+    assert_eq!(errors.errors.len(), 1);
+    assert_eq!(errors.errors[0].category, InvalidAtRule);
+}
+
+#[test]
+fn test_load_imports() {
+    let resource_task = ResourceTask();
+    let mut stylesheet = Stylesheet::from_str(
+        "@import url('data:text/css,p{color:red}'); a { color: blue }",
+        FromStr::from_str("http://example.com/").unwrap(), &mut LogErrorReporter);
+    stylesheet.load_imports(&resource_task, &mut LogErrorReporter);
+    match stylesheet.rules[0] {
+        CSSImportRule(ref rule) => {
+            let imported = rule.stylesheet.get_ref();
+            assert_eq!(imported.rules.len(), 1);
+            match imported.rules[0] {
+                CSSStyleRule(_) => (),
+                _ => fail!("expected a style rule"),
+            }
+        }
+        _ => fail!("expected an @import rule"),
+    }
+    resource_task.send(Exit);
+}
+
+#[test]
+fn test_import_cycle_is_not_loaded() {
+    let resource_task = ResourceTask();
+    let mut errors = ErrorCollector::new();
+    let mut stylesheet = Stylesheet::from_str(
+        "@import 'main.css';", FromStr::from_str("http://example.com/main.css").unwrap(),
+        &mut errors);
+    stylesheet.load_imports(&resource_task, &mut errors);
+    match stylesheet.rules[0] {
+        CSSImportRule(ref rule) => assert!(rule.stylesheet.is_none()),
+        _ => fail!("expected an @import rule"),
+    }
+    assert_eq!(errors.errors.len(), 1);
+    assert_eq!(errors.errors[0].category, InvalidImport);
+    resource_task.send(Exit);
+}
+
+#[test]
+fn test_page_box() {
+    let device = Device::new(Print, Au::from_px(800), Au::from_px(600), 1.);
+    let mut stylist = Stylist::new(Device::new(Print, Au::from_px(800), Au::from_px(600), 1.));
+    let stylesheet = Stylesheet::from_str(
+        "@page { size: 600px 400px; margin: 10px 20px } \
+         @media print { @page { margin-top: 5% } } \
+         @media screen { @page { size: a4 } }",
+        FromStr::from_str("http://example.com/").unwrap(), &mut LogErrorReporter);
+    stylist.add_stylesheet(stylesheet, AuthorOrigin);
+    let page_box = stylist.page_box(&device);
+    assert_eq!(page_box.width, Au::from_px(600));
+    assert_eq!(page_box.height, Au::from_px(400));
+    assert_eq!(page_box.margin_top, Au::from_px(20));
+    assert_eq!(page_box.margin_left, Au::from_px(20));
+    assert_eq!(page_box.content_height(), Au::from_px(370));
+}
+
+#[test]
+fn test_page_size_keywords() {
+    let page_box = |css: &str| {
+        let device = Device::new(Print, Au::from_px(800), Au::from_px(600), 1.);
+        let mut stylist = Stylist::new(Device::new(Print, Au::from_px(800), Au::from_px(600), 1.));
+        stylist.add_stylesheet(Stylesheet::from_str(css,
+                                                    FromStr::from_str("http://example.com/").unwrap(),
+                                                    &mut LogErrorReporter),
+                               AuthorOrigin);
+        stylist.page_box(&device)
+    };
+    let auto = page_box("@page { size: auto }");
+    assert_eq!((auto.width, auto.height), (Au::from_px(800), Au::from_px(600)));
+    let portrait = page_box("@page { size: portrait }");
+    assert_eq!((portrait.width, portrait.height), (Au::from_px(600), Au::from_px(800)));
+    let letter = page_box("@page { size: landscape letter }");
+    assert_eq!((letter.width, letter.height), (Au::from_px(1056), Au::from_px(816)));
+    // Page selectors are not supported, the rule is ignored.
+    let first = page_box("@page :first { size: 10px }");
+    assert_eq!((first.width, first.height), (Au::from_px(800), Au::from_px(600)));
+}
+
+#[test]
+fn test_background_shorthand() {
+    let block = parse_style_attribute(
+        "background: url(img/sprite.png) no-repeat right 10px red",
+        &FromStr::from_str("http://example.com/css/main.css").unwrap(), &mut LogErrorReporter);
+    let style = cascade([block.normal.clone()], None, &screen());
+    let background = &style.Background;
+    assert_eq!(background.background_image.get_ref().to_str(),
+               ~"http://example.com/css/img/sprite.png");
+    assert_eq!(background.background_repeat, background_repeat::no_repeat);
+    assert_eq!(background.background_attachment, background_attachment::scroll);
+    // A horizontal length may be followed by a vertical keyword.
+    let block = parse_style_attribute("background: 10px bottom",
+                                      &FromStr::from_str("http://example.com/").unwrap(),
+                                      &mut LogErrorReporter);
+    let style = cascade([block.normal.clone()], None, &screen());
+    assert_eq!(style.Background.background_position.horizontal, LP_Length(Au::from_px(10)));
+    assert_eq!(style.Background.background_position.vertical, LP_Percentage(1.));
+    let block = parse_style_attribute("background: bottom right",
+                                      &FromStr::from_str("http://example.com/").unwrap(),
+                                      &mut LogErrorReporter);
+    let style = cascade([block.normal.clone()], None, &screen());
+    assert_eq!(style.Background.background_position.horizontal, LP_Percentage(1.));
+    assert_eq!(style.Background.background_position.vertical, LP_Percentage(1.));
+}
+
+#[test]
+fn test_content_and_counters() {
+    let base_url = FromStr::from_str("http://example.com/").unwrap();
+    let block = parse_style_attribute(
+        "content: open-quote counters(item, '.', upper-roman) attr(title) '\\A'; \
+         counter-reset: item section 3; counter-increment: item -1",
+        &base_url, &mut LogErrorReporter);
+    let style = cascade([block.normal.clone()], None, &screen());
+    assert_eq!(style.Box.content, content::Content(~[
+        content::OpenQuote,
+        content::CountersContent(~"item", ~".", content::UpperRoman),
+        content::AttrContent(~"title"),
+        content::StringContent(~"\n"),
+    ]));
+    assert_eq!(style.Box.counter_reset, ~[(~"item", 0), (~"section", 3)]);
+    assert_eq!(style.Box.counter_increment, ~[(~"item", -1)]);
+    assert_eq!(style.Box.quotes.len(), 2);
+    // Invalid values are ignored.
+    let block = parse_style_attribute(
+        "content: counter(item, not-a-style); counter-reset: 3; quotes: '<<'", &base_url,
+        &mut LogErrorReporter);
+    assert_eq!(block.normal.get().len(), 0);
+}
+
+#[test]
+fn test_table_properties() {
+    let base_url = FromStr::from_str("http://example.com/").unwrap();
+    let block = parse_style_attribute(
+        "display: table-cell; border-collapse: collapse; border-spacing: 1px 2px",
+        &base_url, &mut LogErrorReporter);
+    let style = cascade([block.normal.clone()], None, &screen());
+    // The root element is blockified.
+    assert_eq!(style.Box.display, display::block);
+    assert_eq!(style.Table.border_collapse, border_collapse::collapse);
+    assert_eq!(style.Table.border_spacing.horizontal, Au::from_px(1));
+    assert_eq!(style.Table.border_spacing.vertical, Au::from_px(2));
+
+    let block = parse_style_attribute("display: inline-table; float: left; border-spacing: 3px",
+                                      &base_url, &mut LogErrorReporter);
+    let child = cascade([block.normal.clone()], Some(&style), &screen());
+    assert_eq!(child.Box.display, display::table);
+    assert_eq!(child.Table.border_collapse, border_collapse::collapse);
+    assert_eq!(child.Table.border_spacing.vertical, Au::from_px(3));
+
+    // Spacing can not be negative, and has at most two lengths.
+    let block = parse_style_attribute("border-spacing: -1px; border-spacing: 1px 2px 3px",
+                                      &base_url, &mut LogErrorReporter);
+    assert_eq!(block.normal.get().len(), 0);
+}
+
+#[test]
+fn test_error_reporting() {
+    let mut errors = ErrorCollector::new();
+    Stylesheet::from_str(
+        "p { colour: red; color: 12px } \n\
+         p::nope { color: red } \n\
+         @font-face { font-family: x } \n\
+         p { color red }",
+        FromStr::from_str("http://example.com/main.css").unwrap(), &mut errors);
+    let categories: ~[CSSErrorCategory] = errors.errors.iter().map(|error| error.category)
+                                                .collect();
+    assert_eq!(categories, ~[UnknownProperty, InvalidValue, InvalidSelector, InvalidAtRule,
+                             InvalidSyntax]);
+    assert_eq!(errors.errors[0].url.to_str(), ~"http://example.com/main.css");
+    assert_eq!(errors.errors.iter().map(|error| error.line).collect::<~[uint]>(),
+               ~[1, 1, 2, 3, 4]);
+
+    let mut errors = ErrorCollector::new();
+    parse_style_attribute("colour: red", &FromStr::from_str("http://example.com/").unwrap(),
+                          &mut errors);
+    assert_eq!(errors.errors.len(), 1);
+    assert_eq!(errors.errors[0].category, UnknownProperty);
+}
+
+#[test]
+fn test_calc_and_relative_units() {
+    let base_url = FromStr::from_str("http://example.com/").unwrap();
+    let root_block = parse_style_attribute("font-size: 20px", &base_url, &mut LogErrorReporter);
+    let root = cascade([root_block.normal.clone()], None, &screen());
+    let block = parse_style_attribute(
+        "font-size: 2rem; width: calc(50% - 2 * 10px); height: 10vh; \
+         max-width: calc(1vw + 2vmin / 2); text-indent: calc(1em + (1rem))",
+        &base_url, &mut LogErrorReporter);
+    let style = cascade([block.normal.clone()], Some(&root), &screen());
+    assert_eq!(style.Font.font_size, Au::from_px(40));
+    assert_eq!(style.Box.width, LPA_Calc(Calc { length: Au::from_px(-20), percentage: 0.5 }));
+    assert_eq!(style.Box.height, LPA_Length(Au::from_px(60)));
+    assert_eq!(style.Box.max_width, LPN_Length(Au::from_px(14)));
+    assert_eq!(style.Text.text_indent, LP_Length(Au::from_px(60)));
+    // Numbers can not be added to lengths, nor lengths multiplied together.
+    let block = parse_style_attribute("width: calc(10px + 2); height: calc(5% * 2px)",
+                                      &base_url, &mut LogErrorReporter);
+    assert_eq!(block.normal.get().len(), 0);
+}
+
+#[test]
+fn test_declaration_serialization() {
+    let base_url = FromStr::from_str("http://example.com/").unwrap();
+    let block = parse_style_attribute(
+        "margin: 1px 2px 1px 2px; color: RED !important; border: 1px solid red; \
+         border-left-width: 2px; width: calc(50% - 2 * 10px); font: bold 12px/1.5 serif",
+        &base_url, &mut LogErrorReporter);
+    assert_eq!(block.to_css_text(),
+               ~"margin: 1px 2px; border-color: rgb(255, 0, 0); border-style: solid; \
+                 border-width: 1px 1px 1px 2px; width: calc(-20px + 50%); \
+                 font: normal normal 700 12px/1.5 serif; color: rgb(255, 0, 0) !important;");
+    assert_eq!(block.property_value("Border-Top"), Some((~"1px solid rgb(255, 0, 0)", false)));
+    assert_eq!(block.property_value("border"), None);
+    assert_eq!(block.property_value("color"), Some((~"rgb(255, 0, 0)", true)));
+    assert_eq!(block.property_value("padding"), None);
+
+    let block = parse_style_attribute("margin: inherit; font-family: 'inherit', Times  New Roman",
+                                      &base_url, &mut LogErrorReporter);
+    assert_eq!(block.to_css_text(), ~"margin: inherit; font-family: \"inherit\", Times New Roman;");
+
+    let style = cascade([parse_style_attribute("width: calc(50% - 2 * 10px); font-size: 2em",
+                                               &base_url, &mut LogErrorReporter).normal.clone()],
+                        None, &screen());
+    let values = style.to_property_list();
+    let value = |name: &str| {
+        values.iter().find(|&&(ref n, _)| n.as_slice() == name).map(|&(_, ref v)| v.clone())
+              .unwrap()
+    };
+    assert_eq!(value("width"), ~"calc(-20px + 50%)");
+    assert_eq!(value("font-size"), ~"32px");
+    assert_eq!(value("font-weight"), ~"400");
+    assert_eq!(value("background-color"), ~"rgba(0, 0, 0, 0)");
+}
+
+#[test]
+fn test_stylesheet_serialization() {
+    let serialize = |css: &str| {
+        Stylesheet::from_str(css, FromStr::from_str("http://example.com/").unwrap(),
+                             &mut LogErrorReporter).to_css_text()
+    };
+    let expected = ~"@import url(\"http://example.com/a.css\") print;\n\
+                     @namespace svg url(\"http://www.w3.org/2000/svg\");\n\
+                     svg|a, p > a:hover::before { margin: 0px auto; \
+                     font: normal normal 400 12px/normal serif; }\n\
+                     @media screen and (min-width: 600px) {\n  .x { color: rgb(255, 0, 0); }\n}\n\
+                     @page { margin-top: 10px; margin-right: 5%; margin-bottom: 10px; \
+                     margin-left: 5%; }\n\
+                     p { }";
+    assert_eq!(serialize(
+        "@import 'a.css' print; @namespace svg 'http://www.w3.org/2000/svg'; \
+         svg|a,p>a:hover::before { margin: 0 auto; font: 12px serif } \
+         @media screen and (min-width: 600px) { .x { color: red } } \
+         @page { margin: 10px 5% } p {}"), expected.clone());
+    // Serializing again what was serialized gives the same style sheet.
+    assert_eq!(serialize(expected), expected);
+}
@@ -0,0 +1,98 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Interned strings for element names, ids and classes, shared by the DOM, the HTML parser and
+//! selector matching.
+//!
+//! There is a single atom table for the whole process, so atoms can be sent between tasks and
+//! compared with the atoms of any other task. Interned strings are never freed.
+
+use std::cast;
+use std::hashmap::HashMap;
+use std::task;
+use std::to_bytes::{Cb, IterBytes};
+use std::unstable::atomics::{AtomicBool, INIT_ATOMIC_BOOL, Acquire, Release};
+
+/// An interned string. Two atoms are equal if and only if their strings are equal, which is
+/// checked by comparing pointers.
+#[deriving(Clone)]
+pub struct Atom {
+    priv string: &'static str,
+}
+
+static mut TABLE_LOCK: AtomicBool = INIT_ATOMIC_BOOL;
+static mut TABLE: *mut HashMap<~str, Atom> = 0 as *mut HashMap<~str, Atom>;
+
+impl Atom {
+    /// Returns the atom for `string`, interning it if this is the first time it is seen.
+    pub fn from_slice(string: &str) -> Atom {
+        unsafe {
+            // Interning is rare compared to comparing atoms, and the lock is only held for a
+            // hash table lookup, so a spinning lock is good enough.
+            while TABLE_LOCK.swap(true, Acquire) {
+                task::deschedule();
+            }
+            if TABLE.is_null() {
+                let table: ~HashMap<~str, Atom> = ~HashMap::new();
+                TABLE = cast::transmute(table);
+            }
+            let table = &mut *TABLE;
+            let atom = match table.find_equiv(&string) {
+                Some(atom) => *atom,
+                None => {
+                    let owned = string.to_owned();
+                    // The characters of an owned string do not move when the string itself is
+                    // moved into the table, and the table is never freed.
+                    let atom = Atom {
+                        string: cast::transmute(owned.as_slice()),
+                    };
+                    table.insert(owned, atom);
+                    atom
+                }
+            };
+            TABLE_LOCK.store(false, Release);
+            atom
+        }
+    }
+
+    #[inline]
+    pub fn as_slice(&self) -> &'static str {
+        self.string
+    }
+
+    #[inline]
+    fn as_ptr(&self) -> *u8 {
+        self.string.as_imm_buf(|ptr, _| ptr)
+    }
+}
+
+impl Eq for Atom {
+    #[inline]
+    fn eq(&self, other: &Atom) -> bool {
+        self.as_ptr() == other.as_ptr()
+    }
+}
+
+impl IterBytes for Atom {
+    #[inline]
+    fn iter_bytes(&self, lsb0: bool, f: Cb) -> bool {
+        (self.as_ptr() as uint).iter_bytes(lsb0, f)
+    }
+}
+
+impl ToStr for Atom {
+    fn to_str(&self) -> ~str {
+        self.string.to_owned()
+    }
+}
+
+#[test]
+fn test_atom() {
+    let div = Atom::from_slice("div");
+    assert!(div == Atom::from_slice("div"));
+    assert!(div != Atom::from_slice("DIV"));
+    assert!(div != Atom::from_slice("span"));
+    assert_eq!(div.as_slice(), "div");
+    assert_eq!(Atom::from_slice("").as_slice(), "");
+}
@@ -0,0 +1,148 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The browsing history that links are matched against for the `:visited` pseudo-class.
+
+use extra::arc::RWArc;
+use extra::url::Url;
+use std::hashmap::HashSet;
+use std::rt::io::file;
+use std::rt::io::{Append, Open, Read, Reader, Write, Writer, ignore_io_error};
+use std::str;
+
+/// A set of visited URLs. The constellation records the pages that are loaded, and layout asks
+/// whether links point to one of them.
+pub trait HistoryStore {
+    /// Records a visit to `url`.
+    fn add_visit(&mut self, url: &Url);
+
+    /// Returns true if `url` has been visited.
+    fn is_visited(&self, url: &Url) -> bool;
+}
+
+/// A history store shared between the constellation and the layout tasks.
+pub type SharedHistoryStore = RWArc<~HistoryStore:Send+Freeze>;
+
+/// Creates a history store. If `path` is given, the history is loaded from that file and new
+/// visits are saved to it; otherwise it only lasts as long as the browser runs.
+pub fn new_history_store(path: Option<~str>) -> SharedHistoryStore {
+    let store = match path {
+        Some(path) => ~FileHistoryStore::open(path) as ~HistoryStore:Send+Freeze,
+        None => ~MemoryHistoryStore::new() as ~HistoryStore:Send+Freeze,
+    };
+    RWArc::new(store)
+}
+
+/// A history store that is kept in memory.
+pub struct MemoryHistoryStore {
+    priv urls: HashSet<~str>,
+}
+
+impl MemoryHistoryStore {
+    pub fn new() -> MemoryHistoryStore {
+        MemoryHistoryStore {
+            urls: HashSet::new(),
+        }
+    }
+
+    /// Adds the URLs in `contents`, one per line, as saved by `FileHistoryStore`. Lines that are
+    /// not valid UTF-8 are skipped with a warning naming `path`, the file they were read from.
+    fn add_lines(&mut self, contents: &[u8], path: &str) {
+        for line in contents.split_iter(|&byte| byte == '\n' as u8) {
+            if line.is_empty() {
+                continue
+            }
+            if !str::is_utf8(line) {
+                warn!("skipping a line of the history in {:s} that is not valid UTF-8", path);
+                continue
+            }
+            self.urls.insert(str::from_utf8(line));
+        }
+    }
+}
+
+impl HistoryStore for MemoryHistoryStore {
+    fn add_visit(&mut self, url: &Url) {
+        self.urls.insert(url.to_str());
+    }
+
+    fn is_visited(&self, url: &Url) -> bool {
+        self.urls.contains(&url.to_str())
+    }
+}
+
+/// A history store that is kept in memory and saved to a file, with one URL per line. New visits
+/// are appended to the file as they happen.
+pub struct FileHistoryStore {
+    priv memory: MemoryHistoryStore,
+    priv path: ~str,
+}
+
+impl FileHistoryStore {
+    /// Loads the history saved in the file at `path`. A file that does not exist or can not be
+    /// read is an empty history, and lines that are not valid UTF-8 are skipped.
+    pub fn open(path: ~str) -> FileHistoryStore {
+        let mut memory = MemoryHistoryStore::new();
+        // ignore_io_error causes us to get None instead of a task failure.
+        match ignore_io_error(|| file::open(&path.as_slice(), Open, Read)) {
+            Some(ref mut reader) => {
+                let contents = ignore_io_error(|| reader.read_to_end());
+                memory.add_lines(contents, path.as_slice());
+            }
+            None => {}
+        }
+        FileHistoryStore {
+            memory: memory,
+            path: path,
+        }
+    }
+}
+
+impl HistoryStore for FileHistoryStore {
+    fn add_visit(&mut self, url: &Url) {
+        let url = url.to_str();
+        if self.memory.urls.contains(&url) {
+            return
+        }
+        match ignore_io_error(|| file::open(&self.path.as_slice(), Append, Write)) {
+            Some(ref mut writer) => {
+                ignore_io_error(|| writer.write((url + "\n").as_bytes()));
+            }
+            None => warn!("failed to save the history to {:s}", self.path),
+        }
+        self.memory.urls.insert(url);
+    }
+
+    fn is_visited(&self, url: &Url) -> bool {
+        self.memory.is_visited(url)
+    }
+}
+
+#[test]
+fn test_memory_history_store() {
+    use std::from_str::FromStr;
+
+    let mut history = MemoryHistoryStore::new();
+    let page: Url = FromStr::from_str("http://example.com/page.html").unwrap();
+    let other: Url = FromStr::from_str("http://example.com/other.html").unwrap();
+    assert!(!history.is_visited(&page));
+    history.add_visit(&page);
+    assert!(history.is_visited(&page));
+    assert!(!history.is_visited(&other));
+}
+
+#[test]
+fn test_history_lines() {
+    use std::from_str::FromStr;
+
+    let mut history = MemoryHistoryStore::new();
+    let contents = bytes!("http://example.com/page.html\n", 0xff, 0xfe, "\n\n",
+                          "http://example.com/other.html\n");
+    history.add_lines(contents, "history.txt");
+    let page: Url = FromStr::from_str("http://example.com/page.html").unwrap();
+    let other: Url = FromStr::from_str("http://example.com/other.html").unwrap();
+    assert!(history.is_visited(&page));
+    assert!(history.is_visited(&other));
+    assert_eq!(history.urls.len(), 2);
+}
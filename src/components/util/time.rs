@@ -29,6 +29,12 @@ impl ProfilerChan {
 pub enum ProfilerMsg {
     // Normal message used for reporting time
     TimeMsg(ProfilerCategory, f64),
+    // Message used to report the number of hits and of lookups of the style sharing cache, and
+    // the number of styles cascaded and the time that took (in ms)
+    StyleSharingMsg(uint, uint, uint, f64),
+    // Message used to report the number of selectors rejected by the ancestor filter and of
+    // lookups, and the number of selectors matched in full and the time that took (in ms)
+    AncestorFilterMsg(uint, uint, uint, f64),
     // Message used to force print the profiling metrics
     PrintMsg,
 }
@@ -96,6 +102,19 @@ type ProfilerBuckets = TreeMap<ProfilerCategory, ~[f64]>;
 pub struct Profiler {
     port: Port<ProfilerMsg>,
     buckets: ProfilerBuckets,
+    /// The number of times the style sharing cache was hit, and looked up.
+    style_sharing_hits: uint,
+    style_sharing_lookups: uint,
+    /// The number of styles cascaded, and the time that took, from which the time saved by the
+    /// style sharing cache is estimated.
+    cascades: uint,
+    cascade_time: f64,
+    ancestor_filter_rejected: uint,
+    ancestor_filter_lookups: uint,
+    /// The number of selectors matched in full, and the time that took, from which the time saved
+    /// by the ancestor filter is estimated.
+    selectors_matched: uint,
+    selector_matching_time: f64,
     last_msg: Option<ProfilerMsg>,
 }
 
@@ -132,6 +151,14 @@ impl Profiler {
         Profiler {
             port: port,
             buckets: ProfilerCategory::empty_buckets(),
+            style_sharing_hits: 0,
+            style_sharing_lookups: 0,
+            cascades: 0,
+            cascade_time: 0f64,
+            ancestor_filter_rejected: 0,
+            ancestor_filter_lookups: 0,
+            selectors_matched: 0,
+            selector_matching_time: 0f64,
             last_msg: None,
         }
     }
@@ -149,9 +176,23 @@ impl Profiler {
     fn handle_msg(&mut self, msg: ProfilerMsg) {
         match msg {
             TimeMsg(category, t) => self.buckets.find_mut(&category).unwrap().push(t),
+            StyleSharingMsg(hits, lookups, cascades, cascade_time) => {
+                self.style_sharing_hits += hits;
+                self.style_sharing_lookups += lookups;
+                self.cascades += cascades;
+                self.cascade_time += cascade_time;
+            }
+            AncestorFilterMsg(rejected, lookups, matched, matching_time) => {
+                self.ancestor_filter_rejected += rejected;
+                self.ancestor_filter_lookups += lookups;
+                self.selectors_matched += matched;
+                self.selector_matching_time += matching_time;
+            }
             PrintMsg => match self.last_msg {
                 // only print if more data has arrived since the last printout
-                Some(TimeMsg(*)) => self.print_buckets(),
+                Some(TimeMsg(*)) | Some(StyleSharingMsg(*)) | Some(AncestorFilterMsg(*)) => {
+                    self.print_buckets()
+                }
                 _ => ()
             },
         };
@@ -177,10 +218,40 @@ impl Profiler {
                              category.format(), mean, median, min, max, data_len));
             }
         }
+        // The time saved is estimated from the mean time of the work that was not avoided: a hit
+        // of the style sharing cache saves a cascade, and a rejection by the ancestor filter saves
+        // matching a selector in full.
+        if self.style_sharing_lookups > 0 {
+            let hit_rate = (self.style_sharing_hits as f64) * 100f64 /
+                (self.style_sharing_lookups as f64);
+            println(format!("style sharing cache: {:u} hits out of {:u} lookups ({:.1f}%)",
+                            self.style_sharing_hits, self.style_sharing_lookups, hit_rate));
+            println(format!("    saving about {:.4f} ms of cascading",
+                            time_saved(self.style_sharing_hits, self.cascades,
+                                       self.cascade_time)));
+        }
+        if self.ancestor_filter_lookups > 0 {
+            let reject_rate = (self.ancestor_filter_rejected as f64) * 100f64 /
+                (self.ancestor_filter_lookups as f64);
+            println(format!("ancestor filter: {:u} rejected out of {:u} lookups ({:.1f}%)",
+                            self.ancestor_filter_rejected, self.ancestor_filter_lookups,
+                            reject_rate));
+            println(format!("    saving about {:.4f} ms of selector matching",
+                            time_saved(self.ancestor_filter_rejected, self.selectors_matched,
+                                       self.selector_matching_time)));
+        }
         println("");
     }
 }
 
+/// Estimates the time saved by avoiding `avoided` operations, given that `done` of them took
+/// `time` ms in all.
+fn time_saved(avoided: uint, done: uint, time: f64) -> f64 {
+    if done == 0 {
+        return 0f64
+    }
+    (avoided as f64) * time / (done as f64)
+}
 
 pub fn profile<T>(category: ProfilerCategory, 
                   profiler_chan: ProfilerChan,
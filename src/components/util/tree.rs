@@ -4,6 +4,8 @@
 
 //! Helper functions for garbage collected doubly-linked trees.
 
+use atom::Atom;
+
 // Macros to make add_child etc. less painful to write.
 // Code outside this module should instead implement TreeNode
 // and use its default methods.
@@ -347,8 +349,36 @@ pub trait TreeNode<Ref: TreeNodeRef<Self>> {
 
 
 pub trait ElementLike {
-    fn get_local_name<'a>(&'a self) -> &'a str;
+    fn get_local_name(&self) -> Atom;
     fn get_namespace_url<'a>(&'a self) -> &'a str;
+
+    /// Returns the value of the id attribute. Ids are ASCII lowercased in documents in quirks
+    /// mode, where they are matched case-insensitively.
+    fn get_id(&self) -> Option<Atom>;
+
+    /// Returns the classes in the class attribute, lowercased like the id in quirks mode.
+    fn get_classes<'a>(&'a self) -> &'a [Atom];
+
     fn get_attr(&self, ns_url: Option<~str>, name: &str) -> Option<~str>;
     fn get_link(&self) -> Option<~str>;
+
+    /// Returns true if this element has no children other than comments and empty text nodes.
+    fn is_empty(&self) -> bool;
+
+    /// Returns true if the pointer is over this element or one of its descendants.
+    fn is_hovered(&self) -> bool;
+
+    /// Returns true if this element or one of its descendants is being activated, for example
+    /// while a mouse button is held down over it.
+    fn is_active(&self) -> bool;
+
+    /// Returns true if this element has the focus.
+    fn is_focused(&self) -> bool;
+
+    /// Returns `Some(true)` if this element is a disabled form control, `Some(false)` if it is
+    /// a form control that could be disabled but is not, and `None` otherwise.
+    fn get_disabled_state(&self) -> Option<bool>;
+
+    /// Returns true if this element is a checked checkbox or radio button, or a selected option.
+    fn is_checked(&self) -> bool;
 }
@@ -17,6 +17,7 @@ use std::rt::io::Reader;
 use std::os;
 use std::run;
 use std::str;
+use std::hashmap::HashSet;
 use extra::test::{DynTestName, DynTestFn, TestDesc, TestOpts, TestDescAndFn};
 use extra::test::run_tests_console;
 
@@ -61,6 +62,7 @@ struct Reftest {
 
 fn parse_lists(filenames: &[~str]) -> ~[TestDescAndFn] {
     let mut tests: ~[TestDescAndFn] = ~[];
+    let mut pages = HashSet::new();
     let mut next_id = 0;
     for file in filenames.iter() {
         let file_path = Path::new(file.clone());
@@ -90,14 +92,22 @@ fn parse_lists(filenames: &[~str]) -> ~[TestDescAndFn] {
             let reftest = Reftest {
                 name: parts[1] + " / " + parts[2],
                 kind: kind,
-                left: file_left,
-                right: file_right,
+                left: file_left.clone(),
+                right: file_right.clone(),
                 id: next_id,
             };
 
             next_id += 1;
 
             tests.push(make_test(reftest));
+
+            // Every page must also look the same whether it is styled in parallel or not.
+            for page in [file_left, file_right].iter() {
+                if pages.insert(page.clone()) {
+                    tests.push(make_parallel_style_test(page.clone(), next_id));
+                    next_id += 1;
+                }
+            }
         }
     }
     tests
@@ -118,6 +128,44 @@ fn make_test(reftest: Reftest) -> TestDescAndFn {
     }
 }
 
+fn make_parallel_style_test(page: ~str, id: uint) -> TestDescAndFn {
+    let name = page + " (parallel styling)";
+    let page = Cell::new(page);
+    TestDescAndFn {
+        desc: TestDesc {
+            name: DynTestName(name),
+            ignore: false,
+            should_fail: false,
+        },
+        testfn: DynTestFn(|| {
+            check_parallel_style(page.take(), id);
+        }),
+    }
+}
+
+/// Renders `page` with one style task and with several, and checks that the results are equal.
+fn check_parallel_style(page: ~str, id: uint) {
+    let sequential_filename = format!("/tmp/servo-reftest-{:06u}-sequential.png", id);
+    let parallel_filename = format!("/tmp/servo-reftest-{:06u}-parallel.png", id);
+
+    // Unlike the reftests, a page that makes servo fail must fail the test: both runs would
+    // leave no image behind, or the same stale one.
+    let args = ~[~"-y", ~"1", ~"-o", sequential_filename.clone(), page.clone()];
+    let mut process = run::Process::new("./servo", args, run::ProcessOptions::new());
+    let retval = process.finish();
+    assert!(retval == 0);
+
+    let args = ~[~"-y", ~"4", ~"-o", parallel_filename.clone(), page.clone()];
+    let mut process = run::Process::new("./servo", args, run::ProcessOptions::new());
+    let retval = process.finish();
+    assert!(retval == 0);
+
+    let args = ~[sequential_filename.clone(), parallel_filename.clone()];
+    let mut process = run::Process::new("cmp", args, run::ProcessOptions::new());
+    let retval = process.finish();
+    assert!(retval == 0);
+}
+
 fn check_reftest(reftest: Reftest) {
     let left_filename = format!("/tmp/servo-reftest-{:06u}-left.png", reftest.id);
     let right_filename = format!("/tmp/servo-reftest-{:06u}-right.png", reftest.id);